@@ -1,26 +1,64 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
 use forge_domain::*;
 use forge_infra::ForgeInfra;
-use forge_services::{CommandExecutorService, ForgeServices, Infrastructure};
+use forge_services::{CommandExecutorService, ForgeServices, FsSnapshotService, Infrastructure};
 use forge_stream::MpscStream;
+use futures::StreamExt;
 use tracing::error;
 
+/// Orchestrators of the conversations that are running, which receive the
+/// messages appended to them
+type Running<F> = Arc<Mutex<HashMap<ConversationId, Arc<Orchestrator<F>>>>>;
+
 pub struct ForgeAPI<F> {
     app: Arc<F>,
+    running: Running<F>,
 }
 
 impl<F: Services + Infrastructure> ForgeAPI<F> {
     pub fn new(app: Arc<F>) -> Self {
-        Self { app: app.clone() }
+        Self { app: app.clone(), running: Default::default() }
+    }
+
+    fn running(&self, conversation_id: &ConversationId) -> Option<Arc<Orchestrator<F>>> {
+        self.running
+            .lock()
+            .ok()
+            .and_then(|running| running.get(conversation_id).cloned())
+    }
+}
+
+/// Unregisters the orchestrator of a conversation once its turn ends or is
+/// cancelled
+struct RunningGuard<F> {
+    running: Running<F>,
+    conversation_id: ConversationId,
+}
+
+impl<F> RunningGuard<F> {
+    fn register(running: &Running<F>, orch: Arc<Orchestrator<F>>, id: ConversationId) -> Self {
+        if let Ok(mut orchestrators) = running.lock() {
+            orchestrators.insert(id.clone(), orch);
+        }
+        Self { running: running.clone(), conversation_id: id }
+    }
+}
+
+impl<F> Drop for RunningGuard<F> {
+    fn drop(&mut self) {
+        if let Ok(mut orchestrators) = self.running.lock() {
+            orchestrators.remove(&self.conversation_id);
+        }
     }
 }
 
 impl ForgeAPI<ForgeServices<ForgeInfra>> {
-    pub fn init(restricted: bool) -> Self {
-        let infra = Arc::new(ForgeInfra::new(restricted));
+    pub fn init(restricted: bool, low_bandwidth: bool) -> Self {
+        let infra = Arc::new(ForgeInfra::new(restricted, low_bandwidth));
         let app = Arc::new(ForgeServices::new(infra));
         ForgeAPI::new(app)
     }
@@ -36,6 +74,14 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
         self.app.tool_service().list()
     }
 
+    async fn call_tool(&self, context: ToolCallContext, call: ToolCallFull) -> ToolResult {
+        self.app.tool_service().call(context, &call).await
+    }
+
+    async fn remote_agents(&self) -> Result<Vec<AgentPackage>> {
+        self.app.registry_service().packages().await
+    }
+
     async fn models(&self) -> Result<Vec<Model>> {
         Ok(self.app.provider_service().models().await?)
     }
@@ -45,6 +91,7 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
         chat: ChatRequest,
     ) -> anyhow::Result<MpscStream<Result<AgentMessage<ChatResponse>, anyhow::Error>>> {
         let app = self.app.clone();
+        let running = self.running.clone();
         let conversation = app
             .conversation_service()
             .find(&chat.conversation_id)
@@ -55,13 +102,21 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
         Ok(MpscStream::spawn(move |tx| async move {
             let tx = Arc::new(tx);
 
-            let orch = Orchestrator::new(app, conversation, Some(tx.clone()));
+            let orch = Arc::new(Orchestrator::new(app, conversation, Some(tx.clone())));
+            let guard = RunningGuard::register(&running, orch.clone(), chat.conversation_id);
 
             if let Err(err) = orch.dispatch(chat.event).await {
                 if let Err(e) = tx.send(Err(err)).await {
                     error!("Failed to send error to stream: {:#?}", e);
                 }
             }
+
+            // The messages appended after the conversation was last stored are
+            // kept for the next turn
+            drop(guard);
+            if let Err(err) = orch.flush().await {
+                error!("Failed to store the conversation: {:#?}", err);
+            }
         }))
     }
 
@@ -76,7 +131,34 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
     }
 
     async fn upsert_conversation(&self, conversation: Conversation) -> anyhow::Result<()> {
-        self.app.conversation_service().upsert(conversation).await
+        self.app.conversation_service().upsert(conversation).await?;
+        Ok(())
+    }
+
+    async fn append_message(
+        &self,
+        conversation_id: &ConversationId,
+        message: ContextMessage,
+    ) -> anyhow::Result<()> {
+        // The orchestrator of a running conversation would overwrite the stored
+        // one, so the message goes through it
+        if let Some(orch) = self.running(conversation_id) {
+            orch.append_message(message).await?;
+            // The turn may have ended after the orchestrator stored the
+            // conversation for the last time
+            if self.running(conversation_id).is_none() {
+                orch.flush().await?;
+            }
+            return Ok(());
+        }
+
+        self.app
+            .conversation_service()
+            .update(conversation_id, |conversation| {
+                conversation.append_message(message)
+            })
+            .await??;
+        Ok(())
     }
 
     async fn compact_conversation(
@@ -89,6 +171,38 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
             .await
     }
 
+    async fn complete(&self, model: &ModelId, context: Context) -> Result<ContentMessage> {
+        let mut stream = self.app.provider_service().chat(model, context).await?;
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        let mut tool_call_parts = Vec::new();
+        let mut metadata = ResponseMetadata::default();
+        while let Some(message) = stream.next().await {
+            let message = message?;
+            if let Some(part) = message.content {
+                content.push_str(part.as_str());
+            }
+            for tool_call in message.tool_calls {
+                match tool_call {
+                    ToolCall::Full(tool_call) => tool_calls.push(tool_call),
+                    ToolCall::Part(part) => tool_call_parts.push(part),
+                }
+            }
+            if let Some(model) = message.model {
+                metadata = metadata.model(model);
+            }
+        }
+
+        tool_calls.extend(ToolCallFull::try_from_parts(&tool_call_parts)?);
+        tool_calls.extend(ToolCallFull::try_from_xml(&content).unwrap_or_default());
+        Ok(ContentMessage {
+            role: Role::Assistant,
+            content,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            metadata: Some(metadata),
+        })
+    }
+
     fn environment(&self) -> Environment {
         Services::environment_service(self.app.as_ref())
             .get_environment()
@@ -99,6 +213,14 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
         self.app.workflow_service().read(path).await
     }
 
+    async fn workflow_path(&self, path: Option<&Path>) -> PathBuf {
+        let path = path.unwrap_or_else(|| Path::new("forge.yaml"));
+        self.app
+            .workflow_service()
+            .resolve(Some(path.to_path_buf()))
+            .await
+    }
+
     async fn write_workflow(&self, path: Option<&Path>, workflow: &Workflow) -> anyhow::Result<()> {
         self.app.workflow_service().write(path, workflow).await
     }
@@ -127,4 +249,8 @@ impl<F: Services + Infrastructure> API for ForgeAPI<F> {
             .execute_command(command.to_string(), working_dir)
             .await
     }
+
+    async fn undo_snapshot(&self, path: &Path) -> anyhow::Result<()> {
+        self.app.file_snapshot_service().undo_snapshot(path).await
+    }
 }
@@ -1,7 +1,9 @@
 use std::fmt;
 
-use console::{style, Style};
-use similar::{ChangeTag, TextDiff};
+use console::{pad_str, style, Alignment, Style, Term};
+use similar::{ChangeTag, DiffTag, TextDiff};
+
+use crate::Theme;
 
 struct Line(Option<usize>);
 
@@ -14,12 +16,33 @@ impl fmt::Display for Line {
     }
 }
 
+/// Unchanged lines shown around the changes by default
+const DEFAULT_CONTEXT: usize = 3;
+
+/// Narrowest side-by-side diff, so that each side keeps some of the lines
+const MIN_SIDE_BY_SIDE_WIDTH: usize = 60;
+
 pub struct DiffFormat;
 
 impl DiffFormat {
+    /// Formats the changes as a unified diff, as produced by `diff -u`
+    pub fn unified(path: &str, old: &str, new: &str) -> String {
+        TextDiff::from_lines(old, new)
+            .unified_diff()
+            .context_radius(3)
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string()
+    }
+
     pub fn format(old: &str, new: &str) -> String {
+        Self::format_with_context(old, new, DEFAULT_CONTEXT)
+    }
+
+    /// Formats the changes with the given number of unchanged lines around
+    /// them
+    pub fn format_with_context(old: &str, new: &str, context: usize) -> String {
         let diff = TextDiff::from_lines(old, new);
-        let ops = diff.grouped_ops(3);
+        let ops = diff.grouped_ops(context);
         let mut output = String::new();
 
         if ops.is_empty() {
@@ -27,6 +50,7 @@ impl DiffFormat {
             return output;
         }
 
+        let (deleted, inserted) = change_styles();
         for (idx, group) in ops.iter().enumerate() {
             if idx > 0 {
                 output.push_str(&format!("{}\n", style("...").dim()));
@@ -34,8 +58,8 @@ impl DiffFormat {
             for op in group {
                 for change in diff.iter_inline_changes(op) {
                     let (sign, s) = match change.tag() {
-                        ChangeTag::Delete => ("-", Style::new().blue()),
-                        ChangeTag::Insert => ("+", Style::new().yellow()),
+                        ChangeTag::Delete => ("-", deleted.clone()),
+                        ChangeTag::Insert => ("+", inserted.clone()),
                         ChangeTag::Equal => (" ", Style::new().dim()),
                     };
 
@@ -57,6 +81,76 @@ impl DiffFormat {
         }
         output
     }
+
+    /// Formats the changes with the old lines on the left and the new ones on
+    /// the right, in the given number of columns or the width of the terminal
+    pub fn side_by_side(old: &str, new: &str, width: Option<usize>, context: usize) -> String {
+        let diff = TextDiff::from_lines(old, new);
+        let ops = diff.grouped_ops(context);
+        if ops.is_empty() {
+            return format!("{}\n", style("No changes applied").dim());
+        }
+
+        let width = width
+            .unwrap_or_else(|| Term::stdout().size().1 as usize)
+            .max(MIN_SIDE_BY_SIDE_WIDTH);
+        // Each side has a line number and the sides are separated by " │ "
+        let column = (width - 3) / 2 - 5;
+        let (deleted, inserted) = change_styles();
+        let old_lines = diff.old_slices();
+        let new_lines = diff.new_slices();
+        let cell = |lines: &[&str], index: Option<usize>, style: &Style| match index {
+            Some(index) => format!(
+                "{} {}",
+                self::style(Line(Some(index))).dim(),
+                style.apply_to(pad_str(
+                    &lines[index]
+                        .trim_end_matches(['\n', '\r'])
+                        .replace('\t', "    "),
+                    column,
+                    Alignment::Left,
+                    Some("…"),
+                ))
+            ),
+            None => " ".repeat(column + 5),
+        };
+
+        let mut output = String::new();
+        for (idx, group) in ops.iter().enumerate() {
+            if idx > 0 {
+                output.push_str(&format!("{}\n", style("...").dim()));
+            }
+            for op in group {
+                let (old_range, new_range) = (op.old_range(), op.new_range());
+                let changed = op.tag() != DiffTag::Equal;
+                let rows = old_range.len().max(new_range.len());
+                for row in 0..rows {
+                    let old_index = Some(old_range.start + row).filter(|i| old_range.contains(i));
+                    let new_index = Some(new_range.start + row).filter(|i| new_range.contains(i));
+                    let (left, right) = if changed {
+                        (&deleted, &inserted)
+                    } else {
+                        (&Style::new().dim(), &Style::new().dim())
+                    };
+                    output.push_str(&format!(
+                        "{}{}{}\n",
+                        cell(old_lines, old_index, left),
+                        style(" │ ").dim(),
+                        cell(new_lines, new_index, right),
+                    ));
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Styles of the removed and the added lines
+fn change_styles() -> (Style, Style) {
+    match Theme::current() {
+        Theme::Light => (Style::new().red(), Style::new().green()),
+        Theme::Dark | Theme::Plain => (Style::new().blue(), Style::new().yellow()),
+    }
 }
 
 #[cfg(test)]
@@ -98,4 +192,28 @@ mod tests {
         let clean_diff = strip_ansi_codes(&diff);
         assert_snapshot!(clean_diff);
     }
+
+    #[test]
+    fn test_unified() {
+        let old = "line 1\nline 2\nline 3\n";
+        let new = "line 1\nmodified line\nline 3\n";
+        let diff = DiffFormat::unified("src/lib.rs", old, new);
+        assert_snapshot!(diff);
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let old = "line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\n";
+        let new = "line 1\nline 2\nchanged line 3 that is much longer than the column\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\n";
+        let diff = DiffFormat::side_by_side(old, new, Some(80), 1);
+        let clean_diff = strip_ansi_codes(&diff);
+        assert_snapshot!(clean_diff);
+    }
+
+    #[test]
+    fn test_side_by_side_no_differences() {
+        let content = "line 1\nline 2";
+        let diff = DiffFormat::side_by_side(content, content, Some(80), 3);
+        assert!(diff.contains("No changes applied"));
+    }
 }
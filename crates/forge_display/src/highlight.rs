@@ -0,0 +1,194 @@
+use colored::{Color, Colorize};
+
+use crate::Theme;
+
+/// Kind of a token of highlighted code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Keyword,
+    String,
+    Comment,
+    Function,
+    Type,
+    Constant,
+}
+
+/// Highlights the code of a fenced code block whose language is given by its
+/// name or extension, e.g. `rust` or `py`. Returns None for the languages
+/// without a grammar.
+pub fn highlight(code: &str, language: &str, theme: Theme) -> Option<String> {
+    let mut output = String::new();
+    for (token, run) in tokens(code, language)? {
+        match token {
+            Some(token) => output.push_str(&run.color(token_color(token, theme)).to_string()),
+            None => output.push_str(run),
+        }
+    }
+    Some(output)
+}
+
+/// Splits the code into runs of characters of the same kind of token, with
+/// the tree-sitter grammar of the language. Returns None for the languages
+/// without a grammar.
+#[cfg(feature = "highlight")]
+pub fn tokens<'a>(code: &'a str, language: &str) -> Option<Vec<(Option<Token>, &'a str)>> {
+    use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+
+    let (language, highlights) = grammar(language)?;
+    let mut parser = Parser::new();
+    parser.set_language(&language).ok()?;
+    let tree = parser.parse(code, None)?;
+    let query = Query::new(&language, highlights).ok()?;
+
+    // Inner captures, visited after the outer ones, take precedence, e.g. an
+    // escape in a string. Of the captures of the same node, the first wins.
+    let mut kinds = vec![None; code.len()];
+    let mut captured = std::collections::HashSet::new();
+    let mut cursor = QueryCursor::new();
+    let mut captures = cursor.captures(&query, tree.root_node(), code.as_bytes());
+    while let Some((query_match, index)) = captures.next() {
+        let capture = query_match.captures[*index];
+        let range = capture.node.byte_range();
+        if !captured.insert(range.clone()) {
+            continue;
+        }
+        if let Some(token) = capture_token(query.capture_names()[capture.index as usize]) {
+            kinds[range].fill(Some(token));
+        }
+    }
+
+    Some(runs(code, &kinds))
+}
+
+#[cfg(not(feature = "highlight"))]
+pub fn tokens<'a>(_code: &'a str, _language: &str) -> Option<Vec<(Option<Token>, &'a str)>> {
+    None
+}
+
+/// The grammar and the highlight query of a language
+#[cfg(feature = "highlight")]
+fn grammar(language: &str) -> Option<(tree_sitter::Language, &'static str)> {
+    let grammar = match language.to_lowercase().as_str() {
+        "rust" | "rs" => (
+            tree_sitter_rust::LANGUAGE.into(),
+            tree_sitter_rust::HIGHLIGHTS_QUERY,
+        ),
+        "python" | "py" => (
+            tree_sitter_python::LANGUAGE.into(),
+            tree_sitter_python::HIGHLIGHTS_QUERY,
+        ),
+        "go" | "golang" => (
+            tree_sitter_go::LANGUAGE.into(),
+            tree_sitter_go::HIGHLIGHTS_QUERY,
+        ),
+        "java" => (
+            tree_sitter_java::LANGUAGE.into(),
+            tree_sitter_java::HIGHLIGHTS_QUERY,
+        ),
+        "c" | "h" | "cpp" | "c++" | "cc" | "cxx" | "hpp" => (
+            tree_sitter_cpp::LANGUAGE.into(),
+            tree_sitter_cpp::HIGHLIGHT_QUERY,
+        ),
+        "ruby" | "rb" => (
+            tree_sitter_ruby::LANGUAGE.into(),
+            tree_sitter_ruby::HIGHLIGHTS_QUERY,
+        ),
+        "css" => (
+            tree_sitter_css::LANGUAGE.into(),
+            tree_sitter_css::HIGHLIGHTS_QUERY,
+        ),
+        _ => return None,
+    };
+    Some(grammar)
+}
+
+/// Kind of token of the captures of the highlight queries, e.g. `keyword` or
+/// `function.method`
+#[cfg_attr(not(feature = "highlight"), allow(dead_code))]
+fn capture_token(capture: &str) -> Option<Token> {
+    let kind = capture.split('.').next().unwrap_or(capture);
+    let token = match (capture, kind) {
+        ("variable.builtin", _) | (_, "keyword") => Token::Keyword,
+        (_, "string") => Token::String,
+        (_, "comment") => Token::Comment,
+        (_, "function") => Token::Function,
+        (_, "type" | "constructor") => Token::Type,
+        (_, "number" | "constant" | "escape" | "attribute") => Token::Constant,
+        _ => return None,
+    };
+    Some(token)
+}
+
+fn token_color(token: Token, theme: Theme) -> Color {
+    let light = theme == Theme::Light;
+    match token {
+        Token::Keyword => Color::Magenta,
+        Token::String => Color::Green,
+        Token::Comment => Color::BrightBlack,
+        Token::Function => Color::Blue,
+        Token::Type if light => Color::Red,
+        Token::Type => Color::Yellow,
+        Token::Constant if light => Color::Cyan,
+        Token::Constant => Color::BrightCyan,
+    }
+}
+
+/// Splits the code into the runs of characters of the same kind
+#[cfg_attr(not(feature = "highlight"), allow(dead_code))]
+fn runs<'a>(code: &'a str, kinds: &[Option<Token>]) -> Vec<(Option<Token>, &'a str)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (index, _) in code.char_indices().skip(1).chain([(code.len(), ' ')]) {
+        if index < code.len() && kinds[index] == kinds[start] {
+            continue;
+        }
+        if start < index {
+            runs.push((kinds[start], &code[start..index]));
+        }
+        start = index;
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_capture_token() {
+        assert_eq!(capture_token("keyword"), Some(Token::Keyword));
+        assert_eq!(capture_token("function.method"), Some(Token::Function));
+        assert_eq!(capture_token("type.builtin"), Some(Token::Type));
+        assert_eq!(capture_token("punctuation.bracket"), None);
+    }
+
+    #[test]
+    fn test_runs_keep_the_code() {
+        let code = "let x = \"ü\";";
+        let mut kinds = vec![None; code.len()];
+        kinds[..3].fill(Some(Token::Keyword));
+        kinds[8..12].fill(Some(Token::String));
+
+        let actual = runs(code, &kinds);
+        let expected = vec![
+            (Some(Token::Keyword), "let"),
+            (None, " x = "),
+            (Some(Token::String), "\"ü\""),
+            (None, ";"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn test_highlight() {
+        let code = "fn main() {\n    // Greet\n    println!(\"Hello {}\", 42);\n}\n";
+
+        let actual = highlight(code, "rust", Theme::Dark).unwrap();
+        assert_eq!(strip_ansi_escapes::strip_str(actual), code);
+
+        assert_eq!(highlight(code, "cobol", Theme::Dark), None);
+    }
+}
@@ -0,0 +1,64 @@
+//! Shares the input of the terminal between the prompts that ask the user a
+//! question and the listeners that read it in the background, e.g. for ESC
+//! while the agent is working, so that the answers aren't swallowed by the
+//! listeners.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Number of prompts that wait for or hold the input
+static PROMPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Held by whoever reads the input
+static INPUT: Mutex<()> = Mutex::new(());
+
+/// Reserves the input for a prompt until it's dropped
+pub struct PromptGuard {
+    _input: MutexGuard<'static, ()>,
+}
+
+impl PromptGuard {
+    /// Waits for the current read of the background listeners, after which
+    /// they don't read the input until the guard is dropped
+    pub fn acquire() -> Self {
+        PROMPTS.fetch_add(1, Ordering::SeqCst);
+        let input = INPUT
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Self { _input: input }
+    }
+}
+
+impl Drop for PromptGuard {
+    fn drop(&mut self) {
+        PROMPTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Runs a read of a background listener, unless a prompt uses the input in
+/// which case it returns `None`
+pub fn read_in_background<T>(read: impl FnOnce() -> T) -> Option<T> {
+    if PROMPTS.load(Ordering::SeqCst) > 0 {
+        return None;
+    }
+    let _input = INPUT.try_lock().ok()?;
+    Some(read())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_prompt_pauses_background_reads() {
+        assert_eq!(read_in_background(|| 1), Some(1));
+
+        let guard = PromptGuard::acquire();
+        assert_eq!(read_in_background(|| 2), None);
+
+        drop(guard);
+        assert_eq!(read_in_background(|| 3), Some(3));
+    }
+}
@@ -1,9 +1,13 @@
 pub mod diff;
 pub mod grep;
+pub mod highlight;
+pub mod input;
 pub mod markdown;
+pub mod theme;
 pub mod title;
 
 pub use diff::DiffFormat;
 pub use grep::GrepFormat;
 pub use markdown::MarkdownFormat;
+pub use theme::Theme;
 pub use title::*;
@@ -3,6 +3,9 @@ use regex::Regex;
 use termimad::crossterm::style::{Attribute, Color};
 use termimad::{CompoundStyle, LineStyle, MadSkin};
 
+use crate::highlight::highlight;
+use crate::Theme;
+
 /// MarkdownFormat provides functionality for formatting markdown text for
 /// terminal display.
 #[derive(Clone, Setters, Default)]
@@ -10,13 +13,31 @@ use termimad::{CompoundStyle, LineStyle, MadSkin};
 pub struct MarkdownFormat {
     skin: MadSkin,
     max_consecutive_newlines: usize,
+    /// Theme of the syntax highlighting of the code blocks
+    #[setters(skip)]
+    theme: Theme,
 }
 
 impl MarkdownFormat {
-    /// Create a new MarkdownFormat with the default skin
+    /// Create a new MarkdownFormat with the skin of the current theme
     pub fn new() -> Self {
-        let mut skin = MadSkin::default();
-        let compound_style = CompoundStyle::new(Some(Color::Cyan), None, Attribute::Bold.into());
+        Self::with_theme(Theme::current())
+    }
+
+    /// Create a new MarkdownFormat with the skin of the theme
+    pub fn with_theme(theme: Theme) -> Self {
+        let (mut skin, code_color) = match theme {
+            Theme::Dark => (MadSkin::default_dark(), Color::Cyan),
+            Theme::Light => (MadSkin::default_light(), Color::DarkBlue),
+            Theme::Plain => {
+                return Self {
+                    skin: MadSkin::no_style(),
+                    max_consecutive_newlines: 2,
+                    theme,
+                }
+            }
+        };
+        let compound_style = CompoundStyle::new(Some(code_color), None, Attribute::Bold.into());
         skin.inline_code = compound_style.clone();
 
         let mut codeblock_style = CompoundStyle::new(None, None, Default::default());
@@ -24,7 +45,7 @@ impl MarkdownFormat {
 
         skin.code_block = LineStyle::new(codeblock_style, Default::default());
 
-        Self { skin, max_consecutive_newlines: 2 }
+        Self { skin, max_consecutive_newlines: 2, theme }
     }
 
     /// Render the markdown content to a string formatted for terminal display.
@@ -38,11 +59,40 @@ impl MarkdownFormat {
         // Strip excessive newlines before rendering
         let processed_content = self.strip_excessive_newlines(content_string.trim());
 
-        self.skin
-            .term_text(&processed_content)
-            .to_string()
-            .trim()
-            .to_string()
+        // The code blocks in a language with a grammar are highlighted, the rest
+        // is rendered by the skin
+        let code_block = Regex::new(r"(?ms)^```([\w+#-]+)[^\n]*\n(.*?)^```[ \t]*$").unwrap();
+        let mut output = String::new();
+        let mut rendered = 0;
+        for captures in code_block.captures_iter(&processed_content) {
+            let Some(code) = self.highlight(&captures[2], &captures[1]) else {
+                continue;
+            };
+            let block = captures.get(0).unwrap();
+            output.push_str(
+                &self
+                    .skin
+                    .term_text(&processed_content[rendered..block.start()])
+                    .to_string(),
+            );
+            output.push_str(&code);
+            rendered = block.end();
+        }
+        output.push_str(
+            &self
+                .skin
+                .term_text(&processed_content[rendered..])
+                .to_string(),
+        );
+
+        output.trim().to_string()
+    }
+
+    fn highlight(&self, code: &str, language: &str) -> Option<String> {
+        if self.theme == Theme::Plain {
+            return None;
+        }
+        highlight(code, language, self.theme)
     }
 
     /// Strip excessive consecutive newlines from content
@@ -88,6 +138,27 @@ mod tests {
         assert!(actual.is_empty());
     }
 
+    #[test]
+    fn test_render_plain_theme() {
+        let fixture = "# Heading\n\nSome **bold** text and `code`";
+        let markdown = MarkdownFormat::with_theme(Theme::Plain);
+        let actual = markdown.render(fixture);
+
+        assert_eq!(strip_ansi_escapes::strip_str(&actual), actual);
+    }
+
+    #[test]
+    fn test_render_code_block() {
+        let fixture =
+            "Run this:\n\n```rust\nfn main() {\n    println!(\"Hello\");\n}\n```\n\nDone.";
+        let markdown = MarkdownFormat::with_theme(Theme::Dark);
+        let actual = strip_ansi_escapes::strip_str(markdown.render(fixture));
+
+        assert!(actual.starts_with("Run this:"));
+        assert!(actual.contains("\nfn main() {\n    println!(\"Hello\");\n}\n"));
+        assert!(actual.ends_with("Done."));
+    }
+
     #[test]
     fn test_strip_excessive_newlines_default() {
         let fixture = "Line 1\n\n\n\nLine 2";
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use colored::Color;
+
+static CURRENT: AtomicU8 = AtomicU8::new(Theme::Dark as u8);
+
+/// Colors of the output, chosen for the background of the terminal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Dark = 0,
+    Light = 1,
+    /// No colors or styles, e.g. for CI logs
+    Plain = 2,
+}
+
+impl Theme {
+    /// The theme of the output, dark until another one is applied
+    pub fn current() -> Self {
+        match CURRENT.load(Ordering::Relaxed) {
+            1 => Theme::Light,
+            2 => Theme::Plain,
+            _ => Theme::Dark,
+        }
+    }
+
+    /// Uses the theme for all the output of the process, or the plain one when
+    /// the `NO_COLOR` environment variable is set
+    pub fn apply(self) {
+        let theme = self.resolve(std::env::var("NO_COLOR").ok().as_deref());
+        CURRENT.store(theme as u8, Ordering::Relaxed);
+        if theme == Theme::Plain {
+            colored::control::set_override(false);
+            console::set_colors_enabled(false);
+        } else {
+            colored::control::unset_override();
+        }
+    }
+
+    /// NO_COLOR disables colors when set to anything but an empty string, as
+    /// specified by https://no-color.org
+    fn resolve(self, no_color: Option<&str>) -> Self {
+        match no_color {
+            Some(value) if !value.is_empty() => Theme::Plain,
+            _ => self,
+        }
+    }
+
+    /// Color of the regular text
+    pub fn text(&self) -> Color {
+        match self {
+            Theme::Light => Color::Black,
+            Theme::Dark | Theme::Plain => Color::White,
+        }
+    }
+
+    /// Color of the markers of actions and completions
+    pub fn accent(&self) -> Color {
+        match self {
+            Theme::Light => Color::Blue,
+            Theme::Dark | Theme::Plain => Color::Yellow,
+        }
+    }
+
+    pub fn warning(&self) -> Color {
+        match self {
+            Theme::Light => Color::Magenta,
+            Theme::Dark | Theme::Plain => Color::Yellow,
+        }
+    }
+
+    pub fn error(&self) -> Color {
+        Color::Red
+    }
+
+    pub fn debug(&self) -> Color {
+        match self {
+            Theme::Light => Color::BrightBlack,
+            Theme::Dark | Theme::Plain => Color::Cyan,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_no_color() {
+        assert_eq!(Theme::Light.resolve(None), Theme::Light);
+        assert_eq!(Theme::Light.resolve(Some("")), Theme::Light);
+        assert_eq!(Theme::Light.resolve(Some("1")), Theme::Plain);
+    }
+}
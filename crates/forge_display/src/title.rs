@@ -3,12 +3,15 @@ use std::fmt::{self, Display, Formatter};
 use colored::Colorize;
 use derive_setters::Setters;
 
+use crate::Theme;
+
 #[derive(Clone)]
 pub enum Category {
     Action,
     Info,
     Debug,
     Error,
+    Warning,
     Completion,
 }
 
@@ -60,6 +63,14 @@ impl TitleFormat {
         }
     }
 
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            title: message.into(),
+            sub_title: None,
+            category: Category::Warning,
+        }
+    }
+
     pub fn debug(message: impl Into<String>) -> Self {
         Self {
             title: message.into(),
@@ -79,12 +90,14 @@ impl TitleFormat {
     fn format(&self) -> String {
         let mut buf = String::new();
 
+        let theme = Theme::current();
         let icon = match self.category {
-            Category::Action => "⏺".yellow(),
-            Category::Info => "⏺".white(),
-            Category::Debug => "⏺".cyan(),
-            Category::Error => "⏺".red(),
-            Category::Completion => "⏺".yellow(),
+            Category::Action => "⏺".color(theme.accent()),
+            Category::Info => "⏺".color(theme.text()),
+            Category::Debug => "⏺".color(theme.debug()),
+            Category::Error => "⏺".color(theme.error()),
+            Category::Warning => "⏺".color(theme.warning()),
+            Category::Completion => "⏺".color(theme.accent()),
         };
 
         buf.push_str(format!("{icon} ").as_str());
@@ -103,11 +116,14 @@ impl TitleFormat {
         }
 
         let title = match self.category {
-            Category::Action => self.title.white(),
-            Category::Info => self.title.white(),
+            Category::Action => self.title.color(theme.text()),
+            Category::Info => self.title.color(theme.text()),
             Category::Debug => self.title.dimmed(),
-            Category::Error => format!("{} {}", "ERROR:".bold(), self.title).red(),
-            Category::Completion => self.title.white().bold(),
+            Category::Error => format!("{} {}", "ERROR:".bold(), self.title).color(theme.error()),
+            Category::Warning => {
+                format!("{} {}", "WARNING:".bold(), self.title).color(theme.warning())
+            }
+            Category::Completion => self.title.color(theme.text()).bold(),
         };
 
         buf.push_str(title.to_string().as_str());
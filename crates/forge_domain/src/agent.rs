@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use derive_more::derive::Display;
 use derive_setters::Setters;
 use merge::Merge;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
@@ -11,12 +12,13 @@ use crate::merge::Key;
 use crate::temperature::Temperature;
 use crate::template::Template;
 use crate::{
-    Context, Error, Event, EventContext, ModelId, Result, Role, SystemContext, ToolDefinition,
-    ToolName,
+    ApprovalPolicy, Context, CustomTool, EgressPolicy, Error, Event, EventContext, Handover,
+    LanguageServer, ModelId, ResourceLimits, Result, RetryPolicy, Role, ShellPolicy, SystemContext,
+    TemperatureRamp, ToolDefinition, ToolName, TopP,
 };
 
 // Unique identifier for an agent
-#[derive(Debug, Display, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[derive(Debug, Display, Eq, PartialEq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct AgentId(String);
 impl AgentId {
@@ -39,7 +41,7 @@ impl From<ToolName> for AgentId {
 }
 
 /// Configuration for automatic context compaction
-#[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters)]
+#[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters, JsonSchema)]
 #[setters(strip_option, into)]
 pub struct Compact {
     /// Number of most recent messages to preserve during compaction
@@ -81,7 +83,7 @@ pub struct Compact {
     pub summary_tag: Option<SummaryTag>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 #[serde(transparent)]
 pub struct SummaryTag(String);
 
@@ -152,7 +154,7 @@ impl Compact {
         false
     }
 }
-#[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters)]
+#[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters, JsonSchema)]
 #[setters(strip_option, into)]
 pub struct Agent {
     /// Controls whether this agent's output should be hidden from the console
@@ -211,11 +213,26 @@ pub struct Agent {
     pub tools: Option<Vec<ToolName>>,
 
     // The transforms feature has been removed
-    /// Used to specify the events the agent is interested in    
+    /// Used to specify the events the agent is interested in, by name or by a
+    /// pattern in which `*` matches any text, e.g. `ci/*`
     #[serde(skip_serializing_if = "Option::is_none")]
     #[merge(strategy = merge_subscription)]
     pub subscribe: Option<Vec<String>>,
 
+    /// Events dispatched when the agent completes a turn, each only when its
+    /// condition holds, e.g. to send failing tests back to the coder
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub handovers: Option<Vec<Handover>>,
+
+    /// Retries the turn of the agent when it fails with a transient error of
+    /// the provider, e.g. a rate limit, instead of failing the whole run
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub retry: Option<RetryPolicy>,
+
     /// Maximum number of turns the agent can take    
     #[serde(skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
@@ -251,6 +268,79 @@ pub struct Agent {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
     pub temperature: Option<Temperature>,
+
+    /// Raises the temperature when the agent retries after failing to follow
+    /// instructions or repeating the same tool calls
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub temperature_ramp: Option<TemperatureRamp>,
+
+    /// Nucleus sampling used for agent, the model only samples from the most
+    /// likely tokens whose cumulative probability reaches this value. Valid
+    /// range is 0.0 to 1.0.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub top_p: Option<TopP>,
+
+    /// Maximum number of tokens the model may generate in each response
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_tokens: Option<usize>,
+
+    /// Limits applied to the processes spawned by this agent's shell commands
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub resource_limits: Option<ResourceLimits>,
+
+    /// Hosts that this agent's tools are allowed to access over the network
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub egress: Option<EgressPolicy>,
+
+    /// Commands that this agent is allowed to execute with the shell tool
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub shell_policy: Option<ShellPolicy>,
+
+    /// Tools that this agent may only execute after the user approves the call
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub approval: Option<ApprovalPolicy>,
+
+    /// Disables the tools that modify the workspace, such as writing files or
+    /// executing shell commands
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub read_only: Option<bool>,
+
+    /// Shows the changes to a file to the user, who accepts, rejects or
+    /// modifies them, before the file is written
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub review_edits: Option<bool>,
+
+    /// Language servers used by the LSP tool. If not specified, servers for
+    /// Rust, Python, TypeScript/JavaScript and Go are used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub language_servers: Option<Vec<LanguageServer>>,
+
+    /// Tools that run shell commands, declared in addition to the built-in
+    /// ones. They must be listed in `tools` to be used by the agent.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub custom_tools: Option<Vec<CustomTool>>,
 }
 
 fn merge_subscription(base: &mut Option<Vec<String>>, other: Option<Vec<String>>) {
@@ -277,13 +367,48 @@ impl Agent {
             tools: None,
             // transforms field removed
             subscribe: None,
+            handovers: None,
+            retry: None,
             max_turns: None,
             max_walker_depth: None,
             compact: None,
             custom_rules: None,
             hide_content: None,
             temperature: None,
+            temperature_ramp: None,
+            top_p: None,
+            max_tokens: None,
+            resource_limits: None,
+            egress: None,
+            shell_policy: None,
+            approval: None,
+            read_only: None,
+            review_edits: None,
+            language_servers: None,
+            custom_tools: None,
+        }
+    }
+
+    /// Priority of the subscription of the agent to the event, None when it
+    /// isn't subscribed. Names and the events addressed to the agent, e.g.
+    /// `software-designer/user_task_init`, take precedence over patterns, and
+    /// patterns with more literal characters over the other ones.
+    pub fn subscription_priority(&self, event_name: &str) -> Option<usize> {
+        let addressed = event_name
+            .strip_prefix(self.id.as_str())
+            .is_some_and(|name| name.starts_with('/'));
+        if addressed {
+            return Some(usize::MAX);
         }
+        self.subscribe
+            .iter()
+            .flatten()
+            .filter_map(|subscription| match subscription == event_name {
+                true => Some(usize::MAX),
+                false => matches_pattern(subscription, event_name)
+                    .then(|| subscription.chars().filter(|c| *c != '*').count()),
+            })
+            .max()
     }
 
     pub fn tool_definition(&self) -> Result<ToolDefinition> {
@@ -303,27 +428,54 @@ impl Agent {
         }
     }
 
-    pub async fn init_context(&self, mut forge_tools: Vec<ToolDefinition>) -> Result<Context> {
+    /// Sets the sampling parameters of the agent on the context, leaving the
+    /// defaults of the provider for the ones it doesn't override
+    pub fn apply_model_parameters(&self, mut context: Context) -> Context {
+        if let Some(top_p) = self.top_p {
+            context.top_p = Some(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            context.max_tokens = Some(max_tokens);
+        }
+        context
+    }
+
+    /// Sets the temperature of the context after the given number of steps in
+    /// a row in which the agent made no progress
+    pub fn apply_temperature(&self, mut context: Context, stalled_steps: usize) -> Context {
+        match &self.temperature_ramp {
+            Some(ramp) => {
+                context.temperature = ramp.temperature(self.temperature, stalled_steps);
+                context
+            }
+            None => match self.temperature {
+                Some(temperature) => context.temperature(temperature),
+                None => context,
+            },
+        }
+    }
+
+    pub async fn init_context(&self, forge_tools: Vec<ToolDefinition>) -> Result<Context> {
+        Ok(Context::default().extend_tools(self.tool_definitions(forge_tools)))
+    }
+
+    /// Returns the definitions of the tools that are sent to the provider,
+    /// none when the model calls tools through the prompt instead
+    pub fn tool_definitions(&self, mut forge_tools: Vec<ToolDefinition>) -> Vec<ToolDefinition> {
+        // Use the agent's tool_supported flag directly instead of querying the provider
+        if !self.tool_supported.unwrap_or_default() {
+            return Vec::new();
+        }
+
         let allowed = self.tools.iter().flatten().collect::<HashSet<_>>();
 
         // Adding Event tool to the list of tool definitions
         forge_tools.push(Event::tool_definition());
 
-        let tool_defs = forge_tools
+        forge_tools
             .into_iter()
             .filter(|tool| allowed.contains(&tool.name))
-            .collect::<Vec<_>>();
-
-        // Use the agent's tool_supported flag directly instead of querying the provider
-        let tool_supported = self.tool_supported.unwrap_or_default();
-
-        let context = Context::default();
-
-        Ok(context.extend_tools(if tool_supported {
-            tool_defs
-        } else {
-            Vec::new()
-        }))
+            .collect()
     }
 }
 
@@ -337,6 +489,24 @@ impl Key for Agent {
     }
 }
 
+/// Whether the name matches the pattern, in which `*` matches any text. A
+/// pattern without `*` only matches itself.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            // The wildcard takes as many characters as needed for the rest to match
+            name.char_indices()
+                .map(|(index, _)| index)
+                .chain([name.len()])
+                .any(|index| matches_pattern(rest, &name[index..]))
+        }
+    }
+}
+
 /// Estimates the token count from a string representation
 /// This is a simple estimation that should be replaced with a more accurate
 /// tokenizer
@@ -530,4 +700,82 @@ mod tests {
         let agent: Agent = serde_json::from_value(json).unwrap();
         assert_eq!(agent.temperature, None);
     }
+
+    #[test]
+    fn test_apply_model_parameters() {
+        let fixture: Agent = serde_json::from_value(json!({
+            "id": "planner",
+            "top_p": 0.9,
+            "max_tokens": 2048
+        }))
+        .unwrap();
+
+        let actual = fixture.apply_model_parameters(Context::default());
+        let expected = Context::default()
+            .top_p(TopP::new(0.9).unwrap())
+            .max_tokens(2048_usize);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_temperature_ramp() {
+        let fixture: Agent = serde_json::from_value(json!({
+            "id": "test-agent",
+            "temperature": 0.5,
+            "temperature_ramp": {"step": 0.25, "max": 1.0}
+        }))
+        .unwrap();
+
+        let actual = fixture
+            .apply_temperature(Context::default(), 1)
+            .temperature
+            .map(|temperature| temperature.value());
+        assert_eq!(actual, Some(0.75));
+
+        // Going back to the first attempt restores the agent's temperature
+        let context = fixture.apply_temperature(Context::default(), 3);
+        let actual = fixture
+            .apply_temperature(context, 0)
+            .temperature
+            .map(|temperature| temperature.value());
+        assert_eq!(actual, Some(0.5));
+    }
+
+    #[test]
+    fn test_apply_temperature_without_ramp() {
+        let fixture = Agent::new("test-agent");
+        let context = Context::default().temperature(Temperature::new(0.3).unwrap());
+
+        let actual = fixture.apply_temperature(context, 2).temperature;
+        assert_eq!(actual, Some(Temperature::new(0.3).unwrap()));
+    }
+
+    #[test]
+    fn test_subscription_priority() {
+        let fixture = Agent::new("ci-fixer").subscribe(vec![
+            "ci/*".to_string(),
+            "*/failed".to_string(),
+            "ci/lint".to_string(),
+        ]);
+
+        assert_eq!(fixture.subscription_priority("ci/lint"), Some(usize::MAX));
+        assert_eq!(
+            fixture.subscription_priority("ci-fixer/user_task_init"),
+            Some(usize::MAX)
+        );
+        assert_eq!(fixture.subscription_priority("ci/failed"), Some(7));
+        assert_eq!(fixture.subscription_priority("ci/build"), Some(3));
+        assert_eq!(fixture.subscription_priority("deploy/failed"), Some(7));
+        assert_eq!(fixture.subscription_priority("deploy/started"), None);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*/user_task_init", "act/user_task_init"));
+        assert!(matches_pattern("ci/*", "ci/"));
+        assert!(matches_pattern("a*b*c", "a-b-b-c"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(!matches_pattern("ci/*", "cd/build"));
+        assert!(!matches_pattern("a*b*c", "a-b-b-d"));
+    }
 }
@@ -0,0 +1,81 @@
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, NamedTool, ToolCallFull, ToolDefinition, ToolName};
+
+/// Event that delivers the task to a spawned agent
+pub const AGENT_SPAWN_EVENT: &str = "spawn/task";
+
+/// Input for delegating a task to another agent of the workflow. The agent
+/// works in an isolated context and its final output is returned as the
+/// result.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct AgentSpawn {
+    /// Id of the agent of the workflow to delegate the task to, e.g.
+    /// `researcher`.
+    pub agent: String,
+    /// The task that should be handed over to the agent, with all the context
+    /// it needs since it doesn't see the current conversation.
+    pub task: String,
+}
+
+impl NamedTool for AgentSpawn {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_agent_spawn")
+    }
+}
+
+impl AgentSpawn {
+    pub fn tool_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: Self::tool_name(),
+            description: "Launches another agent of the workflow with the given task in its own \
+                          context, waits for it to complete and returns its final output. Use \
+                          it to delegate focused sub-tasks, e.g. research or testing, without \
+                          filling your own context."
+                .to_string(),
+            input_schema: schema_for!(AgentSpawn),
+            output_schema: None,
+            mutating: false,
+        }
+    }
+
+    /// Parses the tool call into a spawn request. Returns `None` if the call is
+    /// meant for a different tool.
+    pub fn parse(tool_call: &ToolCallFull) -> Option<crate::Result<Self>> {
+        if tool_call.name != Self::tool_name() {
+            return None;
+        }
+
+        Some(serde_json::from_value(tool_call.arguments.clone()).map_err(Error::ToolCallArgument))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_agent_spawn() {
+        let fixture = ToolCallFull::new(AgentSpawn::tool_name())
+            .arguments(json!({"agent": "researcher", "task": "Find the callers of foo"}));
+
+        let actual = AgentSpawn::parse(&fixture).unwrap().unwrap();
+
+        let expected = AgentSpawn {
+            agent: "researcher".to_string(),
+            task: "Find the callers of foo".to_string(),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_other_tool() {
+        let fixture = ToolCallFull::new(ToolName::new("forge_tool_fs_read"));
+        let actual = AgentSpawn::parse(&fixture);
+        assert!(actual.is_none());
+    }
+}
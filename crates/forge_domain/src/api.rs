@@ -15,6 +15,12 @@ pub trait API: Sync + Send {
     /// environment
     async fn tools(&self) -> Vec<ToolDefinition>;
 
+    /// Executes a tool call outside of a conversation, e.g. for an MCP client
+    async fn call_tool(&self, context: ToolCallContext, call: ToolCallFull) -> ToolResult;
+
+    /// Provides the agent packages published in the remote registry
+    async fn remote_agents(&self) -> Result<Vec<AgentPackage>>;
+
     /// Provides a list of models available in the current environment
     async fn models(&self) -> Result<Vec<Model>>;
 
@@ -24,6 +30,11 @@ pub trait API: Sync + Send {
         chat: ChatRequest,
     ) -> Result<MpscStream<Result<AgentMessage<ChatResponse>>>>;
 
+    /// Sends the context to the model outside of a conversation and returns
+    /// its complete response, e.g. to replay a recorded turn with another
+    /// model
+    async fn complete(&self, model: &ModelId, context: Context) -> Result<ContentMessage>;
+
     /// Returns the current environment
     fn environment(&self) -> Environment;
 
@@ -33,15 +44,30 @@ pub trait API: Sync + Send {
         config: W,
     ) -> Result<Conversation>;
 
-    /// Adds a new conversation to the conversation store
+    /// Adds a new conversation to the conversation store, or replaces the
+    /// stored one. Fails with `Error::ConversationConflict` if the stored
+    /// conversation has changed since it was read.
     async fn upsert_conversation(&self, conversation: Conversation) -> Result<()>;
 
+    /// Appends a message from an external system, e.g. a CI result, to an
+    /// ongoing conversation. The agents that have started receive it before
+    /// their next request, including the ones that are running.
+    async fn append_message(
+        &self,
+        conversation_id: &ConversationId,
+        message: ContextMessage,
+    ) -> Result<()>;
+
     /// Initializes a workflow configuration from the given path
     /// The workflow at the specified path is merged with the default
     /// configuration If no path is provided, it will try to find forge.yaml
     /// in the current directory or its parent directories
     async fn read_workflow(&self, path: Option<&Path>) -> Result<Workflow>;
 
+    /// Path of the workflow file that is read for the given path, e.g. the
+    /// forge.yaml found in a parent directory
+    async fn workflow_path(&self, path: Option<&Path>) -> PathBuf;
+
     /// Writes the given workflow to the specified path
     /// If no path is provided, it will try to find forge.yaml in the current
     /// directory or its parent directories
@@ -71,4 +97,8 @@ pub trait API: Sync + Send {
         command: &str,
         working_dir: PathBuf,
     ) -> Result<CommandOutput>;
+
+    /// Restores the file from its most recent snapshot, taken by the tools
+    /// before they changed it
+    async fn undo_snapshot(&self, path: &Path) -> Result<()>;
 }
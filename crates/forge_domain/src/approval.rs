@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::ToolName;
+
+/// What happens when an agent calls a tool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalAction {
+    /// The tool is executed without asking
+    #[default]
+    Allow,
+    /// The user is asked to approve the call before the tool is executed
+    Ask,
+    /// The tool is never executed
+    Deny,
+}
+
+/// Decides which tool calls need to be approved by the user.
+///
+/// Rules are keyed by tool name (`forge_tool_process_shell`) or by category,
+/// using a trailing `*` (`forge_tool_fs_*`). When several rules match a tool
+/// the most specific one wins, and tools without a matching rule use the
+/// default action.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Merge, Setters, JsonSchema,
+)]
+#[setters(into)]
+pub struct ApprovalPolicy {
+    /// Action for tools that don't match any of the rules
+    #[serde(default, rename = "default")]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub default_action: ApprovalAction,
+
+    /// Actions for individual tools or categories of tools
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[merge(strategy = merge_rules)]
+    pub tools: BTreeMap<String, ApprovalAction>,
+}
+
+fn merge_rules(
+    base: &mut BTreeMap<String, ApprovalAction>,
+    other: BTreeMap<String, ApprovalAction>,
+) {
+    base.extend(other);
+}
+
+impl ApprovalPolicy {
+    /// Returns the action for the given tool
+    pub fn action(&self, tool: &ToolName) -> ApprovalAction {
+        let name = tool.as_str();
+        self.tools
+            .iter()
+            .filter_map(|(pattern, action)| {
+                let specificity = match pattern.strip_suffix('*') {
+                    Some(prefix) if name.starts_with(prefix) => prefix.len(),
+                    None if pattern == name => usize::MAX,
+                    _ => return None,
+                };
+                Some((specificity, *action))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, action)| action)
+            .unwrap_or(self.default_action)
+    }
+}
+
+/// The user's answer to an approval request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approval {
+    /// Execute this call
+    Approve,
+    /// Don't execute this call
+    Reject,
+    /// Execute this call and every later call to the same tool without asking
+    Always,
+}
+
+impl Approval {
+    pub fn is_approved(&self) -> bool {
+        matches!(self, Approval::Approve | Approval::Always)
+    }
+}
+
+/// The user's answer to the review of an edit, before the file is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditReview {
+    /// Write the proposed content
+    Accept,
+    /// Leave the file unchanged
+    Reject,
+    /// Write the content as modified by the user
+    Edit(String),
+}
+
+/// Sends the answer to a question of the orchestrator, e.g. an approval
+/// request, back to it while it waits before executing the tool. Only the
+/// first answer is used.
+#[derive(Debug, Clone)]
+pub struct Reply<T>(Arc<Mutex<Option<oneshot::Sender<T>>>>);
+
+pub type ApprovalReply = Reply<Approval>;
+
+pub type EditReviewReply = Reply<EditReview>;
+
+impl<T> Reply<T> {
+    /// Creates a reply handle along with the receiver of the answer
+    pub fn channel() -> (Self, oneshot::Receiver<T>) {
+        let (tx, rx) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(tx)))), rx)
+    }
+
+    pub fn send(&self, answer: T) {
+        if let Some(tx) = self.0.lock().ok().and_then(|mut tx| tx.take()) {
+            // The orchestrator might have stopped waiting in the meantime
+            let _ = tx.send(answer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn action(policy: &ApprovalPolicy, tool: &str) -> ApprovalAction {
+        policy.action(&ToolName::new(tool))
+    }
+
+    #[test]
+    fn test_default_policy_allows_everything() {
+        let fixture = ApprovalPolicy::default();
+        assert_eq!(
+            action(&fixture, "forge_tool_process_shell"),
+            ApprovalAction::Allow
+        );
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let fixture = ApprovalPolicy::default()
+            .default_action(ApprovalAction::Ask)
+            .tools(BTreeMap::from([
+                ("forge_tool_fs_*".to_string(), ApprovalAction::Allow),
+                ("forge_tool_fs_remove".to_string(), ApprovalAction::Deny),
+                ("*".to_string(), ApprovalAction::Ask),
+            ]));
+
+        assert_eq!(
+            action(&fixture, "forge_tool_fs_read"),
+            ApprovalAction::Allow
+        );
+        assert_eq!(
+            action(&fixture, "forge_tool_fs_remove"),
+            ApprovalAction::Deny
+        );
+        assert_eq!(
+            action(&fixture, "forge_tool_net_fetch"),
+            ApprovalAction::Ask
+        );
+    }
+
+    #[test]
+    fn test_parse_approval_policy() {
+        let fixture = "default: ask\ntools:\n  forge_tool_fs_read: allow\n";
+        let actual: ApprovalPolicy = serde_yml::from_str(fixture).unwrap();
+
+        let expected = ApprovalPolicy::default()
+            .default_action(ApprovalAction::Ask)
+            .tools(BTreeMap::from([(
+                "forge_tool_fs_read".to_string(),
+                ApprovalAction::Allow,
+            )]));
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_reply_uses_first_answer() {
+        let (fixture, rx) = ApprovalReply::channel();
+        fixture.clone().send(Approval::Always);
+        fixture.send(Approval::Reject);
+
+        let actual = rx.await.unwrap();
+        assert_eq!(actual, Approval::Always);
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// A file generated during the conversation, e.g. the diff of a patch or a
+/// report. Clients can offer to open or download it instead of showing its
+/// content inline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    /// Identifies the artifact across the events of the conversation
+    pub id: String,
+    pub kind: ArtifactKind,
+    /// Absolute path of the file holding the artifact
+    pub path: PathBuf,
+    /// Short description shown to the user, e.g. the file a diff applies to
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArtifactKind {
+    /// A unified diff of the changes made to a file
+    Diff,
+    Image,
+    Report,
+    File,
+}
+
+impl Artifact {
+    pub fn new(kind: ArtifactKind, path: impl Into<PathBuf>, title: impl ToString) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            path: path.into(),
+            title: title.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ChatResponse;
+
+    #[test]
+    fn test_serialize_chat_response() {
+        let mut fixture = Artifact::new(ArtifactKind::Diff, "/tmp/forge/1.diff", "src/main.rs");
+        fixture.id = "1".to_string();
+
+        let actual = serde_json::to_value(ChatResponse::Artifact(fixture)).unwrap();
+
+        let expected = json!({
+            "artifact": {
+                "id": "1",
+                "kind": "diff",
+                "path": "/tmp/forge/1.diff",
+                "title": "src/main.rs"
+            }
+        });
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,6 +1,9 @@
 use serde::Serialize;
 
-use crate::{ToolCallFull, ToolResult, Usage};
+use crate::{
+    ApprovalReply, Artifact, CommandOutputChunk, EditReviewReply, OpenCircuit, Phase, ToolCallFull,
+    ToolCallId, ToolResult, Usage,
+};
 
 /// Events that are emitted by the agent for external consumption. This includes
 /// events for all internal state changes.
@@ -14,6 +17,52 @@ pub enum ChatResponse {
         is_summary: bool,
     },
     ToolCallStart(ToolCallFull),
+    /// Output produced by a tool while it is still executing, correlated with
+    /// the originating tool call through `call_id`.
+    ToolCallOutput {
+        call_id: Option<ToolCallId>,
+        chunk: CommandOutputChunk,
+    },
     ToolCallEnd(ToolResult),
+    /// The tool calls are waiting for the user to approve them, the answer,
+    /// which applies to all of them, is sent through `reply`
+    ApprovalRequest {
+        calls: Vec<ToolCallFull>,
+        /// Why a policy denies the calls, which the user may approve anyway.
+        /// The answer then only applies to these calls.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+        #[serde(skip)]
+        reply: ApprovalReply,
+    },
+    /// A file is about to be written and edits are reviewed, the user accepts,
+    /// rejects or modifies the new content through `reply`
+    EditReview {
+        /// The path of the file as shown to the user
+        path: String,
+        old_content: String,
+        new_content: String,
+        #[serde(skip)]
+        reply: EditReviewReply,
+    },
     Usage(Usage),
+    /// Content from an untrusted source, e.g. a fetched page or an attached
+    /// file, looks like an attempt to override the instructions of the model
+    PromptInjection {
+        source: String,
+        excerpt: String,
+    },
+    /// A file generated during the turn that clients can link to instead of
+    /// inlining its content
+    Artifact(Artifact),
+    /// Forge started a new step of the turn, e.g. waiting for the model
+    Phase(Phase),
+    /// A tool that kept failing is withheld from the model until its cooldown
+    /// ends
+    ToolDisabled(OpenCircuit),
+    /// The request to the model failed and is sent again, the text streamed
+    /// since the last complete text is discarded
+    Retry {
+        cause: String,
+    },
 }
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::ToolName;
+
+/// Number of consecutive failures after which a tool is disabled
+const FAILURE_THRESHOLD: usize = 5;
+
+/// Time a tool stays disabled before it's offered to the model again
+const COOLDOWN: Duration = Duration::from_secs(120);
+
+/// A tool that is disabled because it kept failing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCircuit {
+    pub name: ToolName,
+    /// Number of consecutive failures of the tool
+    pub failures: usize,
+    /// Seconds until the tool is offered to the model again
+    pub retry_in_secs: u64,
+}
+
+impl OpenCircuit {
+    /// Message telling the model that the tool can't be used for now
+    pub fn notice(&self) -> String {
+        format!(
+            "The tool {} failed {} times in a row and is disabled for the next {} seconds. Continue without it or use another tool.",
+            self.name.as_str(),
+            self.failures,
+            self.retry_in_secs
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    failures: usize,
+    opened_at: Option<Instant>,
+}
+
+/// Disables tools that fail repeatedly, e.g. because a service they depend on
+/// is down, so that the agent doesn't spend the session on them. A disabled
+/// tool is offered again after a cooldown, and disabled right away if it
+/// fails once more.
+pub struct CircuitBreaker {
+    threshold: usize,
+    cooldown: Duration,
+    circuits: Mutex<HashMap<ToolName, Circuit>>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(FAILURE_THRESHOLD, COOLDOWN)
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: usize, cooldown: Duration) -> Self {
+        Self { threshold, cooldown, circuits: Default::default() }
+    }
+
+    /// Records the outcome of a call to the tool
+    pub fn record(&self, name: &ToolName, is_error: bool) {
+        self.record_at(name, is_error, Instant::now())
+    }
+
+    fn record_at(&self, name: &ToolName, is_error: bool, now: Instant) {
+        let mut circuits = self.circuits.lock().unwrap();
+        if !is_error {
+            circuits.remove(name);
+            return;
+        }
+
+        let circuit = circuits.entry(name.clone()).or_default();
+        circuit.failures += 1;
+        if circuit.failures >= self.threshold {
+            circuit.opened_at = Some(now);
+        }
+    }
+
+    /// Returns the tool if it's disabled
+    pub fn check(&self, name: &ToolName) -> Option<OpenCircuit> {
+        self.check_at(name, Instant::now())
+    }
+
+    fn check_at(&self, name: &ToolName, now: Instant) -> Option<OpenCircuit> {
+        let circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.get(name)?;
+        let elapsed = now.duration_since(circuit.opened_at?);
+        let remaining = self
+            .cooldown
+            .checked_sub(elapsed)
+            .filter(|remaining| !remaining.is_zero())?;
+        Some(OpenCircuit {
+            name: name.clone(),
+            failures: circuit.failures,
+            retry_in_secs: remaining.as_secs().max(1),
+        })
+    }
+
+    /// Returns the tools that are disabled, sorted by name
+    pub fn open_circuits(&self) -> Vec<OpenCircuit> {
+        let now = Instant::now();
+        let names = self
+            .circuits
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut open = names
+            .iter()
+            .filter_map(|name| self.check_at(name, now))
+            .collect::<Vec<_>>();
+        open.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+        open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let fixture = CircuitBreaker::new(3, Duration::from_secs(60));
+        let name = ToolName::new("forge_tool_net_fetch");
+        let now = Instant::now();
+
+        fixture.record_at(&name, true, now);
+        fixture.record_at(&name, true, now);
+        assert_eq!(fixture.check_at(&name, now), None);
+
+        fixture.record_at(&name, true, now);
+        let actual = fixture.check_at(&name, now + Duration::from_secs(10));
+        let expected = Some(OpenCircuit { name: name.clone(), failures: 3, retry_in_secs: 50 });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let fixture = CircuitBreaker::new(2, Duration::from_secs(60));
+        let name = ToolName::new("forge_tool_net_fetch");
+        let now = Instant::now();
+
+        fixture.record_at(&name, true, now);
+        fixture.record_at(&name, false, now);
+        fixture.record_at(&name, true, now);
+        assert_eq!(fixture.check_at(&name, now), None);
+    }
+
+    #[test]
+    fn test_closes_after_cooldown() {
+        let fixture = CircuitBreaker::new(1, Duration::from_secs(60));
+        let name = ToolName::new("forge_tool_net_fetch");
+        let now = Instant::now();
+
+        fixture.record_at(&name, true, now);
+        let later = now + Duration::from_secs(60);
+        assert_eq!(fixture.check_at(&name, later), None);
+
+        // A failure after the cooldown disables the tool again right away
+        fixture.record_at(&name, true, later);
+        assert!(fixture.check_at(&name, later).is_some());
+    }
+}
@@ -3,8 +3,9 @@ use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
-use super::{ToolCallFull, ToolResult};
+use super::{ResponseMetadata, ToolCallFull, ToolResult};
 use crate::temperature::Temperature;
+use crate::top_p::TopP;
 use crate::{ToolCallRecord, ToolChoice, ToolDefinition};
 
 /// Represents a message being sent to the LLM provider
@@ -24,6 +25,7 @@ impl ContextMessage {
             role: Role::User,
             content: content.to_string(),
             tool_calls: None,
+            metadata: None,
         }
         .into()
     }
@@ -33,6 +35,7 @@ impl ContextMessage {
             role: Role::System,
             content: content.to_string(),
             tool_calls: None,
+            metadata: None,
         }
         .into()
     }
@@ -44,6 +47,7 @@ impl ContextMessage {
             role: Role::Assistant,
             content: content.to_string(),
             tool_calls,
+            metadata: None,
         }
         .into()
     }
@@ -60,6 +64,16 @@ impl ContextMessage {
         }
     }
 
+    /// Attaches the provider metadata to an assistant message
+    pub fn metadata(self, metadata: ResponseMetadata) -> Self {
+        match self {
+            ContextMessage::ContentMessage(message) => {
+                ContextMessage::ContentMessage(message.metadata(metadata))
+            }
+            message => message,
+        }
+    }
+
     pub fn has_tool_call(&self) -> bool {
         match self {
             ContextMessage::ContentMessage(message) => message.tool_calls.is_some(),
@@ -76,6 +90,8 @@ pub struct ContentMessage {
     pub role: Role,
     pub content: String,
     pub tool_calls: Option<Vec<ToolCallFull>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ResponseMetadata>,
 }
 
 impl ContentMessage {
@@ -84,6 +100,7 @@ impl ContentMessage {
             role: Role::Assistant,
             content: content.to_string(),
             tool_calls: None,
+            metadata: None,
         }
     }
 }
@@ -108,6 +125,8 @@ pub struct Context {
     pub max_tokens: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<Temperature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<TopP>,
 }
 
 impl Context {
@@ -164,6 +183,16 @@ impl Context {
         }
     }
 
+    /// Returns the content of the last message in the context, which holds the
+    /// final output once an agent is done processing its events.
+    pub fn last_output(&self) -> Option<String> {
+        self.messages.last().and_then(|message| match message {
+            ContextMessage::ContentMessage(message) => Some(message.content.clone()),
+            ContextMessage::ToolMessage(result) => Some(result.content.clone()),
+            ContextMessage::Image(_) => None,
+        })
+    }
+
     /// Converts the context to textual format
     pub fn to_text(&self) -> String {
         let mut lines = String::new();
@@ -215,6 +244,67 @@ impl Context {
         crate::estimate_token_count(&self.to_text())
     }
 
+    /// Finds a sequence in the context for compaction, starting from the first
+    /// assistant message and including all messages up to the last possible
+    /// message (respecting preservation window)
+    pub fn compaction_sequence(&self, preserve_last_n: usize) -> Option<(usize, usize)> {
+        let messages = &self.messages;
+        if messages.is_empty() {
+            return None;
+        }
+
+        // len will be always > 0
+        let length = messages.len();
+
+        // Find the first assistant message index
+        let start = messages
+            .iter()
+            .enumerate()
+            .find(|(_, message)| message.has_role(Role::Assistant))
+            .map(|(index, _)| index)?;
+
+        // Don't compact if there's no assistant message
+        if start >= length {
+            return None;
+        }
+
+        // Calculate the end index based on preservation window
+        // If we need to preserve all or more messages than we have, there's nothing to
+        // compact
+        if preserve_last_n >= length {
+            return None;
+        }
+
+        // Use saturating subtraction to prevent potential overflow
+        let end = length.saturating_sub(preserve_last_n).saturating_sub(1);
+
+        // Ensure we have at least two messages to create a meaningful summary
+        // If start > end or end is invalid, don't compact
+        if start > end || end >= length || end.saturating_sub(start) < 1 {
+            return None;
+        }
+
+        // Don't break between a tool call and its result
+        if messages.get(end).is_some_and(|msg| msg.has_tool_call()) {
+            // If the last message has a tool call, adjust end to include the tool result
+            // This means either not compacting at all, or reducing the end by 1
+            if end == start {
+                // If start == end and it has a tool call, don't compact
+                return None;
+            } else {
+                // Otherwise reduce end by 1
+                return Some((start, end.saturating_sub(1)));
+            }
+        }
+
+        // Return the sequence only if it has at least one message
+        if end >= start {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
+
     /// Will append a message to the context. If the model supports tools, it
     /// will append the tool calls and results to the message. If the model
     /// does not support tools, it will append the tool calls and results as
@@ -222,19 +312,23 @@ impl Context {
     pub fn append_message(
         mut self,
         content: impl ToString,
+        metadata: ResponseMetadata,
         tool_records: Vec<ToolCallRecord>,
         tool_supported: bool,
     ) -> Self {
         if tool_supported {
-            self.add_message(ContextMessage::assistant(
-                content,
-                Some(
-                    tool_records
-                        .iter()
-                        .map(|record| record.tool_call.clone())
-                        .collect::<Vec<_>>(),
-                ),
-            ))
+            self.add_message(
+                ContextMessage::assistant(
+                    content,
+                    Some(
+                        tool_records
+                            .iter()
+                            .map(|record| record.tool_call.clone())
+                            .collect::<Vec<_>>(),
+                    ),
+                )
+                .metadata(metadata),
+            )
             .add_tool_results(
                 tool_records
                     .iter()
@@ -242,7 +336,7 @@ impl Context {
                     .collect::<Vec<_>>(),
             )
         } else {
-            self = self.add_message(ContextMessage::assistant(content, None));
+            self = self.add_message(ContextMessage::assistant(content, None).metadata(metadata));
             if tool_records.is_empty() {
                 return self;
             }
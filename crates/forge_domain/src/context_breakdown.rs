@@ -0,0 +1,207 @@
+use derive_more::Display;
+
+use crate::{Compact, Context, ContextMessage, Role};
+
+/// The kind of content a message adds to the context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum MessageCategory {
+    #[display("System")]
+    System,
+    #[display("User")]
+    User,
+    #[display("Assistant")]
+    Assistant,
+    #[display("Tool results")]
+    ToolResult,
+    #[display("Attachments")]
+    Attachment,
+}
+
+impl MessageCategory {
+    const ALL: [MessageCategory; 5] = [
+        MessageCategory::System,
+        MessageCategory::User,
+        MessageCategory::Assistant,
+        MessageCategory::ToolResult,
+        MessageCategory::Attachment,
+    ];
+
+    pub fn of(message: &ContextMessage) -> Self {
+        match message {
+            ContextMessage::ContentMessage(message) => match message.role {
+                Role::System => MessageCategory::System,
+                Role::Assistant => MessageCategory::Assistant,
+                // Agents without tool support receive tool results, and text
+                // attachments are always added, as user messages
+                Role::User if message.content.starts_with("<forge_tool_result") => {
+                    MessageCategory::ToolResult
+                }
+                Role::User if message.content.starts_with("---\npath:") => {
+                    MessageCategory::Attachment
+                }
+                Role::User => MessageCategory::User,
+            },
+            ContextMessage::ToolMessage(_) => MessageCategory::ToolResult,
+            ContextMessage::Image(_) => MessageCategory::Attachment,
+        }
+    }
+}
+
+/// Messages and estimated tokens of one category of messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryUsage {
+    pub category: MessageCategory,
+    pub messages: usize,
+    pub tokens: u64,
+}
+
+/// The messages that the next compaction would replace with a summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionPreview {
+    /// Index of the first and last message of the compacted sequence
+    pub sequence: (usize, usize),
+    pub messages: usize,
+    pub tokens: u64,
+}
+
+/// Breakdown of what fills the context window of an agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextBreakdown {
+    /// Usage per category, in a fixed order, including empty categories
+    pub categories: Vec<CategoryUsage>,
+    pub messages: usize,
+    pub tokens: u64,
+    /// What the next compaction would drop, `None` when nothing can be
+    /// compacted
+    pub compaction: Option<CompactionPreview>,
+}
+
+impl ContextBreakdown {
+    pub fn new(context: &Context, compact: Option<&Compact>) -> Self {
+        let tokens = context
+            .messages
+            .iter()
+            .map(|message| {
+                (
+                    MessageCategory::of(message),
+                    Context::default()
+                        .add_message(message.clone())
+                        .estimate_token_count(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let categories = MessageCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let (messages, tokens) = tokens
+                    .iter()
+                    .filter(|(message_category, _)| *message_category == category)
+                    .fold((0, 0), |(messages, total), (_, tokens)| {
+                        (messages + 1, total + tokens)
+                    });
+                CategoryUsage { category, messages, tokens }
+            })
+            .collect();
+
+        let compaction = compact
+            .and_then(|compact| context.compaction_sequence(compact.retention_window))
+            .map(|(start, end)| CompactionPreview {
+                sequence: (start, end),
+                messages: end - start + 1,
+                tokens: tokens[start..=end].iter().map(|(_, tokens)| tokens).sum(),
+            });
+
+        Self {
+            categories,
+            messages: context.messages.len(),
+            tokens: tokens.iter().map(|(_, tokens)| tokens).sum(),
+            compaction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ToolCallId, ToolName, ToolResult};
+
+    fn fixture() -> Context {
+        Context::default()
+            .add_message(ContextMessage::system("You are a helpful assistant"))
+            .add_message(ContextMessage::user("Fix the bug"))
+            .add_message(ContextMessage::user(
+                "---\npath: /project/lib.rs\n---\nfn main() {}",
+            ))
+            .add_message(ContextMessage::assistant("Reading the file", None))
+            .add_message(ContextMessage::tool_result(
+                ToolResult::new(ToolName::new("forge_tool_fs_read"))
+                    .call_id(ToolCallId::new("1"))
+                    .success("fn main() {}"),
+            ))
+            .add_message(ContextMessage::assistant("Found it", None))
+            .add_message(ContextMessage::user("Thanks"))
+    }
+
+    #[test]
+    fn test_categories() {
+        let actual = fixture()
+            .messages
+            .iter()
+            .map(MessageCategory::of)
+            .collect::<Vec<_>>();
+        let expected = vec![
+            MessageCategory::System,
+            MessageCategory::User,
+            MessageCategory::Attachment,
+            MessageCategory::Assistant,
+            MessageCategory::ToolResult,
+            MessageCategory::Assistant,
+            MessageCategory::User,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let context = fixture();
+        let compact = Compact::new(crate::ModelId::new("small")).retention_window(1usize);
+
+        let actual = ContextBreakdown::new(&context, Some(&compact));
+
+        let messages = actual
+            .categories
+            .iter()
+            .map(|usage| (usage.category, usage.messages))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (MessageCategory::System, 1),
+            (MessageCategory::User, 2),
+            (MessageCategory::Assistant, 2),
+            (MessageCategory::ToolResult, 1),
+            (MessageCategory::Attachment, 1),
+        ];
+        assert_eq!(messages, expected);
+        assert_eq!(actual.messages, 7);
+        assert_eq!(
+            actual.tokens,
+            actual
+                .categories
+                .iter()
+                .map(|usage| usage.tokens)
+                .sum::<u64>()
+        );
+        assert_eq!(
+            actual.compaction.map(|preview| preview.sequence),
+            Some((3, 5))
+        );
+    }
+
+    #[test]
+    fn test_breakdown_without_compaction() {
+        let actual = ContextBreakdown::new(&fixture(), None);
+        assert_eq!(actual.compaction, None);
+    }
+}
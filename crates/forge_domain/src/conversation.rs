@@ -1,4 +1,5 @@
 use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 
 use derive_more::derive::Display;
 use derive_setters::Setters;
@@ -7,7 +8,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
-use crate::{Agent, AgentId, Context, Error, Event, ModelId, Result, Workflow};
+use crate::{
+    Agent, AgentId, Context, ContextMessage, Error, Event, ModeConfig, ModelId, Plan, Result,
+    ToolName, Workflow,
+};
 
 #[derive(Debug, Display, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(transparent)]
@@ -32,11 +36,68 @@ impl ConversationId {
 #[derive(Debug, Setters, Serialize, Deserialize, Clone)]
 pub struct Conversation {
     pub id: ConversationId,
+    /// Incremented every time the conversation is stored, so that concurrent
+    /// changes to the same version can be detected
+    #[serde(default)]
+    pub version: u64,
     pub archived: bool,
     pub state: HashMap<AgentId, AgentState>,
     pub variables: HashMap<String, Value>,
     pub agents: Vec<Agent>,
     pub events: Vec<Event>,
+    /// The plan produced in Plan mode, along with the progress made on it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+    /// Tools that the agents are restricted to by the mode of the
+    /// conversation, all of their tools are available when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode_tools: Option<Vec<ToolName>>,
+    /// Tools that the user disabled for the rest of the conversation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_tools: Vec<ToolName>,
+    /// Whether the workflow of the conversation was restricted because the
+    /// workspace isn't trusted, the workflows run by its agents are restricted
+    /// too
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub untrusted: bool,
+    /// Path of the workflow of the conversation, which its agents can't run
+    /// as a nested workflow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_path: Option<PathBuf>,
+}
+
+/// The agents that a reload of the workflow added, removed or modified
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentChanges {
+    pub added: Vec<AgentId>,
+    pub removed: Vec<AgentId>,
+    pub modified: Vec<AgentId>,
+}
+
+impl AgentChanges {
+    fn between(before: &[Agent], after: &[Agent]) -> Self {
+        let value = |agent: &Agent| serde_json::to_value(agent).ok();
+        let mut changes = Self::default();
+        for agent in after {
+            match before.iter().find(|previous| previous.id == agent.id) {
+                None => changes.added.push(agent.id.clone()),
+                Some(previous) if value(previous) != value(agent) => {
+                    changes.modified.push(agent.id.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        changes.removed = before
+            .iter()
+            .filter(|agent| !after.iter().any(|other| other.id == agent.id))
+            .map(|agent| agent.id.clone())
+            .collect();
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -45,11 +106,28 @@ pub struct AgentState {
     pub context: Option<Context>,
     /// holds the events that are waiting to be processed
     pub queue: VecDeque<Event>,
+    /// Messages appended from outside of the conversation that are added to
+    /// the context before the next request of the agent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inbox: Vec<ContextMessage>,
 }
 
 impl Conversation {
     pub const MAIN_AGENT_NAME: &str = "software-engineer";
 
+    /// Name of the event that records the messages appended from outside of
+    /// the conversation
+    pub const MESSAGE_APPENDED_EVENT: &str = "message_appended";
+
+    /// Directory where the agents of the conversation can keep intermediate
+    /// artifacts. It is available to shell commands as `$FORGE_TMP` and is
+    /// removed along with the conversation.
+    pub fn tmp_dir(&self) -> PathBuf {
+        std::env::temp_dir()
+            .join("forge")
+            .join(self.id.into_string())
+    }
+
     /// Returns the model of the main agent
     ///
     /// # Errors
@@ -112,6 +190,46 @@ impl Conversation {
                 agent.tool_supported = Some(tool_supported);
             }
 
+            if let Some(resource_limits) = workflow.resource_limits.clone() {
+                agent.resource_limits = Some(resource_limits);
+            }
+
+            if let Some(egress) = workflow.egress.clone() {
+                agent.egress = Some(egress);
+            }
+
+            if let Some(shell_policy) = workflow.shell_policy.clone() {
+                agent.shell_policy = Some(shell_policy);
+            }
+
+            if let Some(approval) = workflow.approval.clone() {
+                agent.approval = Some(approval);
+            }
+
+            if let Some(read_only) = workflow.read_only {
+                agent.read_only = Some(read_only);
+            }
+
+            if let Some(review_edits) = workflow.review_edits {
+                agent.review_edits = Some(review_edits);
+            }
+
+            if let Some(language_servers) = workflow.language_servers.clone() {
+                agent.language_servers = Some(language_servers);
+            }
+
+            // Tools declared by the agent take precedence over the workflow's ones
+            // with the same name
+            if let Some(custom_tools) = workflow.custom_tools.clone() {
+                let tools = agent.custom_tools.get_or_insert_default();
+                let declared = tools.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+                tools.extend(
+                    custom_tools
+                        .into_iter()
+                        .filter(|tool| !declared.contains(&tool.name)),
+                );
+            }
+
             // Subscribe the main agent to all commands
             if agent.id.as_str() == Conversation::MAIN_AGENT_NAME {
                 let commands = workflow
@@ -131,21 +249,129 @@ impl Conversation {
 
         Self {
             id,
+            version: 0,
             archived: false,
             state: Default::default(),
             variables: workflow.variables.clone(),
             agents,
             events: Default::default(),
+            plan: None,
+            mode_tools: None,
+            disabled_tools: Vec::new(),
+            untrusted: workflow.untrusted,
+            workflow_path: workflow.path.clone(),
+        }
+    }
+
+    /// Replaces the agents with the ones of the workflow, e.g. after the
+    /// workflow file was edited, keeping the messages, events and plan of
+    /// the conversation. The variables of the workflow override the ones set
+    /// so far.
+    pub fn reload(&mut self, workflow: Workflow) -> AgentChanges {
+        let reloaded = Self::new(self.id.clone(), workflow);
+        let changes = AgentChanges::between(&self.agents, &reloaded.agents);
+
+        self.state
+            .retain(|id, _| reloaded.agents.iter().any(|agent| &agent.id == id));
+        self.variables.extend(reloaded.variables);
+        self.agents = reloaded.agents;
+        self.untrusted = reloaded.untrusted;
+        self.workflow_path = reloaded.workflow_path;
+        changes
+    }
+
+    /// Moves the state of the agents, the events and the plan of this
+    /// conversation onto the stored one, which the client changed in the
+    /// meantime, e.g. its mode, tools or workflow, keeping those changes
+    pub fn rebase(&mut self, stored: Conversation) {
+        let Conversation { state, events, plan, .. } = std::mem::replace(self, stored);
+        self.state = state;
+        self.events = events;
+        self.plan = plan;
+    }
+
+    /// A new conversation in which the agent is woken by the event, with the
+    /// variables, mode and disabled tools of this one but none of its messages.
+    /// The other agents subscribe to nothing, so that they only run when the
+    /// agent spawns them in turn.
+    pub fn spawn(&self, agent_id: &AgentId, event: &str) -> Result<Self> {
+        self.get_agent(agent_id)?;
+        let agents = self
+            .agents
+            .iter()
+            .cloned()
+            .map(|mut agent| {
+                agent.subscribe = (agent.id == *agent_id).then(|| vec![event.to_string()]);
+                agent
+            })
+            .collect();
+        Ok(Self {
+            id: ConversationId::generate(),
+            version: 0,
+            archived: false,
+            state: Default::default(),
+            variables: self.variables.clone(),
+            agents,
+            events: Default::default(),
+            plan: None,
+            mode_tools: self.mode_tools.clone(),
+            disabled_tools: self.disabled_tools.clone(),
+            untrusted: self.untrusted,
+            workflow_path: self.workflow_path.clone(),
+        })
+    }
+
+    /// Switches the conversation to the mode: it's available to the templates
+    /// as the `mode` variable and restricts the tools of the agents
+    pub fn set_mode(&mut self, mode: &ModeConfig) {
+        self.set_variable("mode".to_string(), Value::from(mode.name.to_string()));
+        self.mode_tools = mode.tools.clone();
+    }
+
+    /// Removes the tools from the ones offered to the agents until they are
+    /// enabled again
+    pub fn disable_tools(&mut self, tools: &[ToolName]) {
+        for tool in tools {
+            if !self.disabled_tools.contains(tool) {
+                self.disabled_tools.push(tool.clone());
+            }
+        }
+    }
+
+    /// Offers the tools that were disabled to the agents again
+    pub fn enable_tools(&mut self, tools: &[ToolName]) {
+        self.disabled_tools.retain(|tool| !tools.contains(tool));
+    }
+
+    /// Returns the tools that the agents of the conversation may be offered:
+    /// the ones they list, including the custom ones
+    pub fn tool_names(&self) -> Vec<ToolName> {
+        let mut names = Vec::new();
+        let tools = self.agents.iter().flat_map(|agent| {
+            let custom = agent.custom_tools.iter().flatten().map(|tool| &tool.name);
+            agent.tools.iter().flatten().chain(custom)
+        });
+        for tool in tools {
+            if !names.contains(tool) {
+                names.push(tool.clone());
+            }
         }
+        names
     }
 
     pub fn turn_count(&self, id: &AgentId) -> Option<u64> {
         self.state.get(id).map(|s| s.turn_count)
     }
 
-    /// Returns all the agents that are subscribed to the given event.
+    /// Returns all the agents that are subscribed to the given event. Every
+    /// agent is also subscribed to the events prefixed with its id, e.g.
+    /// `software-designer/user_task_init`, so that it can be addressed
+    /// directly. When several subscriptions match, only the agents with the
+    /// most specific one receive the event, see
+    /// [`Agent::subscription_priority`].
     pub fn subscriptions(&self, event_name: &str) -> Vec<Agent> {
-        self.agents
+        let subscribers = self
+            .agents
             .iter()
             .filter(|a| {
                 // Filter out disabled agents
@@ -154,12 +380,14 @@ impl Conversation {
             .filter(|a| {
                 self.turn_count(&a.id).unwrap_or_default() < a.max_turns.unwrap_or(u64::MAX)
             })
-            .filter(|a| {
-                a.subscribe
-                    .as_ref()
-                    .is_some_and(|subs| subs.contains(&event_name.to_string()))
-            })
-            .cloned()
+            .filter_map(|a| Some((a.subscription_priority(event_name)?, a)))
+            .collect::<Vec<_>>();
+        let priority = subscribers.iter().map(|(priority, _)| *priority).max();
+
+        subscribers
+            .into_iter()
+            .filter(|(p, _)| Some(*p) == priority)
+            .map(|(_, agent)| agent.clone())
             .collect::<Vec<_>>()
     }
 
@@ -204,18 +432,36 @@ impl Conversation {
         self.variables.remove(key).is_some()
     }
 
-    /// Generates an HTML representation of the conversation
-    ///
-    /// This method uses Handlebars to render the conversation as HTML
-    /// from the template file, including all agents, events, and variables.
+    /// Appends a message from an external source, e.g. a CI result or a note
+    /// from a teammate, to the inbox of every agent that has started, so that
+    /// it is added to its context before its next request, even while it is
+    /// running. The message is recorded in the events of the conversation.
     ///
     /// # Errors
-    /// - If the template file cannot be found or read
-    /// - If the Handlebars template registration fails
-    /// - If the template rendering fails
-    pub fn to_html(&self) -> String {
-        // Instead of using Handlebars, we now use our Element DSL
-        crate::conversation_html::render_conversation_html(self)
+    /// - `ConversationNotStarted` if no agent has a context yet
+    pub fn append_message(&mut self, message: ContextMessage) -> Result<()> {
+        let states = self
+            .state
+            .values_mut()
+            .filter(|state| state.context.is_some())
+            .collect::<Vec<_>>();
+        if states.is_empty() {
+            return Err(Error::ConversationNotStarted(self.id.clone()));
+        }
+
+        let value = serde_json::to_value(&message).map_err(Error::MessageEncode)?;
+        for state in states {
+            state.inbox.push(message.clone());
+        }
+        self.events
+            .push(Event::new(Self::MESSAGE_APPENDED_EVENT, value));
+
+        Ok(())
+    }
+
+    /// Generates a markdown transcript of the turns of the main agent
+    pub fn to_markdown(&self) -> String {
+        crate::conversation_markdown::render_conversation_markdown(self)
     }
 
     /// Add an event to the queue of subscribed agents
@@ -534,6 +780,16 @@ mod tests {
         assert_eq!(subscriptions.len(), 3);
     }
 
+    #[test]
+    fn test_tmp_dir_is_scoped_to_conversation() {
+        let first = super::Conversation::new(super::ConversationId::generate(), Workflow::new());
+        let second = super::Conversation::new(super::ConversationId::generate(), Workflow::new());
+
+        assert!(first.tmp_dir().starts_with(std::env::temp_dir()));
+        assert!(first.tmp_dir().ends_with(first.id.into_string()));
+        assert_ne!(first.tmp_dir(), second.tmp_dir());
+    }
+
     #[test]
     fn test_main_model_success() {
         // Arrange
@@ -684,4 +940,219 @@ mod tests {
             .unwrap();
         assert_eq!(agent2.tool_supported, Some(true));
     }
+
+    #[test]
+    fn test_append_message() {
+        let workflow = Workflow::new().agents(vec![Agent::new("agent1"), Agent::new("agent2")]);
+        let mut conversation =
+            super::Conversation::new_inner(super::ConversationId::generate(), workflow);
+        conversation
+            .state
+            .entry(crate::AgentId::new("agent1"))
+            .or_default()
+            .context = Some(crate::Context::default());
+
+        let message = crate::ContextMessage::user("The CI build failed on main");
+        conversation.append_message(message.clone()).unwrap();
+
+        let actual = conversation.state[&crate::AgentId::new("agent1")]
+            .inbox
+            .clone();
+        assert_eq!(actual, vec![message]);
+        assert!(!conversation
+            .state
+            .contains_key(&crate::AgentId::new("agent2")));
+        let event = conversation.events.last().unwrap();
+        assert_eq!(event.name, super::Conversation::MESSAGE_APPENDED_EVENT);
+    }
+
+    #[test]
+    fn test_append_message_not_started() {
+        let mut conversation = super::Conversation::new_inner(
+            super::ConversationId::generate(),
+            Workflow::new().agents(vec![Agent::new("agent1")]),
+        );
+
+        let actual = conversation.append_message(crate::ContextMessage::user("note"));
+
+        assert!(matches!(actual, Err(Error::ConversationNotStarted(_))));
+        assert!(conversation.events.is_empty());
+    }
+
+    #[test]
+    fn test_subscriptions_by_agent_id() {
+        let mut engineer = Agent::new("software-engineer");
+        engineer.subscribe = Some(vec!["act/user_task_init".to_string()]);
+        let designer = Agent::new("software-designer");
+        let workflow = Workflow::new().agents(vec![engineer, designer]);
+        let conversation =
+            super::Conversation::new_inner(super::ConversationId::generate(), workflow);
+
+        let ids = |event: &str| {
+            conversation
+                .subscriptions(event)
+                .into_iter()
+                .map(|agent| agent.id.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("act/user_task_init"), vec!["software-engineer"]);
+        assert_eq!(
+            ids("software-designer/user_task_init"),
+            vec!["software-designer"]
+        );
+        assert!(ids("software/user_task_init").is_empty());
+    }
+
+    #[test]
+    fn test_set_mode() {
+        let mut conversation =
+            super::Conversation::new_inner(super::ConversationId::generate(), Workflow::new());
+        let review = crate::ModeConfig::new("review".parse().unwrap())
+            .tools(vec![crate::ToolName::new("forge_tool_fs_read")]);
+
+        conversation.set_mode(&review);
+        assert_eq!(conversation.get_variable("mode"), Some(&json!("REVIEW")));
+        assert_eq!(
+            conversation.mode_tools,
+            Some(vec![crate::ToolName::new("forge_tool_fs_read")])
+        );
+
+        conversation.set_mode(&crate::ModeConfig::new(crate::Mode::act()));
+        assert_eq!(conversation.get_variable("mode"), Some(&json!("ACT")));
+        assert_eq!(conversation.mode_tools, None);
+    }
+
+    #[test]
+    fn test_disable_tools() {
+        let shell = crate::ToolName::new("forge_tool_process_shell");
+        let read = crate::ToolName::new("forge_tool_fs_read");
+        let mut conversation =
+            super::Conversation::new_inner(super::ConversationId::generate(), Workflow::new());
+
+        conversation.disable_tools(&[shell.clone(), read.clone()]);
+        conversation.disable_tools(std::slice::from_ref(&shell));
+        assert_eq!(
+            conversation.disabled_tools,
+            vec![shell.clone(), read.clone()]
+        );
+
+        conversation.enable_tools(std::slice::from_ref(&shell));
+        assert_eq!(conversation.disabled_tools, vec![read]);
+    }
+    #[test]
+    fn test_reload() {
+        let workflow = Workflow::new()
+            .agents(vec![Agent::new("planner"), Agent::new("coder")])
+            .variables(HashMap::from([("style".to_string(), json!("terse"))]));
+        let mut conversation =
+            super::Conversation::new(super::ConversationId::generate(), workflow);
+        conversation.set_variable("mode".to_string(), json!("ACT"));
+        conversation
+            .state
+            .insert(crate::AgentId::new("planner"), Default::default());
+        conversation
+            .state
+            .insert(crate::AgentId::new("coder"), Default::default());
+
+        let workflow = Workflow::new()
+            .agents(vec![
+                Agent::new("coder").max_walker_depth(3_usize),
+                Agent::new("reviewer"),
+            ])
+            .variables(HashMap::from([("style".to_string(), json!("verbose"))]));
+        let actual = conversation.reload(workflow);
+
+        let expected = super::AgentChanges {
+            added: vec![crate::AgentId::new("reviewer")],
+            removed: vec![crate::AgentId::new("planner")],
+            modified: vec![crate::AgentId::new("coder")],
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(conversation.get_variable("style"), Some(&json!("verbose")));
+        assert_eq!(conversation.get_variable("mode"), Some(&json!("ACT")));
+        assert_eq!(
+            conversation.state.keys().collect::<Vec<_>>(),
+            vec![&crate::AgentId::new("coder")]
+        );
+    }
+    #[test]
+    fn test_rebase() {
+        let workflow = Workflow::new().agents(vec![Agent::new("main")]);
+        let mut stored =
+            super::Conversation::new_inner(super::ConversationId::generate(), workflow);
+        let mut fixture = stored.clone();
+        fixture
+            .state
+            .insert(crate::AgentId::new("main"), Default::default());
+        fixture
+            .events
+            .push(crate::Event::new("user_task_init", "hi"));
+        stored.version = 3;
+        stored.disable_tools(&[crate::ToolName::new("forge_tool_fs_remove")]);
+
+        fixture.rebase(stored.clone());
+
+        assert_eq!(fixture.version, 3);
+        assert_eq!(fixture.disabled_tools, stored.disabled_tools);
+        assert_eq!(fixture.state.len(), 1);
+        assert_eq!(fixture.events.len(), 1);
+    }
+
+    #[test]
+    fn test_spawn() {
+        let workflow = Workflow::new()
+            .agents(vec![
+                Agent::new("planner").subscribe(vec!["user_task_init".to_string()]),
+                Agent::new("researcher"),
+            ])
+            .variables(HashMap::from([("style".to_string(), json!("terse"))]));
+        let mut conversation =
+            super::Conversation::new_inner(super::ConversationId::generate(), workflow);
+        conversation
+            .state
+            .insert(crate::AgentId::new("planner"), Default::default());
+
+        let actual = conversation
+            .spawn(&crate::AgentId::new("researcher"), "spawn/task")
+            .unwrap();
+
+        assert_ne!(actual.id, conversation.id);
+        assert!(actual.state.is_empty());
+        assert_eq!(actual.variables, conversation.variables);
+        let ids = actual
+            .subscriptions("spawn/task")
+            .iter()
+            .map(|agent| agent.id.as_str().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["researcher"]);
+        assert_eq!(actual.agents.len(), 2);
+        assert!(actual.subscriptions("user_task_init").is_empty());
+
+        let actual = conversation.spawn(&crate::AgentId::new("tester"), "spawn/task");
+        assert!(matches!(actual, Err(Error::AgentUndefined(_))));
+    }
+
+    #[test]
+    fn test_subscriptions_with_patterns() {
+        let workflow = Workflow::new().agents(vec![
+            Agent::new("catch-all").subscribe(vec!["*".to_string()]),
+            Agent::new("ci-runner").subscribe(vec!["ci/*".to_string()]),
+            Agent::new("linter").subscribe(vec!["ci/lint".to_string()]),
+        ]);
+        let conversation =
+            super::Conversation::new_inner(super::ConversationId::generate(), workflow);
+        let ids = |event: &str| {
+            conversation
+                .subscriptions(event)
+                .iter()
+                .map(|agent| agent.id.as_str().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("ci/lint"), vec!["linter"]);
+        assert_eq!(ids("ci/build"), vec!["ci-runner"]);
+        assert_eq!(ids("deploy"), vec!["catch-all"]);
+        assert_eq!(ids("linter/user_task_init"), vec!["linter"]);
+    }
 }
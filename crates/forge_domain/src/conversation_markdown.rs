@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::context::ContextMessage;
+use crate::conversation::Conversation;
+use crate::{AgentId, Role, ToolCallFull};
+
+/// Renders the turns of the main agent as a markdown transcript, with the
+/// tool calls collapsed and the diffs of the patches fenced, so that it can be
+/// pasted in a pull request or an issue
+pub fn render_conversation_markdown(conversation: &Conversation) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Conversation {}", conversation.id);
+
+    let main_agent_id = AgentId::new(Conversation::MAIN_AGENT_NAME);
+    let Some(context) = conversation.context(&main_agent_id) else {
+        return out;
+    };
+
+    let mut calls: HashMap<&str, &ToolCallFull> = HashMap::new();
+    for message in &context.messages {
+        match message {
+            ContextMessage::ContentMessage(message) => {
+                let title = match message.role {
+                    Role::System => continue,
+                    Role::User => "User",
+                    Role::Assistant => "Assistant",
+                };
+                let _ = writeln!(out, "\n## {title}\n");
+                if !message.content.trim().is_empty() {
+                    let _ = writeln!(out, "{}", message.content.trim());
+                }
+                for call in message.tool_calls.iter().flatten() {
+                    if let Some(call_id) = &call.call_id {
+                        calls.insert(call_id.as_str(), call);
+                    }
+                }
+            }
+            ContextMessage::ToolMessage(result) => {
+                let call = result
+                    .call_id
+                    .as_ref()
+                    .and_then(|id| calls.get(id.as_str()));
+                let path = call
+                    .and_then(|call| call.arguments.get("path"))
+                    .and_then(|path| path.as_str());
+                let status = if result.is_error { " (failed)" } else { "" };
+                let summary = match path {
+                    Some(path) => format!("{} <code>{path}</code>{status}", result.name.as_str()),
+                    None => format!("{}{status}", result.name.as_str()),
+                };
+
+                let _ = writeln!(out, "\n<details>\n<summary>{summary}</summary>\n");
+                if let Some(call) = call {
+                    let arguments = serde_json::to_string_pretty(&call.arguments)
+                        .unwrap_or_else(|_| call.arguments.to_string());
+                    let _ = writeln!(out, "{}\n", fence("json", &arguments));
+                }
+                let content = strip_front_matter(&result.content);
+                match to_diff(content) {
+                    Some(diff) => {
+                        let _ = writeln!(out, "{}", fence("diff", &diff));
+                    }
+                    None => {
+                        let _ = writeln!(out, "{}", fence("", content.trim()));
+                    }
+                }
+                let _ = writeln!(out, "\n</details>");
+            }
+            ContextMessage::Image(url) => {
+                let _ = writeln!(out, "\n![image]({url})");
+            }
+        }
+    }
+    out
+}
+
+/// Wraps the content in a code block, with a fence longer than any run of
+/// backticks in it
+fn fence(lang: &str, content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{lang}\n{content}\n{fence}")
+}
+
+/// Removes the metadata that the file tools write before their output
+fn strip_front_matter(content: &str) -> &str {
+    content
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+        .map(|(_, body)| body)
+        .unwrap_or(content)
+}
+
+/// Converts the diff shown by the patch tool, whose lines start with the old
+/// and new line numbers, to the unified format understood by markdown renderers
+fn to_diff(content: &str) -> Option<String> {
+    let lines = content
+        .trim_end()
+        .lines()
+        .map(|line| {
+            if line == "..." {
+                return Some("@@".to_string());
+            }
+            let (numbers, change) = line.split_once(" |")?;
+            let is_line_numbers = numbers.chars().all(|c| c.is_ascii_digit() || c == ' ');
+            let sign = change.chars().next()?;
+            (is_line_numbers && matches!(sign, ' ' | '+' | '-')).then(|| change.to_string())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{AgentState, Context, ConversationId, ToolCallId, ToolName, ToolResult, Workflow};
+
+    #[test]
+    fn test_render_conversation_markdown() {
+        let call = ToolCallFull::new(ToolName::new("forge_tool_fs_patch"))
+            .call_id(ToolCallId::new("1"))
+            .arguments(json!({"path": "/project/src/main.rs", "search": "hello"}));
+        let result = ToolResult::new(ToolName::new("forge_tool_fs_patch"))
+            .call_id(ToolCallId::new("1"))
+            .success(
+                "---\npath: /project/src/main.rs\ntotal_chars: 40\n---\n1       |-    println!(\"hello\");\n    1   |+    println!(\"hello, world\");\n",
+            );
+        let context = Context::default()
+            .add_message(ContextMessage::system("You are a software engineer"))
+            .add_message(ContextMessage::user("Greet the world"))
+            .add_message(ContextMessage::assistant(
+                "Updating the greeting",
+                Some(vec![call]),
+            ))
+            .add_message(ContextMessage::tool_result(result))
+            .add_message(ContextMessage::assistant(
+                "Done, it prints ```hello, world```",
+                None,
+            ));
+
+        let mut fixture = Conversation::new(ConversationId::generate(), Workflow::new());
+        fixture.id = ConversationId::parse("5af97419-0277-410a-8ca6-0e2a252152c5").unwrap();
+        fixture.state.insert(
+            AgentId::new(Conversation::MAIN_AGENT_NAME),
+            AgentState { context: Some(context), ..Default::default() },
+        );
+
+        assert_snapshot!(render_conversation_markdown(&fixture));
+    }
+
+    #[test]
+    fn test_fence() {
+        assert_eq!(fence("", "a ```b``` c"), "````\na ```b``` c\n````");
+        assert_eq!(fence("json", "{}"), "```json\n{}\n```");
+    }
+}
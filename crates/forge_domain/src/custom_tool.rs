@@ -0,0 +1,133 @@
+use regex::{Captures, Regex};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ToolDefinition, ToolName};
+
+/// A tool declared in the workflow that runs a shell command. The command is a
+/// template whose `{{argument}}` placeholders are replaced by the arguments of
+/// the call, quoted for the shell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CustomTool {
+    pub name: ToolName,
+
+    /// Tells the model what the tool does and when to use it
+    pub description: String,
+
+    /// JSON schema of the arguments of the tool. If not specified, the tool
+    /// takes no arguments.
+    #[serde(default = "empty_schema")]
+    #[schemars(with = "serde_json::Value")]
+    pub input_schema: RootSchema,
+
+    /// The shell command executed when the tool is called
+    pub command: String,
+}
+
+fn empty_schema() -> RootSchema {
+    schemars::schema_for!(())
+}
+
+impl CustomTool {
+    pub fn new(name: impl ToString, command: impl ToString) -> Self {
+        Self {
+            name: ToolName::new(name),
+            description: String::new(),
+            input_schema: empty_schema(),
+            command: command.to_string(),
+        }
+    }
+
+    pub fn tool_definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            input_schema: self.input_schema.clone(),
+            output_schema: None,
+            mutating: true,
+        }
+    }
+
+    /// Renders the command with the arguments of the call. Each placeholder is
+    /// replaced by the single-quoted value of its argument so that the values
+    /// can't inject shell syntax. Missing arguments are replaced by an empty
+    /// string.
+    pub fn render(&self, arguments: &Value) -> String {
+        let placeholder = Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap();
+        placeholder
+            .replace_all(&self.command, |captures: &Captures| {
+                let value = match arguments.get(&captures[1]) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                };
+                quote(&value)
+            })
+            .into_owned()
+    }
+}
+
+/// Quotes the value for a POSIX shell
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let fixture = CustomTool::new("deploy", "./deploy.sh {{ env }} --tag {{tag}} {{dry_run}}");
+
+        let actual = fixture.render(&json!({"env": "staging", "tag": "v1.2", "dry_run": true}));
+        let expected = "./deploy.sh 'staging' --tag 'v1.2' 'true'";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_quotes_shell_syntax() {
+        let fixture = CustomTool::new("grep_logs", "grep {{pattern}} app.log");
+
+        let actual = fixture.render(&json!({"pattern": "it's; rm -rf /"}));
+        let expected = r"grep 'it'\''s; rm -rf /' app.log";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_missing_argument() {
+        let fixture = CustomTool::new("test", "cargo test {{filter}}");
+        let actual = fixture.render(&json!({}));
+        assert_eq!(actual, "cargo test ''");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let fixture = r#"
+name: run_migrations
+description: Applies the pending database migrations
+input_schema:
+  type: object
+  properties:
+    target:
+      type: string
+      description: The migration to migrate to
+command: diesel migration run --target {{target}}
+"#;
+
+        let actual: CustomTool = serde_yml::from_str(fixture).unwrap();
+
+        assert_eq!(actual.name, ToolName::new("run_migrations"));
+        assert_eq!(
+            actual.command,
+            "diesel migration run --target {{target}}".to_string()
+        );
+        let properties = &actual.input_schema.schema.object.unwrap().properties;
+        assert!(properties.contains_key("target"));
+    }
+}
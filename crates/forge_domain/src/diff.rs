@@ -0,0 +1,61 @@
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How the changes to a file are shown to the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStyle {
+    /// Removed and added lines one after the other
+    #[default]
+    Unified,
+    /// Old and new lines next to each other, for wide terminals
+    SideBySide,
+}
+
+/// Configuration of the diffs shown when reviewing and undoing edits
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Merge, JsonSchema)]
+pub struct DiffConfig {
+    #[serde(default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub style: DiffStyle,
+
+    /// Columns of a side-by-side diff, the width of the terminal by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub width: Option<usize>,
+
+    /// Unchanged lines shown around the changes, 3 by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub context: Option<usize>,
+}
+
+impl DiffConfig {
+    pub fn context(&self) -> usize {
+        self.context.unwrap_or(3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_diff_config() {
+        let actual: DiffConfig =
+            serde_yml::from_str("style: side_by_side\nwidth: 160\ncontext: 5").unwrap();
+        let expected = DiffConfig {
+            style: DiffStyle::SideBySide,
+            width: Some(160),
+            context: Some(5),
+        };
+        assert_eq!(actual, expected);
+
+        let actual: DiffConfig = serde_yml::from_str("{}").unwrap();
+        assert_eq!(actual.style, DiffStyle::Unified);
+        assert_eq!(actual.context(), 3);
+    }
+}
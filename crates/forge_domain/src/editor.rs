@@ -0,0 +1,37 @@
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Key bindings of the prompt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EditMode {
+    #[default]
+    Emacs,
+    /// Modal editing, starting in insert mode
+    Vim,
+}
+
+/// Configuration of the editor used to type prompts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Merge, JsonSchema)]
+pub struct EditorConfig {
+    #[serde(default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub mode: EditMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_editor_config() {
+        let actual: EditorConfig = serde_yml::from_str("mode: vim").unwrap();
+        assert_eq!(actual.mode, EditMode::Vim);
+
+        let actual: EditorConfig = serde_yml::from_str("{}").unwrap();
+        assert_eq!(actual.mode, EditMode::Emacs);
+    }
+}
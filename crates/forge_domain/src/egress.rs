@@ -0,0 +1,181 @@
+use std::net::IpAddr;
+
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::{Host, Url};
+
+use crate::{Error, Result};
+
+/// Hosts that serve cloud instance metadata. Requests to these hosts are
+/// blocked unless they are explicitly listed in the allow list.
+const METADATA_HOSTS: [&str; 3] = [
+    "169.254.169.254",
+    "metadata.google.internal",
+    "fd00:ec2::254",
+];
+
+/// Controls which hosts tools are allowed to reach over the network.
+///
+/// Patterns match a host exactly (`example.com`), any of its subdomains
+/// (`*.example.com`) or every host (`*`). Deny patterns take precedence over
+/// allow patterns, and when the allow list is empty every host that isn't
+/// denied is allowed.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Merge, Setters, JsonSchema,
+)]
+#[setters(into)]
+pub struct EgressPolicy {
+    /// Host patterns that tools are allowed to access
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[merge(strategy = crate::merge::vec::append)]
+    pub allow: Vec<String>,
+
+    /// Host patterns that tools are never allowed to access
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[merge(strategy = crate::merge::vec::append)]
+    pub deny: Vec<String>,
+}
+
+impl EgressPolicy {
+    /// Checks whether the host of the url may be accessed
+    pub fn check_url(&self, url: &Url) -> Result<()> {
+        match url.host() {
+            Some(Host::Ipv6(ip)) => self.check_ip(&ip.to_string(), IpAddr::V6(ip)),
+            Some(Host::Ipv4(ip)) => self.check_ip(&ip.to_string(), IpAddr::V4(ip)),
+            Some(Host::Domain(domain)) => self.check_host(domain),
+            None => Err(Error::EgressDenied(url.to_string())),
+        }
+    }
+
+    /// Checks whether the host may be accessed
+    pub fn check_host(&self, host: &str) -> Result<()> {
+        let host = host.trim_end_matches('.').to_lowercase();
+
+        if self.deny.iter().any(|pattern| matches(pattern, &host)) {
+            return Err(Error::EgressDenied(host));
+        }
+
+        if METADATA_HOSTS.contains(&host.as_str()) && !self.allows_explicitly(&host) {
+            return Err(Error::EgressMetadata(host));
+        }
+
+        if !self.allow.is_empty() && !self.allow.iter().any(|pattern| matches(pattern, &host)) {
+            return Err(Error::EgressDenied(host));
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether an address that the host resolved to may be accessed.
+    /// This prevents reaching metadata endpoints through hosts that resolve
+    /// to them.
+    pub fn check_ip(&self, host: &str, ip: IpAddr) -> Result<()> {
+        self.check_host(host)?;
+
+        if is_metadata_ip(ip) && !self.allows_explicitly(host) {
+            return Err(Error::EgressMetadata(host.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns true when the host is listed in the allow list without the use
+    /// of wildcards
+    fn allows_explicitly(&self, host: &str) -> bool {
+        self.allow
+            .iter()
+            .any(|pattern| pattern.eq_ignore_ascii_case(host))
+    }
+}
+
+/// Matches a host against a pattern
+fn matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('.').to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.')),
+        None => pattern == "*" || pattern == host,
+    }
+}
+
+/// Link-local addresses are used by cloud providers to serve instance
+/// metadata
+fn is_metadata_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => ip.is_link_local(),
+            None => {
+                (ip.segments()[0] & 0xffc0) == 0xfe80
+                    || METADATA_HOSTS.contains(&ip.to_string().as_str())
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn check(policy: &EgressPolicy, url: &str) -> bool {
+        policy.check_url(&Url::parse(url).unwrap()).is_ok()
+    }
+
+    #[test]
+    fn test_default_policy_blocks_metadata() {
+        let fixture = EgressPolicy::default();
+
+        assert!(check(&fixture, "https://example.com"));
+        assert!(check(&fixture, "http://127.0.0.1:8080"));
+        assert!(!check(&fixture, "http://169.254.169.254/latest/meta-data"));
+        assert!(!check(&fixture, "http://169.254.1.1"));
+        assert!(!check(&fixture, "http://metadata.google.internal"));
+        assert!(!check(&fixture, "http://[fd00:ec2::254]"));
+        assert!(!check(&fixture, "http://[::ffff:169.254.169.254]"));
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let fixture = EgressPolicy::default().allow(vec!["*.rust-lang.org".to_string()]);
+
+        assert!(check(&fixture, "https://doc.rust-lang.org/std"));
+        assert!(!check(&fixture, "https://rust-lang.org"));
+        assert!(!check(&fixture, "https://evilrust-lang.org"));
+        assert!(!check(&fixture, "https://example.com"));
+    }
+
+    #[test]
+    fn test_deny_takes_precedence() {
+        let fixture = EgressPolicy::default()
+            .allow(vec!["*".to_string()])
+            .deny(vec!["internal.example.com".to_string()]);
+
+        assert!(check(&fixture, "https://example.com"));
+        assert!(!check(&fixture, "https://INTERNAL.example.com"));
+        assert!(!check(&fixture, "http://169.254.169.254"));
+    }
+
+    #[test]
+    fn test_explicit_allow_overrides_metadata() {
+        let fixture = EgressPolicy::default().allow(vec!["169.254.169.254".to_string()]);
+
+        assert!(check(&fixture, "http://169.254.169.254"));
+    }
+
+    #[test]
+    fn test_resolved_metadata_ip() {
+        let fixture = EgressPolicy::default();
+
+        let actual = fixture
+            .check_ip("metadata.attacker.com", "169.254.169.254".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        let expected = "Access to 'metadata.attacker.com' is blocked because it serves cloud instance metadata";
+        assert_eq!(actual, expected);
+    }
+}
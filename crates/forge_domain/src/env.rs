@@ -2,8 +2,9 @@ use std::path::PathBuf;
 
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
+use url::Url;
 
-use crate::{Provider, RetryConfig};
+use crate::{Mode, Provider, RetryConfig, TimeoutConfig};
 
 #[derive(Debug, Setters, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -26,6 +27,30 @@ pub struct Environment {
     pub provider: Provider,
     /// Configuration for the retry mechanism
     pub retry_config: RetryConfig,
+    /// Timeouts of the requests sent to the provider
+    pub timeout_config: TimeoutConfig,
+    /// Location of the community agent registry index
+    pub registry_url: Url,
+    /// Default number of seconds a shell command may run before it is
+    /// terminated
+    pub shell_timeout: u64,
+    /// Number of seconds a tool call may run before it is cancelled
+    pub tool_timeout: u64,
+    /// Number of characters of a tool result sent to the model, the rest can
+    /// be read with the read more tool
+    pub max_tool_result_chars: usize,
+    /// Mode that sessions start in unless the project configures one
+    pub default_mode: Option<Mode>,
+    /// Whether to save bandwidth on slow or metered connections: responses
+    /// are requested in one piece and compressed, and the optional requests
+    /// are skipped
+    pub low_bandwidth: bool,
+    /// Number of seconds a turn has to run before the user is notified that
+    /// it finished or needs an approval, notifications are off when unset
+    pub notify_after: Option<u64>,
+    /// Environment variables that templates may read with the `env` helper,
+    /// provider keys and tokens are never readable
+    pub template_env: Vec<String>,
 }
 
 impl Environment {
@@ -43,4 +68,22 @@ impl Environment {
     pub fn snapshot_path(&self) -> PathBuf {
         self.base_path.join("snapshots")
     }
+
+    /// Directory where the conversations are saved to be resumed later
+    pub fn sessions_path(&self) -> PathBuf {
+        self.base_path.join("sessions")
+    }
+
+    /// Directory of the templates that replace the built-in ones with the
+    /// same name, e.g. `partial-tool-use-example.hbs`
+    pub fn templates_path(&self) -> Option<PathBuf> {
+        self.home
+            .as_ref()
+            .map(|home| home.join(".config").join("forge").join("templates"))
+    }
+
+    /// File where the trust decisions of the user for workspaces are kept
+    pub fn trust_path(&self) -> PathBuf {
+        self.base_path.join("trusted_workspaces.json")
+    }
 }
@@ -19,6 +19,9 @@ pub enum Error {
     #[error("Invalid tool call XML: {0}")]
     ToolCallParse(String),
 
+    #[error("Tool '{0}' timed out after {1} seconds and was cancelled, the processes it started were terminated. Break the task into smaller steps or run long commands in the background.")]
+    ToolCallTimeout(String, u64),
+
     #[error("Invalid conversation id: {0}")]
     ConversationId(uuid::Error),
 
@@ -37,6 +40,15 @@ pub enum Error {
     #[error("Conversation not found: {0}")]
     ConversationNotFound(ConversationId),
 
+    #[error("Conversation {0} was modified concurrently, it was read at version {1} but is now at version {2}")]
+    ConversationConflict(ConversationId, u64, u64),
+
+    #[error("Conversation {0} hasn't started, there is no context to append the message to")]
+    ConversationNotStarted(ConversationId),
+
+    #[error("Failed to encode the message: {0}")]
+    MessageEncode(serde_json::Error),
+
     #[error("Missing description for agent: {0}")]
     MissingAgentDescription(AgentId),
 
@@ -45,6 +57,41 @@ pub enum Error {
 
     #[error("No model defined for agent: {0}")]
     NoModelDefined(AgentId),
+
+    #[error("Access to '{0}' is not permitted by the egress policy")]
+    EgressDenied(String),
+
+    #[error("Access to '{0}' is blocked because it serves cloud instance metadata")]
+    EgressMetadata(String),
+
+    #[error("Invalid shell policy pattern '{0}': {1}")]
+    ShellPolicyPattern(String, regex::Error),
+
+    #[error("Invalid mode '{0}', the name of a mode can't be empty or contain '/' or whitespace")]
+    InvalidMode(String),
+
+    #[error("Invalid handover condition '{0}': {1}")]
+    InvalidCondition(String, String),
+
+    #[error(
+        "Variable '{0}' can't be set, `mode` and `output` are reserved and the name can't be empty"
+    )]
+    ReservedVariable(String),
+
+    #[error("The task list is empty, add tasks or create a plan in Plan mode first")]
+    PlanUndefined,
+
+    #[error("Step {0} doesn't exist, the plan has {1} steps")]
+    PlanStepUndefined(usize, usize),
+
+    #[error("Missing argument '{0}' for the '{1}' action")]
+    TaskArgument(&'static str, &'static str),
+
+    #[error("The provider didn't respond within {0} seconds")]
+    ProviderFirstTokenTimeout(u64),
+
+    #[error("The response of the provider didn't complete within {0} seconds")]
+    ProviderStreamTimeout(u64),
 }
 
 pub type Result<A> = std::result::Result<A, Error>;
@@ -34,6 +34,8 @@ pub struct EventContext {
     suggestions: Vec<String>,
     variables: HashMap<String, Value>,
     current_time: String,
+    /// The plan of the conversation rendered as a checklist
+    plan: Option<String>,
 }
 
 impl EventContext {
@@ -45,6 +47,7 @@ impl EventContext {
             current_time: chrono::Local::now()
                 .format("%Y-%m-%d %H:%M:%S %:z")
                 .to_string(),
+            plan: None,
         }
     }
 }
@@ -62,6 +65,7 @@ impl Event {
             description: "Dispatches an event with the provided name and value".to_string(),
             input_schema: schema_for!(EventMessage),
             output_schema: None,
+            mutating: false,
         }
     }
 
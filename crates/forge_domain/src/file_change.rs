@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// A change that a tool call made to a file of the workspace, reported with
+/// its result so that clients can revert the changes of a turn from the
+/// snapshot store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub kind: FileChangeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    /// The file didn't exist, no snapshot was taken
+    Created,
+    /// The file was written or removed after a snapshot of its content was
+    /// taken
+    Modified,
+    /// The most recent snapshot of the file was restored and removed from the
+    /// store
+    Restored,
+}
+
+impl FileChange {
+    pub fn new(path: impl Into<PathBuf>, kind: FileChangeKind) -> Self {
+        Self { path: path.into(), kind }
+    }
+}
+
+/// Collects the changes made to files while a tool call is executed
+#[derive(Debug, Clone, Default)]
+pub struct FileChanges(Arc<Mutex<Vec<FileChange>>>);
+
+impl FileChanges {
+    pub fn push(&self, change: FileChange) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(change);
+    }
+
+    /// Takes the changes collected so far
+    pub fn take(&self) -> Vec<FileChange> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
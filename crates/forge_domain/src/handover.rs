@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Variable holding the output of the turn of the agent in the conditions
+const OUTPUT_VARIABLE: &str = "output";
+
+/// An event dispatched by the orchestrator when the agent completes a turn,
+/// with the output of the turn as its value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Handover {
+    /// Name of the event to dispatch
+    pub event: String,
+
+    /// Condition over the variables of the conversation, which agents set with
+    /// `forge_tool_variable_set`, and the `output` of the turn, e.g.
+    /// `${tests_passed} == false` or `${output} contains "FAILED"`.
+    /// Comparisons are made with `==`, `!=` and `contains`, and combined with
+    /// `&&` and `||`. The event is always dispatched when not specified.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+impl Handover {
+    pub fn new(event: impl ToString) -> Self {
+        Self { event: event.to_string(), when: None }
+    }
+
+    pub fn when(mut self, condition: impl ToString) -> Self {
+        self.when = Some(condition.to_string());
+        self
+    }
+
+    /// Whether the event is dispatched after a turn with the given output
+    pub fn applies(&self, variables: &HashMap<String, Value>, output: &str) -> Result<bool> {
+        let Some(condition) = &self.when else {
+            return Ok(true);
+        };
+        let resolve = |operand: &str| resolve(operand, variables, output);
+
+        // `&&` binds tighter than `||`
+        for alternative in split(condition, "||") {
+            let mut holds = true;
+            for comparison in split(alternative, "&&") {
+                holds &= compare(condition, comparison, &resolve)?;
+            }
+            if holds {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Checks the syntax of the condition without evaluating it, so that a
+    /// broken condition is reported when the workflow is loaded rather than
+    /// after the turn of the agent
+    pub fn validate(&self) -> Result<()> {
+        let Some(condition) = &self.when else {
+            return Ok(());
+        };
+        for alternative in split(condition, "||") {
+            for comparison in split(alternative, "&&") {
+                compare(condition, comparison, &|_| Value::Null)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn compare(condition: &str, comparison: &str, resolve: &impl Fn(&str) -> Value) -> Result<bool> {
+    let invalid = |reason: &str| Error::InvalidCondition(condition.to_string(), reason.to_string());
+    for operator in ["==", "!=", " contains "] {
+        let operands = split(comparison, operator);
+        if operands.len() == 1 {
+            continue;
+        }
+        let [left, right] = operands[..] else {
+            return Err(invalid(&format!("`{}` is used twice", operator.trim())));
+        };
+        if left.trim().is_empty() || right.trim().is_empty() {
+            return Err(invalid(&format!(
+                "`{}` is missing an operand",
+                operator.trim()
+            )));
+        }
+        let (left, right) = (text(&resolve(left)), text(&resolve(right)));
+        return Ok(match operator {
+            "==" => left == right,
+            "!=" => left != right,
+            _ => left.contains(&right),
+        });
+    }
+
+    if comparison.trim().is_empty() {
+        return Err(invalid("an operand is missing"));
+    }
+    Ok(is_truthy(&resolve(comparison)))
+}
+
+/// The value of a `${variable}`, a quoted string or a JSON literal
+fn resolve(operand: &str, variables: &HashMap<String, Value>, output: &str) -> Value {
+    let operand = operand.trim();
+    if let Some(name) = operand
+        .strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'))
+    {
+        let name = name.trim();
+        if name == OUTPUT_VARIABLE {
+            return Value::from(output);
+        }
+        return variables.get(name).cloned().unwrap_or_default();
+    }
+    for quote in ['"', '\''] {
+        if let Some(text) = operand
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return Value::from(text);
+        }
+    }
+    serde_json::from_str(operand).unwrap_or_else(|_| Value::from(operand))
+}
+
+/// Values are compared by their text, so that the variable `"false"` equals
+/// the literal `false`
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty() && text != "false",
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+/// Splits the expression on the separator, except within quotes
+fn split<'a>(expression: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in expression.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if index >= start && expression[index..].starts_with(separator) => {
+                parts.push(&expression[start..index]);
+                start = index + separator.len();
+            }
+            None => {}
+        }
+    }
+    parts.push(&expression[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn variables() -> HashMap<String, Value> {
+        HashMap::from([
+            ("tests_passed".to_string(), json!(false)),
+            ("attempts".to_string(), json!(2)),
+            ("stage".to_string(), json!("review")),
+        ])
+    }
+
+    fn applies(condition: &str, output: &str) -> bool {
+        Handover::new("fix")
+            .when(condition)
+            .applies(&variables(), output)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_without_condition() {
+        let actual = Handover::new("fix").applies(&variables(), "").unwrap();
+        assert!(actual);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(applies("${tests_passed} == false", ""));
+        assert!(applies("${tests_passed} == 'false'", ""));
+        assert!(!applies("${tests_passed} != false", ""));
+        assert!(applies("${attempts} == 2", ""));
+        assert!(applies("${stage} == \"review\"", ""));
+        assert!(applies(
+            "${output} contains \"FAILED\"",
+            "test foo ... FAILED"
+        ));
+        assert!(!applies("${output} contains 'FAILED'", "test foo ... ok"));
+    }
+
+    #[test]
+    fn test_truthiness() {
+        assert!(!applies("${tests_passed}", ""));
+        assert!(applies("${attempts}", ""));
+        assert!(!applies("${undefined}", ""));
+        assert!(applies("${output}", "done"));
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        assert!(applies("${tests_passed} == false && ${attempts} != 3", ""));
+        assert!(!applies("${tests_passed} == true && ${attempts} == 2", ""));
+        assert!(applies("${tests_passed} == true || ${attempts} == 2", ""));
+        assert!(applies("${output} == 'a || b'", "a || b"));
+    }
+
+    #[test]
+    fn test_invalid_condition() {
+        let actual = Handover::new("fix")
+            .when("${attempts} == 1 == 2")
+            .applies(&variables(), "")
+            .unwrap_err()
+            .to_string();
+        let expected = "Invalid handover condition '${attempts} == 1 == 2': `==` is used twice";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Handover::new("fix").validate().is_ok());
+        assert!(Handover::new("fix")
+            .when("${attempts} == 1 || ${stage} contains 'rev'")
+            .validate()
+            .is_ok());
+
+        // The first alternative holds, which hides the broken second one from
+        // `applies`
+        let fixture = Handover::new("fix").when("${attempts} == 2 || ${stage} ==");
+        assert!(fixture.applies(&variables(), "").unwrap());
+        let actual = fixture.validate().unwrap_err().to_string();
+        let expected =
+            "Invalid handover condition '${attempts} == 2 || ${stage} ==': `==` is missing an operand";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let fixture = "event: fix\nwhen: ${tests_passed} == false\n";
+        let actual: Handover = serde_yml::from_str(fixture).unwrap();
+        let expected = Handover::new("fix").when("${tests_passed} == false");
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,31 +1,59 @@
 mod agent;
+mod agent_spawn;
 mod api;
+mod approval;
+mod artifact;
 mod attachment;
 mod chat_request;
 mod chat_response;
+mod circuit_breaker;
 mod compaction_result;
-mod conversation_html;
+mod conversation_markdown;
 
 mod context;
+mod context_breakdown;
 mod conversation;
+mod custom_tool;
+mod diff;
+mod editor;
+mod egress;
 mod env;
 mod error;
 mod event;
 mod file;
+mod file_change;
+mod handover;
+mod lsp;
 mod merge;
 mod message;
+mod mode;
 mod model;
+mod nesting;
 mod orch;
+mod phase;
+mod plan;
 mod point;
 mod provider;
+mod redaction;
+mod registry;
+mod resource_limits;
+mod response_metadata;
 mod retry_config;
+mod retry_policy;
+mod schema_validation;
 mod services;
 mod shell;
+mod shell_policy;
+mod source;
+mod stream_dedup;
 mod suggestion;
 mod system_context;
 mod temperature;
+mod temperature_ramp;
 mod template;
 mod text_utils;
+mod theme;
+mod timeout_config;
 mod tool;
 mod tool_call;
 mod tool_call_context;
@@ -33,37 +61,69 @@ mod tool_call_parser;
 mod tool_call_record;
 mod tool_choice;
 mod tool_definition;
+mod tool_error;
 mod tool_name;
 mod tool_result;
 mod tool_usage;
+mod top_p;
+mod untrusted;
+mod variable_set;
 mod workflow;
+mod workflow_run;
 
 pub use agent::*;
+pub use agent_spawn::*;
 pub use api::*;
+pub use approval::*;
+pub use artifact::*;
 pub use attachment::*;
 pub use chat_request::*;
 pub use chat_response::*;
+pub use circuit_breaker::*;
 pub use compaction_result::*;
 pub use context::*;
+pub use context_breakdown::*;
 pub use conversation::*;
-pub use conversation_html::*;
+pub use conversation_markdown::*;
+pub use custom_tool::*;
+pub use diff::*;
+pub use editor::*;
+pub use egress::*;
 pub use env::*;
 pub use error::*;
 pub use event::*;
 pub use file::*;
+pub use file_change::*;
+pub use handover::*;
+pub use lsp::*;
 pub use message::*;
+pub use mode::*;
 pub use model::*;
 pub use orch::*;
+pub use phase::*;
+pub use plan::*;
 pub use point::*;
 pub use provider::*;
+pub use redaction::*;
+pub use registry::*;
+pub use resource_limits::*;
+pub use response_metadata::*;
 pub use retry_config::*;
+pub use retry_policy::*;
+pub use schema_validation::*;
 pub use services::*;
 pub use shell::*;
+pub use shell_policy::*;
+pub use source::*;
+pub use stream_dedup::*;
 pub use suggestion::*;
 pub use system_context::*;
 pub use temperature::*;
+pub use temperature_ramp::*;
 pub use template::*;
 pub use text_utils::*;
+pub use theme::*;
+pub use timeout_config::*;
 pub use tool::*;
 pub use tool_call::*;
 pub use tool_call_context::*;
@@ -71,7 +131,12 @@ pub use tool_call_parser::*;
 pub use tool_call_record::*;
 pub use tool_choice::*;
 pub use tool_definition::*;
+pub use tool_error::*;
 pub use tool_name::*;
 pub use tool_result::*;
 pub use tool_usage::*;
+pub use top_p::*;
+pub use untrusted::*;
+pub use variable_set::*;
 pub use workflow::*;
+pub use workflow_run::*;
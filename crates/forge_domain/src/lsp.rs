@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A language server that the LSP tool starts for files with one of its
+/// extensions. The server must speak the Language Server Protocol over stdio.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(into)]
+pub struct LanguageServer {
+    /// The executable of the server
+    pub command: String,
+
+    /// Arguments passed to the server
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+
+    /// File extensions handled by the server, without the leading dot
+    pub extensions: Vec<String>,
+}
+
+impl LanguageServer {
+    pub fn new(command: impl ToString) -> Self {
+        Self {
+            command: command.to_string(),
+            args: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Servers used when none are configured
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("rust-analyzer").extensions(["rs".to_string()]),
+            Self::new("pyright-langserver")
+                .args(["--stdio".to_string()])
+                .extensions(["py".to_string()]),
+            Self::new("typescript-language-server")
+                .args(["--stdio".to_string()])
+                .extensions(
+                    ["ts", "tsx", "js", "jsx"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect::<Vec<_>>(),
+                ),
+            Self::new("gopls").extensions(["go".to_string()]),
+        ]
+    }
+
+    /// Finds the server that handles the file, the first configured one
+    /// taking precedence
+    pub fn find<'a>(servers: &'a [Self], path: &Path) -> Option<&'a Self> {
+        let ext = path.extension()?.to_str()?;
+        servers
+            .iter()
+            .find(|server| server.extensions.iter().any(|e| e == ext))
+    }
+
+    /// The LSP language identifier of the file
+    pub fn language_id(path: &Path) -> &'static str {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("rs") => "rust",
+            Some("py") => "python",
+            Some("ts") => "typescript",
+            Some("tsx") => "typescriptreact",
+            Some("js") => "javascript",
+            Some("jsx") => "javascriptreact",
+            Some("go") => "go",
+            Some("java") => "java",
+            Some("c") => "c",
+            Some("cpp" | "cc" | "h" | "hpp") => "cpp",
+            Some("rb") => "ruby",
+            Some("scala") => "scala",
+            _ => "plaintext",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let servers = vec![LanguageServer::new("pylsp").extensions(["py".to_string()])]
+            .into_iter()
+            .chain(LanguageServer::defaults())
+            .collect::<Vec<_>>();
+
+        let actual = LanguageServer::find(&servers, Path::new("/project/main.py"))
+            .map(|server| server.command.as_str());
+        assert_eq!(actual, Some("pylsp"));
+
+        let actual = LanguageServer::find(&servers, Path::new("/project/index.tsx"))
+            .map(|server| server.command.as_str());
+        assert_eq!(actual, Some("typescript-language-server"));
+
+        let actual = LanguageServer::find(&servers, Path::new("/project/README.md"));
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let fixture = "command: clangd\nextensions: [c, cpp]\n";
+
+        let actual: LanguageServer = serde_yml::from_str(fixture).unwrap();
+        let expected =
+            LanguageServer::new("clangd").extensions(["c".to_string(), "cpp".to_string()]);
+        assert_eq!(actual, expected);
+    }
+}
@@ -23,6 +23,14 @@ pub struct ChatCompletionMessage {
     pub tool_calls: Vec<ToolCall>,
     pub finish_reason: Option<FinishReason>,
     pub usage: Option<Usage>,
+    /// The model that generated the response, as reported by the provider
+    pub model: Option<String>,
+    /// The upstream provider that served the request, as reported by routers
+    /// such as OpenRouter
+    pub provider: Option<String>,
+    /// Whether the provider reconnected before this message, after which it
+    /// may resend the content streamed so far
+    pub reconnected: bool,
 }
 
 /// Represents partial or full content of a message
@@ -0,0 +1,148 @@
+use std::str::FromStr;
+
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::merge::Key;
+use crate::{Error, ToolName};
+
+/// How the agents approach a task, e.g. Plan mode analyzes and proposes
+/// changes without making them and Act mode carries them out. Modes are
+/// defined by the workflow, the name is kept in lowercase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct Mode(String);
+
+impl Mode {
+    pub fn act() -> Self {
+        Self("act".to_string())
+    }
+
+    pub fn plan() -> Self {
+        Self("plan".to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Self::act()
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.to_uppercase())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        // The name prefixes the events of the mode
+        if name.is_empty() || name.contains(|c: char| c == '/' || c.is_whitespace()) {
+            return Err(Error::InvalidMode(s.to_string()));
+        }
+        Ok(Self(name))
+    }
+}
+
+impl TryFrom<String> for Mode {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Mode> for String {
+    fn from(value: Mode) -> Self {
+        value.0
+    }
+}
+
+/// A mode that the user can switch to, as defined in the workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Merge, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct ModeConfig {
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub name: Mode,
+
+    /// Shown when picking a mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub description: Option<String>,
+
+    /// Prefix of the events dispatched for the messages of the user, e.g.
+    /// `review/user_task_init`. Defaults to the name of the mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub event: Option<String>,
+
+    /// Tools that the agents can use in this mode, on top of the restrictions
+    /// of each agent. All the tools of the agents are available when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub tools: Option<Vec<ToolName>>,
+}
+
+impl ModeConfig {
+    pub fn new(name: Mode) -> Self {
+        Self { name, description: None, event: None, tools: None }
+    }
+
+    /// Prefix of the events dispatched for the messages of the user
+    pub fn event_prefix(&self) -> &str {
+        self.event.as_deref().unwrap_or(self.name.as_str())
+    }
+}
+
+impl Key for ModeConfig {
+    type Id = Mode;
+
+    fn key(&self) -> &Self::Id {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("plan".parse::<Mode>().unwrap(), Mode::plan());
+        assert_eq!("ACT".parse::<Mode>().unwrap(), Mode::act());
+        assert_eq!("review".parse::<Mode>().unwrap().as_str(), "review");
+        assert!("".parse::<Mode>().is_err());
+        assert!("code/review".parse::<Mode>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let actual = Mode::plan().to_string().parse::<Mode>().unwrap();
+        assert_eq!(actual, Mode::plan());
+    }
+
+    #[test]
+    fn test_parse_mode_config() {
+        let actual: ModeConfig = serde_yml::from_str(
+            "name: Review\ndescription: Review the changes\ntools: [forge_tool_fs_read]",
+        )
+        .unwrap();
+        let expected = ModeConfig::new("review".parse().unwrap())
+            .description("Review the changes")
+            .tools(vec![ToolName::new("forge_tool_fs_read")]);
+        assert_eq!(actual, expected);
+        assert_eq!(actual.event_prefix(), "review");
+    }
+}
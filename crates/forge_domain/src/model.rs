@@ -1,16 +1,36 @@
 use derive_more::derive::Display;
 use derive_setters::Setters;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::Usage;
+
 #[derive(Clone, Debug, Deserialize, Serialize, Setters)]
 pub struct Model {
     pub id: ModelId,
     pub name: Option<String>,
     pub description: Option<String>,
     pub context_length: Option<u64>,
+    /// Price of the tokens, when the provider publishes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
     // TODO: add provider information to the model
 }
 
+/// Price in USD of a single token
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct ModelPricing {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+impl ModelPricing {
+    /// Returns the cost in USD of a request
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        usage.prompt_tokens as f64 * self.prompt + usage.completion_tokens as f64 * self.completion
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Parameters {
     pub tool_supported: bool,
@@ -22,7 +42,7 @@ impl Parameters {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Hash, Eq, Display)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Hash, Eq, Display, JsonSchema)]
 #[serde(transparent)]
 pub struct ModelId(String);
 
@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::bail;
+
+use crate::AgentId;
+
+/// Number of workflows that can be nested one in another below the workflow
+/// of the conversation
+const MAX_WORKFLOW_DEPTH: usize = 3;
+
+/// Number of agents that can be spawned one by another, starting from the
+/// agent of the conversation
+const MAX_SPAWN_DEPTH: usize = 3;
+
+/// Number of handovers dispatched while handling an event, so that agents
+/// handing over to each other without a condition that ends the loop stop
+const MAX_HANDOVERS: usize = 20;
+
+/// The agents and workflows that lead to the agent of an orchestrator. The
+/// orchestrators of spawned agents and nested workflows get a copy of it, so
+/// that spawning agents and running workflows in turn is bounded too, and
+/// they share the count of the handovers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Nesting {
+    /// Agents that spawned the agent, outermost first
+    spawned_by: Vec<AgentId>,
+    /// Workflows that lead to the workflow of the agent, outermost first,
+    /// starting from the workflow of the conversation when it was read from a
+    /// file
+    workflows: Vec<PathBuf>,
+    /// Number of workflows the workflow of the agent is nested in
+    depth: usize,
+    /// Handovers dispatched so far
+    handovers: Arc<AtomicUsize>,
+}
+
+impl Nesting {
+    /// The nesting of the agents of the conversation with the workflow at the
+    /// path
+    pub fn new(workflow: Option<PathBuf>) -> Self {
+        Self { workflows: workflow.into_iter().collect(), ..Self::default() }
+    }
+
+    /// The nesting of the agent spawned by the parent
+    pub fn spawn(&self, parent: &AgentId, agent_id: &AgentId) -> anyhow::Result<Self> {
+        let mut spawned_by = self.spawned_by.clone();
+        spawned_by.push(parent.clone());
+        if spawned_by.contains(agent_id) {
+            let chain = spawned_by
+                .iter()
+                .chain([agent_id])
+                .map(AgentId::as_str)
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            bail!("Agent '{agent_id}' can't be spawned by itself: {chain}");
+        }
+        if spawned_by.len() > MAX_SPAWN_DEPTH {
+            bail!("Agent '{agent_id}' can't be spawned, agents can only be spawned {MAX_SPAWN_DEPTH} levels deep");
+        }
+        Ok(Self { spawned_by, ..self.clone() })
+    }
+
+    /// The nesting of the agents of the workflow at the path
+    pub fn run_workflow(&self, path: PathBuf) -> anyhow::Result<Self> {
+        if self.workflows.contains(&path) {
+            bail!("Workflow {} is already running", path.display());
+        }
+        if self.depth >= MAX_WORKFLOW_DEPTH {
+            bail!(
+                "Workflow {} can't be run, workflows can only be nested {MAX_WORKFLOW_DEPTH} levels deep",
+                path.display()
+            );
+        }
+        let workflows = self.workflows.iter().cloned().chain([path]).collect();
+        Ok(Self { workflows, depth: self.depth + 1, ..self.clone() })
+    }
+
+    /// Counts a handover of the agent to the event
+    pub fn hand_over(&self, agent_id: &AgentId, event: &str) -> anyhow::Result<()> {
+        if self.handovers.fetch_add(1, Ordering::Relaxed) >= MAX_HANDOVERS {
+            bail!(
+                "Agent '{agent_id}' can't hand over '{event}', at most {MAX_HANDOVERS} handovers are dispatched per message"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_spawn_cycle() {
+        let fixture = Nesting::default()
+            .spawn(&AgentId::new("a"), &AgentId::new("b"))
+            .unwrap();
+
+        let actual = fixture
+            .spawn(&AgentId::new("b"), &AgentId::new("a"))
+            .unwrap_err()
+            .to_string();
+
+        let expected = "Agent 'a' can't be spawned by itself: a -> b -> a";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_spawn_and_workflow_run_alternating() {
+        let mut nesting = Nesting::default();
+        let mut levels = 0;
+        let actual = loop {
+            let next = nesting
+                .spawn(
+                    &AgentId::new(format!("parent-{levels}")),
+                    &AgentId::new("child"),
+                )
+                .and_then(|nesting| nesting.run_workflow(PathBuf::from(format!("{levels}.yaml"))));
+            match next {
+                Ok(next) => nesting = next,
+                Err(error) => break error.to_string(),
+            }
+            levels += 1;
+        };
+
+        let expected = "Agent 'child' can't be spawned, agents can only be spawned 3 levels deep";
+        assert_eq!(actual, expected);
+        assert_eq!(levels, MAX_SPAWN_DEPTH);
+    }
+
+    #[test]
+    fn test_workflow_of_conversation_already_running() {
+        let fixture = Nesting::new(Some(PathBuf::from("/project/forge.yaml")))
+            .run_workflow(PathBuf::from("/project/review.yaml"))
+            .unwrap();
+
+        let actual = fixture
+            .run_workflow(PathBuf::from("/project/forge.yaml"))
+            .unwrap_err()
+            .to_string();
+
+        let expected = "Workflow /project/forge.yaml is already running";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_workflow_depth_below_conversation() {
+        let mut fixture = Nesting::new(Some(PathBuf::from("forge.yaml")));
+        for level in 0..MAX_WORKFLOW_DEPTH {
+            fixture = fixture
+                .run_workflow(PathBuf::from(format!("{level}.yaml")))
+                .unwrap();
+        }
+
+        let actual = fixture.run_workflow(PathBuf::from("last.yaml"));
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_handovers_shared_with_nested_agents() {
+        let fixture = Nesting::default();
+        let nested = fixture
+            .spawn(&AgentId::new("a"), &AgentId::new("b"))
+            .and_then(|nesting| nesting.run_workflow(PathBuf::from("forge.yaml")))
+            .unwrap();
+
+        for _ in 0..MAX_HANDOVERS {
+            nested.hand_over(&AgentId::new("b"), "review").unwrap();
+        }
+        let actual = fixture.hand_over(&AgentId::new("a"), "review");
+
+        assert!(actual.is_err());
+    }
+}
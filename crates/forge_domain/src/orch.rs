@@ -1,5 +1,7 @@
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{bail, Context as AnyhowContext};
 use async_recursion::async_recursion;
@@ -10,13 +12,17 @@ use futures::{Stream, StreamExt};
 use serde_json::Value;
 use tokio::sync::RwLock;
 use tokio_retry::strategy::{jitter, ExponentialBackoff};
-use tokio_retry::RetryIf;
 use tracing::debug;
 
 // Use retry_config default values directly in this file
+use crate::nesting::Nesting;
 use crate::services::Services;
 use crate::*;
 
+/// Number of times the conversation is stored again after a client changed it
+/// concurrently
+const MAX_SYNC_ATTEMPTS: usize = 3;
+
 type ArcSender = Arc<tokio::sync::mpsc::Sender<anyhow::Result<AgentMessage<ChatResponse>>>>;
 
 #[derive(Debug, Clone)]
@@ -36,13 +42,18 @@ pub struct Orchestrator<Services> {
     services: Arc<Services>,
     sender: Option<ArcSender>,
     conversation: Arc<RwLock<Conversation>>,
+    /// Temporary directory of the conversation
+    tmp_dir: PathBuf,
     retry_strategy: std::iter::Take<tokio_retry::strategy::ExponentialBackoff>,
+    /// Agents and workflows that lead to the agent of this orchestrator
+    nesting: Nesting,
 }
 
 struct ChatCompletionResult {
     pub content: String,
     pub tool_calls: Vec<ToolCallFull>,
     pub usage: Option<Usage>,
+    pub metadata: ResponseMetadata,
 }
 
 impl<A: Services> Orchestrator<A> {
@@ -65,6 +76,8 @@ impl<A: Services> Orchestrator<A> {
             services,
             sender,
             retry_strategy,
+            tmp_dir: conversation.tmp_dir(),
+            nesting: Nesting::new(conversation.workflow_path.clone()),
             conversation: Arc::new(RwLock::new(conversation)),
         }
     }
@@ -77,30 +90,248 @@ impl<A: Services> Orchestrator<A> {
         tool_calls: &[ToolCallFull],
         tool_context: ToolCallContext,
     ) -> anyhow::Result<Vec<ToolCallRecord>> {
-        // Always process tool calls sequentially
         let mut tool_call_records = Vec::with_capacity(tool_calls.len());
 
-        for tool_call in tool_calls {
-            // Send the start notification
-            self.send(agent, ChatResponse::ToolCallStart(tool_call.clone()))
-                .await?;
+        // Nested workflows, spawned agents, the task list and the variables are
+        // handled by the orchestrator, consecutive calls to any other tool are
+        // executed together as a batch
+        let is_tool = |call: &ToolCallFull| {
+            WorkflowRun::parse(call).is_none()
+                && AgentSpawn::parse(call).is_none()
+                && TaskList::parse(call).is_none()
+                && VariableSet::parse(call).is_none()
+        };
+        for calls in tool_calls.chunk_by(|a, b| is_tool(a) && is_tool(b)) {
+            let tool_call = &calls[0];
+            let tool_results = if is_tool(tool_call) {
+                self.services
+                    .tool_service()
+                    .call_batch(tool_context.clone(), calls)
+                    .await?
+            } else {
+                self.send(agent, ChatResponse::ToolCallStart(tool_call.clone()))
+                    .await?;
+                let tool_result = self
+                    .call_orchestrator_tool(agent, tool_call, &tool_context)
+                    .await;
+                self.send(agent, ChatResponse::ToolCallEnd(tool_result.clone()))
+                    .await?;
+                vec![tool_result]
+            };
 
-            // Execute the tool
-            let tool_result = self
-                .services
-                .tool_service()
-                .call(tool_context.clone(), tool_call.clone())
-                .await;
+            tool_call_records.extend(
+                calls
+                    .iter()
+                    .cloned()
+                    .zip(tool_results)
+                    .map(|(tool_call, tool_result)| ToolCallRecord { tool_call, tool_result }),
+            );
+        }
 
-            // Send the end notification
-            self.send(agent, ChatResponse::ToolCallEnd(tool_result.clone()))
-                .await?;
+        self.capture_plan(&tool_call_records).await;
+
+        Ok(tool_call_records)
+    }
 
-            // Add the result to our collection
-            tool_call_records.push(ToolCallRecord { tool_call: tool_call.clone(), tool_result });
+    /// Executes a tool handled by the orchestrator once the call is approved,
+    /// like the calls of the other tools, e.g. it must be within the tools of
+    /// the mode
+    async fn call_orchestrator_tool(
+        &self,
+        agent: &Agent,
+        tool_call: &ToolCallFull,
+        tool_context: &ToolCallContext,
+    ) -> ToolResult {
+        if let Err(error) = tool_context.check_approval(tool_call, false).await {
+            return ToolResult::from(tool_call.clone()).failure(error);
         }
+        if let Some(input) = WorkflowRun::parse(tool_call) {
+            self.run_workflow(tool_call, input).await
+        } else if let Some(input) = AgentSpawn::parse(tool_call) {
+            self.spawn_agent(agent, tool_call, input).await
+        } else if let Some(input) = TaskList::parse(tool_call) {
+            self.update_task_list(tool_call, input).await
+        } else if let Some(input) = VariableSet::parse(tool_call) {
+            self.set_variable(tool_call, input).await
+        } else {
+            ToolResult::from(tool_call.clone()).failure(anyhow::anyhow!(
+                "Tool '{}' isn't handled by the orchestrator",
+                tool_call.name.as_str()
+            ))
+        }
+    }
 
-        Ok(tool_call_records)
+    /// Executes a nested workflow in an isolated conversation and returns its
+    /// final output as the result of the tool call
+    async fn run_workflow(
+        &self,
+        tool_call: &ToolCallFull,
+        input: Result<WorkflowRun>,
+    ) -> ToolResult {
+        let result = ToolResult::from(tool_call.clone());
+        match self.execute_workflow(input).await {
+            Ok(output) => result.success(output),
+            Err(error) => result.failure(error),
+        }
+    }
+
+    /// Runs another agent of the workflow with the task in an isolated context
+    /// and returns its final output as the result of the tool call
+    async fn spawn_agent(
+        &self,
+        agent: &Agent,
+        tool_call: &ToolCallFull,
+        input: Result<AgentSpawn>,
+    ) -> ToolResult {
+        let result = ToolResult::from(tool_call.clone());
+        match self.execute_spawn(agent, input).await {
+            Ok(output) => result.success(output),
+            Err(error) => result.failure(error),
+        }
+    }
+
+    /// Applies the operation to the task list of the conversation and returns
+    /// the updated list as the result of the tool call
+    async fn update_task_list(
+        &self,
+        tool_call: &ToolCallFull,
+        input: Result<TaskList>,
+    ) -> ToolResult {
+        let result = ToolResult::from(tool_call.clone());
+        let mut conversation = self.conversation.write().await;
+        match input.and_then(|input| input.apply(&mut conversation.plan)) {
+            Ok(tasks) => result.success(tasks),
+            Err(error) => result.failure(error.into()),
+        }
+    }
+
+    /// Sets the variable of the conversation, which the conditions of the
+    /// handovers read once the turn completes
+    async fn set_variable(
+        &self,
+        tool_call: &ToolCallFull,
+        input: Result<VariableSet>,
+    ) -> ToolResult {
+        let result = ToolResult::from(tool_call.clone());
+        match input {
+            Ok(VariableSet { name, value }) => {
+                let content = format!("Set `{name}` to {value}");
+                self.conversation.write().await.set_variable(name, value);
+                result.success(content)
+            }
+            Err(error) => result.failure(error.into()),
+        }
+    }
+
+    /// Stores the plan of the conversation when a plan file was written
+    async fn capture_plan(&self, records: &[ToolCallRecord]) {
+        let paths = records
+            .iter()
+            .filter(|record| !record.tool_result.is_error)
+            .filter_map(|record| Plan::written_file(&record.tool_call))
+            .collect::<Vec<_>>();
+
+        for path in paths {
+            match tokio::fs::read_to_string(&path).await {
+                Ok(content) => {
+                    if let Some(plan) = Plan::parse(&content) {
+                        debug!(path = %path.display(), steps = plan.steps.len(), "Plan captured");
+                        self.conversation.write().await.plan = Some(plan.path(path));
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(path = %path.display(), error = ?error, "Failed to read the plan")
+                }
+            }
+        }
+    }
+
+    /// The orchestrator of a spawned agent or a nested workflow, whose messages
+    /// reach the client of this one
+    fn nested(&self, conversation: Conversation, nesting: Nesting) -> Self {
+        let mut orch = Orchestrator::new(self.services.clone(), conversation, self.sender.clone());
+        orch.nesting = nesting;
+        orch
+    }
+
+    #[async_recursion]
+    async fn execute_workflow(&self, input: Result<WorkflowRun>) -> anyhow::Result<String> {
+        let input = input?;
+        let env = self.services.environment_service().get_environment();
+        let path = env.cwd.join(&input.path);
+        let path = path.canonicalize().unwrap_or(path);
+        let nesting = self.nesting.run_workflow(path.clone())?;
+
+        let mut workflow = self
+            .services
+            .workflow_service()
+            .read(Some(&path))
+            .await
+            .with_context(|| format!("Failed to read workflow {}", path.display()))?;
+        // The workflow comes from the same workspace as the one running it
+        if self.get_conversation().await?.untrusted {
+            workflow.restrict_untrusted();
+        }
+        let conversation = self
+            .services
+            .conversation_service()
+            .create(workflow)
+            .await?;
+
+        let event_name = input.event_name().to_string();
+        let subscribers = conversation.subscriptions(&event_name);
+        if subscribers.is_empty() {
+            bail!(
+                "No agent in workflow {} subscribes to '{event_name}'",
+                path.display()
+            );
+        }
+
+        // The messages of the nested workflow reach the client, which counts its
+        // turns and usage and answers its approvals
+        let orch = self.nested(conversation, nesting);
+        orch.dispatch(Event::new(event_name, input.task)).await?;
+
+        let conversation = orch.get_conversation().await?;
+        let output = subscribers
+            .iter()
+            .filter_map(|agent| conversation.context(&agent.id))
+            .filter_map(|context| context.last_output())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        Ok(output)
+    }
+
+    #[async_recursion]
+    async fn execute_spawn(
+        &self,
+        parent: &Agent,
+        input: Result<AgentSpawn>,
+    ) -> anyhow::Result<String> {
+        let input = input?;
+        let agent_id = AgentId::new(&input.agent);
+        let nesting = self.nesting.spawn(&parent.id, &agent_id)?;
+
+        let conversation = self
+            .get_conversation()
+            .await?
+            .spawn(&agent_id, AGENT_SPAWN_EVENT)?;
+        if conversation.subscriptions(AGENT_SPAWN_EVENT).is_empty() {
+            bail!("Agent '{agent_id}' is disabled");
+        }
+
+        // The messages of the spawned agent reach the client, which counts its
+        // turns and usage and answers its approvals
+        let orch = self.nested(conversation, nesting);
+        orch.dispatch(Event::new(AGENT_SPAWN_EVENT, input.task))
+            .await?;
+
+        orch.get_conversation()
+            .await?
+            .context(&agent_id)
+            .and_then(|context| context.last_output())
+            .with_context(|| format!("Agent '{agent_id}' completed without an output"))
     }
 
     async fn send(&self, agent: &Agent, message: ChatResponse) -> anyhow::Result<()> {
@@ -117,14 +348,32 @@ impl<A: Services> Orchestrator<A> {
         Ok(())
     }
 
-    /// Get the allowed tools for an agent
-    fn get_allowed_tools(&self, agent: &Agent) -> Vec<ToolDefinition> {
+    /// Get the allowed tools for an agent, within the ones of the mode of the
+    /// conversation and without the ones the user disabled
+    fn get_allowed_tools(&self, agent: &Agent, conversation: &Conversation) -> Vec<ToolDefinition> {
         let allowed = agent.tools.iter().flatten().collect::<HashSet<_>>();
+        let mode_tools = conversation.mode_tools.as_ref();
+        let read_only = agent.read_only.unwrap_or_default();
+        // Custom tools run shell commands, so they are disabled in read-only mode
+        let custom_tools = agent
+            .custom_tools
+            .iter()
+            .flatten()
+            .filter(|_| !read_only)
+            .map(|tool| tool.tool_definition());
         self.services
             .tool_service()
             .list()
             .into_iter()
+            .chain(std::iter::once(WorkflowRun::tool_definition()))
+            .chain(std::iter::once(AgentSpawn::tool_definition()))
+            .chain(std::iter::once(TaskList::tool_definition()))
+            .chain(std::iter::once(VariableSet::tool_definition()))
+            .chain(custom_tools)
             .filter(|tool| allowed.contains(&tool.name))
+            .filter(|tool| mode_tools.is_none_or(|tools| tools.contains(&tool.name)))
+            .filter(|tool| !conversation.disabled_tools.contains(&tool.name))
+            .filter(|tool| !(read_only && tool.mutating))
             .collect()
     }
 
@@ -132,6 +381,7 @@ impl<A: Services> Orchestrator<A> {
         &self,
         context: Context,
         agent: &Agent,
+        tools: &[ToolDefinition],
         variables: &HashMap<String, Value>,
     ) -> anyhow::Result<Context> {
         Ok(if let Some(system_prompt) = &agent.system_prompt {
@@ -150,7 +400,7 @@ impl<A: Services> Orchestrator<A> {
 
             let tool_information = match agent.tool_supported.unwrap_or_default() {
                 true => None,
-                false => Some(ToolUsagePrompt::from(&self.get_allowed_tools(agent)).to_string()),
+                false => Some(ToolUsagePrompt::from(tools).to_string()),
             };
 
             let ctx = SystemContext {
@@ -161,12 +411,10 @@ impl<A: Services> Orchestrator<A> {
                 files,
                 custom_rules: agent.custom_rules.as_ref().cloned().unwrap_or_default(),
                 variables: variables.clone(),
+                tmp_dir: Some(self.tmp_dir.clone()),
             };
 
-            let system_message = self
-                .services
-                .template_service()
-                .render(system_prompt.template.as_str(), &ctx)?;
+            let system_message = self.render(system_prompt.template.as_str(), &ctx).await?;
 
             context.set_first_system_message(system_message)
         } else {
@@ -201,6 +449,7 @@ impl<A: Services> Orchestrator<A> {
         let mut messages = Vec::new();
         let mut request_usage: Option<Usage> = None;
         let mut content = String::new();
+        let mut dedup = StreamDedup::new();
         let mut xml_tool_calls = None;
         let mut tool_interrupted = false;
 
@@ -216,9 +465,16 @@ impl<A: Services> Orchestrator<A> {
                 .calculate_usage(&message, context, request_usage, agent)
                 .await?;
 
-            // Process content
-            if let Some(content_part) = message.content.clone() {
-                let content_part = content_part.as_str().to_string();
+            // Process content, without the text resent by the provider after a
+            // reconnect
+            if message.reconnected {
+                dedup.reconnect();
+            }
+            if let Some(content_part) = message.content.as_ref() {
+                let content_part = dedup.push(content_part.as_str());
+                if content_part.is_empty() {
+                    continue;
+                }
 
                 content.push_str(&content_part);
 
@@ -255,13 +511,31 @@ impl<A: Services> Orchestrator<A> {
             }
         }
 
-        // Get the full content from all messages
-        let mut content = messages
-            .iter()
-            .flat_map(|m| m.content.iter())
-            .map(|content| content.as_str())
-            .collect::<Vec<_>>()
-            .join("");
+        // Text held back as a possible replay belongs to the content
+        if !tool_interrupted {
+            let content_part = dedup.finish();
+            if !content_part.is_empty() {
+                content.push_str(&content_part);
+                self.send(
+                    agent,
+                    ChatResponse::Text {
+                        text: content_part,
+                        is_complete: false,
+                        is_md: false,
+                        is_summary: false,
+                    },
+                )
+                .await?;
+
+                if should_interrupt_for_xml {
+                    xml_tool_calls = ToolCallFull::try_from_xml(&content)
+                        .ok()
+                        .into_iter()
+                        .flatten()
+                        .next();
+                }
+            }
+        }
 
         if tool_interrupted && !content.trim().ends_with("</forge_tool_call>") {
             if let Some((i, right)) = content.rmatch_indices("</forge_tool_call>").next() {
@@ -317,10 +591,16 @@ impl<A: Services> Orchestrator<A> {
             .chain(xml_tool_calls)
             .collect();
 
-        Ok(ChatCompletionResult { content, tool_calls, usage: request_usage })
+        let metadata = ResponseMetadata::from_messages(&messages);
+
+        Ok(ChatCompletionResult { content, tool_calls, usage: request_usage, metadata })
     }
 
     pub async fn dispatch(&self, event: Event) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.tmp_dir)
+            .await
+            .with_context(|| format!("Failed to create {}", self.tmp_dir.display()))?;
+
         let inactive_agents = {
             let mut conversation = self.conversation.write().await;
             debug!(
@@ -341,11 +621,27 @@ impl<A: Services> Orchestrator<A> {
         Ok(())
     }
     async fn sync_conversation(&self) -> anyhow::Result<()> {
-        let conversation = self.conversation.read().await.clone();
-        self.services
-            .conversation_service()
-            .upsert(conversation)
-            .await?;
+        // The lock is held until the new version is known, so that agents running
+        // in parallel don't store the same version twice
+        let mut conversation = self.conversation.write().await;
+        let service = self.services.conversation_service();
+        let mut attempts = 0;
+        let version = loop {
+            match service.upsert(conversation.clone()).await {
+                Err(error) if is_conflict(&error) && attempts < MAX_SYNC_ATTEMPTS => {
+                    // The client changed the conversation during the turn, e.g. its
+                    // mode or tools, which is kept along with the state of the agents
+                    attempts += 1;
+                    let stored = service
+                        .find(&conversation.id)
+                        .await?
+                        .context("Conversation not found")?;
+                    conversation.rebase(stored);
+                }
+                result => break result?,
+            }
+        };
+        conversation.version = version;
         Ok(())
     }
 
@@ -353,6 +649,21 @@ impl<A: Services> Orchestrator<A> {
         Ok(self.conversation.read().await.clone())
     }
 
+    /// Renders a prompt template, without the helpers that read the user's
+    /// environment and files if the workspace isn't trusted
+    async fn render(
+        &self,
+        template: &str,
+        object: &impl serde::Serialize,
+    ) -> anyhow::Result<String> {
+        let template_service = self.services.template_service();
+        if self.conversation.read().await.untrusted {
+            template_service.render_untrusted(template, object)
+        } else {
+            template_service.render(template, object)
+        }
+    }
+
     async fn complete_turn(&self, agent_id: &AgentId) -> anyhow::Result<()> {
         let mut conversation = self.conversation.write().await;
         conversation
@@ -363,6 +674,43 @@ impl<A: Services> Orchestrator<A> {
         Ok(())
     }
 
+    /// Adds the messages waiting in the inbox of the agent to its context
+    async fn receive_messages(&self, agent_id: &AgentId, context: Context) -> Context {
+        let mut conversation = self.conversation.write().await;
+        let inbox = conversation
+            .state
+            .get_mut(agent_id)
+            .map(|state| std::mem::take(&mut state.inbox))
+            .unwrap_or_default();
+        inbox
+            .into_iter()
+            .fold(context, |context, message| context.add_message(message))
+    }
+
+    /// Appends a message from outside of the conversation while the
+    /// orchestrator runs it, see `Conversation::append_message`. The message
+    /// is kept in the conversation that the orchestrator stores, instead of
+    /// the stored one that it overwrites.
+    pub async fn append_message(&self, message: ContextMessage) -> anyhow::Result<()> {
+        self.conversation.write().await.append_message(message)?;
+        Ok(())
+    }
+
+    /// Stores the conversation if messages are waiting in the inbox of its
+    /// agents, which may have been appended since it was last stored
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let conversation = self.conversation.read().await;
+        if conversation
+            .state
+            .values()
+            .all(|state| state.inbox.is_empty())
+        {
+            return Ok(());
+        }
+        drop(conversation);
+        self.sync_conversation().await
+    }
+
     async fn set_context(&self, agent_id: &AgentId, context: Context) -> anyhow::Result<()> {
         let mut conversation = self.conversation.write().await;
         conversation
@@ -374,11 +722,20 @@ impl<A: Services> Orchestrator<A> {
     }
 
     // Get the ToolCallContext for an agent
-    fn get_tool_call_context(&self, agent_id: &AgentId) -> ToolCallContext {
+    fn get_tool_call_context(&self, agent: &Agent) -> ToolCallContext {
         // Create a new ToolCallContext with the agent ID
         ToolCallContext::default()
-            .agent_id(agent_id.clone())
+            .agent_id(agent.id.clone())
             .sender(self.sender.clone())
+            .resource_limits(agent.resource_limits.clone().unwrap_or_default())
+            .egress(agent.egress.clone().unwrap_or_default())
+            .shell_policy(agent.shell_policy.clone().unwrap_or_default())
+            .approval(agent.approval.clone().unwrap_or_default())
+            .read_only(agent.read_only.unwrap_or_default())
+            .review_edits(agent.review_edits.unwrap_or_default())
+            .language_servers(agent.language_servers.clone().unwrap_or_default())
+            .custom_tools(agent.custom_tools.clone().unwrap_or_default())
+            .tmp_dir(self.tmp_dir.clone())
     }
 
     // Create a helper method with the core functionality
@@ -392,27 +749,33 @@ impl<A: Services> Orchestrator<A> {
             "Initializing agent"
         );
         let agent = conversation.get_agent(agent_id)?;
+        let tools = self.get_allowed_tools(agent, &conversation);
 
         let mut context = if agent.ephemeral.unwrap_or_default() {
-            agent.init_context(self.get_allowed_tools(agent)).await?
+            agent.init_context(tools.clone()).await?
         } else {
             match conversation.context(&agent.id) {
-                Some(context) => context.clone(),
-                None => agent.init_context(self.get_allowed_tools(agent)).await?,
+                // The tools may have been enabled or disabled since the last turn
+                Some(context) => context.clone().tools(agent.tool_definitions(tools.clone())),
+                None => agent.init_context(tools.clone()).await?,
             }
         };
 
+        self.send(agent, ChatResponse::Phase(Phase::RenderingPrompt))
+            .await?;
+
         // Render the system prompts with the variables
-        context = self.set_system_prompt(context, agent, variables).await?;
+        context = self
+            .set_system_prompt(context, agent, &tools, variables)
+            .await?;
 
         // Render user prompts
         context = self
             .set_user_prompt(context, agent, variables, event)
             .await?;
 
-        if let Some(temperature) = agent.temperature {
-            context = context.temperature(temperature);
-        }
+        context = agent.apply_temperature(context, 0);
+        context = agent.apply_model_parameters(context);
 
         // Process attachments in a more declarative way
         let attachments = self
@@ -421,24 +784,41 @@ impl<A: Services> Orchestrator<A> {
             .attachments(&event.value.to_string())
             .await?;
 
-        // Process each attachment and fold the results into the context
-        context = attachments
-            .into_iter()
-            .fold(context.clone(), |ctx, attachment| {
-                ctx.add_message(match attachment.content_type {
-                    ContentType::Image => ContextMessage::Image(attachment.content),
-                    ContentType::Text => ContextMessage::user(attachment.content),
-                })
-            });
+        // Add each attachment to the context, warning the user about text that
+        // looks like a prompt injection
+        for attachment in attachments {
+            let message = match attachment.content_type {
+                ContentType::Image => ContextMessage::Image(attachment.content),
+                ContentType::Text => {
+                    if let Some(excerpt) = detect_injection(&attachment.content) {
+                        let source = attachment.path.clone();
+                        self.send(agent, ChatResponse::PromptInjection { source, excerpt })
+                            .await?;
+                    }
+                    ContextMessage::user(attachment.content)
+                }
+            };
+            context = context.add_message(message);
+        }
 
         self.set_context(&agent.id, context.clone()).await?;
 
-        let tool_context = self.get_tool_call_context(&agent.id);
+        let tool_context = self
+            .get_tool_call_context(agent)
+            .disabled_tools(conversation.disabled_tools.clone())
+            .mode_tools(conversation.mode_tools.clone());
 
         let mut empty_tool_call_count = 0;
+        // Steps in a row in which the agent didn't make progress, which raise
+        // the temperature
+        let mut stalled_steps = 0;
+        let mut previous_tool_calls = Vec::new();
+        let mut disabled_tools = HashSet::new();
 
         while !tool_context.get_complete().await {
-            // Set context for the current loop iteration
+            // Set context for the current loop iteration, with the messages
+            // appended to the conversation since the last request
+            context = self.receive_messages(&agent.id, context).await;
             self.set_context(&agent.id, context.clone()).await?;
 
             // Determine which model to use - prefer workflow model if available, fallback
@@ -448,18 +828,38 @@ impl<A: Services> Orchestrator<A> {
                 .as_ref()
                 .ok_or(Error::MissingModel(agent.id.clone()))?;
 
-            let response = self
-                .services
-                .provider_service()
-                .chat(model_id, context.clone())
-                .await?;
-
-            let ChatCompletionResult { tool_calls, content, usage } =
-                self.collect_messages(agent, &context, response).await?;
+            // Tools that keep failing are withheld from the model until their
+            // cooldown ends, the model and the user are told once about each
+            let circuits = self.services.tool_service().open_circuits();
+            for circuit in circuits.iter() {
+                if disabled_tools.insert(circuit.name.clone()) {
+                    self.send(agent, ChatResponse::ToolDisabled(circuit.clone()))
+                        .await?;
+                    context = context.add_message(ContextMessage::user(circuit.notice()));
+                }
+            }
+            disabled_tools.retain(|name| circuits.iter().any(|circuit| circuit.name == *name));
+            let mut request = context.clone();
+            request
+                .tools
+                .retain(|tool| !disabled_tools.contains(&tool.name));
+
+            self.send(
+                agent,
+                ChatResponse::Phase(Phase::WaitingForModel(model_id.clone())),
+            )
+            .await?;
+            let started = Instant::now();
+            let ChatCompletionResult { tool_calls, content, usage, metadata } =
+                self.chat(agent, model_id, &context, request).await?;
+            let metadata = metadata.latency_ms(started.elapsed().as_millis() as u64);
+            debug!(agent_id = %agent.id, metadata = %metadata, "Response metadata");
 
             // Check if context requires compression and decide to compact
             if agent.should_compact(&context, usage.map(|usage| usage.prompt_tokens as usize)) {
                 debug!(agent_id = %agent.id, "Compaction needed, applying compaction");
+                self.send(agent, ChatResponse::Phase(Phase::Compacting))
+                    .await?;
                 context = self
                     .services
                     .compaction_service()
@@ -470,6 +870,7 @@ impl<A: Services> Orchestrator<A> {
             }
 
             let empty_tool_calls = tool_calls.is_empty();
+            let repeated_tool_calls = is_repeated(&previous_tool_calls, &tool_calls);
 
             debug!(
                 agent_id = %agent.id,
@@ -481,6 +882,7 @@ impl<A: Services> Orchestrator<A> {
             // Process tool calls and update context
             context = context.append_message(
                 content,
+                metadata,
                 self.get_all_tool_results(agent, &tool_calls, tool_context.clone())
                     .await?,
                 agent.tool_supported.unwrap_or_default(),
@@ -502,6 +904,22 @@ impl<A: Services> Orchestrator<A> {
                 }
             }
 
+            // Retrying with the same parameters usually reproduces the same answer, so
+            // diversify the next attempt until the agent makes progress again
+            stalled_steps = if empty_tool_calls || repeated_tool_calls {
+                stalled_steps + 1
+            } else {
+                0
+            };
+            context = agent.apply_temperature(context, stalled_steps);
+            debug!(
+                agent_id = %agent.id,
+                stalled_steps = stalled_steps,
+                temperature = ?context.temperature,
+                "Temperature for the next attempt"
+            );
+            previous_tool_calls = tool_calls;
+
             // Update context in the conversation
             self.set_context(&agent.id, context.clone()).await?;
             self.sync_conversation().await?;
@@ -521,11 +939,13 @@ impl<A: Services> Orchestrator<A> {
         event: &Event,
     ) -> anyhow::Result<Context> {
         let content = if let Some(user_prompt) = &agent.user_prompt {
-            let event_context = EventContext::new(event.clone()).variables(variables.clone());
+            let plan = self.conversation.read().await.plan.clone();
+            let event_context = EventContext::new(event.clone())
+                .variables(variables.clone())
+                .plan(plan.map(|plan| plan.to_string()));
             debug!(event_context = ?event_context, "Event context");
-            self.services
-                .template_service()
-                .render(user_prompt.template.as_str(), &event_context)?
+            self.render(user_prompt.template.as_str(), &event_context)
+                .await?
         } else {
             // Use the raw event value as content if no user_prompt is provided
             event.value.to_string()
@@ -543,18 +963,123 @@ impl<A: Services> Orchestrator<A> {
             let mut conversation = self.conversation.write().await;
             conversation.poll_event(agent_id)
         } {
-            RetryIf::spawn(
-                self.retry_strategy.clone().map(jitter),
-                || self.init_agent(agent_id, &event),
-                is_parse_error,
-            )
-            .await?;
+            self.init_agent(agent_id, &event).await?;
+            self.hand_over(agent_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the request to the provider and collects the response, retrying
+    /// after each delay of the agent's retry policy while the error is
+    /// retried. Only the request is retried, so that the tool calls of the
+    /// previous steps of the turn aren't executed again, and a `Retry-After`
+    /// of the provider extends the delay. Without a retry policy only the tool
+    /// calls that fail to parse are retried.
+    async fn chat(
+        &self,
+        agent: &Agent,
+        model_id: &ModelId,
+        context: &Context,
+        request: Context,
+    ) -> anyhow::Result<ChatCompletionResult> {
+        let policy = agent.retry.as_ref();
+        let delays = match policy {
+            Some(policy) => policy.delays().collect::<Vec<_>>(),
+            None => self.retry_strategy.clone().collect(),
+        };
+        let mut delays = delays.into_iter().map(jitter);
+        loop {
+            let result = async {
+                let response = self
+                    .services
+                    .provider_service()
+                    .chat(model_id, request.clone())
+                    .await?;
+                self.collect_messages(agent, context, response).await
+            }
+            .await;
+            let error = match result {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            let retried = match policy {
+                Some(policy) => is_retried(&agent.id, policy, &error),
+                None => is_parse_error(&error),
+            };
+            let Some(delay) = delays.next().filter(|_| retried) else {
+                return Err(error);
+            };
+            let delay = RetryOn::retry_after(&error).map_or(delay, |after| after.max(delay));
+            // The text of the failed attempt was already streamed to the client
+            self.send(agent, ChatResponse::Retry { cause: error.to_string() })
+                .await?;
+            tokio::time::sleep(delay).await;
         }
+    }
+
+    /// Dispatches the events of the handovers of the agent whose conditions
+    /// hold after its turn, with the output of the turn as their value. Events
+    /// that no agent receives under the priority rules of the subscriptions
+    /// are skipped.
+    #[async_recursion]
+    async fn hand_over(&self, agent_id: &AgentId) -> anyhow::Result<()> {
+        let events = {
+            let conversation = self.conversation.read().await;
+            let agent = conversation.get_agent(agent_id)?;
+            let output = conversation
+                .context(agent_id)
+                .and_then(|context| context.last_output())
+                .unwrap_or_default();
+            let mut events = Vec::new();
+            for handover in agent.handovers.iter().flatten() {
+                if !handover.applies(&conversation.variables, &output)? {
+                    continue;
+                }
+                if conversation.subscriptions(&handover.event).is_empty() {
+                    tracing::warn!(agent = %agent_id, event = %handover.event, "No agent receives the handover");
+                    continue;
+                }
+                debug!(agent = %agent_id, event = %handover.event, "Handing over");
+                events.push(Event::new(&handover.event, output.clone()));
+            }
+            events
+        };
 
+        for event in events {
+            self.nesting.hand_over(agent_id, &event.name)?;
+            self.dispatch(event).await?;
+        }
         Ok(())
     }
 }
 
+/// Returns true when the model made exactly the same tool calls as in the
+/// previous iteration
+fn is_repeated(previous: &[ToolCallFull], current: &[ToolCallFull]) -> bool {
+    !current.is_empty()
+        && previous.len() == current.len()
+        && previous
+            .iter()
+            .zip(current)
+            .all(|(a, b)| a.name == b.name && a.arguments == b.arguments)
+}
+
+fn is_retried(agent_id: &AgentId, policy: &RetryPolicy, error: &anyhow::Error) -> bool {
+    let retried = policy.retries(error);
+    if retried {
+        tracing::warn!(agent = %agent_id, error = %error, "Retrying the request of the agent");
+    }
+    retried
+}
+
+fn is_conflict(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ConversationConflict(..))
+    )
+}
+
 fn is_parse_error(error: &anyhow::Error) -> bool {
     let check = error
         .downcast_ref::<Error>()
@@ -572,3 +1097,271 @@ fn is_parse_error(error: &anyhow::Error) -> bool {
 
     check
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use pretty_assertions::assert_eq;
+    use url::Url;
+
+    use super::*;
+
+    /// Services of which only the provider answers, with the given responses
+    /// one request after another
+    #[derive(Clone)]
+    struct Stub {
+        responses: Arc<Mutex<Vec<Vec<anyhow::Result<ChatCompletionMessage>>>>>,
+    }
+
+    impl Stub {
+        fn new(responses: Vec<Vec<anyhow::Result<ChatCompletionMessage>>>) -> Self {
+            Self { responses: Arc::new(Mutex::new(responses)) }
+        }
+    }
+
+    impl Services for Stub {
+        type ToolService = Stub;
+        type ProviderService = Stub;
+        type ConversationService = Stub;
+        type TemplateService = Stub;
+        type AttachmentService = Stub;
+        type EnvironmentService = Stub;
+        type CompactionService = Stub;
+        type WorkflowService = Stub;
+        type SuggestionService = Stub;
+        type RegistryService = Stub;
+
+        fn tool_service(&self) -> &Self::ToolService {
+            self
+        }
+        fn provider_service(&self) -> &Self::ProviderService {
+            self
+        }
+        fn conversation_service(&self) -> &Self::ConversationService {
+            self
+        }
+        fn template_service(&self) -> &Self::TemplateService {
+            self
+        }
+        fn attachment_service(&self) -> &Self::AttachmentService {
+            self
+        }
+        fn environment_service(&self) -> &Self::EnvironmentService {
+            self
+        }
+        fn compaction_service(&self) -> &Self::CompactionService {
+            self
+        }
+        fn workflow_service(&self) -> &Self::WorkflowService {
+            self
+        }
+        fn suggestion_service(&self) -> &Self::SuggestionService {
+            self
+        }
+        fn registry_service(&self) -> &Self::RegistryService {
+            self
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ProviderService for Stub {
+        async fn chat(
+            &self,
+            _id: &ModelId,
+            _context: Context,
+        ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+            let messages = self.responses.lock().unwrap().remove(0);
+            Ok(Box::pin(tokio_stream::iter(messages)))
+        }
+
+        async fn models(&self) -> anyhow::Result<Vec<Model>> {
+            unimplemented!()
+        }
+    }
+
+    impl EnvironmentService for Stub {
+        fn get_environment(&self) -> Environment {
+            Environment {
+                os: std::env::consts::OS.to_string(),
+                pid: std::process::id(),
+                cwd: std::env::current_dir().unwrap_or_default(),
+                home: None,
+                shell: "/bin/sh".to_string(),
+                base_path: PathBuf::new(),
+                provider: Provider::anthropic("test-key"),
+                retry_config: Default::default(),
+                timeout_config: Default::default(),
+                registry_url: Url::parse(DEFAULT_REGISTRY_URL).unwrap(),
+                shell_timeout: 240,
+                tool_timeout: 300,
+                max_tool_result_chars: 50_000,
+                default_mode: None,
+                low_bandwidth: false,
+                notify_after: None,
+                template_env: vec![],
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ToolService for Stub {
+        async fn call(&self, _context: ToolCallContext, _call: &ToolCallFull) -> ToolResult {
+            unimplemented!()
+        }
+
+        fn list(&self) -> Vec<ToolDefinition> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ConversationService for Stub {
+        async fn find(&self, _id: &ConversationId) -> anyhow::Result<Option<Conversation>> {
+            unimplemented!()
+        }
+
+        async fn upsert(&self, _conversation: Conversation) -> anyhow::Result<u64> {
+            unimplemented!()
+        }
+
+        async fn create(&self, _workflow: Workflow) -> anyhow::Result<Conversation> {
+            unimplemented!()
+        }
+
+        async fn update<F, T>(&self, _id: &ConversationId, _f: F) -> anyhow::Result<T>
+        where
+            F: FnOnce(&mut Conversation) -> T + Send,
+        {
+            unimplemented!()
+        }
+
+        async fn compact_conversation(
+            &self,
+            _id: &ConversationId,
+        ) -> anyhow::Result<CompactionResult> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TemplateService for Stub {
+        fn render(
+            &self,
+            _template: impl ToString,
+            _object: &impl serde::Serialize,
+        ) -> anyhow::Result<String> {
+            unimplemented!()
+        }
+
+        fn render_untrusted(
+            &self,
+            _template: impl ToString,
+            _object: &impl serde::Serialize,
+        ) -> anyhow::Result<String> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl AttachmentService for Stub {
+        async fn attachments(&self, _url: &str) -> anyhow::Result<Vec<Attachment>> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CompactionService for Stub {
+        async fn compact_context(
+            &self,
+            _agent: &Agent,
+            _context: Context,
+        ) -> anyhow::Result<Context> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl WorkflowService for Stub {
+        async fn resolve(&self, _path: Option<PathBuf>) -> PathBuf {
+            unimplemented!()
+        }
+
+        async fn read(&self, _path: Option<&Path>) -> anyhow::Result<Workflow> {
+            unimplemented!()
+        }
+
+        async fn write(&self, _path: Option<&Path>, _workflow: &Workflow) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn update_workflow<F>(&self, _path: Option<&Path>, _f: F) -> anyhow::Result<Workflow>
+        where
+            F: FnOnce(&mut Workflow) + Send,
+        {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl SuggestionService for Stub {
+        async fn suggestions(&self) -> anyhow::Result<Vec<File>> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RegistryService for Stub {
+        async fn packages(&self) -> anyhow::Result<Vec<AgentPackage>> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_retry_discards_partial_text() {
+        let services = Stub::new(vec![
+            vec![
+                Ok(ChatCompletionMessage::assistant(Content::part("Hel"))),
+                Err(anyhow::anyhow!(
+                    "invalid status code: 503 Service Unavailable"
+                )),
+            ],
+            vec![Ok(ChatCompletionMessage::assistant(Content::part("Hello")))],
+        ]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let conversation = Conversation::new(ConversationId::generate(), Workflow::new());
+        let fixture = Orchestrator::new(Arc::new(services), conversation, Some(Arc::new(tx)));
+        let agent = Agent::new("coder").retry(RetryPolicy::default().backoff_ms(1));
+        let context = Context::default();
+
+        let actual = fixture
+            .chat(
+                &agent,
+                &ModelId::new("test-model"),
+                &context,
+                context.clone(),
+            )
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            match message.unwrap().message {
+                ChatResponse::Text { text, is_complete, .. } => {
+                    events.push(format!("text({text}, {is_complete})"))
+                }
+                ChatResponse::Retry { .. } => events.push("retry".to_string()),
+                _ => {}
+            }
+        }
+        let expected = vec![
+            "text(Hel, false)".to_string(),
+            "retry".to_string(),
+            "text(Hello, false)".to_string(),
+            "text(Hello, true)".to_string(),
+        ];
+        assert_eq!(events, expected);
+        assert_eq!(actual.content, "Hello");
+    }
+}
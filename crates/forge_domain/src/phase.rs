@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ModelId, ToolName};
+
+/// Step of the orchestration that forge is currently performing, used to tell
+/// the user what it's doing while no output is shown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    RenderingPrompt,
+    WaitingForModel(ModelId),
+    ExecutingTool(ToolName),
+    Compacting,
+}
+
+impl Phase {
+    /// Name of the phase used to configure its message
+    pub fn key(&self) -> &'static str {
+        match self {
+            Phase::RenderingPrompt => "rendering_prompt",
+            Phase::WaitingForModel(_) => "waiting_for_model",
+            Phase::ExecutingTool(_) => "executing_tool",
+            Phase::Compacting => "compacting",
+        }
+    }
+
+    fn default_message(&self) -> &'static str {
+        match self {
+            Phase::RenderingPrompt => "Rendering prompt",
+            Phase::WaitingForModel(_) => "Waiting for {model}",
+            Phase::ExecutingTool(_) => "Running {tool}",
+            Phase::Compacting => "Compacting",
+        }
+    }
+}
+
+/// Configuration of the spinner shown while forge is working
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Merge, JsonSchema)]
+pub struct SpinnerConfig {
+    /// Flag to show the spinner, enabled by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub enabled: Option<bool>,
+
+    /// Messages shown for each phase, keyed by its name: `rendering_prompt`,
+    /// `waiting_for_model`, `executing_tool` or `compacting`. `{model}` and
+    /// `{tool}` are replaced with the model and the tool in use.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub messages: BTreeMap<String, String>,
+}
+
+impl SpinnerConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Returns the message shown while the phase is in progress
+    pub fn message(&self, phase: &Phase) -> String {
+        let template = self
+            .messages
+            .get(phase.key())
+            .map(String::as_str)
+            .unwrap_or(phase.default_message());
+        match phase {
+            Phase::WaitingForModel(model) => template.replace("{model}", model.as_str()),
+            Phase::ExecutingTool(tool) => template.replace("{tool}", tool.as_str()),
+            Phase::RenderingPrompt | Phase::Compacting => template.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_default_messages() {
+        let fixture = SpinnerConfig::default();
+
+        assert!(fixture.is_enabled());
+        assert_eq!(fixture.message(&Phase::RenderingPrompt), "Rendering prompt");
+        assert_eq!(
+            fixture.message(&Phase::WaitingForModel(ModelId::new("gpt-4o"))),
+            "Waiting for gpt-4o"
+        );
+        assert_eq!(
+            fixture.message(&Phase::ExecutingTool(ToolName::new("forge_tool_fs_read"))),
+            "Running forge_tool_fs_read"
+        );
+    }
+
+    #[test]
+    fn test_custom_messages() {
+        let fixture: SpinnerConfig = serde_yml::from_str(
+            "enabled: false\nmessages:\n  executing_tool: \"Using {tool}\"\n  compacting: Summarizing",
+        )
+        .unwrap();
+
+        assert!(!fixture.is_enabled());
+        assert_eq!(
+            fixture.message(&Phase::ExecutingTool(ToolName::new("forge_tool_fs_read"))),
+            "Using forge_tool_fs_read"
+        );
+        assert_eq!(fixture.message(&Phase::Compacting), "Summarizing");
+        assert_eq!(fixture.message(&Phase::RenderingPrompt), "Rendering prompt");
+    }
+}
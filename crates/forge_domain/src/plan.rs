@@ -0,0 +1,459 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use derive_setters::Setters;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, NamedTool, ToolCallFull, ToolDefinition, ToolName};
+
+/// Tools whose successful calls may write a plan file
+const PLAN_WRITERS: [&str; 2] = ["forge_tool_fs_create", "forge_tool_fs_patch"];
+
+/// Directory where the planning agent documents its plans
+const PLANS_DIR: &str = "plans";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Completed,
+}
+
+impl StepStatus {
+    /// Parses the status written in a plan file, e.g. `Not Started`, `In
+    /// Progress` or `Completed`
+    fn parse(value: &str) -> Self {
+        let value = value.trim().trim_matches(['[', ']', '*']).to_lowercase();
+        if value.starts_with("completed") || value.starts_with("done") {
+            StepStatus::Completed
+        } else if value.starts_with("in progress") || value.starts_with("in_progress") {
+            StepStatus::InProgress
+        } else {
+            StepStatus::Pending
+        }
+    }
+
+    fn marker(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "[ ]",
+            StepStatus::InProgress => "[~]",
+            StepStatus::Completed => "[x]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub description: String,
+    #[serde(default)]
+    pub status: StepStatus,
+}
+
+impl PlanStep {
+    pub fn new(description: impl ToString) -> Self {
+        Self {
+            description: description.to_string(),
+            status: StepStatus::Pending,
+        }
+    }
+}
+
+/// The steps of the plan produced in Plan mode. It is kept on the
+/// conversation so that Act mode can carry it out and track its progress.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Setters)]
+#[setters(into, strip_option)]
+pub struct Plan {
+    /// The file the plan was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    /// Extracts the steps of a plan documented in Markdown. Steps are the
+    /// numbered items of the `Implementation Plan` section, or of the whole
+    /// document when there is no such section, and top-level checklist items.
+    /// A nested `Status:` item sets the status of its step. Returns `None`
+    /// when the document has no steps.
+    pub fn parse(markdown: &str) -> Option<Self> {
+        let section = implementation_section(markdown).unwrap_or(markdown);
+        let mut steps: Vec<PlanStep> = Vec::new();
+
+        for line in section.lines() {
+            if line.starts_with(char::is_whitespace) {
+                let item = line
+                    .trim_start()
+                    .trim_start_matches(['-', '*'])
+                    .trim_start();
+                let status = item
+                    .get(..7)
+                    .filter(|prefix| prefix.eq_ignore_ascii_case("status:"))
+                    .map(|_| StepStatus::parse(&item[7..]));
+                if let (Some(status), Some(step)) = (status, steps.last_mut()) {
+                    step.status = status;
+                }
+            } else if let Some(step) = checklist_item(line).or_else(|| numbered_item(line)) {
+                steps.push(step);
+            }
+        }
+
+        (!steps.is_empty()).then_some(Self { path: None, steps })
+    }
+
+    /// Returns the plan file written by the tool call, if any
+    pub fn written_file(tool_call: &ToolCallFull) -> Option<PathBuf> {
+        if !PLAN_WRITERS.contains(&tool_call.name.as_str()) {
+            return None;
+        }
+        let path = Path::new(tool_call.arguments.get("path")?.as_str()?);
+        let in_plans_dir = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .is_some_and(|name| name == PLANS_DIR);
+        let is_markdown = path.extension().is_some_and(|ext| ext == "md");
+        (in_plans_dir && is_markdown).then(|| path.to_path_buf())
+    }
+
+    /// Sets the status of a step, numbered from 1
+    pub fn set_status(&mut self, step: usize, status: StepStatus) -> crate::Result<()> {
+        let count = self.steps.len();
+        let step = step
+            .checked_sub(1)
+            .and_then(|index| self.steps.get_mut(index))
+            .ok_or(Error::PlanStepUndefined(step, count))?;
+        step.status = status;
+        Ok(())
+    }
+
+    /// Number of completed steps
+    pub fn completed(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| step.status == StepStatus::Completed)
+            .count()
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{} {}. {}",
+                step.status.marker(),
+                index + 1,
+                step.description
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the content of the `Implementation Plan` section
+fn implementation_section(markdown: &str) -> Option<&str> {
+    let mut offset = 0;
+    let mut start = None;
+    for line in markdown.split_inclusive('\n') {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if let Some(start) = start {
+                return Some(&markdown[start..offset]);
+            }
+            if heading.trim().eq_ignore_ascii_case("implementation plan") {
+                start = Some(offset + line.len());
+            }
+        }
+        offset += line.len();
+    }
+    start.map(|start| &markdown[start..])
+}
+
+/// Parses an item like `1. **Add the parser**`
+fn numbered_item(line: &str) -> Option<PlanStep> {
+    let (number, description) = line.split_once(". ")?;
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let description = description.trim().trim_matches('*').trim();
+    (!description.is_empty()).then(|| PlanStep::new(description))
+}
+
+/// Parses an item like `- [x] Add the parser`
+fn checklist_item(line: &str) -> Option<PlanStep> {
+    let item = line
+        .strip_prefix("- [")
+        .or_else(|| line.strip_prefix("* ["))?;
+    let (mark, description) = item.split_once(']')?;
+    let status = match mark {
+        "x" | "X" => StepStatus::Completed,
+        "~" | "-" => StepStatus::InProgress,
+        _ => StepStatus::Pending,
+    };
+    let description = description.trim().trim_matches('*').trim();
+    (!description.is_empty()).then(|| PlanStep { description: description.to_string(), status })
+}
+
+/// Operations of the task-list tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, JsonSchema, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskAction {
+    Add,
+    Update,
+    List,
+}
+
+/// Input for the task list of the conversation, which is kept as the steps of
+/// its plan. The operations are applied by the orchestrator.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct TaskList {
+    /// The operation to perform: `add` appends a task, `update` changes the
+    /// status of a task and `list` returns the tasks.
+    pub action: TaskAction,
+    /// Description of the task to add. Required by `add`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Number of the task to update, starting at 1. Required by `update`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<usize>,
+    /// The new status of the task, one of `pending`, `in_progress` or
+    /// `completed`. Required by `update`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<StepStatus>,
+}
+
+impl NamedTool for TaskList {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_task_list")
+    }
+}
+
+impl TaskList {
+    pub fn tool_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: Self::tool_name(),
+            description: "Manages the list of tasks you are working through and returns the \
+                          updated list. Break multi-step work down into tasks with `add`, mark \
+                          a task as in_progress with `update` when you start working on it and \
+                          as completed once it is done. Use `list` to review the tasks and \
+                          their status."
+                .to_string(),
+            input_schema: schema_for!(TaskList),
+            output_schema: None,
+            mutating: false,
+        }
+    }
+
+    /// Parses the tool call into a task-list operation. Returns `None` if the
+    /// call is meant for a different tool.
+    pub fn parse(tool_call: &ToolCallFull) -> Option<crate::Result<Self>> {
+        if tool_call.name != Self::tool_name() {
+            return None;
+        }
+
+        Some(serde_json::from_value(tool_call.arguments.clone()).map_err(Error::ToolCallArgument))
+    }
+
+    /// Applies the operation to the plan of the conversation, creating the
+    /// plan when the first task is added, and returns the rendered list
+    pub fn apply(&self, plan: &mut Option<Plan>) -> crate::Result<String> {
+        match self.action {
+            TaskAction::Add => {
+                let task = self
+                    .task
+                    .as_ref()
+                    .filter(|task| !task.trim().is_empty())
+                    .ok_or(Error::TaskArgument("task", "add"))?;
+                plan.get_or_insert_default()
+                    .steps
+                    .push(PlanStep::new(task.trim()));
+            }
+            TaskAction::Update => {
+                let number = self.number.ok_or(Error::TaskArgument("number", "update"))?;
+                let status = self.status.ok_or(Error::TaskArgument("status", "update"))?;
+                plan.as_mut()
+                    .ok_or(Error::PlanUndefined)?
+                    .set_status(number, status)?;
+            }
+            TaskAction::List => {}
+        }
+
+        Ok(plan
+            .as_ref()
+            .map(|plan| plan.to_string())
+            .unwrap_or_else(|| "The task list is empty".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    const FIXTURE: &str = r#"# Add retries
+
+## Objective
+Retry failed requests.
+
+## Implementation Plan
+1. **Add the retry configuration**
+  - Dependencies: None
+  - Status: Completed
+2. **Wrap the provider calls**
+  - Dependencies: Task 1
+  - Status: In Progress
+3. **Document the options**
+  - Status: Not Started
+
+## Potential Risks and Mitigations
+1. **Retrying non-idempotent requests**
+  Mitigation: Only retry on transport errors
+"#;
+
+    #[test]
+    fn test_parse() {
+        let actual = Plan::parse(FIXTURE).unwrap();
+        let expected = Plan {
+            path: None,
+            steps: vec![
+                PlanStep {
+                    description: "Add the retry configuration".to_string(),
+                    status: StepStatus::Completed,
+                },
+                PlanStep {
+                    description: "Wrap the provider calls".to_string(),
+                    status: StepStatus::InProgress,
+                },
+                PlanStep::new("Document the options"),
+            ],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_checklist() {
+        let fixture = "Steps:\n- [x] Read the code\n- [ ] Fix the bug\n  - [ ] nested\n";
+        let actual = Plan::parse(fixture).unwrap().steps;
+        let expected = vec![
+            PlanStep {
+                description: "Read the code".to_string(),
+                status: StepStatus::Completed,
+            },
+            PlanStep::new("Fix the bug"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_without_steps() {
+        let actual = Plan::parse("# Notes\n\nNothing to do.");
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn test_set_status() {
+        let mut fixture = Plan::parse(FIXTURE).unwrap();
+
+        fixture.set_status(3, StepStatus::InProgress).unwrap();
+        assert_eq!(fixture.steps[2].status, StepStatus::InProgress);
+
+        assert!(fixture.set_status(0, StepStatus::Completed).is_err());
+        assert!(fixture.set_status(4, StepStatus::Completed).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let actual = Plan::parse(FIXTURE).unwrap().to_string();
+        let expected = "[x] 1. Add the retry configuration\n[~] 2. Wrap the provider calls\n[ ] 3. Document the options";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_written_file() {
+        let fixture = ToolCallFull::new(ToolName::new("forge_tool_fs_create"))
+            .arguments(json!({"path": "/project/plans/2025-04-01-retries-v1.md", "content": ""}));
+        let actual = Plan::written_file(&fixture);
+        let expected = Some(PathBuf::from("/project/plans/2025-04-01-retries-v1.md"));
+        assert_eq!(actual, expected);
+
+        let fixture = ToolCallFull::new(ToolName::new("forge_tool_fs_create"))
+            .arguments(json!({"path": "/project/src/plans.md", "content": ""}));
+        assert_eq!(Plan::written_file(&fixture), None);
+
+        let fixture = ToolCallFull::new(ToolName::new("forge_tool_fs_read"))
+            .arguments(json!({"path": "/project/plans/2025-04-01-retries-v1.md"}));
+        assert_eq!(Plan::written_file(&fixture), None);
+    }
+
+    #[test]
+    fn test_parse_task_list() {
+        let fixture = ToolCallFull::new(TaskList::tool_name())
+            .arguments(json!({"action": "update", "number": 2, "status": "completed"}));
+        let actual = TaskList::parse(&fixture).unwrap().unwrap();
+        let expected = TaskList {
+            action: TaskAction::Update,
+            task: None,
+            number: Some(2),
+            status: Some(StepStatus::Completed),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_task_list_apply() {
+        let add = |task: &str| TaskList {
+            action: TaskAction::Add,
+            task: Some(task.to_string()),
+            number: None,
+            status: None,
+        };
+        let mut plan = None;
+
+        add("Write the test").apply(&mut plan).unwrap();
+        add("Fix the bug").apply(&mut plan).unwrap();
+        let actual = TaskList {
+            action: TaskAction::Update,
+            task: None,
+            number: Some(1),
+            status: Some(StepStatus::Completed),
+        }
+        .apply(&mut plan)
+        .unwrap();
+
+        let expected = "[x] 1. Write the test\n[ ] 2. Fix the bug";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_task_list_apply_missing_argument() {
+        let fixture = TaskList {
+            action: TaskAction::Update,
+            task: None,
+            number: Some(1),
+            status: None,
+        };
+        let mut plan = Plan::parse("- [ ] Fix the bug");
+
+        let actual = fixture.apply(&mut plan).unwrap_err().to_string();
+        let expected = "Missing argument 'status' for the 'update' action";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_task_list_apply_without_plan() {
+        let fixture = TaskList {
+            action: TaskAction::List,
+            task: None,
+            number: None,
+            status: None,
+        };
+        let actual = fixture.apply(&mut None).unwrap();
+        assert_eq!(actual, "The task list is empty");
+    }
+}
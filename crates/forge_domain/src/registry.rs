@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::ToolName;
+
+/// Default location of the community registry index
+pub const DEFAULT_REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/antinomyhq/forge-registry/main/index.json";
+
+/// Index of community published agent and workflow packages
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    #[serde(default)]
+    pub packages: Vec<AgentPackage>,
+}
+
+/// A single package listed in the registry index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentPackage {
+    /// Unique name of the package
+    pub name: String,
+    /// Short description of what the package does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Location of the workflow file that the package provides
+    pub workflow: Url,
+    /// Tools that the package requires to be available
+    #[serde(default)]
+    pub tools: Vec<ToolName>,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_registry_index() {
+        let fixture = r#"{
+            "packages": [
+                {
+                    "name": "reviewer",
+                    "description": "Reviews pull requests",
+                    "workflow": "https://example.com/reviewer.yaml",
+                    "tools": ["forge_tool_fs_read"]
+                },
+                {
+                    "name": "minimal",
+                    "workflow": "https://example.com/minimal.yaml"
+                }
+            ]
+        }"#;
+
+        let actual: RegistryIndex = serde_json::from_str(fixture).unwrap();
+
+        let expected = RegistryIndex {
+            packages: vec![
+                AgentPackage {
+                    name: "reviewer".to_string(),
+                    description: Some("Reviews pull requests".to_string()),
+                    workflow: Url::parse("https://example.com/reviewer.yaml").unwrap(),
+                    tools: vec![ToolName::new("forge_tool_fs_read")],
+                },
+                AgentPackage {
+                    name: "minimal".to_string(),
+                    description: None,
+                    workflow: Url::parse("https://example.com/minimal.yaml").unwrap(),
+                    tools: vec![],
+                },
+            ],
+        };
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,76 @@
+use derive_more::derive::Display;
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Limits applied to processes spawned by the shell tool. Limits that can't be
+/// enforced on the current platform are ignored.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Merge, Setters, JsonSchema,
+)]
+#[setters(strip_option)]
+pub struct ResourceLimits {
+    /// Maximum amount of memory in megabytes that a command may allocate,
+    /// enforced as the address space limit (`RLIMIT_AS`) of each process on
+    /// Unix. Cgroups and job objects aren't used, so the limit isn't enforced
+    /// on Windows and doesn't cover the processes of a command together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_memory_mb: Option<u64>,
+
+    /// Maximum CPU time in seconds that a command may consume
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_cpu_secs: Option<u64>,
+
+    /// Maximum number of bytes captured from each of stdout and stderr, the
+    /// command is terminated once it produces more
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_output_bytes: Option<usize>,
+}
+
+/// The resource limit that a command was terminated for exceeding. A process
+/// that runs out of memory can't be told apart from one that crashed, so the
+/// memory limit is only reported as a possible cause along with the signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display)]
+pub enum ResourceViolation {
+    #[display("killed by signal {signal}, possibly for exceeding the memory limit")]
+    PossiblyMemory { signal: i32 },
+    #[display("CPU time limit exceeded")]
+    CpuTime,
+    #[display("output size limit exceeded")]
+    OutputSize,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_resource_limits() {
+        let mut fixture = ResourceLimits::default()
+            .max_memory_mb(512)
+            .max_cpu_secs(10);
+        fixture.merge(ResourceLimits::default().max_cpu_secs(60));
+
+        let expected = ResourceLimits::default()
+            .max_memory_mb(512)
+            .max_cpu_secs(60);
+        assert_eq!(fixture, expected);
+    }
+
+    #[test]
+    fn test_parse_resource_limits() {
+        let fixture = "max_memory_mb: 1024\nmax_output_bytes: 65536\n";
+        let actual: ResourceLimits = serde_yml::from_str(fixture).unwrap();
+
+        let expected = ResourceLimits::default()
+            .max_memory_mb(1024)
+            .max_output_bytes(65536);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+
+use derive_setters::Setters;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatCompletionMessage, FinishReason};
+
+/// Details reported by the provider about how an assistant message was
+/// generated. The model that was actually served can differ from the
+/// requested one when a router such as OpenRouter falls back to another
+/// upstream.
+#[derive(Default, Clone, Debug, Deserialize, PartialEq, Serialize, Setters)]
+#[setters(strip_option, into)]
+pub struct ResponseMetadata {
+    /// The model that generated the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The upstream provider that served the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+    /// Time from sending the request until the response was complete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl ResponseMetadata {
+    /// Collects the metadata reported across the chunks of a streamed
+    /// response, later chunks taking precedence
+    pub fn from_messages<'a>(
+        messages: impl IntoIterator<Item = &'a ChatCompletionMessage>,
+    ) -> Self {
+        messages
+            .into_iter()
+            .fold(Self::default(), |metadata, message| Self {
+                model: message.model.clone().or(metadata.model),
+                provider: message.provider.clone().or(metadata.provider),
+                finish_reason: message.finish_reason.clone().or(metadata.finish_reason),
+                latency_ms: metadata.latency_ms,
+            })
+    }
+}
+
+impl fmt::Display for ResponseMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(model) = &self.model {
+            parts.push(format!("model: {model}"));
+        }
+        if let Some(provider) = &self.provider {
+            parts.push(format!("provider: {provider}"));
+        }
+        if let Some(finish_reason) = &self.finish_reason {
+            parts.push(format!("finish reason: {finish_reason:?}"));
+        }
+        if let Some(latency_ms) = self.latency_ms {
+            parts.push(format!("latency: {latency_ms}ms"));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Content;
+
+    #[test]
+    fn test_from_messages() {
+        let fixture = vec![
+            ChatCompletionMessage::assistant(Content::part("Hello"))
+                .model("openai/gpt-4o")
+                .provider("OpenAI"),
+            ChatCompletionMessage::assistant(Content::part(" world")),
+            ChatCompletionMessage::assistant(Content::part(""))
+                .model("openai/gpt-4o-mini")
+                .finish_reason(FinishReason::Stop),
+        ];
+
+        let actual = ResponseMetadata::from_messages(&fixture);
+        let expected = ResponseMetadata::default()
+            .model("openai/gpt-4o-mini")
+            .provider("OpenAI")
+            .finish_reason(FinishReason::Stop);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_display() {
+        let fixture = ResponseMetadata::default()
+            .model("claude-3-7-sonnet")
+            .finish_reason(FinishReason::ToolCalls)
+            .latency_ms(1200u64);
+
+        let actual = fixture.to_string();
+        let expected = "model: claude-3-7-sonnet, finish reason: ToolCalls, latency: 1200ms";
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,232 @@
+use std::time::Duration;
+
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+const DEFAULT_ATTEMPTS: usize = 3;
+const DEFAULT_BACKOFF_MS: u64 = 1000;
+const DEFAULT_BACKOFF_FACTOR: u64 = 2;
+
+/// Longest wait a provider can ask for with `Retry-After` before a retry, so
+/// that a request doesn't hang for hours
+const MAX_RETRY_AFTER_SECS: u64 = 300;
+
+/// How the requests of an agent to the provider are retried when they fail,
+/// e.g. because the provider is rate limiting them, instead of failing the
+/// whole run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option)]
+pub struct RetryPolicy {
+    /// Number of times a failed request is retried, 3 by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<usize>,
+
+    /// Delay before the first retry in milliseconds, 1000 by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_ms: Option<u64>,
+
+    /// Factor the delay is multiplied by after each retry, 2 by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_factor: Option<u64>,
+
+    /// Failures that are retried, all of them by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_on: Option<Vec<RetryOn>>,
+}
+
+/// Class of failures of a turn that are usually transient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryOn {
+    /// The provider answered with 429 Too Many Requests
+    RateLimit,
+    /// The provider answered with a 5xx status code
+    ServerError,
+    /// The provider didn't respond in time
+    Timeout,
+    /// The connection to the provider failed
+    Network,
+    /// The model produced a tool call that couldn't be parsed
+    Parse,
+}
+
+impl RetryOn {
+    /// The class of the failure, None if it isn't transient
+    pub fn classify(error: &anyhow::Error) -> Option<Self> {
+        if let Some(error) = error.downcast_ref::<Error>() {
+            match error {
+                Error::ToolCallParse(_)
+                | Error::ToolCallArgument(_)
+                | Error::ToolCallMissingName => return Some(RetryOn::Parse),
+                Error::ProviderFirstTokenTimeout(_) | Error::ProviderStreamTimeout(_) => {
+                    return Some(RetryOn::Timeout)
+                }
+                _ => {}
+            }
+        }
+
+        // Errors of the HTTP client only reach the domain as their messages
+        error.chain().find_map(|cause| {
+            let message = cause.to_string().to_lowercase();
+            if let Some(status) = status_code(&message) {
+                return match status {
+                    429 => Some(RetryOn::RateLimit),
+                    408 => Some(RetryOn::Timeout),
+                    500..=599 => Some(RetryOn::ServerError),
+                    _ => None,
+                };
+            }
+            if message.contains("timed out") {
+                return Some(RetryOn::Timeout);
+            }
+            ["error sending request", "connection", "transport error"]
+                .iter()
+                .any(|pattern| message.contains(pattern))
+                .then_some(RetryOn::Network)
+        })
+    }
+
+    /// How long the provider asked to wait before retrying, from the
+    /// `Retry-After` of an `Invalid status code: 429 ... Retry-After: 30 ...`
+    /// error
+    pub fn retry_after(error: &anyhow::Error) -> Option<Duration> {
+        error.chain().find_map(|cause| {
+            let message = cause.to_string().to_lowercase();
+            let (_, rest) = message.split_once("retry-after: ")?;
+            let digits = rest
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>();
+            let seconds = digits.parse::<u64>().ok()?;
+            Some(Duration::from_secs(seconds.min(MAX_RETRY_AFTER_SECS)))
+        })
+    }
+}
+
+/// The status code of an `Invalid status code: 429 ...` error of a provider
+fn status_code(message: &str) -> Option<u16> {
+    let (_, rest) = message.split_once("invalid status code: ")?;
+    let digits = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+impl RetryPolicy {
+    /// Delays before each retry
+    pub fn delays(&self) -> impl Iterator<Item = Duration> {
+        let backoff_ms = self.backoff_ms.unwrap_or(DEFAULT_BACKOFF_MS);
+        let factor = self.backoff_factor.unwrap_or(DEFAULT_BACKOFF_FACTOR);
+        (0..self.attempts.unwrap_or(DEFAULT_ATTEMPTS)).map(move |retry| {
+            let factor = factor.saturating_pow(retry.try_into().unwrap_or(u32::MAX));
+            Duration::from_millis(backoff_ms.saturating_mul(factor))
+        })
+    }
+
+    /// Whether the request that failed with the error is retried
+    pub fn retries(&self, error: &anyhow::Error) -> bool {
+        RetryOn::classify(error).is_some_and(|class| {
+            self.retry_on
+                .as_ref()
+                .is_none_or(|classes| classes.contains(&class))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let fixtures = [
+            (
+                anyhow::anyhow!("Invalid status code: 429 Too Many Requests Reason: slow down"),
+                Some(RetryOn::RateLimit),
+            ),
+            (
+                anyhow::anyhow!("Invalid status code: 503 Service Unavailable, reason: busy")
+                    .context("POST https://openrouter.ai/api/v1/chat/completions"),
+                Some(RetryOn::ServerError),
+            ),
+            (
+                anyhow::anyhow!("Invalid status code: 401 Unauthorized, reason: bad key"),
+                None,
+            ),
+            (
+                Error::ProviderFirstTokenTimeout(30).into(),
+                Some(RetryOn::Timeout),
+            ),
+            (
+                anyhow::anyhow!("error sending request for url (https://api.anthropic.com)"),
+                Some(RetryOn::Network),
+            ),
+            (Error::ToolCallMissingName.into(), Some(RetryOn::Parse)),
+            (Error::HeadAgentUndefined.into(), None),
+        ];
+        for (error, expected) in fixtures {
+            assert_eq!(RetryOn::classify(&error), expected, "{error:#}");
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let fixture = anyhow::anyhow!(
+            "Invalid status code: 429 Too Many Requests Retry-After: 30 Reason: slow down"
+        );
+        assert_eq!(
+            RetryOn::retry_after(&fixture),
+            Some(Duration::from_secs(30))
+        );
+
+        let fixture =
+            anyhow::anyhow!("Invalid status code: 429 Too Many Requests Retry-After: 86400");
+        assert_eq!(
+            RetryOn::retry_after(&fixture),
+            Some(Duration::from_secs(300))
+        );
+
+        let fixture =
+            anyhow::anyhow!("Invalid status code: 429 Too Many Requests Reason: slow down");
+        assert_eq!(RetryOn::retry_after(&fixture), None);
+    }
+
+    #[test]
+    fn test_retries() {
+        let fixture = RetryPolicy::default().retry_on(vec![RetryOn::RateLimit]);
+        let rate_limit = anyhow::anyhow!("Invalid status code: 429 Too Many Requests");
+        let server_error = anyhow::anyhow!("Invalid status code: 500 Internal Server Error");
+
+        assert!(fixture.retries(&rate_limit));
+        assert!(!fixture.retries(&server_error));
+        assert!(RetryPolicy::default().retries(&server_error));
+    }
+
+    #[test]
+    fn test_delays() {
+        let fixture = RetryPolicy::default().attempts(3_usize).backoff_ms(500_u64);
+        let actual = fixture.delays().collect::<Vec<_>>();
+        let expected = vec![
+            Duration::from_millis(500),
+            Duration::from_millis(1000),
+            Duration::from_millis(2000),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let fixture = "attempts: 5\nretry_on: [rate_limit, server_error]\n";
+        let actual: RetryPolicy = serde_yml::from_str(fixture).unwrap();
+        let expected = RetryPolicy::default()
+            .attempts(5_usize)
+            .retry_on(vec![RetryOn::RateLimit, RetryOn::ServerError]);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+
+use schemars::schema::{InstanceType, RootSchema, Schema, SingleOrVec};
+use schemars::Map;
+use serde_json::Value;
+
+/// Unknown keys within this many edits of a known key, whose value would be
+/// valid for it, are reported as typos of it. Other unknown keys are ignored,
+/// like serde does, so that e.g. a list of YAML anchors can be kept in the
+/// file.
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// A value that doesn't match the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Location of the value, e.g. `agents[0].temperature`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "{}", self.message),
+            path => write!(f, "{path}: {}", self.message),
+        }
+    }
+}
+
+/// Checks the value against the schema, returning all the violations instead
+/// of stopping at the first one like serde
+pub fn validate(schema: &RootSchema, value: &Value) -> Vec<SchemaViolation> {
+    let mut validator = Validator { definitions: &schema.definitions, violations: Vec::new() };
+    validator.check(&Schema::Object(schema.schema.clone()), value, "");
+    validator.violations
+}
+
+struct Validator<'a> {
+    definitions: &'a Map<String, Schema>,
+    violations: Vec<SchemaViolation>,
+}
+
+impl Validator<'_> {
+    fn violation(&mut self, path: &str, message: impl ToString) {
+        self.violations
+            .push(SchemaViolation { path: path.to_string(), message: message.to_string() });
+    }
+
+    fn check(&mut self, schema: &Schema, value: &Value, path: &str) {
+        let schema = match schema {
+            Schema::Bool(true) => return,
+            Schema::Bool(false) => return self.violation(path, "isn't allowed"),
+            Schema::Object(schema) => schema,
+        };
+
+        if let Some(reference) = &schema.reference {
+            let definition = reference
+                .strip_prefix("#/definitions/")
+                .and_then(|name| self.definitions.get(name));
+            if let Some(definition) = definition {
+                self.check(definition, value, path);
+            }
+            return;
+        }
+
+        if let Some(subschemas) = &schema.subschemas {
+            for subschema in subschemas.all_of.iter().flatten() {
+                self.check(subschema, value, path);
+            }
+            if let Some(alternatives) = subschemas.any_of.as_ref().or(subschemas.one_of.as_ref()) {
+                self.check_alternatives(alternatives, value, path);
+            }
+        }
+
+        if let Some(values) = &schema.enum_values {
+            if !values.contains(value) {
+                let expected = values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return self.violation(
+                    path,
+                    format!("expected one of {expected}, found {}", describe(value)),
+                );
+            }
+        }
+
+        if let Some(types) = &schema.instance_type {
+            let types = match types {
+                SingleOrVec::Single(instance_type) => vec![**instance_type],
+                SingleOrVec::Vec(types) => types.clone(),
+            };
+            if !types
+                .iter()
+                .any(|instance_type| is_instance(*instance_type, value))
+            {
+                let expected = types.into_iter().map(type_name).collect::<Vec<_>>();
+                return self.violation(
+                    path,
+                    format!(
+                        "expected {}, found {}",
+                        expected.join(" or "),
+                        describe(value)
+                    ),
+                );
+            }
+        }
+
+        if let (Some(number), Some(actual)) = (&schema.number, value.as_f64()) {
+            if let Some(minimum) = number.minimum.filter(|minimum| actual < *minimum) {
+                self.violation(path, format!("must be at least {minimum}, found {actual}"));
+            }
+            if let Some(maximum) = number.maximum.filter(|maximum| actual > *maximum) {
+                self.violation(path, format!("must be at most {maximum}, found {actual}"));
+            }
+        }
+
+        if let (Some(object), Value::Object(map)) = (&schema.object, value) {
+            for key in &object.required {
+                if !map.contains_key(key) {
+                    self.violation(path, format!("missing key `{key}`"));
+                }
+            }
+            for (key, value) in map {
+                let child = match path {
+                    "" => key.clone(),
+                    path => format!("{path}.{key}"),
+                };
+                if let Some(property) = object.properties.get(key) {
+                    self.check(property, value, &child);
+                } else if let Some(additional) = &object.additional_properties {
+                    self.check(additional, value, &child);
+                } else if let Some(known) = self.suggest(key, value, &object.properties) {
+                    self.violation(&child, format!("unknown key, did you mean `{known}`?"));
+                }
+            }
+        }
+
+        if let (Some(array), Value::Array(items)) = (&schema.array, value) {
+            if let Some(SingleOrVec::Single(item_schema)) = &array.items {
+                for (index, item) in items.iter().enumerate() {
+                    self.check(item_schema, item, &format!("{path}[{index}]"));
+                }
+            }
+        }
+    }
+
+    /// Reports the violations of the alternative that comes closest to the
+    /// value, e.g. of the object of an optional object rather than `null`
+    fn check_alternatives(&mut self, alternatives: &[Schema], value: &Value, path: &str) {
+        // Documented enum variants are alternatives of a single value each
+        if let Some(values) = self.enum_values(alternatives) {
+            if !values.contains(value) {
+                let expected = values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.violation(
+                    path,
+                    format!("expected one of {expected}, found {}", describe(value)),
+                );
+            }
+            return;
+        }
+
+        let mut closest: Option<Vec<SchemaViolation>> = None;
+        for alternative in alternatives {
+            let mut validator = Validator { definitions: self.definitions, violations: Vec::new() };
+            validator.check(alternative, value, path);
+            let violations = validator.violations;
+            if violations.is_empty() {
+                return;
+            }
+            // A violation of the value itself means that it isn't of the type
+            // of the alternative at all
+            let score = |violations: &[SchemaViolation]| {
+                let mismatch = violations.iter().any(|violation| violation.path == path);
+                (mismatch, violations.len())
+            };
+            if closest
+                .as_ref()
+                .is_none_or(|closest| score(&violations) < score(closest))
+            {
+                closest = Some(violations);
+            }
+        }
+        self.violations.extend(closest.into_iter().flatten());
+    }
+
+    /// The values of alternatives that are all enums
+    fn enum_values(&self, alternatives: &[Schema]) -> Option<Vec<Value>> {
+        let mut values = Vec::new();
+        for alternative in alternatives {
+            let Schema::Object(schema) = alternative else {
+                return None;
+            };
+            let schema = match &schema.reference {
+                Some(reference) => match reference
+                    .strip_prefix("#/definitions/")
+                    .and_then(|name| self.definitions.get(name))
+                {
+                    Some(Schema::Object(definition)) => definition,
+                    _ => return None,
+                },
+                None => schema,
+            };
+            values.extend(schema.enum_values.clone()?);
+        }
+        Some(values)
+    }
+
+    /// The known key that the unknown one is most likely a typo of, provided
+    /// that its value would be valid for the known key
+    fn suggest<'a>(
+        &self,
+        key: &str,
+        value: &Value,
+        known: &'a Map<String, Schema>,
+    ) -> Option<&'a str> {
+        let (_, candidate, schema) = known
+            .iter()
+            .map(|(candidate, schema)| (distance(key, candidate), candidate, schema))
+            .filter(|(distance, ..)| *distance <= MAX_TYPO_DISTANCE)
+            .min_by_key(|(distance, ..)| *distance)?;
+
+        let mut validator = Validator { definitions: self.definitions, violations: Vec::new() };
+        validator.check(schema, value, "");
+        let fits = validator
+            .violations
+            .iter()
+            .all(|violation| !violation.path.is_empty());
+        fits.then_some(candidate.as_str())
+    }
+}
+
+fn is_instance(instance_type: InstanceType, value: &Value) -> bool {
+    match instance_type {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => value.is_i64() || value.is_u64(),
+    }
+}
+
+fn type_name(instance_type: InstanceType) -> &'static str {
+    match instance_type {
+        InstanceType::Null => "null",
+        InstanceType::Boolean => "a boolean",
+        InstanceType::Object => "an object",
+        InstanceType::Array => "a list",
+        InstanceType::Number => "a number",
+        InstanceType::String => "a string",
+        InstanceType::Integer => "an integer",
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => format!("`{value}`"),
+        Value::Number(value) => format!("`{value}`"),
+        Value::String(value) => format!("\"{value}\""),
+        Value::Array(_) => "a list".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+/// Levenshtein distance between the two strings
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use schemars::{schema_for, JsonSchema};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    struct Fixture {
+        name: String,
+        retries: Option<u32>,
+        tags: Vec<String>,
+        nested: Option<Nested>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    struct Nested {
+        enabled: bool,
+    }
+
+    fn violations(value: Value) -> Vec<String> {
+        validate(&schema_for!(Fixture), &value)
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let actual = violations(json!({"name": "a", "tags": [], "nested": null, "anchors": [1]}));
+        assert_eq!(actual, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_violations() {
+        let actual = violations(json!({
+            "retries": -1,
+            "tags": ["a", 2],
+            "nested": {"enabled": "yes"},
+            "nmae": "a"
+        }));
+        let expected = vec![
+            "missing key `name`",
+            "nested.enabled: expected a boolean, found \"yes\"",
+            "nmae: unknown key, did you mean `name`?",
+            "retries: must be at least 0, found -1",
+            "tags[1]: expected a string, found `2`",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("temprature", "temperature"), 1);
+        assert_eq!(distance("modle", "model"), 2);
+        assert_eq!(distance("", "abc"), 3);
+    }
+}
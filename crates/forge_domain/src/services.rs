@@ -1,9 +1,12 @@
+use std::collections::HashSet;
 use std::path::Path;
 
+use futures::{StreamExt, TryStreamExt};
+
 use crate::{
-    Agent, Attachment, ChatCompletionMessage, CompactionResult, Context, Conversation,
-    ConversationId, Environment, File, Model, ModelId, ResultStream, ToolCallContext, ToolCallFull,
-    ToolDefinition, ToolResult, Workflow,
+    Agent, AgentPackage, Attachment, ChatCompletionMessage, CompactionResult, Context,
+    Conversation, ConversationId, Environment, File, Model, ModelId, OpenCircuit, ResultStream,
+    ToolCallContext, ToolCallFull, ToolDefinition, ToolResult, Workflow,
 };
 
 #[async_trait::async_trait]
@@ -16,11 +19,71 @@ pub trait ProviderService: Send + Sync + 'static {
     async fn models(&self) -> anyhow::Result<Vec<Model>>;
 }
 
+/// Maximum number of read-only tool calls executed at the same time
+const MAX_PARALLEL_TOOL_CALLS: usize = 8;
+
 #[async_trait::async_trait]
 pub trait ToolService: Send + Sync {
-    // TODO: should take `call` by reference
-    async fn call(&self, context: ToolCallContext, call: ToolCallFull) -> ToolResult;
+    async fn call(&self, context: ToolCallContext, call: &ToolCallFull) -> ToolResult;
+
+    /// Executes the tool calls of a single turn, see `execute_batch`.
+    /// Implementations can override this to share setup across the calls of a
+    /// turn, e.g. to ask for the approval of all the calls at once.
+    async fn call_batch(
+        &self,
+        context: ToolCallContext,
+        calls: &[ToolCallFull],
+    ) -> anyhow::Result<Vec<ToolResult>> {
+        execute_batch(self, context, calls).await
+    }
+
     fn list(&self) -> Vec<ToolDefinition>;
+
+    /// Returns the tools that are disabled because they kept failing
+    fn open_circuits(&self) -> Vec<OpenCircuit> {
+        Vec::new()
+    }
+}
+
+/// Executes the tool calls of a single turn with the service, notifying the
+/// start and the end of each call through the context. Consecutive calls to
+/// tools whose definitions aren't `mutating` are executed concurrently, the
+/// other calls, including the calls to tools the service doesn't list, are
+/// executed one at a time in order. The results are in the order of the calls.
+pub async fn execute_batch<S: ToolService + ?Sized>(
+    service: &S,
+    context: ToolCallContext,
+    calls: &[ToolCallFull],
+) -> anyhow::Result<Vec<ToolResult>> {
+    let mut results = Vec::with_capacity(calls.len());
+    let read_only = service
+        .list()
+        .into_iter()
+        .filter(|definition| !definition.mutating)
+        .map(|definition| definition.name)
+        .collect::<HashSet<_>>();
+    let is_parallel = |call: &ToolCallFull| read_only.contains(&call.name);
+    for group in calls.chunk_by(|a, b| is_parallel(a) && is_parallel(b)) {
+        let mut pending = Vec::with_capacity(group.len());
+        for call in group {
+            let context = context.clone();
+            pending.push(async move {
+                context.send_tool_call_start(call).await?;
+                let result = service
+                    .call(context.clone().call_id(call.call_id.clone()), call)
+                    .await;
+                context.send_tool_call_end(&result).await?;
+                anyhow::Ok(result)
+            });
+        }
+
+        let group_results = futures::stream::iter(pending)
+            .buffered(MAX_PARALLEL_TOOL_CALLS)
+            .try_collect::<Vec<_>>()
+            .await?;
+        results.extend(group_results);
+    }
+    Ok(results)
 }
 
 #[async_trait::async_trait]
@@ -32,7 +95,12 @@ pub trait CompactionService: Send + Sync {
 pub trait ConversationService: Send + Sync {
     async fn find(&self, id: &ConversationId) -> anyhow::Result<Option<Conversation>>;
 
-    async fn upsert(&self, conversation: Conversation) -> anyhow::Result<()>;
+    /// Stores the conversation and returns its new version
+    ///
+    /// # Errors
+    /// - `ConversationConflict` if the stored conversation has changed since
+    ///   the given one was read
+    async fn upsert(&self, conversation: Conversation) -> anyhow::Result<u64>;
 
     async fn create(&self, workflow: Workflow) -> anyhow::Result<Conversation>;
 
@@ -55,6 +123,14 @@ pub trait TemplateService: Send + Sync {
         template: impl ToString,
         object: &impl serde::Serialize,
     ) -> anyhow::Result<String>;
+
+    /// Renders a template of an untrusted workspace, which can't read the
+    /// environment or the files of the user
+    fn render_untrusted(
+        &self,
+        template: impl ToString,
+        object: &impl serde::Serialize,
+    ) -> anyhow::Result<String>;
 }
 
 #[async_trait::async_trait]
@@ -100,6 +176,12 @@ pub trait SuggestionService: Send + Sync {
     async fn suggestions(&self) -> anyhow::Result<Vec<File>>;
 }
 
+#[async_trait::async_trait]
+pub trait RegistryService: Send + Sync {
+    /// Fetches the packages published in the remote registry index
+    async fn packages(&self) -> anyhow::Result<Vec<AgentPackage>>;
+}
+
 /// Core app trait providing access to services and repositories.
 /// This trait follows clean architecture principles for dependency management
 /// and service/repository composition.
@@ -113,6 +195,7 @@ pub trait Services: Send + Sync + 'static + Clone {
     type CompactionService: CompactionService;
     type WorkflowService: WorkflowService;
     type SuggestionService: SuggestionService;
+    type RegistryService: RegistryService;
 
     fn tool_service(&self) -> &Self::ToolService;
     fn provider_service(&self) -> &Self::ProviderService;
@@ -123,4 +206,5 @@ pub trait Services: Send + Sync + 'static + Clone {
     fn compaction_service(&self) -> &Self::CompactionService;
     fn workflow_service(&self) -> &Self::WorkflowService;
     fn suggestion_service(&self) -> &Self::SuggestionService;
+    fn registry_service(&self) -> &Self::RegistryService;
 }
@@ -1,13 +1,47 @@
+use serde::Serialize;
+
+use crate::ResourceViolation;
+
 /// Output from a command execution
 pub struct CommandOutput {
     pub command: String,
     pub stdout: String,
     pub stderr: String,
     pub exit_code: Option<i32>,
+    /// Whether the command was terminated for exceeding its timeout
+    pub timed_out: bool,
+    /// Resource limit the command was terminated for exceeding, if any
+    pub violation: Option<ResourceViolation>,
 }
 
 impl CommandOutput {
     pub fn success(&self) -> bool {
-        self.exit_code.is_none_or(|code| code >= 0)
+        !self.timed_out && self.violation.is_none() && self.exit_code == Some(0)
+    }
+}
+
+/// Identifies the stream a chunk of command output was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A chunk of output emitted by a command while it is still running
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandOutputChunk {
+    pub stream: OutputStream,
+    pub content: String,
+}
+
+impl CommandOutputChunk {
+    pub fn stdout(content: impl ToString) -> Self {
+        Self { stream: OutputStream::Stdout, content: content.to_string() }
+    }
+
+    pub fn stderr(content: impl ToString) -> Self {
+        Self { stream: OutputStream::Stderr, content: content.to_string() }
     }
 }
@@ -0,0 +1,101 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ToolCallFull;
+
+/// Where the output of a tool call came from, used to cite the files and URLs
+/// that an answer is based on
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Source {
+    File {
+        path: PathBuf,
+        /// Range of characters that was read, the whole file when not set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        char_range: Option<(u64, u64)>,
+    },
+    Url {
+        url: String,
+    },
+}
+
+impl Source {
+    /// Returns the source of the content that the tool call reads
+    pub fn from_call(call: &ToolCallFull) -> Option<Self> {
+        let argument = |name: &str| call.arguments.get(name);
+
+        match call.name.as_str() {
+            "forge_tool_fs_read" => {
+                let path = argument("path")?.as_str()?;
+                let start = argument("start_char").and_then(|value| value.as_u64());
+                let end = argument("end_char").and_then(|value| value.as_u64());
+                let char_range = match (start, end) {
+                    (None, None) => None,
+                    (start, end) => Some((start.unwrap_or(0), end.unwrap_or(u64::MAX))),
+                };
+                Some(Source::File { path: PathBuf::from(path), char_range })
+            }
+            "forge_tool_net_fetch" => {
+                let url = argument("url")?.as_str()?;
+                Some(Source::Url { url: url.to_string() })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File { path, .. } => write!(f, "{}", path.display()),
+            Source::Url { url } => write!(f, "{url}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ToolName;
+
+    fn call(name: &str, arguments: serde_json::Value) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new(name)).arguments(arguments)
+    }
+
+    #[test]
+    fn test_source_from_read() {
+        let actual = Source::from_call(&call(
+            "forge_tool_fs_read",
+            json!({"path": "/project/src/lib.rs", "start_char": 100}),
+        ));
+        let expected = Some(Source::File {
+            path: PathBuf::from("/project/src/lib.rs"),
+            char_range: Some((100, u64::MAX)),
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_source_from_fetch() {
+        let actual = Source::from_call(&call(
+            "forge_tool_net_fetch",
+            json!({"url": "https://docs.rs/tokio"}),
+        ));
+        let expected = Some(Source::Url { url: "https://docs.rs/tokio".to_string() });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_no_source_for_other_tools() {
+        let actual = Source::from_call(&call(
+            "forge_tool_process_shell",
+            json!({"command": "cat README.md"}),
+        ));
+        assert_eq!(actual, None);
+    }
+}
@@ -0,0 +1,167 @@
+/// Minimum number of characters that must repeat the end of the received
+/// content for a chunk to be considered a replay. Shorter repetitions are too
+/// likely to be legitimate, e.g. repeated lines of code.
+const MIN_REPLAY_LEN: usize = 64;
+
+/// Number of bytes at the end of the received content where a replay may
+/// start
+const REPLAY_WINDOW: usize = 16 * 1024;
+
+/// Removes the text that some providers resend after reconnecting, which
+/// repeats the end of the content received so far. Once the stream
+/// reconnected, chunks that may be the start of such a replay are held back
+/// until they either reach the end of the received content, in which case
+/// they are dropped, or diverge from it. Before that every chunk is passed
+/// through, so that legitimately repeated output is kept.
+#[derive(Debug, Default)]
+pub struct StreamDedup {
+    content: String,
+    pending: String,
+    reconnected: bool,
+}
+
+impl StreamDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the stream as reconnected, from then on replays are removed
+    pub fn reconnect(&mut self) {
+        self.reconnected = true;
+    }
+
+    /// Adds a chunk of the stream and returns the new text it contains, which
+    /// may be empty
+    pub fn push(&mut self, chunk: &str) -> String {
+        let mut text = std::mem::take(&mut self.pending);
+        text.push_str(chunk);
+        if !self.reconnected {
+            self.content.push_str(&text);
+            return text;
+        }
+
+        // The replay reached the end of the received content, only the text
+        // after it is new
+        if let Some(overlap) = self.overlap(&text) {
+            let new = text[overlap..].to_string();
+            self.content.push_str(&new);
+            return new;
+        }
+
+        // The text repeats recent content, it may be the start of a replay
+        if self.window().contains(text.as_str()) {
+            self.pending = text;
+            return String::new();
+        }
+
+        self.content.push_str(&text);
+        text
+    }
+
+    /// Returns the text held back when the stream ends
+    pub fn finish(&mut self) -> String {
+        let text = std::mem::take(&mut self.pending);
+        self.content.push_str(&text);
+        text
+    }
+
+    /// Length of the longest prefix of the text, of at least
+    /// `MIN_REPLAY_LEN`, that the received content ends with
+    fn overlap(&self, text: &str) -> Option<usize> {
+        let max = text.len().min(self.content.len());
+        (MIN_REPLAY_LEN..=max)
+            .rev()
+            .filter(|len| text.is_char_boundary(*len))
+            .find(|len| self.content.ends_with(&text[..*len]))
+    }
+
+    fn window(&self) -> &str {
+        let mut start = self.content.len().saturating_sub(REPLAY_WINDOW);
+        while !self.content.is_char_boundary(start) {
+            start += 1;
+        }
+        &self.content[start..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Streams the chunks, reconnecting before the one at the index
+    fn stream_with_reconnect(chunks: &[&str], reconnect: usize) -> String {
+        let mut dedup = StreamDedup::new();
+        let mut actual = String::new();
+        for (index, chunk) in chunks.iter().enumerate() {
+            if index == reconnect {
+                dedup.reconnect();
+            }
+            actual.push_str(&dedup.push(chunk));
+        }
+        actual.push_str(&dedup.finish());
+        actual
+    }
+
+    fn stream(chunks: &[&str]) -> String {
+        stream_with_reconnect(chunks, 0)
+    }
+
+    #[test]
+    fn test_no_replay() {
+        let chunks = ["The quick ", "brown fox ", "jumps over ", "the lazy dog."];
+        assert_eq!(stream(&chunks), chunks.concat());
+    }
+
+    #[test]
+    fn test_replay_across_chunks() {
+        let first = "Forge reads the workflow, starts the agents and streams ";
+        let second = "their responses back to the terminal as they are produced by the provider. ";
+        let fixture = [
+            first,
+            second,
+            // The provider reconnects and resends the second sentence in parts
+            "their responses back ",
+            "to the terminal as they are produced by the provider. ",
+            "Then it exits.",
+        ];
+
+        let actual = stream(&fixture);
+        let expected = format!("{first}{second}Then it exits.");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_replay_with_new_text() {
+        let sentence = "The configuration is read from forge.yaml in the working directory. ";
+        let expected = format!("{sentence}It is merged with the defaults.");
+        let fixture = [sentence, expected.as_str()];
+
+        let actual = stream(&fixture);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_repetition_without_reconnect_is_kept() {
+        let line = "    let value = compute_the_value_of_the_expression(input, options);\n";
+        let fixture = [line, line, line];
+        assert_eq!(
+            stream_with_reconnect(&fixture, usize::MAX),
+            fixture.concat()
+        );
+    }
+
+    #[test]
+    fn test_short_repetition_is_kept() {
+        let fixture = ["assert!(a);\n", "assert!(a);\n", "done"];
+        assert_eq!(stream(&fixture), fixture.concat());
+    }
+
+    #[test]
+    fn test_repeated_text_before_the_end_is_kept() {
+        let line = "    let value = compute_the_value_of_the_expression(input, options);\n";
+        let fixture = [line, "    println!(\"{value}\");\n", line, "}"];
+        assert_eq!(stream(&fixture), fixture.concat());
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
@@ -35,4 +36,9 @@ pub struct SystemContext {
     // Variables to pass to the system context
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub variables: HashMap<String, Value>,
+
+    /// Directory where the agents can keep intermediate artifacts of the
+    /// conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmp_dir: Option<PathBuf>,
 }
@@ -1,6 +1,9 @@
 use std::fmt;
 use std::ops::Deref;
 
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, NumberValidation, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// A newtype for temperature values with built-in validation
@@ -68,6 +71,25 @@ impl fmt::Display for Temperature {
     }
 }
 
+impl JsonSchema for Temperature {
+    fn schema_name() -> String {
+        "Temperature".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::Number.into()),
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(0.0),
+                maximum: Some(2.0),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl Serialize for Temperature {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -0,0 +1,102 @@
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::temperature::Temperature;
+
+/// Raises the temperature when an agent retries after failing to follow
+/// instructions or repeating itself. Retrying with identical parameters
+/// usually reproduces the same bad answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[serde(default)]
+pub struct TemperatureRamp {
+    /// Temperature that the ramp starts from when the agent doesn't configure
+    /// one
+    pub initial: Temperature,
+
+    /// Amount by which the temperature is raised on every retry
+    pub step: f32,
+
+    /// Temperature that retries never exceed
+    pub max: Temperature,
+}
+
+impl Default for TemperatureRamp {
+    fn default() -> Self {
+        Self {
+            initial: Temperature::new_unchecked(0.5),
+            step: 0.2,
+            max: Temperature::new_unchecked(1.0),
+        }
+    }
+}
+
+impl TemperatureRamp {
+    /// Returns the temperature for the given retry, where `0` is the first
+    /// attempt and keeps the agent's own temperature
+    pub fn temperature(&self, base: Option<Temperature>, retry: usize) -> Option<Temperature> {
+        if retry == 0 {
+            return base;
+        }
+
+        let start = base.unwrap_or(self.initial).value();
+        let value = (start + self.step.max(0.0) * retry as f32)
+            .min(self.max.value())
+            .max(start);
+
+        Some(Temperature::new_unchecked(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn temperature(value: f32) -> Option<Temperature> {
+        Some(Temperature::new(value).unwrap())
+    }
+
+    #[test]
+    fn test_first_attempt_keeps_base_temperature() {
+        let fixture = TemperatureRamp::default();
+
+        assert_eq!(fixture.temperature(None, 0), None);
+        assert_eq!(fixture.temperature(temperature(0.2), 0), temperature(0.2));
+    }
+
+    #[test]
+    fn test_ramp_is_bounded() {
+        let fixture = TemperatureRamp::default().step(0.25);
+
+        let actual = (1..=4)
+            .map(|retry| fixture.temperature(temperature(0.25), retry))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            temperature(0.5),
+            temperature(0.75),
+            temperature(1.0),
+            temperature(1.0),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ramp_without_base_temperature() {
+        let fixture = TemperatureRamp::default().step(0.25);
+
+        let actual = fixture.temperature(None, 1);
+        let expected = temperature(0.75);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ramp_never_lowers_temperature() {
+        let fixture = TemperatureRamp::default();
+
+        let actual = fixture.temperature(temperature(1.5), 2);
+        let expected = temperature(1.5);
+        assert_eq!(actual, expected);
+    }
+}
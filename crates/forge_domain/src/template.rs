@@ -1,3 +1,6 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -15,3 +18,15 @@ impl<V> Template<V> {
         }
     }
 }
+
+/// A template is written as a string in the workflow, whatever the context it
+/// is rendered with
+impl<V> JsonSchema for Template<V> {
+    fn schema_name() -> String {
+        "Template".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
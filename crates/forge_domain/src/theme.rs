@@ -0,0 +1,15 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Colors of the terminal output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// For terminals with a dark background
+    #[default]
+    Dark,
+    /// For terminals with a light background
+    Light,
+    /// No colors, e.g. for CI logs. Also used when NO_COLOR is set.
+    Plain,
+}
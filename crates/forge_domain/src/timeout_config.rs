@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use derive_setters::Setters;
+use serde::{Deserialize, Serialize};
+
+/// Timeouts applied to the requests sent to the provider
+#[derive(Debug, Clone, Serialize, Deserialize, Setters, PartialEq)]
+#[setters(into)]
+pub struct TimeoutConfig {
+    /// Seconds allowed to establish the connection to the provider
+    pub connect_timeout_secs: u64,
+
+    /// Seconds allowed between sending a request and receiving the first
+    /// event of the response
+    pub first_token_timeout_secs: u64,
+
+    /// Seconds allowed for the whole response to be streamed. Reasoning models
+    /// can take a long time to complete a response.
+    pub stream_timeout_secs: u64,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 30,
+            first_token_timeout_secs: 300,
+            stream_timeout_secs: 3600,
+        }
+    }
+}
+
+impl TimeoutConfig {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs)
+    }
+
+    pub fn first_token_timeout(&self) -> Duration {
+        Duration::from_secs(self.first_token_timeout_secs)
+    }
+
+    pub fn stream_timeout(&self) -> Duration {
+        Duration::from_secs(self.stream_timeout_secs)
+    }
+}
@@ -22,6 +22,10 @@ where
         let input: T::Input = serde_json::from_value(input)?;
         self.0.call(context, input).await
     }
+
+    fn is_mutating(&self) -> bool {
+        self.0.is_mutating()
+    }
 }
 
 pub struct Tool {
@@ -1,10 +1,17 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use derive_setters::Setters;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::RwLock;
 
-use crate::{AgentId, AgentMessage, ChatResponse};
+use crate::{
+    AgentId, AgentMessage, ApprovalAction, ApprovalPolicy, ApprovalReply, Artifact, ChatResponse,
+    CommandOutputChunk, CustomTool, EditReview, EditReviewReply, EgressPolicy, FileChange,
+    FileChanges, LanguageServer, ResourceLimits, ShellPolicy, ToolCallFull, ToolCallId,
+    ToolErrorCode, ToolName, ToolResult,
+};
 
 /// Type alias for Arc<Sender<Result<AgentMessage<ChatResponse>>>>
 type ArcSender = Arc<Sender<anyhow::Result<AgentMessage<ChatResponse>>>>;
@@ -15,6 +22,40 @@ pub struct ToolCallContext {
     #[setters(strip_option)]
     pub agent_id: Option<AgentId>,
     pub sender: Option<ArcSender>,
+    /// Id of the tool call that is currently being executed
+    pub call_id: Option<ToolCallId>,
+    /// Limits applied to processes spawned while executing the tool
+    pub resource_limits: ResourceLimits,
+    /// Hosts that the tool is allowed to access over the network
+    pub egress: EgressPolicy,
+    /// Commands that the tool is allowed to execute
+    pub shell_policy: ShellPolicy,
+    /// Tools that may only be executed after the user approves the call
+    pub approval: ApprovalPolicy,
+    /// Answers of the user to the approval of the calls of the batch being
+    /// executed, by tool
+    pub approvals: HashMap<ToolName, bool>,
+    /// Rejects the tools that modify the workspace
+    pub read_only: bool,
+    /// Tools that the user disabled for the conversation, rejected if they are
+    /// called anyway
+    pub disabled_tools: Vec<ToolName>,
+    /// Tools that the mode of the conversation restricts the agents to, the
+    /// others are rejected if they are called anyway
+    pub mode_tools: Option<Vec<ToolName>>,
+    /// Shows the changes to a file to the user, who accepts, rejects or
+    /// modifies them, before the file is written
+    pub review_edits: bool,
+    /// Temporary directory of the conversation, exposed to shell commands as
+    /// `$FORGE_TMP`
+    #[setters(strip_option)]
+    pub tmp_dir: Option<PathBuf>,
+    /// Language servers used by the LSP tool, the defaults are used when empty
+    pub language_servers: Vec<LanguageServer>,
+    /// Tools declared in the workflow that run shell commands
+    pub custom_tools: Vec<CustomTool>,
+    /// Changes made to files by the tool call being executed
+    pub changes: FileChanges,
     /// Indicates whether the tool execution has been completed
     /// This is wrapped in an RWLock for thread-safety
     #[setters(skip)]
@@ -27,6 +68,20 @@ impl ToolCallContext {
         Self {
             agent_id: None,
             sender: None,
+            call_id: None,
+            resource_limits: Default::default(),
+            egress: Default::default(),
+            shell_policy: Default::default(),
+            approval: Default::default(),
+            approvals: HashMap::new(),
+            read_only: false,
+            disabled_tools: Vec::new(),
+            mode_tools: None,
+            review_edits: false,
+            language_servers: Vec::new(),
+            custom_tools: Vec::new(),
+            changes: Default::default(),
+            tmp_dir: None,
             is_complete: Arc::new(RwLock::new(false)),
         }
     }
@@ -67,6 +122,237 @@ impl ToolCallContext {
         }
     }
 
+    /// Notifies that the execution of a tool call has started
+    pub async fn send_tool_call_start(&self, call: &ToolCallFull) -> anyhow::Result<()> {
+        if let Some(agent_id) = &self.agent_id {
+            self.send(AgentMessage::new(
+                agent_id.clone(),
+                ChatResponse::ToolCallStart(call.clone()),
+            ))
+            .await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Notifies that the execution of a tool call has finished
+    pub async fn send_tool_call_end(&self, result: &ToolResult) -> anyhow::Result<()> {
+        if let Some(agent_id) = &self.agent_id {
+            self.send(AgentMessage::new(
+                agent_id.clone(),
+                ChatResponse::ToolCallEnd(result.clone()),
+            ))
+            .await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Warns the user that the content read by the tool looks like a prompt
+    /// injection
+    pub async fn send_prompt_injection(
+        &self,
+        source: impl ToString,
+        excerpt: impl ToString,
+    ) -> anyhow::Result<()> {
+        if let Some(agent_id) = &self.agent_id {
+            self.send(AgentMessage::new(
+                agent_id.clone(),
+                ChatResponse::PromptInjection {
+                    source: source.to_string(),
+                    excerpt: excerpt.to_string(),
+                },
+            ))
+            .await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Publishes a file generated by the tool, e.g. the diff of a patch
+    pub async fn send_artifact(&self, artifact: Artifact) -> anyhow::Result<()> {
+        if let Some(agent_id) = &self.agent_id {
+            self.send(AgentMessage::new(
+                agent_id.clone(),
+                ChatResponse::Artifact(artifact),
+            ))
+            .await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records a change that the tool made to a file
+    pub fn record_change(&self, change: FileChange) {
+        self.changes.push(change);
+    }
+
+    /// Returns the custom tool with the given name
+    pub fn custom_tool(&self, name: &ToolName) -> Option<&CustomTool> {
+        self.custom_tools.iter().find(|tool| tool.name == *name)
+    }
+
+    /// Checks whether the tool call may be executed, asking the user when the
+    /// approval policy requires it. Calls that need approval are rejected when
+    /// there is nobody to ask, calls to tools that modify the workspace, as
+    /// reported by `mutating`, are rejected in read-only mode and the tools
+    /// that the user disabled or that aren't available in the mode of the
+    /// conversation are rejected.
+    pub async fn check_approval(&self, call: &ToolCallFull, mutating: bool) -> anyhow::Result<()> {
+        self.check_access(call, mutating)?;
+        if self.approval.action(&call.name) != ApprovalAction::Ask {
+            return Ok(());
+        }
+
+        let approved = match self.approvals.get(&call.name) {
+            Some(approved) => *approved,
+            None => self.ask_approval(vec![call.clone()], None).await?,
+        };
+        if approved {
+            Ok(())
+        } else {
+            Err(ToolErrorCode::PermissionDenied.error(format!(
+                "The user rejected the call to tool '{}'",
+                call.name.as_str()
+            )))
+        }
+    }
+
+    /// Asks the user once to approve all the calls of a batch that require
+    /// approval, given along with whether their tools modify the workspace.
+    /// The answer is used by `check_approval` when the calls are executed.
+    /// Calls that would be rejected anyway aren't asked for.
+    pub async fn approve_batch(mut self, calls: &[(&ToolCallFull, bool)]) -> anyhow::Result<Self> {
+        let asked = calls
+            .iter()
+            .filter(|(call, mutating)| self.check_access(call, *mutating).is_ok())
+            .filter(|(call, _)| self.approval.action(&call.name) == ApprovalAction::Ask)
+            .map(|(call, _)| (*call).clone())
+            .collect::<Vec<_>>();
+        if asked.is_empty() {
+            return Ok(self);
+        }
+
+        let approved = self.ask_approval(asked.clone(), None).await?;
+        self.approvals
+            .extend(asked.into_iter().map(|call| (call.name, approved)));
+        Ok(self)
+    }
+
+    /// Asks the user to approve a call that a policy of the conversation
+    /// denies for the given reason, e.g. a shell command with `on_deny: ask`.
+    /// The call is rejected when there is nobody to ask.
+    pub async fn approve_denied(&self, call: &ToolCallFull, reason: &str) -> anyhow::Result<bool> {
+        self.ask_approval(vec![call.clone()], Some(reason.to_string()))
+            .await
+    }
+
+    /// Asks the user to approve the calls, which are rejected when there is
+    /// nobody to ask
+    async fn ask_approval(
+        &self,
+        calls: Vec<ToolCallFull>,
+        reason: Option<String>,
+    ) -> anyhow::Result<bool> {
+        match (&self.agent_id, &self.sender) {
+            (Some(agent_id), Some(_)) => {
+                let (reply, rx) = ApprovalReply::channel();
+                self.send(AgentMessage::new(
+                    agent_id.clone(),
+                    ChatResponse::ApprovalRequest { calls, reason, reply },
+                ))
+                .await?;
+                Ok(rx.await.is_ok_and(|approval| approval.is_approved()))
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Rejects the calls that the conversation doesn't allow, whatever the
+    /// user would answer
+    fn check_access(&self, call: &ToolCallFull, mutating: bool) -> anyhow::Result<()> {
+        let name = call.name.as_str();
+        let is_mutating = mutating || self.custom_tool(&call.name).is_some();
+        if self.read_only && is_mutating {
+            return Err(ToolErrorCode::PermissionDenied
+                .error(format!("Tool '{name}' is disabled in read-only mode")));
+        }
+        if self.disabled_tools.contains(&call.name) {
+            return Err(ToolErrorCode::PermissionDenied
+                .error(format!("Tool '{name}' is disabled in this conversation")));
+        }
+        if self
+            .mode_tools
+            .as_ref()
+            .is_some_and(|tools| !tools.contains(&call.name))
+        {
+            return Err(ToolErrorCode::PermissionDenied.error(format!(
+                "Tool '{name}' is not available in the current mode"
+            )));
+        }
+
+        if self.approval.action(&call.name) == ApprovalAction::Deny {
+            return Err(ToolErrorCode::PermissionDenied.error(format!(
+                "Tool '{name}' is not allowed by the approval policy"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the content to write to the file, which is the proposed one
+    /// unless the user modifies it while reviewing the edit. Fails when the
+    /// user rejects the edit, or when edits are reviewed and there is nobody to
+    /// ask.
+    pub async fn review_edit(
+        &self,
+        path: impl ToString,
+        old_content: &str,
+        new_content: String,
+    ) -> anyhow::Result<String> {
+        if !self.review_edits {
+            return Ok(new_content);
+        }
+
+        let path = path.to_string();
+        let review = match (&self.agent_id, &self.sender) {
+            (Some(agent_id), Some(_)) => {
+                let (reply, rx) = EditReviewReply::channel();
+                self.send(AgentMessage::new(
+                    agent_id.clone(),
+                    ChatResponse::EditReview {
+                        path: path.clone(),
+                        old_content: old_content.to_string(),
+                        new_content: new_content.clone(),
+                        reply,
+                    },
+                ))
+                .await?;
+                rx.await.unwrap_or(EditReview::Reject)
+            }
+            _ => EditReview::Reject,
+        };
+
+        match review {
+            EditReview::Accept => Ok(new_content),
+            EditReview::Edit(content) => Ok(content),
+            EditReview::Reject => Err(ToolErrorCode::PermissionDenied
+                .error(format!("The user rejected the changes to {path}"))),
+        }
+    }
+
+    /// Streams a chunk of output for the tool call being executed
+    pub async fn send_output(&self, chunk: CommandOutputChunk) -> anyhow::Result<()> {
+        if let Some(agent_id) = &self.agent_id {
+            self.send(AgentMessage::new(
+                agent_id.clone(),
+                ChatResponse::ToolCallOutput { call_id: self.call_id.clone(), chunk },
+            ))
+            .await
+        } else {
+            Ok(())
+        }
+    }
+
     pub async fn send_text(&self, content: impl ToString) -> anyhow::Result<()> {
         if let Some(agent_id) = &self.agent_id {
             self.send(AgentMessage::new(
@@ -87,7 +373,10 @@ impl ToolCallContext {
 
 #[cfg(test)]
 mod tests {
+    use pretty_assertions::assert_eq;
+
     use super::*;
+    use crate::{Approval, ToolName};
 
     #[test]
     fn test_create_context() {
@@ -108,6 +397,228 @@ mod tests {
         assert!(context.get_complete().await);
     }
 
+    #[tokio::test]
+    async fn test_send_output_with_call_id() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .call_id(Some(ToolCallId::new("call_1")));
+
+        context
+            .send_output(CommandOutputChunk::stdout("hello"))
+            .await
+            .unwrap();
+
+        let actual = rx.recv().await.unwrap().unwrap().message;
+        assert!(matches!(
+            actual,
+            ChatResponse::ToolCallOutput { call_id: Some(id), chunk }
+                if id.as_str() == "call_1" && chunk == CommandOutputChunk::stdout("hello")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_approval() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .approval(ApprovalPolicy::default().default_action(ApprovalAction::Ask));
+        let call = ToolCallFull::new(ToolName::new("forge_tool_fs_remove"));
+
+        let answer = tokio::spawn(async move {
+            match rx.recv().await.unwrap().unwrap().message {
+                ChatResponse::ApprovalRequest { reply, .. } => reply.send(Approval::Reject),
+                message => panic!("Unexpected message: {message:?}"),
+            }
+        });
+
+        let actual = context
+            .check_approval(&call, true)
+            .await
+            .unwrap_err()
+            .to_string();
+        answer.await.unwrap();
+        assert_eq!(
+            actual,
+            "The user rejected the call to tool 'forge_tool_fs_remove'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_approval_disabled_tool() {
+        let context = ToolCallContext::default()
+            .disabled_tools(vec![ToolName::new("forge_tool_process_shell")]);
+
+        let actual = context
+            .check_approval(
+                &ToolCallFull::new(ToolName::new("forge_tool_process_shell")),
+                true,
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            actual,
+            "Tool 'forge_tool_process_shell' is disabled in this conversation"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_approval_mode_tools() {
+        let context =
+            ToolCallContext::default().mode_tools(Some(vec![ToolName::new("forge_tool_fs_read")]));
+
+        let actual = context
+            .check_approval(
+                &ToolCallFull::new(ToolName::new("forge_tool_fs_create")),
+                true,
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            actual,
+            "Tool 'forge_tool_fs_create' is not available in the current mode"
+        );
+
+        let actual = context
+            .check_approval(
+                &ToolCallFull::new(ToolName::new("forge_tool_fs_read")),
+                false,
+            )
+            .await;
+        assert!(actual.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_approval_read_only() {
+        let context = ToolCallContext::default().read_only(true);
+
+        let actual = context
+            .check_approval(
+                &ToolCallFull::new(ToolName::new("forge_tool_fs_create")),
+                true,
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            actual,
+            "Tool 'forge_tool_fs_create' is disabled in read-only mode"
+        );
+        assert!(context
+            .check_approval(
+                &ToolCallFull::new(ToolName::new("forge_tool_fs_read")),
+                false
+            )
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_approval_read_only_custom_tool() {
+        let context = ToolCallContext::default()
+            .read_only(true)
+            .custom_tools(vec![CustomTool::new("deploy", "./deploy.sh")]);
+
+        let actual = context
+            .check_approval(&ToolCallFull::new(ToolName::new("deploy")), false)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(actual, "Tool 'deploy' is disabled in read-only mode");
+    }
+
+    #[tokio::test]
+    async fn test_check_approval_without_sender() {
+        let context = ToolCallContext::default()
+            .approval(ApprovalPolicy::default().default_action(ApprovalAction::Ask));
+        let call = ToolCallFull::new(ToolName::new("forge_tool_fs_remove"));
+
+        assert!(context.check_approval(&call, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approve_batch() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .disabled_tools(vec![ToolName::new("forge_tool_net_fetch")])
+            .approval(ApprovalPolicy::default().default_action(ApprovalAction::Ask));
+        let remove = ToolCallFull::new(ToolName::new("forge_tool_fs_remove"));
+        let shell = ToolCallFull::new(ToolName::new("forge_tool_process_shell"));
+        let fetch = ToolCallFull::new(ToolName::new("forge_tool_net_fetch"));
+
+        // A single request for the calls that may be executed
+        let answer = tokio::spawn(async move {
+            match rx.recv().await.unwrap().unwrap().message {
+                ChatResponse::ApprovalRequest { calls, reply, .. } => {
+                    reply.send(Approval::Approve);
+                    calls
+                }
+                message => panic!("Unexpected message: {message:?}"),
+            }
+        });
+
+        let context = context
+            .approve_batch(&[(&remove, true), (&shell, true), (&fetch, false)])
+            .await
+            .unwrap();
+        assert_eq!(answer.await.unwrap(), vec![remove.clone(), shell.clone()]);
+
+        // Nobody is asked anymore
+        assert!(context.check_approval(&remove, true).await.is_ok());
+        assert!(context.check_approval(&shell, true).await.is_ok());
+        assert!(context.check_approval(&fetch, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_review_edit() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .review_edits(true);
+
+        let answer = tokio::spawn(async move {
+            match rx.recv().await.unwrap().unwrap().message {
+                ChatResponse::EditReview { path, old_content, reply, .. } => {
+                    assert_eq!(path, "src/main.rs");
+                    assert_eq!(old_content, "hello");
+                    reply.send(EditReview::Edit("hello, world!".to_string()))
+                }
+                message => panic!("Unexpected message: {message:?}"),
+            }
+        });
+
+        let actual = context
+            .review_edit("src/main.rs", "hello", "hello, world".to_string())
+            .await
+            .unwrap();
+        answer.await.unwrap();
+        assert_eq!(actual, "hello, world!");
+    }
+
+    #[tokio::test]
+    async fn test_review_edit_disabled_or_without_sender() {
+        let actual = ToolCallContext::default()
+            .review_edit("src/main.rs", "", "hello".to_string())
+            .await
+            .unwrap();
+        assert_eq!(actual, "hello");
+
+        let actual = ToolCallContext::default()
+            .review_edits(true)
+            .review_edit("src/main.rs", "", "hello".to_string())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(actual, "The user rejected the changes to src/main.rs");
+    }
+
     #[test]
     fn test_with_sender() {
         // This is just a type check test - we don't actually create a sender
@@ -16,6 +16,10 @@ pub struct ToolDefinition {
     pub description: String,
     pub input_schema: RootSchema,
     pub output_schema: Option<RootSchema>,
+    /// Whether the tool can modify the workspace, such tools are disabled in
+    /// read-only mode
+    #[serde(skip)]
+    pub mutating: bool,
 }
 
 impl ToolDefinition {
@@ -26,6 +30,7 @@ impl ToolDefinition {
             description: String::new(),
             input_schema: schemars::schema_for!(()), // Empty input schema
             output_schema: None,
+            mutating: false,
         }
     }
 }
@@ -37,13 +42,14 @@ where
 {
     fn from(t: &T) -> Self {
         let input: RootSchema = schemars::schema_for!(T::Input);
-        let output: RootSchema = schemars::schema_for!(String);
+        let output: RootSchema = t.output_schema();
 
         ToolDefinition {
             name: T::tool_name(),
             description: t.description(),
             input_schema: input,
             output_schema: Some(output),
+            mutating: t.is_mutating(),
         }
     }
 }
@@ -57,4 +63,16 @@ pub trait ExecutableTool {
     type Input: DeserializeOwned;
 
     async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String>;
+
+    /// JSON schema of the result of the tool. Tools that return text keep the
+    /// default, tools that return JSON describe its structure.
+    fn output_schema(&self) -> RootSchema {
+        schemars::schema_for!(String)
+    }
+
+    /// Whether the tool can modify the workspace or run code of the project,
+    /// such tools are disabled in read-only mode
+    fn is_mutating(&self) -> bool {
+        false
+    }
 }
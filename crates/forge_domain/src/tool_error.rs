@@ -0,0 +1,156 @@
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+use crate::Error;
+
+/// The class of a failed tool call, so that the orchestrator, the policies
+/// and the UI can react to a failure without matching the text of the error
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ToolErrorCode {
+    /// The file, directory or resource doesn't exist
+    NotFound,
+    /// The call is blocked by a policy, the read-only mode or the system
+    PermissionDenied,
+    /// The call didn't complete in the allowed time
+    Timeout,
+    /// The arguments of the call are malformed or out of range
+    InvalidArgs,
+    /// The call is based on a state that changed, e.g. a patch whose search
+    /// text is no longer in the file
+    Conflict,
+    /// The requested content exceeds the size the tool can return
+    TooLarge,
+    /// The command run by the tool exited with a non-zero status, which is
+    /// the command's outcome rather than a failure of the tool
+    CommandFailed,
+}
+
+impl ToolErrorCode {
+    /// Returns the code of the first cause of the error that carries one
+    pub fn from_error(error: &anyhow::Error) -> Option<Self> {
+        error.chain().find_map(|cause| {
+            if let Some(error) = cause.downcast_ref::<ToolError>() {
+                return Some(error.code);
+            }
+            if let Some(error) = cause.downcast_ref::<Error>() {
+                return Self::from_domain_error(error);
+            }
+            if let Some(error) = cause.downcast_ref::<std::io::Error>() {
+                return Self::from_io_error(error);
+            }
+            if cause.downcast_ref::<serde_json::Error>().is_some() {
+                return Some(Self::InvalidArgs);
+            }
+            None
+        })
+    }
+
+    fn from_domain_error(error: &Error) -> Option<Self> {
+        match error {
+            Error::ToolCallArgument(_) | Error::ToolCallParse(_) | Error::TaskArgument(_, _) => {
+                Some(Self::InvalidArgs)
+            }
+            Error::ToolCallTimeout(_, _) => Some(Self::Timeout),
+            Error::EgressDenied(_) | Error::EgressMetadata(_) => Some(Self::PermissionDenied),
+            Error::ConversationConflict(_, _, _) => Some(Self::Conflict),
+            Error::PlanStepUndefined(_, _) => Some(Self::NotFound),
+            _ => None,
+        }
+    }
+
+    fn from_io_error(error: &std::io::Error) -> Option<Self> {
+        match error.kind() {
+            ErrorKind::NotFound => Some(Self::NotFound),
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => {
+                Some(Self::PermissionDenied)
+            }
+            ErrorKind::TimedOut => Some(Self::Timeout),
+            ErrorKind::InvalidInput => Some(Self::InvalidArgs),
+            ErrorKind::AlreadyExists => Some(Self::Conflict),
+            ErrorKind::FileTooLarge => Some(Self::TooLarge),
+            _ => None,
+        }
+    }
+
+    /// Whether the failure is caused by the call rather than by the tool, in
+    /// which case the tool keeps working for other calls
+    pub fn is_caller_error(&self) -> bool {
+        *self != Self::Timeout
+    }
+
+    /// Creates an error with the message that carries the code
+    pub fn error(self, message: impl Into<String>) -> anyhow::Error {
+        ToolError { code: self, message: message.into() }.into()
+    }
+}
+
+/// An error raised by a tool with its code
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ToolError {
+    pub code: ToolErrorCode,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let fixture = ToolErrorCode::TooLarge.error("The range exceeds 40000 characters");
+        assert_eq!(
+            ToolErrorCode::from_error(&fixture),
+            Some(ToolErrorCode::TooLarge)
+        );
+        assert_eq!(fixture.to_string(), "The range exceeds 40000 characters");
+
+        let fixture = anyhow::Error::from(std::io::Error::from(ErrorKind::NotFound))
+            .context("Failed to read /project/lib.rs");
+        assert_eq!(
+            ToolErrorCode::from_error(&fixture),
+            Some(ToolErrorCode::NotFound)
+        );
+
+        let fixture = anyhow::Error::from(Error::ToolCallTimeout("shell".to_string(), 300));
+        assert_eq!(
+            ToolErrorCode::from_error(&fixture),
+            Some(ToolErrorCode::Timeout)
+        );
+
+        let fixture = serde_json::from_str::<u64>("\"text\"")
+            .context("Invalid arguments")
+            .unwrap_err();
+        assert_eq!(
+            ToolErrorCode::from_error(&fixture),
+            Some(ToolErrorCode::InvalidArgs)
+        );
+
+        let fixture = anyhow::anyhow!("Something went wrong");
+        assert_eq!(ToolErrorCode::from_error(&fixture), None);
+    }
+
+    #[test]
+    fn test_is_caller_error() {
+        assert!(ToolErrorCode::CommandFailed.is_caller_error());
+        assert!(ToolErrorCode::InvalidArgs.is_caller_error());
+        assert!(!ToolErrorCode::Timeout.is_caller_error());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let actual = serde_json::to_string(&ToolErrorCode::PermissionDenied).unwrap();
+        assert_eq!(actual, "\"permission_denied\"");
+        assert_eq!(
+            ToolErrorCode::PermissionDenied.to_string(),
+            "permission_denied"
+        );
+    }
+}
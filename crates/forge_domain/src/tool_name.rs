@@ -1,6 +1,10 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Prefixes of the names of the built-in tools
+const BUILT_IN_PREFIXES: [&str; 2] = ["forge_tool_", "tool_forge_"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct ToolName(String);
 
@@ -18,8 +22,52 @@ impl ToolName {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Returns the group of a built-in tool, e.g. `fs` for `forge_tool_fs_read`
+    /// or `process` for the shell tool. Other tools don't belong to a group.
+    pub fn group(&self) -> Option<&str> {
+        let name = BUILT_IN_PREFIXES
+            .iter()
+            .find_map(|prefix| self.as_str().strip_prefix(prefix))?;
+        Some(name.split_once('_').map_or(name, |(group, _)| group))
+    }
+
+    /// Returns true if the pattern names the tool: its full name, its name
+    /// without the built-in prefix, e.g. `fs_read`, or its group
+    pub fn matches(&self, pattern: &str) -> bool {
+        self.as_str() == pattern
+            || BUILT_IN_PREFIXES
+                .iter()
+                .any(|prefix| self.as_str().strip_prefix(prefix) == Some(pattern))
+            || self.group() == Some(pattern)
+    }
 }
 
 pub trait NamedTool {
     fn tool_name() -> ToolName;
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_group() {
+        assert_eq!(ToolName::new("forge_tool_fs_read").group(), Some("fs"));
+        assert_eq!(ToolName::new("tool_forge_task_list").group(), Some("task"));
+        assert_eq!(ToolName::new("forge_tool_lsp").group(), Some("lsp"));
+        assert_eq!(ToolName::new("deploy").group(), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let shell = ToolName::new("forge_tool_process_shell");
+        assert!(shell.matches("forge_tool_process_shell"));
+        assert!(shell.matches("process_shell"));
+        assert!(shell.matches("process"));
+        assert!(!shell.matches("shell"));
+        assert!(!shell.matches("fs"));
+    }
+}
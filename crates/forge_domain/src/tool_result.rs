@@ -1,7 +1,7 @@
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
-use crate::{ToolCallFull, ToolCallId, ToolName};
+use crate::{FileChange, Source, ToolCallFull, ToolCallId, ToolErrorCode, ToolName};
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Setters)]
 #[setters(strip_option, into)]
@@ -12,6 +12,15 @@ pub struct ToolResult {
     pub content: String,
     #[setters(skip)]
     pub is_error: bool,
+    /// The class of the failure, when the error of the tool carries one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ToolErrorCode>,
+    /// Where the content came from, when it was read from a file or a URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// The files the call changed, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FileChange>,
 }
 
 impl ToolResult {
@@ -21,12 +30,16 @@ impl ToolResult {
             call_id: None,
             content: String::default(),
             is_error: false,
+            error_code: None,
+            source: None,
+            changes: Vec::new(),
         }
     }
 
     pub fn success(mut self, content: impl Into<String>) -> Self {
         self.content = content.into();
         self.is_error = false;
+        self.error_code = None;
         self
     }
 
@@ -40,6 +53,7 @@ impl ToolResult {
 
         self.content = output;
         self.is_error = true;
+        self.error_code = ToolErrorCode::from_error(&err);
         self
     }
 }
@@ -51,6 +65,23 @@ impl From<ToolCallFull> for ToolResult {
             call_id: value.call_id,
             content: String::default(),
             is_error: false,
+            error_code: None,
+            source: None,
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl From<&ToolCallFull> for ToolResult {
+    fn from(value: &ToolCallFull) -> Self {
+        Self {
+            name: value.name.clone(),
+            call_id: value.call_id.clone(),
+            content: String::default(),
+            is_error: false,
+            error_code: None,
+            source: None,
+            changes: Vec::new(),
         }
     }
 }
@@ -156,5 +187,20 @@ mod tests {
             ToolResult::new(ToolName::new("test_tool")).failure(anyhow::anyhow!("error message"));
         assert!(failure.is_error);
         assert_eq!(failure.content, "\nERROR:\nCaused by: error message\n");
+        assert_eq!(failure.error_code, None);
+    }
+
+    #[test]
+    fn test_failure_error_code() {
+        let actual = ToolResult::new(ToolName::new("test_tool"))
+            .failure(ToolErrorCode::NotFound.error("File not found: /project/lib.rs"));
+        assert_eq!(actual.error_code, Some(ToolErrorCode::NotFound));
+        assert_eq!(
+            actual.content,
+            "\nERROR:\nCaused by: File not found: /project/lib.rs\n"
+        );
+
+        let actual = actual.success("content");
+        assert_eq!(actual.error_code, None);
     }
 }
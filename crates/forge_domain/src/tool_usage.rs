@@ -1,13 +1,19 @@
 use std::collections::{BTreeMap, HashSet};
 use std::fmt::Display;
 
-use schemars::schema::{InstanceType, SingleOrVec};
+use schemars::schema::{InstanceType, RootSchema, SingleOrVec};
 use serde::Serialize;
 
 use crate::ToolDefinition;
 
 pub struct ToolUsagePrompt<'a> {
-    tools: &'a Vec<ToolDefinition>,
+    tools: &'a [ToolDefinition],
+}
+
+impl<'a> From<&'a [ToolDefinition]> for ToolUsagePrompt<'a> {
+    fn from(value: &'a [ToolDefinition]) -> Self {
+        Self { tools: value }
+    }
 }
 
 impl<'a> From<&'a Vec<ToolDefinition>> for ToolUsagePrompt<'a> {
@@ -19,47 +25,18 @@ impl<'a> From<&'a Vec<ToolDefinition>> for ToolUsagePrompt<'a> {
 impl Display for ToolUsagePrompt<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for tool in self.tools.iter() {
-            let required = tool
-                .input_schema
-                .schema
-                .clone()
-                .object
-                .iter()
-                .flat_map(|object| object.required.clone().into_iter())
-                .collect::<HashSet<_>>();
-
-            let parameters = tool
-                .input_schema
-                .schema
-                .object
-                .clone()
-                .into_iter()
-                .flat_map(|object| object.properties.into_iter())
-                .flat_map(|(name, props)| {
-                    let object = props.into_object();
-                    let instance = object.instance_type.clone();
-                    object
-                        .metadata
-                        .into_iter()
-                        .map(move |meta| (name.clone(), meta, instance.clone()))
-                })
-                .flat_map(|(name, meta, instance)| {
-                    meta.description
-                        .into_iter()
-                        .map(move |desc| (name.clone(), desc, instance.clone()))
-                })
-                .map(|(name, desc, instance)| {
-                    let parameter = Parameter {
-                        description: desc,
-                        type_of: instance,
-                        is_required: required.contains(&name),
-                    };
-
-                    (name, parameter)
-                })
-                .collect::<BTreeMap<_, _>>();
-
-            let schema = Schema { name: tool.name.as_str().to_string(), arguments: parameters };
+            // Only structured results are described, most tools return text
+            let returns = tool
+                .output_schema
+                .as_ref()
+                .map(properties)
+                .filter(|properties| !properties.is_empty());
+
+            let schema = Schema {
+                name: tool.name.as_str().to_string(),
+                arguments: properties(&tool.input_schema),
+                returns,
+            };
 
             writeln!(f, "{schema}")?;
         }
@@ -68,10 +45,54 @@ impl Display for ToolUsagePrompt<'_> {
     }
 }
 
+/// Returns the documented properties of an object schema
+fn properties(schema: &RootSchema) -> BTreeMap<String, Parameter> {
+    let required = schema
+        .schema
+        .clone()
+        .object
+        .iter()
+        .flat_map(|object| object.required.clone().into_iter())
+        .collect::<HashSet<_>>();
+
+    schema
+        .schema
+        .object
+        .clone()
+        .into_iter()
+        .flat_map(|object| object.properties.into_iter())
+        .flat_map(|(name, props)| {
+            let object = props.into_object();
+            let instance = object.instance_type.clone();
+            object
+                .metadata
+                .into_iter()
+                .map(move |meta| (name.clone(), meta, instance.clone()))
+        })
+        .flat_map(|(name, meta, instance)| {
+            meta.description
+                .into_iter()
+                .map(move |desc| (name.clone(), desc, instance.clone()))
+        })
+        .map(|(name, desc, instance)| {
+            let parameter = Parameter {
+                description: desc,
+                type_of: instance,
+                is_required: required.contains(&name),
+            };
+
+            (name, parameter)
+        })
+        .collect::<BTreeMap<_, _>>()
+}
+
 #[derive(Serialize)]
 struct Schema {
     name: String,
     arguments: BTreeMap<String, Parameter>,
+    /// Fields of the JSON result of the tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    returns: Option<BTreeMap<String, Parameter>>,
 }
 
 #[derive(Serialize)]
@@ -141,4 +162,19 @@ mod tests {
         let prompt = ToolUsagePrompt::from(&tools);
         assert_snapshot!(prompt);
     }
+
+    #[derive(JsonSchema)]
+    pub struct Harvest {
+        /// Number of mangoes picked
+        #[allow(dead_code)]
+        count: u64,
+    }
+
+    #[test]
+    fn test_tool_usage_prompt_with_output_schema() {
+        let tools =
+            vec![ToolDefinition::from(&MangoTool).output_schema(schemars::schema_for!(Harvest))];
+        let prompt = ToolUsagePrompt::from(&tools);
+        assert_snapshot!(prompt);
+    }
 }
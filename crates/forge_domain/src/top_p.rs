@@ -0,0 +1,131 @@
+use std::fmt;
+use std::ops::Deref;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, NumberValidation, Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A newtype for nucleus sampling values with built-in validation
+///
+/// The model only samples from the most likely tokens whose cumulative
+/// probability reaches `top_p`:
+/// - Lower values (e.g., 0.1) restrict the output to the most likely tokens
+/// - 1.0 considers all the tokens
+/// - Valid range is 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct TopP(f32);
+
+impl TopP {
+    /// Creates a new TopP value, returning an error if outside the valid range
+    /// (0.0 to 1.0)
+    pub fn new(value: f32) -> Result<Self, String> {
+        if Self::is_valid(value) {
+            Ok(Self(value))
+        } else {
+            Err(format!("top_p must be between 0.0 and 1.0, got {value}"))
+        }
+    }
+
+    /// Returns true if the value is within the valid range (0.0 to 1.0)
+    pub fn is_valid(value: f32) -> bool {
+        (0.0..=1.0).contains(&value)
+    }
+
+    /// Returns the inner f32 value
+    pub fn value(&self) -> f32 {
+        self.0
+    }
+}
+
+impl Deref for TopP {
+    type Target = f32;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<TopP> for f32 {
+    fn from(top_p: TopP) -> Self {
+        top_p.0
+    }
+}
+
+impl fmt::Display for TopP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl JsonSchema for TopP {
+    fn schema_name() -> String {
+        "TopP".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::Number.into()),
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(0.0),
+                maximum: Some(1.0),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl Serialize for TopP {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Round to avoid serializing floating point noise, e.g. 0.9 as
+        // 0.899999976
+        let formatted = format!("{:.2}", self.0);
+        let value = formatted.parse::<f32>().unwrap();
+        serializer.serialize_f32(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for TopP {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let value = f32::deserialize(deserializer)?;
+        Self::new(value).map_err(Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_top_p_creation() {
+        for value in [0.0, 0.5, 1.0] {
+            assert_eq!(TopP::new(value).unwrap().value(), value);
+        }
+        for value in [-0.1, 1.1, 2.0] {
+            assert!(TopP::new(value).is_err(), "{value} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_top_p_deserialization() {
+        let actual: TopP = serde_json::from_value(json!(0.9)).unwrap();
+        assert_eq!(actual.value(), 0.9);
+
+        let actual = serde_json::from_value::<TopP>(json!(1.5))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(actual, "top_p must be between 0.0 and 1.0, got 1.5");
+    }
+}
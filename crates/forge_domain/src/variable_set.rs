@@ -0,0 +1,96 @@
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, NamedTool, ToolCallFull, ToolDefinition, ToolName};
+
+/// Variables set by forge itself, which agents can't overwrite: the mode of
+/// the conversation and the output of the turn in handover conditions
+const RESERVED_VARIABLES: [&str; 2] = ["mode", "output"];
+
+/// Input for setting a variable of the conversation, e.g. the outcome of the
+/// tests, which the `when` conditions of the handovers and the templates of the
+/// prompts can read.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct VariableSet {
+    /// Name of the variable, e.g. `tests_passed`.
+    pub name: String,
+    /// Value of the variable, e.g. `true`.
+    pub value: Value,
+}
+
+impl NamedTool for VariableSet {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_variable_set")
+    }
+}
+
+impl VariableSet {
+    pub fn tool_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: Self::tool_name(),
+            description: "Sets a variable of the conversation to the given value, e.g. \
+                          `tests_passed` to `false` after running the tests. The variables \
+                          decide which agent the work is handed over to once you complete \
+                          your turn."
+                .to_string(),
+            input_schema: schema_for!(VariableSet),
+            output_schema: None,
+            mutating: false,
+        }
+    }
+
+    /// Parses the tool call into the variable to set. Returns `None` if the
+    /// call is meant for a different tool.
+    pub fn parse(tool_call: &ToolCallFull) -> Option<crate::Result<Self>> {
+        if tool_call.name != Self::tool_name() {
+            return None;
+        }
+
+        let input = serde_json::from_value::<Self>(tool_call.arguments.clone())
+            .map_err(Error::ToolCallArgument);
+        Some(input.and_then(|input| {
+            let name = input.name.trim();
+            if name.is_empty() || RESERVED_VARIABLES.contains(&name) {
+                return Err(Error::ReservedVariable(input.name));
+            }
+            Ok(input)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_variable_set() {
+        let fixture = ToolCallFull::new(VariableSet::tool_name())
+            .arguments(json!({"name": "tests_passed", "value": false}));
+
+        let actual = VariableSet::parse(&fixture).unwrap().unwrap();
+
+        let expected = VariableSet { name: "tests_passed".to_string(), value: json!(false) };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_reserved_variable() {
+        let fixture = ToolCallFull::new(VariableSet::tool_name())
+            .arguments(json!({"name": "mode", "value": "ACT"}));
+
+        let actual = VariableSet::parse(&fixture).unwrap();
+
+        assert!(matches!(actual, Err(Error::ReservedVariable(name)) if name == "mode"));
+    }
+
+    #[test]
+    fn test_parse_other_tool() {
+        let fixture = ToolCallFull::new(ToolName::new("forge_tool_fs_read"));
+        let actual = VariableSet::parse(&fixture);
+        assert!(actual.is_none());
+    }
+}
@@ -1,16 +1,32 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use derive_setters::Setters;
 use merge::Merge;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::temperature::Temperature;
-use crate::{Agent, AgentId, ModelId};
+use crate::{
+    Agent, AgentId, ApprovalAction, ApprovalPolicy, CustomTool, DiffConfig, EditorConfig,
+    EgressPolicy, LanguageServer, Mode, ModeConfig, ModelId, ResourceLimits, SchemaViolation,
+    ShellPolicy, SpinnerConfig, Theme,
+};
+
+/// Tools that can run code or access the network, which are disabled until the
+/// user trusts the workspace
+const UNTRUSTED_TOOLS: [&str; 4] = [
+    "forge_tool_process_shell",
+    "tool_forge_cargo_check",
+    "forge_tool_net_fetch",
+    "forge_tool_net_download",
+];
 
 /// Configuration for a workflow that contains all settings
 /// required to initialize a workflow.
-#[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters)]
+#[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters, JsonSchema)]
 #[setters(strip_option)]
 pub struct Workflow {
     /// Agents that are part of this workflow
@@ -67,6 +83,115 @@ pub struct Workflow {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
     pub tool_supported: Option<bool>,
+
+    /// Limits applied to the processes spawned by shell commands of all agents.
+    /// If not specified, each agent's individual setting will be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub resource_limits: Option<ResourceLimits>,
+
+    /// Hosts that the tools of all agents are allowed to access over the
+    /// network. If not specified, each agent's individual setting will be
+    /// used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub egress: Option<EgressPolicy>,
+
+    /// Commands that all agents are allowed to execute with the shell tool.
+    /// If not specified, each agent's individual setting will be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub shell_policy: Option<ShellPolicy>,
+
+    /// Tools that all agents may only execute after the user approves the
+    /// call. If not specified, each agent's individual setting will be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub approval: Option<ApprovalPolicy>,
+
+    /// Flag to disable the tools that modify the workspace for all agents in
+    /// this workflow. If not specified, each agent's individual setting will
+    /// be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub read_only: Option<bool>,
+
+    /// Flag to let the user review the changes to a file before it's written
+    /// for all agents in this workflow. If not specified, each agent's
+    /// individual setting will be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub review_edits: Option<bool>,
+
+    /// Language servers used by the LSP tool for all agents in this workflow.
+    /// If not specified, each agent's individual setting will be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub language_servers: Option<Vec<LanguageServer>>,
+
+    /// Tools that run shell commands, available to all agents in this
+    /// workflow in addition to the ones they declare
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub custom_tools: Option<Vec<CustomTool>>,
+
+    /// Mode that sessions start in for this project, taking precedence over
+    /// the global `FORGE_DEFAULT_MODE` and the mode used last
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub default_mode: Option<Mode>,
+
+    /// Modes that the user can switch to, in addition to the built-in Act and
+    /// Plan modes, which can be redefined here
+    #[merge(strategy = crate::merge::vec::unify_by_key)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<ModeConfig>,
+
+    /// Configuration of the spinner shown while forge is working, e.g. custom
+    /// messages for each phase or disabling it
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub spinner: Option<SpinnerConfig>,
+
+    /// Configuration of the editor used to type prompts, e.g. vim key bindings
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub editor: Option<EditorConfig>,
+
+    /// Configuration of the diffs shown when reviewing and undoing edits, e.g.
+    /// side by side
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub diff: Option<DiffConfig>,
+
+    /// Colors of the terminal output: dark, light or plain
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub theme: Option<Theme>,
+
+    /// Whether the workflow was restricted because the workspace isn't
+    /// trusted, so that the workflows it runs are restricted too
+    #[serde(skip)]
+    #[merge(strategy = merge::bool::overwrite_true)]
+    pub untrusted: bool,
+
+    /// Path of the file the workflow was read from
+    #[serde(skip)]
+    #[merge(strategy = crate::merge::option)]
+    pub path: Option<PathBuf>,
 }
 
 impl Default for Workflow {
@@ -75,7 +200,7 @@ impl Default for Workflow {
     }
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize, Merge, Setters)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, Merge, Setters, JsonSchema)]
 #[setters(strip_option, into)]
 pub struct Command {
     #[merge(strategy = crate::merge::std::overwrite)]
@@ -102,7 +227,102 @@ impl Workflow {
             custom_rules: None,
             temperature: None,
             tool_supported: None,
+            resource_limits: None,
+            egress: None,
+            shell_policy: None,
+            approval: None,
+            read_only: None,
+            review_edits: None,
+            language_servers: None,
+            custom_tools: None,
+            default_mode: None,
+            modes: Vec::new(),
+            spinner: None,
+            editor: None,
+            diff: None,
+            theme: None,
+            untrusted: false,
+            path: None,
+        }
+    }
+
+    /// Restricts the workflow of a workspace that the user doesn't trust, since
+    /// its configuration could be malicious. The commands and custom tools it
+    /// declares are removed, the language servers it configures are replaced
+    /// by the default ones and the tools that can run code or access the
+    /// network are denied for all agents.
+    pub fn restrict_untrusted(&mut self) {
+        self.untrusted = true;
+        self.commands.clear();
+        self.custom_tools = None;
+        self.language_servers = None;
+        for agent in self.agents.iter_mut() {
+            agent.custom_tools = None;
+            agent.language_servers = None;
         }
+
+        let approval = self.approval.get_or_insert_default();
+        for tool in UNTRUSTED_TOOLS {
+            approval
+                .tools
+                .insert(tool.to_string(), ApprovalAction::Deny);
+        }
+    }
+
+    /// Returns the modes that the user can switch to: the built-in ones, as
+    /// redefined by the workflow, followed by the ones it adds
+    pub fn available_modes(&self) -> Vec<ModeConfig> {
+        let mut modes = Workflow::default().modes;
+        crate::merge::vec::unify_by_key(&mut modes, self.modes.clone());
+        modes
+    }
+
+    /// JSON schema of the workflow files, which editors can use to complete
+    /// and check them
+    pub fn schema() -> RootSchema {
+        schema_for!(Workflow)
+    }
+
+    /// Checks a workflow file against the schema, reporting every misplaced
+    /// value with its path rather than the first error of serde. The
+    /// handovers of a workflow that matches the schema are checked too.
+    pub fn validate(value: &Value) -> Vec<SchemaViolation> {
+        let violations = crate::validate(&Self::schema(), value);
+        if !violations.is_empty() {
+            return violations;
+        }
+        match serde_json::from_value::<Workflow>(value.clone()) {
+            Ok(workflow) => workflow.validate_handovers(),
+            Err(_) => violations,
+        }
+    }
+
+    /// Reports the handovers whose condition is broken or whose event no
+    /// agent would receive under the priority rules of the subscriptions
+    fn validate_handovers(&self) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        for (index, agent) in self.agents.iter().enumerate() {
+            for (position, handover) in agent.handovers.iter().flatten().enumerate() {
+                let path = format!("agents[{index}].handovers[{position}]");
+                if let Err(error) = handover.validate() {
+                    violations.push(SchemaViolation {
+                        path: format!("{path}.when"),
+                        message: error.to_string(),
+                    });
+                }
+                let subscribed = self.agents.iter().any(|agent| {
+                    !agent.disable.unwrap_or_default()
+                        && agent.subscription_priority(&handover.event).is_some()
+                });
+                if !subscribed {
+                    violations.push(SchemaViolation {
+                        path: format!("{path}.event"),
+                        message: format!("no agent is subscribed to `{}`", handover.event),
+                    });
+                }
+            }
+        }
+        violations
     }
 
     fn find_agent(&self, id: &AgentId) -> Option<&Agent> {
@@ -137,6 +357,55 @@ mod tests {
         assert_eq!(actual.custom_rules, None);
         assert_eq!(actual.temperature, None);
         assert_eq!(actual.tool_supported, None);
+        assert_eq!(actual.resource_limits, None);
+        assert_eq!(actual.egress, None);
+        assert_eq!(actual.shell_policy, None);
+        assert_eq!(actual.approval, None);
+        assert_eq!(actual.read_only, None);
+        assert_eq!(actual.language_servers, None);
+        assert_eq!(actual.custom_tools, None);
+        assert_eq!(actual.default_mode, None);
+        assert!(actual.modes.is_empty());
+    }
+
+    #[test]
+    fn test_restrict_untrusted() {
+        let fixture = r#"
+commands:
+  - name: deploy
+    description: Deploys the project
+custom_tools:
+  - name: install
+    description: Installs the dependencies
+    command: ./install.sh
+approval:
+  tools:
+    forge_tool_fs_remove: ask
+    forge_tool_process_shell: allow
+language_servers:
+  - command: ./pwn.sh
+    extensions: [rs]
+agents:
+  - id: coder
+    language_servers:
+      - command: ./pwn.sh
+        extensions: [rs]
+"#;
+        let mut actual: Workflow = serde_yml::from_str(fixture).unwrap();
+
+        actual.restrict_untrusted();
+
+        assert!(actual.untrusted);
+        assert!(actual.commands.is_empty());
+        assert_eq!(actual.custom_tools, None);
+        assert_eq!(actual.language_servers, None);
+        assert_eq!(actual.agents[0].language_servers, None);
+        let approval = actual.approval.unwrap();
+        let action = |tool: &str| approval.action(&crate::ToolName::new(tool));
+        assert_eq!(action("forge_tool_process_shell"), ApprovalAction::Deny);
+        assert_eq!(action("forge_tool_net_fetch"), ApprovalAction::Deny);
+        assert_eq!(action("forge_tool_fs_remove"), ApprovalAction::Ask);
+        assert_eq!(action("forge_tool_fs_read"), ApprovalAction::Allow);
     }
 
     #[test]
@@ -161,6 +430,15 @@ mod tests {
         assert_eq!(actual.tool_supported, Some(true));
     }
 
+    #[test]
+    fn test_workflow_with_theme() {
+        let fixture = "theme: light";
+
+        let actual: Workflow = serde_yml::from_str(fixture).unwrap();
+
+        assert_eq!(actual.theme, Some(Theme::Light));
+    }
+
     #[test]
     fn test_workflow_merge_tool_supported() {
         // Fixture
@@ -188,4 +466,97 @@ mod tests {
         // Assert
         assert_eq!(base.tool_supported, Some(true));
     }
+
+    #[test]
+    fn test_available_modes() {
+        let fixture = r#"
+modes:
+  - name: plan
+    tools: [forge_tool_fs_read]
+  - name: review
+    description: Review the changes
+"#;
+        let workflow: Workflow = serde_yml::from_str(fixture).unwrap();
+
+        let actual = workflow.available_modes();
+
+        let names = actual
+            .iter()
+            .map(|mode| mode.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["act", "plan", "review"]);
+        assert_eq!(
+            actual[1].tools,
+            Some(vec![crate::ToolName::new("forge_tool_fs_read")])
+        );
+        assert!(actual[1].description.is_some());
+    }
+
+    #[test]
+    fn test_default_workflow_is_valid() {
+        let fixture: Value =
+            serde_yml::from_str(include_str!("../../../forge.default.yaml")).unwrap();
+        let actual = Workflow::validate(&fixture);
+        assert_eq!(actual, vec![]);
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let fixture: Value = serde_yml::from_str(
+            r#"
+agents:
+  - id: reviewer
+    temprature: 0.2
+    tools: forge_tool_fs_read
+  - description: Writes tests
+    max_turns: ten
+diff:
+  style: split
+"#,
+        )
+        .unwrap();
+
+        let actual = Workflow::validate(&fixture)
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            "agents[0].temprature: unknown key, did you mean `temperature`?",
+            "agents[0].tools: expected a list or null, found \"forge_tool_fs_read\"",
+            "agents[1]: missing key `id`",
+            "agents[1].max_turns: expected an integer or null, found \"ten\"",
+            "diff.style: expected one of \"unified\", \"side_by_side\", found \"split\"",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_validate_handovers() {
+        let fixture: Value = serde_yml::from_str(
+            r#"
+agents:
+  - id: tester
+    subscribe: [test]
+    handovers:
+      - event: fix
+        when: ${output} ==
+      - event: deploy
+  - id: fixer
+    subscribe: [fix]
+    handovers:
+      - event: test
+"#,
+        )
+        .unwrap();
+
+        let actual = Workflow::validate(&fixture)
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            "agents[0].handovers[0].when: Invalid handover condition '${output} ==': `==` is missing an operand",
+            "agents[0].handovers[1].event: no agent is subscribed to `deploy`",
+        ];
+        assert_eq!(actual, expected);
+    }
 }
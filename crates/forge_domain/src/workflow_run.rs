@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, NamedTool, ToolCallFull, ToolDefinition, ToolName};
+
+/// Event dispatched to the nested workflow when none is specified
+pub const DEFAULT_WORKFLOW_RUN_EVENT: &str = "act/user_task_init";
+
+/// Input for running another workflow file as a tool. The workflow is executed
+/// in an isolated conversation and its final output is returned as the result.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct WorkflowRun {
+    /// Path to the workflow file to execute, relative to the current working
+    /// directory or absolute.
+    pub path: PathBuf,
+    /// The task that should be handed over to the workflow.
+    pub task: String,
+    /// Name of the event used to deliver the task to the workflow. Defaults to
+    /// `act/user_task_init`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+}
+
+impl NamedTool for WorkflowRun {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_workflow_run")
+    }
+}
+
+impl WorkflowRun {
+    pub fn tool_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: Self::tool_name(),
+            description: "Runs another workflow file in an isolated conversation with the given \
+                          task and returns its final output. Use it to delegate focused \
+                          sub-tasks to specialised workflows."
+                .to_string(),
+            input_schema: schema_for!(WorkflowRun),
+            output_schema: None,
+            mutating: false,
+        }
+    }
+
+    /// Parses the tool call into a workflow run request. Returns `None` if the
+    /// call is meant for a different tool.
+    pub fn parse(tool_call: &ToolCallFull) -> Option<crate::Result<Self>> {
+        if tool_call.name != Self::tool_name() {
+            return None;
+        }
+
+        Some(serde_json::from_value(tool_call.arguments.clone()).map_err(Error::ToolCallArgument))
+    }
+
+    /// Name of the event used to deliver the task to the nested workflow
+    pub fn event_name(&self) -> &str {
+        self.event.as_deref().unwrap_or(DEFAULT_WORKFLOW_RUN_EVENT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_workflow_run() {
+        let fixture = ToolCallFull::new(WorkflowRun::tool_name())
+            .arguments(json!({"path": "review.yaml", "task": "Review the changes"}));
+
+        let actual = WorkflowRun::parse(&fixture).unwrap().unwrap();
+
+        let expected = WorkflowRun {
+            path: PathBuf::from("review.yaml"),
+            task: "Review the changes".to_string(),
+            event: None,
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.event_name(), DEFAULT_WORKFLOW_RUN_EVENT);
+    }
+
+    #[test]
+    fn test_parse_other_tool() {
+        let fixture = ToolCallFull::new(ToolName::new("forge_tool_fs_read"));
+        let actual = WorkflowRun::parse(&fixture);
+        assert!(actual.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_arguments() {
+        let fixture =
+            ToolCallFull::new(WorkflowRun::tool_name()).arguments(json!({"path": "review.yaml"}));
+        let actual = WorkflowRun::parse(&fixture).unwrap();
+        assert!(actual.is_err());
+    }
+}
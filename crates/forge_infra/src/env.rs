@@ -1,9 +1,11 @@
 use std::path::PathBuf;
 
-use forge_domain::{Environment, Provider, RetryConfig};
+use forge_domain::{Environment, Mode, Provider, RetryConfig, TimeoutConfig, DEFAULT_REGISTRY_URL};
+use url::Url;
 
 pub struct ForgeEnvironmentService {
     restricted: bool,
+    low_bandwidth: bool,
 }
 
 type ProviderSearch = (&'static str, Box<dyn FnOnce(&str) -> Provider>);
@@ -14,8 +16,10 @@ impl ForgeEnvironmentService {
     /// # Arguments
     /// * `unrestricted` - If true, use unrestricted shell mode (sh/bash) If
     ///   false, use restricted shell mode (rbash)
-    pub fn new(restricted: bool) -> Self {
-        Self { restricted }
+    /// * `low_bandwidth` - If true, save bandwidth even when
+    ///   `FORGE_LOW_BANDWIDTH` isn't set
+    pub fn new(restricted: bool, low_bandwidth: bool) -> Self {
+        Self { restricted, low_bandwidth }
     }
 
     /// Get path to appropriate shell based on platform and mode
@@ -33,9 +37,9 @@ impl ForgeEnvironmentService {
 
     /// Resolves the provider key and provider from environment variables
     ///
-    /// Returns a tuple of (provider_key, provider)
+    /// Returns a tuple of (name of the key variable, provider)
     /// Panics if no API key is found in the environment
-    fn resolve_provider(&self) -> Provider {
+    fn resolve_provider(&self) -> (&'static str, Provider) {
         let keys: [ProviderSearch; 4] = [
             ("FORGE_KEY", Box::new(Provider::antinomy)),
             ("OPENROUTER_API_KEY", Box::new(Provider::open_router)),
@@ -50,8 +54,8 @@ impl ForgeEnvironmentService {
             .join(", ");
 
         keys.into_iter()
-            .find_map(|(key, fun)| {
-                std::env::var(key).ok().map(|key| {
+            .find_map(|(name, fun)| {
+                std::env::var(name).ok().map(|key| {
                     let mut provider = fun(&key);
 
                     if let Ok(url) = std::env::var("OPENAI_URL") {
@@ -63,7 +67,7 @@ impl ForgeEnvironmentService {
                         provider.anthropic_url(url);
                     }
 
-                    provider
+                    (name, provider)
                 })
             })
             .unwrap_or_else(|| panic!("No API key found. Please set one of: {env_variables}"))
@@ -108,11 +112,109 @@ impl ForgeEnvironmentService {
         }
     }
 
+    /// Resolves the timeouts of the provider requests. Each timeout can be set
+    /// for the provider in use by prefixing it with the name of the provider,
+    /// e.g. `ANTHROPIC_FIRST_TOKEN_TIMEOUT_SECS`, or for every provider with
+    /// `FORGE_`.
+    fn resolve_timeout_config(&self, key_name: &str) -> TimeoutConfig {
+        let provider = key_name.trim_end_matches("_KEY").trim_end_matches("_API");
+        let resolve = |name: &str, default: u64| {
+            [format!("{provider}_{name}"), format!("FORGE_{name}")]
+                .iter()
+                .find_map(|var| std::env::var(var).ok()?.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+
+        let defaults = TimeoutConfig::default();
+        TimeoutConfig {
+            connect_timeout_secs: resolve("CONNECT_TIMEOUT_SECS", defaults.connect_timeout_secs),
+            first_token_timeout_secs: resolve(
+                "FIRST_TOKEN_TIMEOUT_SECS",
+                defaults.first_token_timeout_secs,
+            ),
+            stream_timeout_secs: resolve("STREAM_TIMEOUT_SECS", defaults.stream_timeout_secs),
+        }
+    }
+
+    /// Resolves the registry index location, allowing it to be overridden
+    /// through `FORGE_REGISTRY_URL`
+    fn resolve_registry_url(&self) -> Url {
+        std::env::var("FORGE_REGISTRY_URL")
+            .ok()
+            .and_then(|url| Url::parse(&url).ok())
+            .unwrap_or_else(|| Url::parse(DEFAULT_REGISTRY_URL).unwrap())
+    }
+
+    /// Resolves the default shell command timeout in seconds from
+    /// `FORGE_SHELL_TIMEOUT`
+    fn resolve_shell_timeout(&self) -> u64 {
+        std::env::var("FORGE_SHELL_TIMEOUT")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+            .unwrap_or(240) // Default value, below the overall tool call
+                            // timeout
+    }
+
+    /// Resolves the number of seconds a tool call may run from
+    /// `FORGE_TOOL_TIMEOUT`
+    fn resolve_tool_timeout(&self) -> u64 {
+        std::env::var("FORGE_TOOL_TIMEOUT")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+            .unwrap_or(300)
+    }
+
+    /// Resolves the maximum size of a tool result from
+    /// `FORGE_MAX_TOOL_RESULT_CHARS`
+    fn resolve_max_tool_result_chars(&self) -> usize {
+        std::env::var("FORGE_MAX_TOOL_RESULT_CHARS")
+            .ok()
+            .and_then(|val| val.parse::<usize>().ok())
+            .unwrap_or(50_000)
+    }
+
+    /// Resolves whether to save bandwidth from `FORGE_LOW_BANDWIDTH`
+    fn resolve_low_bandwidth(&self) -> bool {
+        self.low_bandwidth
+            || std::env::var("FORGE_LOW_BANDWIDTH")
+                .is_ok_and(|val| val.eq_ignore_ascii_case("true") || val == "1")
+    }
+
+    /// Resolves the duration of a turn after which the user is notified from
+    /// `FORGE_NOTIFY_AFTER`
+    fn resolve_notify_after(&self) -> Option<u64> {
+        std::env::var("FORGE_NOTIFY_AFTER")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+    }
+
+    /// Resolves the mode that sessions start in from `FORGE_DEFAULT_MODE`
+    fn resolve_default_mode(&self) -> Option<Mode> {
+        std::env::var("FORGE_DEFAULT_MODE")
+            .ok()
+            .and_then(|val| val.parse::<Mode>().ok())
+    }
+
+    /// Resolves the comma separated names of the environment variables that
+    /// templates may read from `FORGE_TEMPLATE_ENV`
+    fn resolve_template_env(&self) -> Vec<String> {
+        std::env::var("FORGE_TEMPLATE_ENV")
+            .map(|val| {
+                val.split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn get(&self) -> Environment {
         dotenv::dotenv().ok();
         let cwd = std::env::current_dir().unwrap_or(PathBuf::from("."));
-        let provider = self.resolve_provider();
+        let (key_name, provider) = self.resolve_provider();
         let retry_config = self.resolve_retry_config();
+        let timeout_config = self.resolve_timeout_config(key_name);
 
         Environment {
             os: std::env::consts::OS.to_string(),
@@ -125,6 +227,15 @@ impl ForgeEnvironmentService {
             home: dirs::home_dir(),
             provider,
             retry_config,
+            timeout_config,
+            registry_url: self.resolve_registry_url(),
+            shell_timeout: self.resolve_shell_timeout(),
+            tool_timeout: self.resolve_tool_timeout(),
+            max_tool_result_chars: self.resolve_max_tool_result_chars(),
+            default_mode: self.resolve_default_mode(),
+            low_bandwidth: self.resolve_low_bandwidth(),
+            notify_after: self.resolve_notify_after(),
+            template_env: self.resolve_template_env(),
         }
     }
 }
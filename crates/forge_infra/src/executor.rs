@@ -1,11 +1,15 @@
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
-use forge_domain::{CommandOutput, Environment};
+use forge_domain::{
+    CommandOutput, CommandOutputChunk, Environment, OutputStream, ResourceLimits, ResourceViolation,
+};
 use forge_services::CommandExecutorService;
 use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
 
 /// Service for executing shell commands
@@ -23,7 +27,12 @@ impl ForgeCommandExecutorService {
         Self { restricted, env, ready: Arc::new(Mutex::new(())) }
     }
 
-    fn prepare_command(&self, command_str: &str, working_dir: &Path) -> Command {
+    fn prepare_command(
+        &self,
+        command_str: &str,
+        working_dir: &Path,
+        limits: &ResourceLimits,
+    ) -> Command {
         // Create a basic command
         let is_windows = cfg!(target_os = "windows");
         let shell = if self.restricted && !is_windows {
@@ -56,40 +65,106 @@ impl ForgeCommandExecutorService {
 
         command.kill_on_drop(true);
 
+        // Run the command in its own process group so that it can be terminated
+        // along with any processes it spawns
+        #[cfg(unix)]
+        command.process_group(0);
+
+        #[cfg(unix)]
+        apply_limits(&mut command, limits);
+        #[cfg(not(unix))]
+        let _ = limits;
+
         // Set the working directory
         command.current_dir(working_dir);
 
+        // The command runs in a background process group that would be stopped
+        // by SIGTTIN when reading the terminal, and hang until the timeout, so
+        // it gets no input instead
+        #[cfg(unix)]
+        command.stdin(std::process::Stdio::null());
+        #[cfg(not(unix))]
+        command.stdin(std::process::Stdio::inherit());
+
         // Configure the command for output
         command
-            .stdin(std::process::Stdio::inherit())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
         command
     }
 
-    /// Internal method to execute commands with streaming to console
+    /// Internal method to execute commands with streaming to console, or to
+    /// the sender when one is provided. When the command doesn't finish
+    /// within the timeout or exceeds one of the resource limits, its process
+    /// group is killed and the output captured so far is returned.
     async fn execute_command_internal(
         &self,
         command: String,
         working_dir: &Path,
+        sink: OutputSink,
+        timeout: Option<Duration>,
+        limits: ResourceLimits,
+        env: Vec<(String, String)>,
     ) -> anyhow::Result<CommandOutput> {
         let ready = self.ready.lock().await;
 
-        let mut prepared_command = self.prepare_command(&command, working_dir);
+        let mut prepared_command = self.prepare_command(&command, working_dir, &limits);
+        prepared_command.envs(env);
 
         // Spawn the command
         let mut child = prepared_command.spawn()?;
+        let mut group = ProcessGroupGuard::new(&child);
 
         let mut stdout_pipe = child.stdout.take();
         let mut stderr_pipe = child.stderr.take();
+        let mut stdout_buffer = Vec::new();
+        let mut stderr_buffer = Vec::new();
 
         // Stream the output of the command to stdout and stderr concurrently
-        let (status, stdout_buffer, stderr_buffer) = tokio::try_join!(
-            child.wait(),
-            stream(&mut stdout_pipe, io::stdout()),
-            stream(&mut stderr_pipe, io::stderr())
-        )?;
+        let execution = async {
+            tokio::try_join!(
+                child.wait(),
+                stream(
+                    &mut stdout_pipe,
+                    &sink,
+                    OutputStream::Stdout,
+                    &mut stdout_buffer,
+                    limits.max_output_bytes
+                ),
+                stream(
+                    &mut stderr_pipe,
+                    &sink,
+                    OutputStream::Stderr,
+                    &mut stderr_buffer,
+                    limits.max_output_bytes
+                )
+            )
+        };
+
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, execution).await.ok(),
+            None => Some(execution.await),
+        };
+
+        let (status, mut violation) = match result {
+            Some(Ok((status, _, _))) => (Some(status), None),
+            Some(Err(error)) if OutputLimitExceeded::matches(&error) => {
+                (None, Some(ResourceViolation::OutputSize))
+            }
+            Some(Err(error)) => return Err(error.into()),
+            None => (None, None),
+        };
+
+        let timed_out = status.is_none() && violation.is_none();
+        if status.is_none() {
+            kill_process_group(&mut child).await?;
+        }
+        group.disarm();
+
+        if let Some(status) = status.as_ref() {
+            violation = detect_violation(status, &limits);
+        }
 
         // Drop happens after `try_join` due to <https://github.com/tokio-rs/tokio/issues/4309>
         drop(stdout_pipe);
@@ -99,32 +174,233 @@ impl ForgeCommandExecutorService {
         Ok(CommandOutput {
             stdout: String::from_utf8_lossy(&stdout_buffer).into_owned(),
             stderr: String::from_utf8_lossy(&stderr_buffer).into_owned(),
-            exit_code: status.code(),
+            exit_code: status.and_then(|status| status.code()),
             command,
+            timed_out,
+            violation,
         })
     }
 }
 
-/// reads the output from A and writes it to W
-async fn stream<A: AsyncReadExt + Unpin, W: Write>(
+/// Error used to abort reading the output once `max_output_bytes` is reached
+#[derive(Debug)]
+struct OutputLimitExceeded;
+
+impl std::fmt::Display for OutputLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", ResourceViolation::OutputSize)
+    }
+}
+
+impl std::error::Error for OutputLimitExceeded {}
+
+impl OutputLimitExceeded {
+    fn matches(error: &io::Error) -> bool {
+        error
+            .get_ref()
+            .is_some_and(|inner| inner.is::<OutputLimitExceeded>())
+    }
+}
+
+/// Applies the memory and CPU time limits to the spawned shell. The limits
+/// are inherited by every process started by the command.
+#[cfg(unix)]
+fn apply_limits(command: &mut Command, limits: &ResourceLimits) {
+    let memory = limits
+        .max_memory_mb
+        .map(|mb| mb.saturating_mul(1024 * 1024));
+    let cpu = limits.max_cpu_secs;
+    if memory.is_none() && cpu.is_none() {
+        return;
+    }
+
+    // SAFETY: the closure runs in the forked child before exec and only calls
+    // `setrlimit`, which is async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = memory {
+                let limit = libc::rlimit { rlim_cur: bytes as _, rlim_max: bytes as _ };
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(secs) = cpu {
+                // The hard limit is one second above the soft limit so that the
+                // process receives SIGXCPU before being killed.
+                let limit =
+                    libc::rlimit { rlim_cur: secs as _, rlim_max: secs.saturating_add(1) as _ };
+                if libc::setrlimit(libc::RLIMIT_CPU, &limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Works out whether a finished command was terminated because of one of the
+/// configured limits. The shell reports a signal that terminated its child as
+/// an exit code of `128 + signal`, so both forms are checked.
+#[cfg(unix)]
+fn detect_violation(
+    status: &std::process::ExitStatus,
+    limits: &ResourceLimits,
+) -> Option<ResourceViolation> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let signal = status.signal().or_else(|| {
+        status
+            .code()
+            .filter(|code| *code > 128)
+            .map(|code| code - 128)
+    })?;
+
+    if limits.max_cpu_secs.is_some() && signal == libc::SIGXCPU {
+        return Some(ResourceViolation::CpuTime);
+    }
+
+    // Running out of address space surfaces as a failed allocation, which most
+    // programs turn into an abort or a crash, but so do plenty of bugs. The
+    // signal is kept so that the agent can tell.
+    let memory_signals = [libc::SIGKILL, libc::SIGSEGV, libc::SIGABRT];
+    if limits.max_memory_mb.is_some() && memory_signals.contains(&signal) {
+        return Some(ResourceViolation::PossiblyMemory { signal });
+    }
+
+    None
+}
+
+#[cfg(not(unix))]
+fn detect_violation(
+    _status: &std::process::ExitStatus,
+    _limits: &ResourceLimits,
+) -> Option<ResourceViolation> {
+    None
+}
+
+/// Destination for the output of a running command
+enum OutputSink {
+    Console,
+    Channel(Sender<CommandOutputChunk>),
+}
+
+/// Kills the process group of a command that is still running when the
+/// future executing it is dropped, e.g. when the tool call is cancelled
+/// because it timed out. `kill_on_drop` only kills the shell, not the
+/// processes it started.
+struct ProcessGroupGuard {
+    pid: Option<u32>,
+}
+
+impl ProcessGroupGuard {
+    fn new(child: &Child) -> Self {
+        Self { pid: child.id() }
+    }
+
+    /// Called once the command has finished or has been killed
+    fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.pid {
+            // SAFETY: the command is spawned as the leader of its own process
+            // group and is still running, so signalling the group only affects
+            // processes started by it.
+            unsafe {
+                libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// Kills the process group of the child so that any processes spawned by the
+/// command are terminated as well, and waits for the child to exit.
+async fn kill_process_group(child: &mut Child) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: the command is spawned as the leader of its own process group,
+        // so signalling the group only affects processes started by it.
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    // Fallback for platforms without process groups, and a no-op if the child
+    // was already killed as part of the group.
+    let _ = child.start_kill();
+    child.wait().await?;
+    Ok(())
+}
+
+/// reads the output from A into the buffer while forwarding it to the sink.
+/// Fails with [`OutputLimitExceeded`] once the buffer reaches `max_bytes`.
+async fn stream<A: AsyncReadExt + Unpin>(
     io: &mut Option<A>,
-    mut writer: W,
-) -> io::Result<Vec<u8>> {
-    let mut output = Vec::new();
+    sink: &OutputSink,
+    stream: OutputStream,
+    output: &mut Vec<u8>,
+    max_bytes: Option<usize>,
+) -> io::Result<()> {
     if let Some(io) = io.as_mut() {
         let mut buff = [0; 1024];
+        // Bytes of a multi-byte character that was split across reads
+        let mut pending = Vec::new();
         loop {
-            let n = io.read(&mut buff).await?;
-            if n == 0 {
+            let read = io.read(&mut buff).await?;
+            if read == 0 {
                 break;
             }
-            writer.write_all(&buff[..n])?;
-            // note: flush is necessary else we get the cursor could not be found error.
-            writer.flush()?;
+            let n = match max_bytes {
+                Some(max) => read.min(max.saturating_sub(output.len())),
+                None => read,
+            };
             output.extend_from_slice(&buff[..n]);
+
+            match sink {
+                OutputSink::Console => {
+                    let mut writer: Box<dyn Write> = match stream {
+                        OutputStream::Stdout => Box::new(io::stdout()),
+                        OutputStream::Stderr => Box::new(io::stderr()),
+                    };
+                    writer.write_all(&buff[..n])?;
+                    // note: flush is necessary else we get the cursor could not be found error.
+                    writer.flush()?;
+                }
+                OutputSink::Channel(sender) => {
+                    pending.extend_from_slice(&buff[..n]);
+
+                    let valid = match std::str::from_utf8(&pending) {
+                        Ok(_) => pending.len(),
+                        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                        Err(_) => pending.len(),
+                    };
+                    let content = String::from_utf8_lossy(&pending[..valid]).into_owned();
+                    pending.drain(..valid);
+
+                    // The receiver going away shouldn't abort the command, so the
+                    // output is still collected even if the chunk can't be delivered.
+                    if !content.is_empty() {
+                        let _ = sender.send(CommandOutputChunk { stream, content }).await;
+                    }
+                }
+            }
+
+            if n < read {
+                return Err(io::Error::other(OutputLimitExceeded));
+            }
+        }
+
+        if let OutputSink::Channel(sender) = sink {
+            if !pending.is_empty() {
+                let content = String::from_utf8_lossy(&pending).into_owned();
+                let _ = sender.send(CommandOutputChunk { stream, content }).await;
+            }
         }
     }
-    Ok(output)
+    Ok(())
 }
 
 /// The implementation for CommandExecutorService
@@ -135,14 +411,43 @@ impl CommandExecutorService for ForgeCommandExecutorService {
         command: String,
         working_dir: PathBuf,
     ) -> anyhow::Result<CommandOutput> {
-        self.execute_command_internal(command, &working_dir).await
+        self.execute_command_internal(
+            command,
+            &working_dir,
+            OutputSink::Console,
+            None,
+            ResourceLimits::default(),
+            Vec::new(),
+        )
+        .await
+    }
+
+    async fn execute_command_streamed(
+        &self,
+        command: String,
+        working_dir: PathBuf,
+        sender: Sender<CommandOutputChunk>,
+        timeout: Option<Duration>,
+        limits: ResourceLimits,
+        env: Vec<(String, String)>,
+    ) -> anyhow::Result<CommandOutput> {
+        self.execute_command_internal(
+            command,
+            &working_dir,
+            OutputSink::Channel(sender),
+            timeout,
+            limits,
+            env,
+        )
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use forge_domain::Provider;
+    use forge_domain::{Provider, DEFAULT_REGISTRY_URL};
     use pretty_assertions::assert_eq;
+    use url::Url;
 
     use super::*;
 
@@ -156,6 +461,15 @@ mod tests {
             base_path: PathBuf::from("/base"),
             provider: Provider::open_router("test-key"),
             retry_config: Default::default(),
+            timeout_config: Default::default(),
+            registry_url: Url::parse(DEFAULT_REGISTRY_URL).unwrap(),
+            shell_timeout: 240,
+            tool_timeout: 300,
+            max_tool_result_chars: 50_000,
+            default_mode: None,
+            low_bandwidth: false,
+            notify_after: None,
+            template_env: vec![],
         }
     }
 
@@ -175,10 +489,208 @@ mod tests {
             stderr: "".to_string(),
             command: "echo \"hello world\"".into(),
             exit_code: Some(0),
+            timed_out: false,
+            violation: None,
         };
 
         assert_eq!(actual.stdout.trim(), expected.stdout.trim());
         assert_eq!(actual.stderr, expected.stderr);
         assert_eq!(actual.success(), expected.success());
     }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_command_executor_streamed_env() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let (tx, _rx) = tokio::sync::mpsc::channel(16);
+
+        let actual = fixture
+            .execute_command_streamed(
+                "echo $FORGE_TMP".to_string(),
+                PathBuf::from("."),
+                tx,
+                None,
+                ResourceLimits::default(),
+                vec![("FORGE_TMP".to_string(), "/tmp/forge/1".to_string())],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(actual.stdout.trim(), "/tmp/forge/1");
+    }
+
+    #[tokio::test]
+    async fn test_command_executor_streamed() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+
+        let actual = fixture
+            .execute_command_streamed(
+                "echo 'hello world'".to_string(),
+                PathBuf::from("."),
+                tx,
+                None,
+                ResourceLimits::default(),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        let streamed = chunks
+            .iter()
+            .filter(|chunk| chunk.stream == OutputStream::Stdout)
+            .map(|chunk| chunk.content.as_str())
+            .collect::<String>();
+
+        assert_eq!(streamed, actual.stdout);
+        assert_eq!(actual.stdout.trim(), "hello world");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_without_input() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let (tx, _rx) = tokio::sync::mpsc::channel(16);
+
+        let actual = fixture
+            .execute_command_streamed(
+                "read line; echo \"read: $line\"".to_string(),
+                PathBuf::from("."),
+                tx,
+                Some(Duration::from_secs(5)),
+                ResourceLimits::default(),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        assert!(!actual.timed_out);
+        assert_eq!(actual.stdout.trim(), "read:");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_timeout() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let (tx, _rx) = tokio::sync::mpsc::channel(16);
+
+        let actual = fixture
+            .execute_command_streamed(
+                "echo partial; sleep 5".to_string(),
+                PathBuf::from("."),
+                tx,
+                Some(Duration::from_millis(500)),
+                ResourceLimits::default(),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        assert!(actual.timed_out);
+        assert!(!actual.success());
+        assert_eq!(actual.stdout.trim(), "partial");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_output_limit() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let (tx, _rx) = tokio::sync::mpsc::channel(1024);
+
+        let actual = fixture
+            .execute_command_streamed(
+                "yes".to_string(),
+                PathBuf::from("."),
+                tx,
+                Some(Duration::from_secs(10)),
+                ResourceLimits::default().max_output_bytes(1024usize),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(actual.violation, Some(ResourceViolation::OutputSize));
+        assert!(!actual.timed_out);
+        assert!(!actual.success());
+        assert_eq!(actual.stdout.len(), 1024);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_cpu_limit() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let (tx, _rx) = tokio::sync::mpsc::channel(16);
+
+        let actual = fixture
+            .execute_command_streamed(
+                "while :; do :; done".to_string(),
+                PathBuf::from("."),
+                tx,
+                Some(Duration::from_secs(10)),
+                ResourceLimits::default().max_cpu_secs(1u64),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(actual.violation, Some(ResourceViolation::CpuTime));
+        assert!(!actual.success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_detect_violation_keeps_signal() {
+        use std::os::unix::process::ExitStatusExt;
+
+        // The shell reports the crash of its child as exit code 128 + SIGSEGV
+        let fixture = std::process::ExitStatus::from_raw((128 + libc::SIGSEGV) << 8);
+
+        let actual = detect_violation(&fixture, &ResourceLimits::default().max_memory_mb(64u64));
+
+        let expected = Some(ResourceViolation::PossiblyMemory { signal: libc::SIGSEGV });
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_detect_violation_without_memory_limit() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let fixture = std::process::ExitStatus::from_raw(libc::SIGSEGV);
+
+        let actual = detect_violation(&fixture, &ResourceLimits::default());
+
+        assert_eq!(actual, None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_command_executor_cancelled() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let (tx, _rx) = tokio::sync::mpsc::channel(16);
+
+        // The call is cancelled while a process started by the command runs
+        let execution = fixture.execute_command_streamed(
+            format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+            PathBuf::from("."),
+            tx,
+            None,
+            ResourceLimits::default(),
+            Vec::new(),
+        );
+        let actual = tokio::time::timeout(Duration::from_millis(500), execution).await;
+        assert!(actual.is_err());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // The process is gone, or a zombie waiting to be reaped
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        assert!(stat.is_err() || stat.unwrap().contains(") Z "));
+    }
 }
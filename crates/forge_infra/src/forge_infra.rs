@@ -27,8 +27,8 @@ pub struct ForgeInfra {
 }
 
 impl ForgeInfra {
-    pub fn new(restricted: bool) -> Self {
-        let environment_service = Arc::new(ForgeEnvironmentService::new(restricted));
+    pub fn new(restricted: bool, low_bandwidth: bool) -> Self {
+        let environment_service = Arc::new(ForgeEnvironmentService::new(restricted, low_bandwidth));
         let env = environment_service.get_environment();
         let file_snapshot_service = Arc::new(ForgeFileSnapshotService::new(env.clone()));
         Self {
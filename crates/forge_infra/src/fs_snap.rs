@@ -29,4 +29,10 @@ impl FsSnapshotService for ForgeFileSnapshotService {
     async fn undo_snapshot(&self, file_path: &Path) -> Result<()> {
         self.inner.undo_snapshot(file_path.to_path_buf()).await
     }
+
+    async fn read_snapshot(&self, file_path: &Path, index: usize) -> Result<Vec<u8>> {
+        self.inner
+            .read_snapshot(file_path.to_path_buf(), index)
+            .await
+    }
 }
@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use forge_display::input::PromptGuard;
 use forge_services::InquireService;
 use inquire::ui::{RenderConfig, Styled};
 use inquire::{InquireError, MultiSelect, Select, Text};
@@ -28,7 +29,13 @@ impl ForgeInquire {
         F: FnOnce() -> std::result::Result<T, InquireError> + Send + 'static,
         T: Send + 'static,
     {
-        let result = tokio::task::spawn_blocking(f).await?;
+        // Keeps the listeners of the terminal, e.g. for ESC, from reading the
+        // answer
+        let result = tokio::task::spawn_blocking(|| {
+            let _guard = PromptGuard::acquire();
+            f()
+        })
+        .await?;
 
         match result {
             Ok(value) => Ok(Some(value)),
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use forge_api::{ToolErrorCode, ToolName, ToolResult};
+use tokio::time::Instant;
+
+/// Exit code of a run that failed
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit code of a headless run in which a tool call failed and wasn't
+/// recovered from, e.g. the tests it ran
+pub const EXIT_TOOL_FAILURE: u8 = 2;
+/// Exit code of a headless run stopped by `--max-turns` or `--timeout`
+pub const EXIT_BUDGET_EXCEEDED: u8 = 3;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum BudgetExceeded {
+    #[error("The run was stopped after {0} requests to the model (--max-turns)")]
+    Turns(u64),
+    #[error("The run was stopped after {0}s (--timeout)")]
+    Timeout(u64),
+}
+
+/// Limits of a headless run, so that CI jobs don't run away
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    max_turns: Option<u64>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    turns: u64,
+}
+
+impl Budget {
+    /// Creates the budget of a run that starts now
+    pub fn new(max_turns: Option<u64>, timeout: Option<Duration>) -> Self {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        Self { max_turns, timeout, deadline, turns: 0 }
+    }
+
+    /// Records a request to the model, failing once the run made more of them
+    /// than allowed
+    pub fn record_turn(&mut self) -> Result<(), BudgetExceeded> {
+        self.turns += 1;
+        match self.max_turns {
+            Some(max_turns) if self.turns > max_turns => Err(BudgetExceeded::Turns(max_turns)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolves once the run exceeds its timeout, never without one
+    pub fn expired(&self) -> impl std::future::Future<Output = BudgetExceeded> + 'static {
+        let deadline = self.deadline;
+        let timeout = self.timeout.unwrap_or_default().as_secs();
+        async move {
+            match deadline {
+                Some(deadline) => {
+                    tokio::time::sleep_until(deadline).await;
+                    BudgetExceeded::Timeout(timeout)
+                }
+                None => std::future::pending().await,
+            }
+        }
+    }
+}
+
+/// Tool calls of a headless run that failed and weren't recovered from. Any of
+/// them fails the run with [`EXIT_TOOL_FAILURE`], even when the agent then
+/// completed the task. A failure is recovered from when a later call of the
+/// same tool succeeds, e.g. the tests pass after a fix. Calls to missing
+/// files, with invalid arguments or denied by a policy are mistakes of the
+/// agent rather than failures of the task, and don't count.
+#[derive(Debug, Clone, Default)]
+pub struct ToolFailures(Vec<ToolName>);
+
+impl ToolFailures {
+    pub fn record(&mut self, result: &ToolResult) {
+        if !result.is_error {
+            self.0.retain(|name| *name != result.name);
+            return;
+        }
+        let mistake = matches!(
+            result.error_code,
+            Some(
+                ToolErrorCode::NotFound
+                    | ToolErrorCode::InvalidArgs
+                    | ToolErrorCode::PermissionDenied
+            )
+        );
+        if !mistake && !self.0.contains(&result.name) {
+            self.0.push(result.name.clone());
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    /// Names of the tools whose calls failed, in the order they first failed
+    pub fn tools(&self) -> &[ToolName] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_record_turn() {
+        let mut budget = Budget::new(Some(2), None);
+        assert_eq!(budget.record_turn(), Ok(()));
+        assert_eq!(budget.record_turn(), Ok(()));
+        assert_eq!(budget.record_turn(), Err(BudgetExceeded::Turns(2)));
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let mut budget = Budget::default();
+        for _ in 0..100 {
+            assert_eq!(budget.record_turn(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_tool_failure_fails_completed_run() {
+        let mut failures = ToolFailures::default();
+        failures.record(&ToolResult::new(ToolName::new("forge_tool_fs_read")).success("ok"));
+        assert!(!failures.any());
+
+        failures.record(
+            &ToolResult::new(ToolName::new("forge_tool_process_shell"))
+                .failure(anyhow::anyhow!("tests failed")),
+        );
+        failures.record(
+            &ToolResult::new(ToolName::new("forge_tool_attempt_completion")).success("Done"),
+        );
+
+        assert!(failures.any());
+        assert_eq!(
+            failures.tools(),
+            &[ToolName::new("forge_tool_process_shell")]
+        );
+    }
+
+    #[test]
+    fn test_tool_failure_recovered() {
+        let shell = ToolName::new("forge_tool_process_shell");
+        let mut failures = ToolFailures::default();
+        failures.record(&ToolResult::new(shell.clone()).failure(anyhow::anyhow!("tests failed")));
+        failures.record(&ToolResult::new(shell).success("tests passed"));
+
+        assert!(!failures.any());
+    }
+
+    #[test]
+    fn test_tool_mistake_ignored() {
+        let mut failures = ToolFailures::default();
+        failures.record(
+            &ToolResult::new(ToolName::new("forge_tool_fs_read"))
+                .failure(ToolErrorCode::NotFound.error("No such file")),
+        );
+
+        assert!(!failures.any());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expired() {
+        let budget = Budget::new(None, Some(Duration::from_secs(60)));
+
+        let actual = budget.expired().await;
+        assert_eq!(actual, BudgetExceeded::Timeout(60));
+    }
+}
@@ -0,0 +1,187 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tokio::sync::Notify;
+
+/// Byte sent by the terminal when ESC is pressed on its own. Keys such as the
+/// arrows send it too, followed by the rest of their sequence in the same read.
+#[cfg(unix)]
+const ESC: u8 = 0x1b;
+
+/// Listens for ESC while the agent is working so that its turn can be
+/// cancelled. The terminal is switched to non-canonical mode without echo,
+/// which delivers the key without waiting for Enter but keeps the output and
+/// Ctrl-C working as usual, and restored once the listener is paused. The
+/// listener doesn't read while a prompt asks the user a question, and keeps
+/// the other keys typed in the meantime for the next message.
+#[derive(Default)]
+pub struct CancelKey {
+    pressed: Arc<Notify>,
+    typed: Arc<Mutex<Vec<u8>>>,
+    listener: Option<Listener>,
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+struct Listener {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl CancelKey {
+    /// Starts listening with a fresh state, so that a key pressed during a
+    /// previous turn doesn't cancel this one
+    pub fn listen() -> Self {
+        let mut key = Self::default();
+        key.resume();
+        key
+    }
+
+    /// Resolves once ESC is pressed
+    pub fn pressed(&self) -> impl Future<Output = ()> + 'static {
+        let pressed = self.pressed.clone();
+        async move { pressed.notified().await }
+    }
+
+    /// Gives the terminal back, e.g. while the user answers a question
+    pub fn pause(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.stop.store(true, Ordering::Relaxed);
+            let _ = listener.thread.join();
+            #[cfg(unix)]
+            // SAFETY: restores the attributes read from the same descriptor
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &listener.original);
+            }
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.listener.is_none() {
+            self.listener = Listener::spawn(self.pressed.clone(), self.typed.clone());
+        }
+    }
+
+    /// Takes the text typed ahead while the listener was reading
+    pub fn take_typed(&mut self) -> String {
+        let typed = std::mem::take(&mut *self.typed.lock().unwrap_or_else(|e| e.into_inner()));
+        type_ahead(&typed)
+    }
+}
+
+/// Turns the keys typed ahead into the text of the next message, applying
+/// the backspaces and dropping the other control keys
+fn type_ahead(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for c in String::from_utf8_lossy(bytes).chars() {
+        match c {
+            '\u{7f}' | '\u{8}' => {
+                text.pop();
+            }
+            '\n' => text.push(c),
+            c if !c.is_control() => text.push(c),
+            _ => {}
+        }
+    }
+    text.trim_end_matches('\n').to_string()
+}
+
+impl Drop for CancelKey {
+    fn drop(&mut self) {
+        self.pause();
+    }
+}
+
+impl Listener {
+    #[cfg(unix)]
+    fn spawn(pressed: Arc<Notify>, typed: Arc<Mutex<Vec<u8>>>) -> Option<Self> {
+        use std::io::IsTerminal;
+
+        use forge_display::input::read_in_background;
+
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+
+        let fd = libc::STDIN_FILENO;
+        // SAFETY: termios is a plain C struct that tcgetattr fills in
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+            return None;
+        }
+        let mut attributes = original;
+        attributes.c_lflag &= !(libc::ICANON | libc::ECHO);
+        attributes.c_cc[libc::VMIN] = 1;
+        attributes.c_cc[libc::VTIME] = 0;
+        // SAFETY: the attributes are a modified copy of the current ones
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &attributes) } != 0 {
+            return None;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let mut buf = [0u8; 16];
+                while !stop.load(Ordering::Relaxed) {
+                    // Waking up regularly lets the listener notice that it was paused
+                    let read = read_in_background(|| {
+                        let mut poll = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+                        // SAFETY: polls a single valid descriptor
+                        if unsafe { libc::poll(&mut poll, 1, 100) } <= 0 {
+                            return None;
+                        }
+                        // SAFETY: reads at most the length of the buffer
+                        Some(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) })
+                    });
+                    let read = match read {
+                        Some(Some(read)) => read,
+                        Some(None) => continue,
+                        // A prompt reads the input
+                        None => {
+                            std::thread::sleep(std::time::Duration::from_millis(100));
+                            continue;
+                        }
+                    };
+                    if read <= 0 {
+                        break;
+                    }
+                    let read = &buf[..read as usize];
+                    if read == [ESC] {
+                        pressed.notify_one();
+                    } else if read[0] != ESC {
+                        // Keys such as the arrows are dropped
+                        typed
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .extend_from_slice(read);
+                    }
+                }
+            })
+        };
+
+        Some(Self { stop, thread, original })
+    }
+
+    #[cfg(not(unix))]
+    fn spawn(_pressed: Arc<Notify>, _typed: Arc<Mutex<Vec<u8>>>) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_type_ahead() {
+        assert_eq!(type_ahead(b"fix the tesx\x7ft\n"), "fix the test");
+        assert_eq!(type_ahead(b"one\ntwo\x04"), "one\ntwo");
+        assert_eq!(type_ahead(b""), "");
+    }
+}
@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tempfile::NamedTempFile;
+use tokio::process::Command;
+
+/// Lines kept before the first error of a failing job
+const CONTEXT_LINES: usize = 30;
+
+/// Maximum number of log lines attached to the conversation
+const MAX_LINES: usize = 400;
+
+/// Markers of the lines where a failure is reported
+const FAILURE_MARKERS: [&str; 7] = [
+    "##[error]",
+    "error:",
+    "error[",
+    "failed",
+    "panicked at",
+    "traceback",
+    "exception",
+];
+
+/// CI services whose failing logs can be fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiProvider {
+    /// GitHub Actions, queried through the GitHub CLI (`gh`)
+    GitHubActions,
+}
+
+impl CiProvider {
+    /// Detects the CI provider used by the project in the directory
+    pub fn detect(cwd: &Path) -> Option<Self> {
+        cwd.join(".github/workflows")
+            .is_dir()
+            .then_some(CiProvider::GitHubActions)
+    }
+}
+
+/// A workflow run whose jobs failed
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedRun {
+    pub database_id: u64,
+    pub workflow_name: String,
+    pub display_title: String,
+    /// The event that triggered the run, e.g. `push` or `pull_request`
+    pub event: String,
+    pub head_branch: String,
+    pub head_sha: String,
+    pub url: String,
+}
+
+impl FailedRun {
+    /// Finds the latest failed run of the current branch, or of the
+    /// repository when the branch can't be determined
+    pub async fn latest(cwd: &Path) -> Result<Option<Self>> {
+        let mut args = vec![
+            "run",
+            "list",
+            "--status",
+            "failure",
+            "--limit",
+            "1",
+            "--json",
+            "databaseId,workflowName,displayTitle,event,headBranch,headSha,url",
+        ];
+        let branch = current_branch(cwd).await;
+        if let Some(branch) = branch.as_deref() {
+            args.extend(["--branch", branch]);
+        }
+
+        let output = gh(cwd, &args).await?;
+        let runs: Vec<FailedRun> =
+            serde_json::from_str(&output).context("Failed to parse the runs listed by gh")?;
+        Ok(runs.into_iter().next())
+    }
+
+    /// Finds a run by its id
+    pub async fn find(cwd: &Path, id: u64) -> Result<Self> {
+        let output = gh(
+            cwd,
+            &[
+                "run",
+                "view",
+                &id.to_string(),
+                "--json",
+                "databaseId,workflowName,displayTitle,event,headBranch,headSha,url",
+            ],
+        )
+        .await?;
+        serde_json::from_str(&output).context("Failed to parse the run returned by gh")
+    }
+
+    /// Downloads the log of the failed jobs of the run
+    pub async fn log(&self, cwd: &Path) -> Result<String> {
+        gh(
+            cwd,
+            &["run", "view", &self.database_id.to_string(), "--log-failed"],
+        )
+        .await
+    }
+
+    /// The prompt that starts a conversation about the failure, with the
+    /// trimmed log attached
+    pub fn prompt(&self, log: &NamedTempFile) -> String {
+        format!(
+            "The CI workflow \"{}\" failed for \"{}\".\n\nEvent: {}\nBranch: {}\nCommit: {}\nRun: {}\n\nThe log of the failed jobs is attached. Find the cause of the failure and fix it.\n\n@[{}]",
+            self.workflow_name,
+            self.display_title,
+            self.event,
+            self.head_branch,
+            self.head_sha,
+            self.url,
+            log.path().display()
+        )
+    }
+}
+
+async fn current_branch(cwd: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(cwd)
+        .output()
+        .await
+        .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !branch.is_empty()).then_some(branch)
+}
+
+/// Runs the GitHub CLI and returns its stdout
+async fn gh(cwd: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("gh")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .await
+        .context("Failed to run the GitHub CLI, make sure `gh` is installed and authenticated")?;
+    if !output.status.success() {
+        bail!(
+            "gh {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Trims the log of the failed jobs to the region around the failure. Lines
+/// are printed by gh as `job\tstep\ttimestamp message`; the job and step are
+/// kept once as a header and the timestamps are removed.
+pub fn trim_log(log: &str) -> String {
+    let mut steps: Vec<String> = Vec::new();
+    let lines = log
+        .lines()
+        .map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(job), Some(step), Some(message)) => {
+                    let step = format!("{job} / {step}");
+                    if !steps.contains(&step) {
+                        steps.push(step);
+                    }
+                    strip_timestamp(message)
+                }
+                _ => line,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let first_failure = lines.iter().position(|line| {
+        let line = line.to_lowercase();
+        FAILURE_MARKERS.iter().any(|marker| line.contains(marker))
+    });
+    let start = first_failure.map_or(0, |index| index.saturating_sub(CONTEXT_LINES));
+    let region = &lines[start..];
+
+    let mut trimmed = steps
+        .iter()
+        .map(|step| format!("Failed step: {step}\n"))
+        .collect::<String>();
+    if !trimmed.is_empty() {
+        trimmed.push('\n');
+    }
+    if start > 0 {
+        trimmed.push_str(&format!("... {start} lines omitted\n"));
+    }
+    if region.len() > MAX_LINES {
+        let half = MAX_LINES / 2;
+        trimmed.push_str(&region[..half].join("\n"));
+        trimmed.push_str(&format!(
+            "\n... {} lines omitted\n",
+            region.len() - MAX_LINES
+        ));
+        trimmed.push_str(&region[region.len() - half..].join("\n"));
+    } else {
+        trimmed.push_str(&region.join("\n"));
+    }
+
+    trimmed
+}
+
+/// Removes the ISO 8601 timestamp that starts each line of the log
+fn strip_timestamp(message: &str) -> &str {
+    match message.split_once(' ') {
+        Some((timestamp, rest))
+            if timestamp.ends_with('Z') && timestamp.contains('T') && timestamp.len() >= 20 =>
+        {
+            rest
+        }
+        _ => message,
+    }
+}
+
+/// Writes the trimmed log into a temporary file so that it can be attached to
+/// the prompt. The file is removed once the returned handle is dropped.
+pub fn into_attachment(log: &str) -> Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("forge-ci-")
+        .suffix(".log")
+        .tempfile()?;
+    std::io::Write::write_all(&mut file, log.as_bytes())?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_trim_log() {
+        let mut fixture = (1..=40)
+            .map(|i| format!("test\tRun tests\t2025-04-01T10:00:00.0000000Z compiling crate{i}"))
+            .collect::<Vec<_>>();
+        fixture.push(
+            "test\tRun tests\t2025-04-01T10:00:01.0000000Z error[E0308]: mismatched types"
+                .to_string(),
+        );
+        fixture.push(
+            "test\tRun tests\t2025-04-01T10:00:02.0000000Z ##[error]Process completed with exit code 101."
+                .to_string(),
+        );
+
+        let actual = trim_log(&fixture.join("\n"));
+
+        let mut expected = vec![
+            "Failed step: test / Run tests".to_string(),
+            "".to_string(),
+            "... 10 lines omitted".to_string(),
+        ];
+        expected.extend((11..=40).map(|i| format!("compiling crate{i}")));
+        expected.push("error[E0308]: mismatched types".to_string());
+        expected.push("##[error]Process completed with exit code 101.".to_string());
+        assert_eq!(actual, expected.join("\n"));
+    }
+
+    #[test]
+    fn test_trim_log_long_failure() {
+        let fixture = (0..1000)
+            .map(|i| format!("error: failure {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let actual = trim_log(&fixture);
+
+        let lines = actual.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), MAX_LINES + 1);
+        assert_eq!(lines[0], "error: failure 0");
+        assert_eq!(lines[MAX_LINES / 2], "... 600 lines omitted");
+        assert_eq!(lines[MAX_LINES], "error: failure 999");
+    }
+
+    #[test]
+    fn test_parse_runs() {
+        let fixture = r#"[{"databaseId":14217,"displayTitle":"Fix parser","event":"pull_request","headBranch":"fix-parser","headSha":"a1b2c3","url":"https://github.com/antinomyhq/forge/actions/runs/14217","workflowName":"CI"}]"#;
+
+        let actual: Vec<FailedRun> = serde_json::from_str(fixture).unwrap();
+        let expected = vec![FailedRun {
+            database_id: 14217,
+            workflow_name: "CI".to_string(),
+            display_title: "Fix parser".to_string(),
+            event: "pull_request".to_string(),
+            head_branch: "fix-parser".to_string(),
+            head_sha: "a1b2c3".to_string(),
+            url: "https://github.com/antinomyhq/forge/actions/runs/14217".to_string(),
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(CiProvider::detect(dir.path()), None);
+
+        std::fs::create_dir_all(dir.path().join(".github/workflows")).unwrap();
+        assert_eq!(
+            CiProvider::detect(dir.path()),
+            Some(CiProvider::GitHubActions)
+        );
+    }
+}
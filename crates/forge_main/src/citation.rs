@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use forge_api::Source;
+
+use crate::info::Info;
+
+/// Files and URLs that an answer is based on, rendered as footnotes under it
+#[derive(Debug, Default)]
+pub struct Citations {
+    sources: Vec<Source>,
+}
+
+impl Citations {
+    pub fn add(&mut self, source: Source) {
+        if !self.sources.contains(&source) {
+            self.sources.push(source);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Formats the sources as numbered footnotes, with paths relative to the
+    /// working directory and line numbers for partial reads
+    pub fn footnotes(&self, cwd: &Path) -> Info {
+        self.sources.iter().enumerate().fold(
+            Info::new().add_title("Sources"),
+            |info, (index, source)| {
+                info.add_key_value(format!("[{}]", index + 1), cite(source, cwd))
+            },
+        )
+    }
+}
+
+fn cite(source: &Source, cwd: &Path) -> String {
+    match source {
+        Source::File { path, char_range } => {
+            let display = path.strip_prefix(cwd).unwrap_or(path).display();
+            match char_range.and_then(|range| line_range(path, range)) {
+                Some((start, end)) if start == end => format!("{display}:{start}"),
+                Some((start, end)) => format!("{display}:{start}-{end}"),
+                None => display.to_string(),
+            }
+        }
+        Source::Url { url } => url.clone(),
+    }
+}
+
+/// Converts a range of characters in the file to the lines it spans
+fn line_range(path: &Path, (start, end): (u64, u64)) -> Option<(usize, usize)> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let last = content.chars().count().saturating_sub(1) as u64;
+    let line_at = |position: u64| {
+        content
+            .chars()
+            .take(position.min(last) as usize)
+            .filter(|c| *c == '\n')
+            .count()
+            + 1
+    };
+
+    Some((line_at(start), line_at(end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_footnotes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        std::fs::write(&path, "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+
+        let mut fixture = Citations::default();
+        fixture.add(Source::File { path: path.clone(), char_range: None });
+        fixture.add(Source::File { path: path.clone(), char_range: Some((12, u64::MAX)) });
+        fixture.add(Source::Url { url: "https://docs.rs/tokio".to_string() });
+        fixture.add(Source::File { path: path.clone(), char_range: None });
+
+        let actual = fixture
+            .sources
+            .iter()
+            .map(|source| cite(source, dir.path()))
+            .collect::<Vec<_>>();
+        let expected = vec!["lib.rs", "lib.rs:2-3", "https://docs.rs/tokio"];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cite_missing_file() {
+        let fixture = Source::File {
+            path: PathBuf::from("/missing/lib.rs"),
+            char_range: Some((0, 10)),
+        };
+
+        let actual = cite(&fixture, Path::new("/project"));
+        assert_eq!(actual, "/missing/lib.rs");
+    }
+}
@@ -1,28 +1,36 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use forge_api::Mode;
 
+// The options shared by the subcommands are global, so that they can be passed
+// before or after the subcommand, e.g. `forge agents list -w forge.yaml`
 #[derive(Parser)]
 #[command(version = env!("CARGO_PKG_VERSION"))]
 pub struct Cli {
+    /// Prompt to process without entering interactive mode, same as
+    /// --prompt.
+    #[arg(value_name = "PROMPT")]
+    pub message: Option<String>,
+
     /// Path to a file containing initial commands to execute.
     ///
     /// The application will execute the commands from this file first,
     /// then continue in interactive mode.
-    #[arg(long, short = 'c')]
+    #[arg(long, short = 'c', global = true)]
     pub command: Option<String>,
 
     /// Direct prompt to process without entering interactive mode.
     ///
     /// Allows running a single command directly from the command line.
-    #[arg(long, short = 'p')]
+    #[arg(long, short = 'p', global = true)]
     pub prompt: Option<String>,
 
     /// Enable verbose output mode.
     ///
     /// When enabled, shows additional debugging information and tool execution
     /// details.
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, global = true)]
     pub verbose: bool,
 
     /// Enable restricted shell mode for enhanced security.
@@ -38,20 +46,350 @@ pub struct Cli {
     /// - Setting/modifying environment variables
     /// - Executing commands with absolute paths
     /// - Modifying shell options
-    #[arg(long, default_value_t = false, short = 'r')]
+    #[arg(long, default_value_t = false, short = 'r', global = true)]
     pub restricted: bool,
 
+    /// Disable the tools that modify the workspace.
+    ///
+    /// Agents can read and search files but can't write, patch or remove
+    /// them, or execute shell commands, which makes it safe to explore what
+    /// an agent would do on a production checkout.
+    #[arg(long, default_value_t = false, global = true)]
+    pub read_only: bool,
+
+    /// Review the changes to a file before it's written.
+    ///
+    /// The diff is shown when an agent creates or patches a file, and the
+    /// change can be accepted, rejected or modified in an editor.
+    #[arg(long, default_value_t = false, global = true)]
+    pub review_edits: bool,
+
+    /// Don't read the input piped through stdin.
+    ///
+    /// Content piped into forge is attached to the prompt, or is the prompt
+    /// when none is given. Use this when forge is started with a stdin that
+    /// is never closed, e.g. by an IDE or a supervisor, so that it doesn't
+    /// wait for it.
+    #[arg(long, default_value_t = false, global = true)]
+    pub no_stdin: bool,
+
+    /// Trust the workspace for this session without asking.
+    ///
+    /// Until a workspace is trusted, the shell and network tools and the
+    /// commands and tools declared in its forge.yaml are disabled. Useful for
+    /// headless runs, where there is nobody to ask.
+    #[arg(long, default_value_t = false, global = true)]
+    pub trust: bool,
+
+    /// Take over the lock of another session in the workspace.
+    ///
+    /// Only one session can run in a workspace at a time, so that sessions
+    /// don't overwrite each other's changes. The lock of a session that ended
+    /// without releasing it is taken over on its own, use this to take over
+    /// the lock of a session that is still running. Also lets `forge init`
+    /// overwrite existing files.
+    #[arg(long, default_value_t = false, global = true)]
+    pub force: bool,
+
+    /// Save bandwidth on slow or metered connections.
+    ///
+    /// Responses are received in one piece instead of being streamed and are
+    /// compressed, the list of models and the updates aren't fetched unless
+    /// requested, and usage data is sent in batches. Same as setting
+    /// FORGE_LOW_BANDWIDTH=true.
+    #[arg(long, default_value_t = false, global = true)]
+    pub low_bandwidth: bool,
+
+    /// Mode to start the session in, e.g. plan, act or one defined by the
+    /// workflow.
+    ///
+    /// Overrides the `default_mode` of the workflow, the FORGE_DEFAULT_MODE
+    /// environment variable and the mode used last in the project.
+    #[arg(long, global = true)]
+    pub mode: Option<Mode>,
+
+    /// Format of the output of a prompt or an event given on the command line.
+    ///
+    /// `json` replaces the terminal UI with newline-delimited JSON events on
+    /// stdout: the text of the model, the tool calls and their results, the
+    /// usage and a final result with the exit code. Messages meant for the
+    /// user are written to stderr.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub output: OutputFormat,
+
+    /// Stop a prompt or an event given on the command line after this many
+    /// requests to the model, across all agents.
+    ///
+    /// Forge then exits with code 3, as it does when --timeout is exceeded.
+    #[arg(long, global = true)]
+    pub max_turns: Option<u64>,
+
+    /// Stop a prompt or an event given on the command line after this many
+    /// seconds.
+    #[arg(long, value_name = "SECS", global = true)]
+    pub timeout: Option<u64>,
+
     /// Path to a file containing the workflow to execute.
-    #[arg(long, short = 'w')]
+    #[arg(long, short = 'w', global = true)]
     pub workflow: Option<PathBuf>,
 
     /// Dispatch an event to the workflow.
     /// For example: --event '{"name": "fix_issue", "value": "449"}'
-    #[arg(long, short = 'e')]
+    #[arg(long, short = 'e', global = true)]
     pub event: Option<String>,
 
     /// Path to a file containing the conversation to execute.
     /// This file should be in JSON format.
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub conversation: Option<PathBuf>,
+
+    /// Pick a past conversation of the workspace to continue.
+    ///
+    /// Conversations are saved after every turn, the same list is available
+    /// with the /sessions command.
+    #[arg(long, default_value_t = false, global = true)]
+    pub resume: bool,
+
+    #[command(subcommand)]
+    pub subcommands: Option<TopLevelCommand>,
+}
+
+impl Cli {
+    /// The prompt to process without entering interactive mode, given with
+    /// --prompt, as the first argument or to the run subcommand
+    pub fn prompt(&self) -> Option<String> {
+        let message = match &self.subcommands {
+            Some(TopLevelCommand::Run { message }) => message,
+            _ => &self.message,
+        };
+        self.prompt.clone().or_else(|| message.clone())
+    }
+
+    /// Whether the input piped through stdin is read, which is when stdin
+    /// isn't a terminal unless --no-stdin is given
+    pub fn reads_stdin(&self, stdin_is_terminal: bool) -> bool {
+        !stdin_is_terminal && !self.no_stdin
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TopLevelCommand {
+    /// Process a prompt, or start an interactive session when none is given.
+    ///
+    /// This is what forge does without a subcommand.
+    Run {
+        /// Prompt to process without entering interactive mode.
+        #[arg(value_name = "PROMPT")]
+        message: Option<String>,
+    },
+
+    /// Discover and inspect agents.
+    #[command(subcommand)]
+    Agents(AgentsCommand),
+
+    /// Show the reports of headless workflow runs.
+    #[command(subcommand)]
+    Report(ReportCommand),
+
+    /// Inspect the current workflow.
+    #[command(subcommand)]
+    Workflow(WorkflowCommand),
+
+    /// Start a conversation to fix the latest failing CI run of the current
+    /// branch, with the log of the failed jobs attached.
+    ///
+    /// GitHub Actions is supported through the GitHub CLI (gh), which must be
+    /// installed and authenticated.
+    Ci {
+        /// Id of the run to fix instead of the latest failing one.
+        #[arg(long)]
+        run: Option<u64>,
+    },
+
+    /// Serve forge's file system and shell tools over the Model Context
+    /// Protocol (MCP) on stdin and stdout.
+    ///
+    /// Lets other agent frontends, like IDEs or desktop assistants, use forge's
+    /// tools. The tools follow the policies of the workflow and the
+    /// --read-only flag.
+    McpServe,
+
+    /// Create a starter forge.yaml and .forgeignore in the current directory.
+    ///
+    /// The settings recommended for the project are detected from its
+    /// manifests, e.g. Cargo.toml, package.json or pyproject.toml. Existing
+    /// files are kept unless --force is given.
+    Init,
+
+    /// Check that forge can work in this environment and print how to fix
+    /// what doesn't.
+    ///
+    /// Checks that the provider is reachable and accepts the key, that the
+    /// shell runs, that the base path is writable and that the tree-sitter
+    /// grammars are available.
+    Doctor,
+
+    /// Step through a conversation dumped with /dump turn by turn.
+    ///
+    /// Shows the context sent to the model, its response and the results of
+    /// the tool calls for each turn, and can send a turn again to another
+    /// model to compare their behavior.
+    Debug {
+        /// Path to the JSON dump of the conversation.
+        dump: PathBuf,
+
+        /// Agent whose turns are shown, the main agent by default.
+        #[arg(long)]
+        agent: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AgentsCommand {
+    /// List the agents configured in the current workflow.
+    List {
+        /// List the agent packages published in the community registry
+        /// instead. The registry location can be changed using the
+        /// FORGE_REGISTRY_URL environment variable.
+        #[arg(long, default_value_t = false)]
+        remote: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ReportCommand {
+    /// Print the report of the most recent headless run.
+    Last,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum WorkflowCommand {
+    /// Render the agent graph of the workflow: the events that start each
+    /// agent, the commands of the user and the handovers between agents.
+    ///
+    /// Nothing is sent to a model, which makes it a cheap way to check the
+    /// routing of a workflow.
+    Graph {
+        /// Format of the graph.
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+
+    /// Print the JSON schema of the workflow files, e.g. for the completions
+    /// and checks of an editor.
+    Schema,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT, render it with `dot -Tsvg`
+    Dot,
+    /// Mermaid flowchart, rendered by GitHub in Markdown files
+    Mermaid,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The terminal UI
+    Text,
+    /// Newline-delimited JSON events, for scripts and CI pipelines
+    Json,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_output_json() {
+        let actual = Cli::parse_from(["forge", "-p", "explain this repo", "--output", "json"]);
+        assert_eq!(actual.output, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_budget() {
+        let actual = Cli::parse_from([
+            "forge",
+            "-p",
+            "fix it",
+            "--max-turns",
+            "20",
+            "--timeout",
+            "600",
+        ]);
+        assert_eq!(actual.max_turns, Some(20));
+        assert_eq!(actual.timeout, Some(600));
+    }
+
+    #[test]
+    fn test_bare_prompt() {
+        let actual = Cli::parse_from(["forge", "explain this repo"]);
+        assert_eq!(actual.prompt(), Some("explain this repo".to_string()));
+        assert!(actual.subcommands.is_none());
+
+        let actual = Cli::parse_from(["forge", "-p", "explain this repo", "--verbose"]);
+        assert_eq!(actual.prompt(), Some("explain this repo".to_string()));
+        assert!(actual.verbose);
+    }
+
+    #[test]
+    fn test_reads_stdin() {
+        // git diff | forge -p "review this diff"
+        let actual = Cli::parse_from(["forge", "-p", "review this diff"]);
+        assert_eq!(actual.prompt(), Some("review this diff".to_string()));
+        assert!(actual.reads_stdin(false));
+        assert!(!actual.reads_stdin(true));
+
+        let actual = Cli::parse_from(["forge", "-p", "review this diff", "--no-stdin"]);
+        assert!(!actual.reads_stdin(false));
+    }
+
+    #[test]
+    fn test_run() {
+        let actual = Cli::parse_from(["forge", "run", "--mode", "plan", "explain this repo"]);
+        assert_eq!(actual.prompt(), Some("explain this repo".to_string()));
+        assert_eq!(actual.mode, Some(Mode::plan()));
+
+        let actual = Cli::parse_from(["forge", "run"]);
+        assert_eq!(actual.prompt(), None);
+    }
+
+    #[test]
+    fn test_global_flags_after_subcommand() {
+        let actual = Cli::parse_from(["forge", "agents", "list", "-w", "custom.yaml"]);
+        assert_eq!(actual.workflow, Some(PathBuf::from("custom.yaml")));
+        assert!(matches!(
+            actual.subcommands,
+            Some(TopLevelCommand::Agents(AgentsCommand::List {
+                remote: false
+            }))
+        ));
+        assert_eq!(actual.prompt(), None);
+    }
+
+    #[test]
+    fn test_init_force() {
+        let actual = Cli::parse_from(["forge", "init", "--force"]);
+        assert!(matches!(actual.subcommands, Some(TopLevelCommand::Init)));
+        assert!(actual.force);
+    }
+
+    #[test]
+    fn test_workflow_graph() {
+        let actual = Cli::parse_from(["forge", "workflow", "graph", "--format", "mermaid"]);
+        assert!(matches!(
+            actual.subcommands,
+            Some(TopLevelCommand::Workflow(WorkflowCommand::Graph {
+                format: GraphFormat::Mermaid
+            }))
+        ));
+    }
 }
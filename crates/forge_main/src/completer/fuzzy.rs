@@ -0,0 +1,127 @@
+/// Score of each character of the query found in the path
+const MATCH_SCORE: i64 = 16;
+/// Bonus for a character that directly follows the previous match, so that
+/// `main` ranks `main.rs` above `my_animation.rs`
+const CONSECUTIVE_BONUS: i64 = 24;
+/// Bonus for a character that starts a word, e.g. the `c` of `input_completer`
+const BOUNDARY_BONUS: i64 = 20;
+/// Bonus when the whole query is found in the file name rather than spread
+/// over the directories
+const FILE_NAME_BONUS: i64 = 40;
+
+/// Scores how well the query matches the path, the way fuzzy finders such as
+/// skim do: the characters of the query must appear in the path in the same
+/// order, ignoring case, and matches that are consecutive or start words score
+/// higher. Returns None when the query doesn't match.
+pub fn fuzzy_score(query: &str, path: &str) -> Option<i64> {
+    let query = query.chars().collect::<Vec<_>>();
+    let file_name_start = path
+        .trim_end_matches('/')
+        .rfind('/')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+
+    // A match in the file name is preferred to a better one across directories
+    let path_chars = path.chars().collect::<Vec<_>>();
+    let file_name_offset = path[..file_name_start].chars().count();
+    if let Some(score) = score_chars(&query, &path_chars[file_name_offset..]) {
+        return Some(score + FILE_NAME_BONUS);
+    }
+    score_chars(&query, &path_chars)
+}
+
+/// Matches the query greedily from left to right
+fn score_chars(query: &[char], text: &[char]) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut start = 0;
+
+    for q in query {
+        let index = (start..text.len()).find(|&i| eq_ignore_case(text[i], *q))?;
+        score += MATCH_SCORE;
+        if is_boundary(text, index) {
+            score += BOUNDARY_BONUS;
+        }
+        match previous {
+            Some(previous) if previous + 1 == index => score += CONSECUTIVE_BONUS,
+            // Skipped characters make the match weaker
+            Some(previous) => score -= (index - previous - 1) as i64,
+            None => {}
+        }
+        previous = Some(index);
+        start = index + 1;
+    }
+
+    Some(score)
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Whether the character starts a word of the path: it follows a separator or
+/// is an uppercase letter following a lowercase one
+fn is_boundary(text: &[char], index: usize) -> bool {
+    match index.checked_sub(1).map(|i| text[i]) {
+        None => true,
+        Some(previous) => {
+            matches!(previous, '/' | '_' | '-' | '.' | ' ')
+                || (previous.is_lowercase() && text[index].is_uppercase())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_no_match() {
+        assert_eq!(fuzzy_score("xyz", "src/main.rs"), None);
+        assert_eq!(fuzzy_score("sm", "ms"), None);
+    }
+
+    #[test]
+    fn test_ranking() {
+        fn rank<'a>(query: &str, paths: &[&'a str]) -> Vec<&'a str> {
+            let mut paths = paths
+                .iter()
+                .filter_map(|path| fuzzy_score(query, path).map(|score| (score, *path)))
+                .collect::<Vec<_>>();
+            paths.sort_by_key(|(score, path)| (std::cmp::Reverse(*score), *path));
+            paths.into_iter().map(|(_, path)| path).collect()
+        }
+
+        assert_eq!(
+            rank(
+                "main",
+                &["src/my_animation.rs", "src/main.rs", "docs/readme.md"]
+            ),
+            vec!["src/main.rs", "src/my_animation.rs"]
+        );
+        assert_eq!(
+            rank(
+                "inco",
+                &[
+                    "src/completer/input_completer.rs",
+                    "src/input.rs",
+                    "src/info.rs"
+                ]
+            ),
+            vec!["src/completer/input_completer.rs"]
+        );
+        assert_eq!(
+            rank("uirs", &["crates/forge_main/src/ui.rs", "README.md"]),
+            vec!["crates/forge_main/src/ui.rs"]
+        );
+    }
+
+    #[test]
+    fn test_file_name_preferred() {
+        let in_file_name = fuzzy_score("ui", "crates/forge_main/src/ui.rs").unwrap();
+        let in_directories = fuzzy_score("ui", "crates/ux/input.rs").unwrap();
+        assert!(in_file_name > in_directories);
+    }
+}
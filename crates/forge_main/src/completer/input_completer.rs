@@ -1,23 +1,42 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::Arc;
 
 use forge_walker::Walker;
 use reedline::{Completer, Suggestion};
 
+use crate::completer::fuzzy::fuzzy_score;
 use crate::completer::search_term::SearchTerm;
 use crate::completer::CommandCompleter;
 use crate::model::ForgeCommandManager;
 
+/// Number of files suggested for a mention
+const MAX_SUGGESTIONS: usize = 100;
+/// Number of recently changed files, according to git, ranked higher
+const RECENT_FILES: usize = 50;
+/// Penalty for each directory between the workspace and the file, so that
+/// files closer to it rank higher
+const DEPTH_PENALTY: i64 = 4;
+
 #[derive(Clone)]
 pub struct InputCompleter {
+    cwd: PathBuf,
     walker: Walker,
     command: CommandCompleter,
+    /// Rank of the files changed recently, loaded on the first mention
+    recent: Option<HashMap<String, usize>>,
 }
 
 impl InputCompleter {
     pub fn new(cwd: PathBuf, command_manager: Arc<ForgeCommandManager>) -> Self {
-        let walker = Walker::max_all().cwd(cwd).skip_binary(true);
-        Self { walker, command: CommandCompleter::new(command_manager) }
+        let walker = Walker::max_all().cwd(cwd.clone()).skip_binary(true);
+        Self {
+            cwd,
+            walker,
+            command: CommandCompleter::new(command_manager),
+            recent: None,
+        }
     }
 }
 
@@ -34,29 +53,21 @@ impl Completer for InputCompleter {
 
         if let Some(query) = SearchTerm::new(line, pos).process() {
             let files = self.walker.get_blocking().unwrap_or_default();
-            files
+            let paths = files
                 .into_iter()
                 .filter(|file| !file.is_dir())
-                .filter_map(|file| {
-                    if let Some(file_name) = file.file_name.as_ref() {
-                        let file_name_lower = file_name.to_lowercase();
-                        let query_lower = query.term.to_lowercase();
-                        if file_name_lower.contains(&query_lower) {
-                            let path_md_fmt = format!("[{}]", file.path);
-                            Some(Suggestion {
-                                description: None,
-                                value: path_md_fmt,
-                                style: None,
-                                extra: None,
-                                span: query.span,
-                                append_whitespace: true,
-                            })
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
+                .map(|file| file.path);
+            let recent = self.recent.get_or_insert_with(|| recent_files(&self.cwd));
+
+            rank(paths, query.term, recent)
+                .into_iter()
+                .map(|path| Suggestion {
+                    description: None,
+                    value: format!("[{path}]"),
+                    style: None,
+                    extra: None,
+                    span: query.span,
+                    append_whitespace: true,
                 })
                 .collect()
         } else {
@@ -64,3 +75,92 @@ impl Completer for InputCompleter {
         }
     }
 }
+
+/// Orders the paths that fuzzily match the query by how well they match, how
+/// recently they were changed and how close they are to the workspace
+fn rank(
+    paths: impl Iterator<Item = String>,
+    query: &str,
+    recent: &HashMap<String, usize>,
+) -> Vec<String> {
+    let mut ranked = paths
+        .filter_map(|path| {
+            let mut score = fuzzy_score(query, &path)?;
+            if let Some(rank) = recent.get(&path) {
+                score += RECENT_FILES.saturating_sub(*rank) as i64;
+            }
+            score -= path.matches('/').count() as i64 * DEPTH_PENALTY;
+            Some((score, path))
+        })
+        .collect::<Vec<_>>();
+    // Shorter paths win ties, e.g. `main.rs` over `maintain.rs`
+    ranked.sort_by(|(a, a_path), (b, b_path)| {
+        b.cmp(a)
+            .then_with(|| a_path.len().cmp(&b_path.len()))
+            .then_with(|| a_path.cmp(b_path))
+    });
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// Returns the files changed recently, relative to the workspace, with their
+/// rank: uncommitted changes first, then the files of the latest commits. It's
+/// empty outside of a git repository.
+fn recent_files(cwd: &Path) -> HashMap<String, usize> {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+            .unwrap_or_default()
+    };
+    let changed = git(&["diff", "--name-only", "--relative", "HEAD"]);
+    let committed = git(&[
+        "log",
+        "--name-only",
+        "--relative",
+        "--format=",
+        "-n",
+        &RECENT_FILES.to_string(),
+    ]);
+
+    let mut recent = HashMap::new();
+    for path in changed.lines().chain(committed.lines()) {
+        if recent.len() == RECENT_FILES {
+            break;
+        }
+        if !path.is_empty() && !recent.contains_key(path) {
+            recent.insert(path.to_string(), recent.len());
+        }
+    }
+    recent
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let paths = ["src/main.rs", "crates/app/src/main.rs", "src/domain.rs"]
+            .into_iter()
+            .map(String::from);
+        let recent = HashMap::from([("crates/app/src/main.rs".to_string(), 0)]);
+
+        let actual = rank(paths.clone(), "main", &HashMap::new());
+        let expected = vec!["src/main.rs", "crates/app/src/main.rs", "src/domain.rs"];
+        assert_eq!(actual, expected);
+
+        let actual = rank(paths, "main", &recent);
+        let expected = vec!["crates/app/src/main.rs", "src/main.rs", "src/domain.rs"];
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,4 +1,5 @@
 mod command;
+mod fuzzy;
 mod input_completer;
 mod search_term;
 
@@ -1,10 +1,23 @@
+use forge_api::{ContextMessage, Conversation, Usage};
+use forge_display::highlight::{self, Token};
 use forge_template::Element;
 use serde_json::to_string_pretty;
 
-use crate::context::ContextMessage;
-use crate::conversation::Conversation;
+/// Tokens and cost of the session that produced the conversation, which the
+/// conversation itself doesn't record
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageSummary {
+    pub usage: Usage,
+    /// Cost in USD, when the provider publishes the prices of the model
+    pub cost: Option<f64>,
+}
 
-pub fn render_conversation_html(conversation: &Conversation) -> String {
+/// Renders the conversation as a self-contained HTML page, with the tool calls
+/// collapsed, the code highlighted and the images embedded
+pub fn render_conversation_html(
+    conversation: &Conversation,
+    usage: Option<&UsageSummary>,
+) -> String {
     let html = Element::new("html")
         .attr("lang", "en")
         .append(
@@ -30,6 +43,8 @@ pub fn render_conversation_html(conversation: &Conversation) -> String {
                             Element::new("p").text(format!("Archived: {}", conversation.archived)),
                         ),
                 )
+                // Summary Section
+                .append(create_summary_section(conversation, usage))
                 // Variables Section
                 .append(create_variables_section(conversation))
                 // Agent States Section
@@ -43,6 +58,54 @@ pub fn render_conversation_html(conversation: &Conversation) -> String {
     html.render()
 }
 
+fn create_summary_section(conversation: &Conversation, usage: Option<&UsageSummary>) -> Element {
+    let results = conversation
+        .state
+        .values()
+        .filter_map(|state| state.context.as_ref())
+        .flat_map(|context| context.messages.iter())
+        .filter_map(|message| match message {
+            ContextMessage::ToolMessage(result) => Some(result),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let failed = results.iter().filter(|result| result.is_error).count();
+    let turns: u64 = conversation
+        .state
+        .values()
+        .map(|state| state.turn_count)
+        .sum();
+
+    let row = |key: &str, value: String| {
+        Element::new("tr")
+            .append(Element::new("th").text(key))
+            .append(Element::new("td").text(value))
+    };
+    let mut table = Element::new("table")
+        .append(row("Turns", turns.to_string()))
+        .append(row(
+            "Tool Calls",
+            format!("{} ({failed} failed)", results.len()),
+        ));
+    if let Some(summary) = usage {
+        table = table
+            .append(row(
+                "Prompt Tokens",
+                summary.usage.prompt_tokens.to_string(),
+            ))
+            .append(row(
+                "Completion Tokens",
+                summary.usage.completion_tokens.to_string(),
+            ))
+            .append(row("Total Tokens", summary.usage.total_tokens.to_string()))
+            .append(summary.cost.map(|cost| row("Cost", format!("${cost:.4}"))));
+    }
+
+    Element::new("div.section")
+        .append(Element::new("h2").text("Summary"))
+        .append(table)
+}
+
 fn create_variables_section(conversation: &Conversation) -> Element {
     let table = Element::new("table").append(
         Element::new("tr")
@@ -57,7 +120,7 @@ fn create_variables_section(conversation: &Conversation) -> Element {
             table.append(
                 Element::new("tr")
                     .append(Element::new("td").text(key))
-                    .append(Element::new("td").append(Element::new("pre").text(value.to_string()))),
+                    .append(Element::new("td").append(json_element(value))),
             )
         });
 
@@ -185,34 +248,37 @@ fn create_agent_states_section(conversation: &Conversation) -> Element {
                                 Element::new("summary")
                                     .text(format!("{} Message", content_message.role)),
                             )
-                            .append(Element::new("pre").text(&content_message.content));
+                            .append(content_element(&content_message.content))
+                            .append(
+                                content_message.metadata.as_ref().map(|metadata| {
+                                    Element::new("p")
+                                        .append(Element::new("strong").text("Response: "))
+                                        .text(metadata.to_string())
+                                }),
+                            );
 
                             // Add tool calls if any
                             if let Some(tool_calls) = &content_message.tool_calls {
                                 if !tool_calls.is_empty() {
                                     message_div.append(Element::new("div").append(
                                         tool_calls.iter().map(|tool_call| {
-                                            Element::new("div.tool-call")
+                                            Element::new("details.tool-call")
                                                 .append(
-                                                    Element::new("p").append(
-                                                        Element::new("strong")
-                                                            .text(tool_call.name.as_str()),
-                                                    ),
+                                                    Element::new("summary")
+                                                        .append(
+                                                            Element::new("strong")
+                                                                .text("Tool Call: "),
+                                                        )
+                                                        .append(Element::span(
+                                                            tool_call.name.as_str(),
+                                                        )),
                                                 )
                                                 .append(tool_call.call_id.as_ref().map(|call_id| {
                                                     Element::new("p")
                                                         .append(Element::new("strong").text("ID: "))
                                                         .text(call_id.as_str())
                                                 }))
-                                                .append(Element::new("p").append(
-                                                    Element::new("strong").text("Arguments: "),
-                                                ))
-                                                .append(
-                                                    Element::new("pre").text(
-                                                        to_string_pretty(&tool_call.arguments)
-                                                            .unwrap_or_default(),
-                                                    ),
-                                                )
+                                                .append(json_element(&tool_call.arguments))
                                         }),
                                     ))
                                 } else {
@@ -224,26 +290,40 @@ fn create_agent_states_section(conversation: &Conversation) -> Element {
                         }
                         ContextMessage::ToolMessage(tool_result) => {
                             // Tool Message
-                            Element::new("details.message-card.message-tool")
+                            let card = match tool_result.is_error {
+                                true => "details.message-card.message-tool.tool-error",
+                                false => "details.message-card.message-tool",
+                            };
+                            let status = if tool_result.is_error {
+                                " (failed)"
+                            } else {
+                                ""
+                            };
+                            Element::new(card)
                                 .append(
                                     Element::new("summary")
                                         .append(Element::new("strong").text("Tool Result: "))
-                                        .append(Element::span(tool_result.name.as_str())),
+                                        .append(Element::span(format!(
+                                            "{}{status}",
+                                            tool_result.name.as_str()
+                                        ))),
                                 )
-                                .append(Element::new("pre").text(&tool_result.content))
+                                .append(content_element(&tool_result.content))
                         }
                         ContextMessage::Image(url) => {
-                            // Image message
+                            // Image message, embedded when the attachment is inlined
                             Element::new("div.message-card.message-user")
                                 .append(Element::new("strong").text("Image Attachment"))
-                                .append(Element::new("p").text(format!("URL: {url}")))
+                                .append(image_element(url))
                         }
                     }),
                 );
 
                 // Create tools section
-                let tools_section = Element::new("div")
-                    .append(Element::new("strong").text("Tools"))
+                let tools_section = Element::new("details")
+                    .append(Element::new("summary").append(
+                        Element::new("strong").text(format!("Tools ({})", context.tools.len())),
+                    ))
                     .append(context.tools.iter().map(|tool| {
                         Element::new("div.tool-call")
                             .append(
@@ -260,10 +340,9 @@ fn create_agent_states_section(conversation: &Conversation) -> Element {
                                 Element::new("pre")
                                     .append(Element::new("strong").text("Input Schema: ")),
                             )
-                            .append(
-                                Element::new("pre")
-                                    .text(to_string_pretty(&tool.input_schema).unwrap_or_default()),
-                            )
+                            .append(json_element(
+                                &serde_json::to_value(&tool.input_schema).unwrap_or_default(),
+                            ))
                             .append(tool.output_schema.as_ref().map(|schema| {
                                 Element::new("pre").append(
                                     Element::new("strong")
@@ -332,19 +411,100 @@ fn create_agent_states_section(conversation: &Conversation) -> Element {
         })
 }
 
+/// Renders text with its fenced code blocks highlighted
+fn content_element(content: &str) -> Element {
+    let mut element = Element::new("div.content");
+    let mut rest = content;
+    while let Some((before, block)) = rest.split_once("```") {
+        let Some((info, after_info)) = block.split_once('\n') else {
+            break;
+        };
+        let Some((code, after)) = after_info.split_once("```") else {
+            break;
+        };
+        if !before.trim().is_empty() {
+            element = element.append(Element::new("pre").text(before.trim_end()));
+        }
+        // Only a plain language name is kept, the info string is the model's
+        let language = info.trim();
+        let language = if is_language(language) { language } else { "" };
+        element = element.append(
+            Element::new("pre.code")
+                .attr("data-language", language)
+                .append(highlight(code.trim_end_matches('\n'), language)),
+        );
+        rest = after.strip_prefix('\n').unwrap_or(after);
+    }
+    if !rest.trim().is_empty() {
+        element = element.append(Element::new("pre").text(rest.trim_end()));
+    }
+    element
+}
+
+/// Whether the info string of a code block is a language name, e.g. `rust`
+/// or `c++`
+fn is_language(info: &str) -> bool {
+    info.chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '+' | '#' | '-'))
+}
+
+fn json_element(value: &serde_json::Value) -> Element {
+    let json = to_string_pretty(value).unwrap_or_default();
+    Element::new("pre.code").append(highlight(&json, "json"))
+}
+
+/// Embeds the images inlined as data URLs and links the others
+fn image_element(url: &str) -> Element {
+    let safe = !url.contains(['"', '<', '>']);
+    if safe && url.starts_with("data:image/") {
+        Element::new("img")
+            .attr("src", url)
+            .attr("alt", "Attachment")
+    } else {
+        Element::new("p").text(format!("URL: {url}"))
+    }
+}
+
+/// Splits the code into spans of the tokens of its language, the code of the
+/// languages without a grammar isn't highlighted
+fn highlight(code: &str, language: &str) -> Vec<Element> {
+    let Some(tokens) = highlight::tokens(code, language) else {
+        return vec![Element::span(code)];
+    };
+    tokens
+        .into_iter()
+        .map(|(token, text)| match token {
+            Some(token) => Element::new(format!("span.{}", token_class(token))).text(text),
+            None => Element::span(text),
+        })
+        .collect()
+}
+
+fn token_class(token: Token) -> &'static str {
+    match token {
+        Token::Keyword => "tok-keyword",
+        Token::String => "tok-string",
+        Token::Comment => "tok-comment",
+        Token::Function => "tok-function",
+        Token::Type => "tok-type",
+        Token::Constant => "tok-constant",
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use forge_api::{ConversationId, Workflow};
+
     use super::*;
-    use crate::conversation::Conversation;
 
     #[test]
     fn test_render_empty_conversation() {
         // Create a new empty conversation
-        let id = crate::conversation::ConversationId::generate();
-        let workflow = crate::Workflow::new();
+        let id = ConversationId::generate();
+        let workflow = Workflow::new();
 
         let fixture = Conversation::new(id, workflow);
-        let actual = render_conversation_html(&fixture);
+        let actual = render_conversation_html(&fixture, None);
 
         // We're verifying that the function runs without errors
         // and returns a non-empty string for an empty conversation
@@ -357,4 +517,82 @@ mod tests {
         assert!(actual.contains("Events"));
         assert!(actual.contains("Agent States"));
     }
+
+    #[test]
+    fn test_highlight() {
+        let fixture = "let name = \"forge\"; // the name\nfn run<'a>() -> u32 { 42 }";
+
+        let actual = highlight(fixture, "rust")
+            .iter()
+            .map(|element| element.render())
+            .collect::<String>();
+
+        let expected = concat!(
+            "<span class=\"tok-keyword\">let</span><span> name = </span>",
+            "<span class=\"tok-string\">\"forge\"</span><span>; </span>",
+            "<span class=\"tok-comment\">// the name</span><span>\n</span>",
+            "<span class=\"tok-keyword\">fn</span><span> </span>",
+            "<span class=\"tok-function\">run</span><span>&lt;'a&gt;() -&gt; </span>",
+            "<span class=\"tok-type\">u32</span><span> { </span>",
+            "<span class=\"tok-constant\">42</span><span> }</span>"
+        );
+        assert_eq!(actual, expected);
+
+        let actual = highlight("IDENTIFICATION DIVISION.", "cobol")
+            .iter()
+            .map(|element| element.render())
+            .collect::<String>();
+        assert_eq!(actual, "<span>IDENTIFICATION DIVISION.</span>");
+    }
+
+    #[test]
+    fn test_content_element() {
+        let fixture = "Run it:\n```py\n# build\nbuild()\n```\nDone.";
+
+        let actual = content_element(fixture).render();
+
+        assert!(actual.starts_with("<div class=\"content\"><pre>Run it:</pre>"));
+        assert!(actual.contains(
+            "<pre class=\"code\" data-language=\"py\"><span class=\"tok-comment\"># build</span>"
+        ));
+        assert!(actual.ends_with("<pre>Done.</pre></div>"));
+    }
+
+    #[test]
+    fn test_content_element_info_string() {
+        let fixture = "```\"><script>alert(1)</script>\nx\n```";
+
+        let actual = content_element(fixture).render();
+
+        let expected =
+            "<div class=\"content\"><pre class=\"code\" data-language=\"\"><span>x</span></pre></div>";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_image_element() {
+        let actual = image_element("data:image/png;base64,iVBORw0KGgo=").render();
+        let expected = "<img src=\"data:image/png;base64,iVBORw0KGgo=\" alt=\"Attachment\"></img>";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_usage_summary() {
+        let id = ConversationId::generate();
+        let fixture = Conversation::new(id, Workflow::new());
+        let summary = UsageSummary {
+            usage: Usage {
+                prompt_tokens: 1200,
+                completion_tokens: 300,
+                total_tokens: 1500,
+                ..Default::default()
+            },
+            cost: Some(0.0123),
+        };
+
+        let actual = render_conversation_html(&fixture, Some(&summary));
+
+        assert!(actual.contains("<th>Total Tokens</th><td>1500</td>"));
+        assert!(actual.contains("<th>Cost</th><td>$0.0123</td>"));
+    }
 }
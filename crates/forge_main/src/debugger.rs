@@ -0,0 +1,197 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Context as _, Result};
+use forge_api::{AgentId, ContentMessage, Context, ContextMessage, Conversation, Role, ToolResult};
+
+/// A request sent to the model during a recorded conversation, identified by
+/// the positions of its messages in the context of the agent
+#[derive(Debug, Clone, PartialEq)]
+struct Turn {
+    /// First message added to the context since the previous turn
+    start: usize,
+    /// The response of the model
+    response: usize,
+    /// Number of tool results that follow the response
+    results: usize,
+}
+
+/// The turns of an agent in a conversation dumped with /dump, used to step
+/// through the requests sent to the model and the responses it gave
+pub struct Recording {
+    context: Context,
+    turns: Vec<Turn>,
+}
+
+impl Recording {
+    pub fn new(context: Context) -> Self {
+        let messages = &context.messages;
+        let mut turns = Vec::new();
+        let mut start = 0;
+        for (index, message) in messages.iter().enumerate() {
+            if !matches!(message, ContextMessage::ContentMessage(message) if message.role == Role::Assistant)
+            {
+                continue;
+            }
+            let results = messages[index + 1..]
+                .iter()
+                .take_while(|message| matches!(message, ContextMessage::ToolMessage(_)))
+                .count();
+            turns.push(Turn { start, response: index, results });
+            start = index + 1 + results;
+        }
+        Self { context, turns }
+    }
+
+    /// Reads the turns of the agent from a conversation dump, by default the
+    /// ones of the main agent
+    pub fn from_dump(content: &str, agent: Option<&str>) -> Result<Self> {
+        let conversation: Conversation =
+            serde_json::from_str(content).context("Failed to parse the conversation dump")?;
+        let agent = AgentId::new(agent.unwrap_or(Conversation::MAIN_AGENT_NAME));
+        match conversation.context(&agent) {
+            Some(context) => Ok(Self::new(context.clone())),
+            None => bail!("The conversation has no messages for the agent {agent}"),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// Returns the context that was sent to the model for the turn
+    pub fn request(&self, index: usize) -> Context {
+        let mut context = self.context.clone();
+        context.messages.truncate(self.turns[index].response);
+        context
+    }
+
+    /// Returns the tool results of the turn
+    pub fn results(&self, index: usize) -> Vec<ToolResult> {
+        let turn = &self.turns[index];
+        self.context.messages[turn.response + 1..=turn.response + turn.results]
+            .iter()
+            .filter_map(|message| match message {
+                ContextMessage::ToolMessage(result) => Some(result.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Describes the turn: the messages added to the context since the
+    /// previous one, the response of the model and the results of its tool
+    /// calls
+    pub fn render(&self, index: usize) -> String {
+        let turn = &self.turns[index];
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Request: {} messages, {} tools",
+            turn.response,
+            self.context.tools.len()
+        );
+        for message in &self.context.messages[turn.start..turn.response] {
+            let _ = writeln!(out, "{}", render_message(message));
+        }
+
+        if let ContextMessage::ContentMessage(response) = &self.context.messages[turn.response] {
+            out.push_str(&render_response(response, &self.results(index)));
+        }
+        out
+    }
+}
+
+fn render_message(message: &ContextMessage) -> String {
+    match message {
+        ContextMessage::ContentMessage(message) => {
+            format!("[{}] {}", message.role, message.content.trim())
+        }
+        ContextMessage::ToolMessage(result) => render_result(result),
+        ContextMessage::Image(url) => format!("[Image] {url}"),
+    }
+}
+
+fn render_result(result: &ToolResult) -> String {
+    let status = if result.is_error { " (error)" } else { "" };
+    format!(
+        "[Result {}{status}] {}",
+        result.name.as_str(),
+        result.content.trim()
+    )
+}
+
+/// Describes a response of the model along with the results of its tool
+/// calls
+pub fn render_response(response: &ContentMessage, results: &[ToolResult]) -> String {
+    let mut out = String::new();
+    let model = response
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.model.as_deref())
+        .unwrap_or("unknown model");
+    let _ = writeln!(out, "Response ({model}):");
+    let _ = writeln!(out, "{}", response.content.trim());
+    for call in response.tool_calls.iter().flatten() {
+        let _ = writeln!(out, "[Call {}] {}", call.name.as_str(), call.arguments);
+    }
+    for result in results {
+        let _ = writeln!(out, "{}", render_result(result));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{ToolCallFull, ToolName};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture() -> Context {
+        let call = ToolCallFull::new(ToolName::new("forge_tool_fs_read"))
+            .arguments(json!({"path": "/project/README.md"}));
+        Context::default()
+            .add_message(ContextMessage::system("You are a software engineer"))
+            .add_message(ContextMessage::user("Summarize the readme"))
+            .add_message(ContextMessage::assistant("Reading it", Some(vec![call])))
+            .add_message(ContextMessage::tool_result(
+                ToolResult::new(ToolName::new("forge_tool_fs_read")).success("# Forge"),
+            ))
+            .add_message(ContextMessage::assistant("It describes Forge", None))
+    }
+
+    #[test]
+    fn test_turns() {
+        let actual = Recording::new(fixture()).turns;
+        let expected = vec![
+            Turn { start: 0, response: 2, results: 1 },
+            Turn { start: 4, response: 4, results: 0 },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_request() {
+        let recording = Recording::new(fixture());
+        assert_eq!(recording.request(0).messages.len(), 2);
+        assert_eq!(recording.request(1).messages.len(), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        let actual = Recording::new(fixture()).render(0);
+        let expected = r#"Request: 2 messages, 0 tools
+[System] You are a software engineer
+[User] Summarize the readme
+Response (unknown model):
+Reading it
+[Call forge_tool_fs_read] {"path":"/project/README.md"}
+[Result forge_tool_fs_read] # Forge
+"#;
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+use std::time::Duration;
+
+use colored::Colorize;
+use forge_api::{Environment, ToolName, API};
+use tempfile::NamedTempFile;
+use tokio::net::TcpStream;
+use url::Url;
+
+/// Time allowed to connect to the provider
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tool that is only available when forge is built with the tree-sitter
+/// grammars
+const SYNTAX_TOOL: &str = "forge_tool_fs_outline";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Forge works, with some features unavailable
+    Warning,
+    Error,
+}
+
+/// The outcome of a check, with what to do about it when it didn't pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl ToString) -> Self {
+        Self {
+            name,
+            status: Status::Ok,
+            detail: detail.to_string(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &'static str, detail: impl ToString, fix: impl ToString) -> Self {
+        Self {
+            name,
+            status: Status::Warning,
+            detail: detail.to_string(),
+            fix: Some(fix.to_string()),
+        }
+    }
+
+    fn error(name: &'static str, detail: impl ToString, fix: impl ToString) -> Self {
+        Self {
+            name,
+            status: Status::Error,
+            detail: detail.to_string(),
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+/// The checks of the environment run by `forge doctor`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis(Vec<Check>);
+
+impl Diagnosis {
+    /// Checks that the provider, the shell, the storage and the code parsers
+    /// work in the environment
+    pub async fn run<F: API>(api: &F) -> Self {
+        let env = api.environment();
+        Self(vec![
+            check_base_url(&env.provider.to_base_url()).await,
+            check_provider_key(api).await,
+            check_shell(api, &env).await,
+            check_base_path(&env),
+            check_grammars(api).await,
+        ])
+    }
+
+    /// Number of checks that failed
+    pub fn errors(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|check| check.status == Status::Error)
+            .count()
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.0 {
+            let symbol = match check.status {
+                Status::Ok => "✓".green(),
+                Status::Warning => "!".yellow(),
+                Status::Error => "✗".red(),
+            };
+            writeln!(
+                f,
+                "{symbol} {}: {}",
+                check.name.bright_cyan().bold(),
+                check.detail
+            )?;
+            if let Some(fix) = &check.fix {
+                writeln!(f, "  {} {}", "→".dimmed(), fix.dimmed())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Host and port of the URL, with the default port of its scheme
+fn address(url: &Url) -> Option<(String, u16)> {
+    Some((url.host_str()?.to_string(), url.port_or_known_default()?))
+}
+
+async fn check_base_url(url: &Url) -> Check {
+    const NAME: &str = "Base URL";
+    let fix = "Check your network connection, proxy, or the OPENAI_URL and ANTHROPIC_URL overrides";
+    let Some(address) = address(url) else {
+        return Check::error(NAME, format!("{url} has no host"), fix);
+    };
+    match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&address)).await {
+        Ok(Ok(_)) => Check::ok(NAME, format!("{url} is reachable")),
+        Ok(Err(error)) => Check::error(NAME, format!("{url} is unreachable: {error}"), fix),
+        Err(_) => Check::error(
+            NAME,
+            format!("{url} didn't respond within {}s", CONNECT_TIMEOUT.as_secs()),
+            fix,
+        ),
+    }
+}
+
+async fn check_provider_key<F: API>(api: &F) -> Check {
+    const NAME: &str = "Provider key";
+    match api.models().await {
+        Ok(models) => Check::ok(NAME, format!("valid, {} models available", models.len())),
+        Err(error) => Check::error(
+            NAME,
+            format!("the models couldn't be listed: {error:#}"),
+            "Check the key set in FORGE_KEY, OPENROUTER_API_KEY, OPENAI_API_KEY or ANTHROPIC_API_KEY",
+        ),
+    }
+}
+
+async fn check_shell<F: API>(api: &F, env: &Environment) -> Check {
+    const NAME: &str = "Shell";
+    let fix = "Set SHELL to the path of an installed shell";
+    match api.execute_shell_command("echo ok", env.cwd.clone()).await {
+        Ok(output) if output.exit_code == Some(0) => Check::ok(NAME, &env.shell),
+        Ok(output) => Check::error(
+            NAME,
+            format!("{} failed: {}", env.shell, output.stderr.trim()),
+            fix,
+        ),
+        Err(error) => Check::error(NAME, format!("{} can't be run: {error:#}", env.shell), fix),
+    }
+}
+
+fn check_base_path(env: &Environment) -> Check {
+    const NAME: &str = "Base path";
+    let path = &env.base_path;
+    let writable =
+        std::fs::create_dir_all(path).and_then(|_| NamedTempFile::new_in(path).map(|_| ()));
+    match writable {
+        Ok(()) => Check::ok(NAME, format!("{} is writable", path.display())),
+        Err(error) => Check::error(
+            NAME,
+            format!("{} isn't writable: {error}", path.display()),
+            format!(
+                "Make it writable, e.g. `chmod -R u+w {}`, so that conversations, history and snapshots can be saved",
+                path.display()
+            ),
+        ),
+    }
+}
+
+async fn check_grammars<F: API>(api: &F) -> Check {
+    const NAME: &str = "Tree-sitter grammars";
+    let syntax_tool = ToolName::new(SYNTAX_TOOL);
+    if api
+        .tools()
+        .await
+        .iter()
+        .any(|tool| tool.name == syntax_tool)
+    {
+        Check::ok(NAME, "available")
+    } else {
+        Check::warning(
+            NAME,
+            "unavailable, code outline, search and rename are disabled",
+            "Install a build of forge with the default features",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_address() {
+        let url = Url::parse("https://openrouter.ai/api/v1/").unwrap();
+        assert_eq!(address(&url), Some(("openrouter.ai".to_string(), 443)));
+
+        let url = Url::parse("http://localhost:11434/v1/").unwrap();
+        assert_eq!(address(&url), Some(("localhost".to_string(), 11434)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let diagnosis = Diagnosis(vec![
+            Check::ok("Shell", "/bin/zsh"),
+            Check::warning("Tree-sitter grammars", "unavailable", "Reinstall"),
+            Check::error("Provider key", "invalid", "Check the key"),
+        ]);
+        assert_eq!(diagnosis.errors(), 1);
+    }
+}
@@ -1,10 +1,13 @@
+use std::process::Command;
 use std::sync::Arc;
 
-use forge_api::Environment;
+use forge_api::{EditMode, Environment};
 use nu_ansi_term::{Color, Style};
 use reedline::{
-    default_emacs_keybindings, ColumnarMenu, DefaultHinter, EditCommand, Emacs, FileBackedHistory,
-    KeyCode, KeyModifiers, MenuBuilder, Prompt, Reedline, ReedlineEvent, ReedlineMenu, Signal,
+    default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
+    ColumnarMenu, DefaultHinter, EditCommand, Emacs, FileBackedHistory, KeyCode, KeyModifiers,
+    Keybindings, MenuBuilder, Prompt, Reedline, ReedlineEvent, ReedlineMenu, Signal,
+    ValidationResult, Validator, Vi,
 };
 
 use super::completer::InputCompleter;
@@ -13,6 +16,7 @@ use crate::model::ForgeCommandManager;
 // TODO: Store the last `HISTORY_CAPACITY` commands in the history file
 const HISTORY_CAPACITY: usize = 1024;
 const COMPLETION_MENU: &str = "completion_menu";
+const CODE_FENCE: &str = "```";
 
 pub struct ForgeEditor {
     editor: Reedline,
@@ -26,8 +30,9 @@ pub enum ReadResult {
 }
 
 impl ForgeEditor {
-    fn init() -> reedline::Keybindings {
-        let mut keybindings = default_emacs_keybindings();
+    /// Adds the bindings of forge to the default ones of the mode, in vim they
+    /// apply to the insert mode
+    fn init(mut keybindings: Keybindings) -> Keybindings {
         // on TAB press shows the completion menu, and if we've exact match it will
         // insert it
         keybindings.add_binding(
@@ -53,6 +58,13 @@ impl ForgeEditor {
             ReedlineEvent::SearchHistory,
         );
 
+        // on CTRL + g press opens the prompt in the external editor
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('g'),
+            ReedlineEvent::OpenEditor,
+        );
+
         // on ALT + Enter press inserts a newline
         keybindings.add_binding(
             KeyModifiers::ALT,
@@ -63,7 +75,7 @@ impl ForgeEditor {
         keybindings
     }
 
-    pub fn new(env: Environment, manager: Arc<ForgeCommandManager>) -> Self {
+    pub fn new(env: Environment, manager: Arc<ForgeCommandManager>, mode: EditMode) -> Self {
         // Store file history in system config directory
         let history_file = env.history_path();
 
@@ -78,10 +90,22 @@ impl ForgeEditor {
                 .with_selected_text_style(Style::new().on(Color::White).fg(Color::Black)),
         );
 
-        let edit_mode = Box::new(Emacs::new(Self::init()));
+        let edit_mode: Box<dyn reedline::EditMode> = match mode {
+            EditMode::Emacs => Box::new(Emacs::new(Self::init(default_emacs_keybindings()))),
+            EditMode::Vim => Box::new(Vi::new(
+                Self::init(default_vi_insert_keybindings()),
+                default_vi_normal_keybindings(),
+            )),
+        };
+
+        // The extension lets the editor highlight the prompt as markdown
+        let buffer_file =
+            std::env::temp_dir().join(format!("forge-prompt-{}.md", std::process::id()));
 
         let editor = Reedline::create()
             .with_completer(Box::new(InputCompleter::new(env.cwd, manager)))
+            .with_validator(Box::new(InputValidator))
+            .with_buffer_editor(editor_command(&external_editor()), buffer_file)
             .with_history(history)
             .with_hinter(Box::new(
                 DefaultHinter::default().with_style(Style::new().fg(Color::DarkGray)),
@@ -95,6 +119,14 @@ impl ForgeEditor {
         Self { editor }
     }
 
+    /// Inserts text in the buffer of the next prompt
+    pub fn insert(&mut self, text: String) {
+        if !text.is_empty() {
+            self.editor
+                .run_edit_commands(&[EditCommand::InsertString(text)]);
+        }
+    }
+
     pub fn prompt(&mut self, prompt: &dyn Prompt) -> anyhow::Result<ReadResult> {
         let signal = self.editor.read_line(prompt);
         signal.map(Into::into).map_err(|e| anyhow::anyhow!(e))
@@ -105,6 +137,7 @@ impl From<Signal> for ReadResult {
     fn from(signal: Signal) -> Self {
         match signal {
             Signal::Success(buffer) => {
+                let buffer = buffer.replace("\\\n", "\n");
                 let trimmed = buffer.trim();
                 if trimmed.is_empty() {
                     ReadResult::Empty
@@ -117,3 +150,68 @@ impl From<Signal> for ReadResult {
         }
     }
 }
+
+/// Keeps the prompt open while the last line ends with `\` or a code block
+/// isn't closed, so that multi-line text can be typed, or pasted in terminals
+/// without bracketed paste, without being submitted line by line
+struct InputValidator;
+
+impl Validator for InputValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        let fences = line
+            .lines()
+            .filter(|line| line.trim_start().starts_with(CODE_FENCE))
+            .count();
+        if line.ends_with('\\') || fences % 2 == 1 {
+            ValidationResult::Incomplete
+        } else {
+            ValidationResult::Complete
+        }
+    }
+}
+
+/// Returns the editor of the user from `$VISUAL` or `$EDITOR`, `vi` otherwise
+pub fn external_editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string())
+}
+
+/// Creates the command that starts the editor, whose name can be followed by
+/// arguments, e.g. `code --wait`
+pub fn editor_command(editor: &str) -> Command {
+    let mut args = editor.split_whitespace();
+    let mut command = Command::new(args.next().unwrap_or("vi"));
+    command.args(args);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_validator() {
+        let complete =
+            |line: &str| matches!(InputValidator.validate(line), ValidationResult::Complete);
+        assert!(complete("Fix the login form"));
+        assert!(!complete("Fix the login form \\"));
+        assert!(!complete("Explain\n```rust\nfn main() {}"));
+        assert!(complete("Explain\n```rust\nfn main() {}\n```"));
+    }
+
+    #[test]
+    fn test_line_continuations_are_removed() {
+        let actual = ReadResult::from(Signal::Success("first \\\nsecond".to_string()));
+        assert!(matches!(actual, ReadResult::Success(text) if text == "first \nsecond"));
+    }
+
+    #[test]
+    fn test_editor_command() {
+        let actual = editor_command("code --wait");
+        assert_eq!(actual.get_program(), "code");
+        assert_eq!(actual.get_args().collect::<Vec<_>>(), vec!["--wait"]);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use forge_api::{Event, NamedTool, Workflow};
+
+use crate::cli::GraphFormat;
+use crate::ui::{EVENT_USER_TASK_INIT, EVENT_USER_TASK_UPDATE};
+
+struct AgentNode {
+    id: String,
+    model: Option<String>,
+    disabled: bool,
+}
+
+struct EventNode {
+    name: String,
+    /// Whether the event is sent by the user, as a task or a custom command,
+    /// rather than by an agent
+    entry: bool,
+}
+
+/// The routing of a workflow: the events that start each agent and the
+/// agents that can hand work over to others by dispatching events
+pub struct WorkflowGraph {
+    agents: Vec<AgentNode>,
+    events: Vec<EventNode>,
+    /// Indices of an event and of an agent subscribed to it
+    subscriptions: Vec<(usize, usize)>,
+    /// Indices of an agent and of an event it can dispatch, with the label of
+    /// the edge
+    handovers: Vec<(usize, usize, String)>,
+}
+
+impl WorkflowGraph {
+    pub fn new(workflow: &Workflow) -> Self {
+        let commands = workflow
+            .commands
+            .iter()
+            .map(|command| command.name.as_str())
+            .collect::<BTreeSet<_>>();
+        let is_entry = |name: &str| {
+            commands.contains(name)
+                || name.rsplit_once('/').is_some_and(|(_, event)| {
+                    event == EVENT_USER_TASK_INIT || event == EVENT_USER_TASK_UPDATE
+                })
+        };
+
+        // Commands nobody subscribes to are kept, they are routing mistakes
+        let mut names = commands
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        for agent in &workflow.agents {
+            let handovers = agent.handovers.iter().flatten();
+            for name in agent
+                .subscribe
+                .iter()
+                .flatten()
+                .chain(handovers.map(|handover| &handover.event))
+            {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        let events = names
+            .into_iter()
+            .map(|name| EventNode { entry: is_entry(&name), name })
+            .collect::<Vec<_>>();
+
+        let agents = workflow
+            .agents
+            .iter()
+            .map(|agent| AgentNode {
+                id: agent.id.as_str().to_string(),
+                model: agent.model.as_ref().map(|model| model.as_str().to_string()),
+                disabled: agent.disable.unwrap_or_default(),
+            })
+            .collect();
+
+        // Only the agents with the most specific subscription receive an event
+        let priorities = events
+            .iter()
+            .map(|event| {
+                workflow
+                    .agents
+                    .iter()
+                    .filter_map(|agent| agent.subscription_priority(&event.name))
+                    .max()
+            })
+            .collect::<Vec<_>>();
+
+        let mut subscriptions = Vec::new();
+        let mut handovers = Vec::new();
+        for (index, agent) in workflow.agents.iter().enumerate() {
+            let dispatches = agent
+                .tools
+                .iter()
+                .flatten()
+                .any(|tool| *tool == Event::tool_name());
+            for (event_index, event) in events.iter().enumerate() {
+                let subscribed = agent
+                    .subscription_priority(&event.name)
+                    .is_some_and(|priority| Some(priority) == priorities[event_index]);
+                if subscribed {
+                    subscriptions.push((event_index, index));
+                }
+                let conditions = agent
+                    .handovers
+                    .iter()
+                    .flatten()
+                    .filter(|handover| handover.event == event.name)
+                    .map(|handover| match &handover.when {
+                        Some(condition) => format!("when {condition}"),
+                        None => "handover".to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                if !conditions.is_empty() {
+                    handovers.push((index, event_index, conditions.join(" or ")));
+                } else if dispatches && !event.entry && !subscribed {
+                    // The dispatch tool can send any event, the ones the user doesn't send
+                    // are the ones meant for agents
+                    handovers.push((index, event_index, "dispatch".to_string()));
+                }
+            }
+        }
+
+        Self { agents, events, subscriptions, handovers }
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    fn agent_label(agent: &AgentNode, separator: &str) -> String {
+        let mut label = agent.id.clone();
+        if let Some(model) = &agent.model {
+            label.push_str(separator);
+            label.push_str(model);
+        }
+        if agent.disabled {
+            label.push_str(separator);
+            label.push_str("(disabled)");
+        }
+        label
+    }
+
+    fn to_dot(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph workflow {{");
+        let _ = writeln!(out, "  rankdir=LR;");
+        for (index, event) in self.events.iter().enumerate() {
+            let style = if event.entry { ", peripheries=2" } else { "" };
+            let _ = writeln!(
+                out,
+                "  e{index} [label={}, shape=ellipse{style}];",
+                quote(&event.name)
+            );
+        }
+        for (index, agent) in self.agents.iter().enumerate() {
+            let style = if agent.disabled { ", style=dashed" } else { "" };
+            let label = quote(&Self::agent_label(agent, "\n")).replace('\n', "\\n");
+            let _ = writeln!(out, "  a{index} [label={label}, shape=box{style}];");
+        }
+        for (event, agent) in &self.subscriptions {
+            let _ = writeln!(out, "  e{event} -> a{agent};");
+        }
+        for (agent, event, label) in &self.handovers {
+            let _ = writeln!(
+                out,
+                "  a{agent} -> e{event} [style=dashed, label={}];",
+                quote(label)
+            );
+        }
+        let _ = writeln!(out, "}}");
+        out
+    }
+
+    fn to_mermaid(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('"', "#quot;"));
+        let mut out = String::new();
+        let _ = writeln!(out, "flowchart LR");
+        for (index, event) in self.events.iter().enumerate() {
+            let class = if event.entry { ":::entry" } else { "" };
+            let _ = writeln!(out, "  e{index}([{}]){class}", quote(&event.name));
+        }
+        for (index, agent) in self.agents.iter().enumerate() {
+            let class = if agent.disabled { ":::disabled" } else { "" };
+            let label = quote(&Self::agent_label(agent, "<br/>"));
+            let _ = writeln!(out, "  a{index}[{label}]{class}");
+        }
+        for (event, agent) in &self.subscriptions {
+            let _ = writeln!(out, "  e{event} --> a{agent}");
+        }
+        for (agent, event, label) in &self.handovers {
+            if label == "dispatch" {
+                let _ = writeln!(out, "  a{agent} -. dispatch .-> e{event}");
+            } else {
+                // Conditions hold characters that Mermaid only accepts quoted
+                let _ = writeln!(out, "  a{agent} -.->|{}| e{event}", quote(label));
+            }
+        }
+        let _ = writeln!(out, "  classDef entry stroke-width:3px");
+        let _ = writeln!(out, "  classDef disabled stroke-dasharray:5 5");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{Agent, Command, Handover, ModelId, ToolName};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture() -> Workflow {
+        let mut workflow = Workflow::new();
+        workflow.commands = vec![
+            Command::default().name("fix_issue"),
+            Command::default().name("release"),
+        ];
+        workflow.agents = vec![
+            Agent::new("engineer")
+                .model(ModelId::new("anthropic/claude-3.7-sonnet"))
+                .tools(vec![ToolName::new("forge_tool_event_dispatch")])
+                .subscribe(vec![
+                    "act/user_task_init".to_string(),
+                    "fix_issue".to_string(),
+                ]),
+            Agent::new("reviewer")
+                .subscribe(vec!["review".to_string()])
+                .disable(true),
+        ];
+        workflow
+    }
+
+    #[test]
+    fn test_dot() {
+        let actual = WorkflowGraph::new(&fixture()).render(GraphFormat::Dot);
+        let expected = r#"digraph workflow {
+  rankdir=LR;
+  e0 [label="fix_issue", shape=ellipse, peripheries=2];
+  e1 [label="release", shape=ellipse, peripheries=2];
+  e2 [label="act/user_task_init", shape=ellipse, peripheries=2];
+  e3 [label="review", shape=ellipse];
+  a0 [label="engineer\nanthropic/claude-3.7-sonnet", shape=box];
+  a1 [label="reviewer\n(disabled)", shape=box, style=dashed];
+  e0 -> a0;
+  e2 -> a0;
+  e3 -> a1;
+  a0 -> e3 [style=dashed, label="dispatch"];
+}
+"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_conditional_handovers() {
+        let mut workflow = Workflow::new();
+        workflow.agents = vec![
+            Agent::new("tester")
+                .subscribe(vec!["test".to_string()])
+                .handovers(vec![
+                    Handover::new("fix").when("${output} contains \"FAILED\""),
+                    Handover::new("release"),
+                ]),
+            Agent::new("fixer").subscribe(vec!["fix".to_string()]),
+        ];
+
+        let actual = WorkflowGraph::new(&workflow).render(GraphFormat::Mermaid);
+        assert!(actual.contains("  a0 -.->|\"when ${output} contains #quot;FAILED#quot;\"| e1\n"));
+        assert!(actual.contains("  a0 -.->|\"handover\"| e2\n"));
+        assert!(actual.contains("  e1 --> a1\n"));
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let actual = WorkflowGraph::new(&fixture()).render(GraphFormat::Mermaid);
+        let expected = r#"flowchart LR
+  e0(["fix_issue"]):::entry
+  e1(["release"]):::entry
+  e2(["act/user_task_init"]):::entry
+  e3(["review"])
+  a0["engineer<br/>anthropic/claude-3.7-sonnet"]
+  a1["reviewer<br/>(disabled)"]:::disabled
+  e0 --> a0
+  e2 --> a0
+  e3 --> a1
+  a0 -. dispatch .-> e3
+  classDef entry stroke-width:3px
+  classDef disabled stroke-dasharray:5 5
+"#;
+        assert_eq!(actual, expected);
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt;
 use std::path::{Path, PathBuf};
 
 use colored::Colorize;
-use forge_api::Environment;
+use forge_api::{AgentPackage, ContextBreakdown, Environment, Workflow};
 use forge_tracker::VERSION;
 
 use crate::model::ForgeCommandManager;
@@ -110,6 +110,83 @@ impl From<&UIState> for Info {
     }
 }
 
+impl From<&ContextBreakdown> for Info {
+    fn from(breakdown: &ContextBreakdown) -> Self {
+        let info =
+            breakdown
+                .categories
+                .iter()
+                .fold(Info::new().add_title("Context"), |info, usage| {
+                    info.add_key_value(
+                        usage.category,
+                        format!("{} messages, ~{} tokens", usage.messages, usage.tokens),
+                    )
+                });
+        let info = info.add_key_value(
+            "Total",
+            format!(
+                "{} messages, ~{} tokens",
+                breakdown.messages, breakdown.tokens
+            ),
+        );
+
+        let info = info.add_title("Next Compaction");
+        match &breakdown.compaction {
+            Some(preview) => info.add_key_value(
+                "Summarizes",
+                format!(
+                    "messages {}-{} ({} messages, ~{} tokens)",
+                    preview.sequence.0 + 1,
+                    preview.sequence.1 + 1,
+                    preview.messages,
+                    preview.tokens
+                ),
+            ),
+            None => info.add_key("Nothing to compact"),
+        }
+    }
+}
+
+impl From<&Workflow> for Info {
+    fn from(workflow: &Workflow) -> Self {
+        let mut info = Info::new().add_title("Agents");
+
+        for agent in &workflow.agents {
+            info = match &agent.description {
+                Some(description) => info.add_key_value(&agent.id, description),
+                None => info.add_key(&agent.id),
+            };
+        }
+
+        info
+    }
+}
+
+impl From<&[AgentPackage]> for Info {
+    fn from(packages: &[AgentPackage]) -> Self {
+        let mut info = Info::new();
+
+        for package in packages {
+            info = info.add_title(&package.name);
+            if let Some(description) = &package.description {
+                info = info.add_key_value("Description", description);
+            }
+            if !package.tools.is_empty() {
+                let tools = package
+                    .tools
+                    .iter()
+                    .map(|tool| tool.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                info = info.add_key_value("Tools", tools);
+            }
+            info = info.add_key_value("Workflow", &package.workflow);
+        }
+
+        info
+    }
+}
+
 impl fmt::Display for Info {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for section in &self.sections {
@@ -0,0 +1,256 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use forge_walker::IGNORE_FILE;
+
+/// Name of the workflow created in the project
+const WORKFLOW_FILE: &str = "forge.yaml";
+
+/// Kinds of projects whose settings `forge init` recommends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Cargo,
+    Npm,
+    Python,
+}
+
+impl ProjectKind {
+    /// Detects the kinds of the project in the directory from their manifests,
+    /// several of them for a project mixing languages
+    pub fn detect(cwd: &Path) -> Vec<Self> {
+        let exists = |files: &[&str]| files.iter().any(|file| cwd.join(file).is_file());
+        let mut kinds = Vec::new();
+        if exists(&["Cargo.toml"]) {
+            kinds.push(ProjectKind::Cargo);
+        }
+        if exists(&["package.json"]) {
+            kinds.push(ProjectKind::Npm);
+        }
+        if exists(&["pyproject.toml", "setup.py", "requirements.txt"]) {
+            kinds.push(ProjectKind::Python);
+        }
+        kinds
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ProjectKind::Cargo => "Rust",
+            ProjectKind::Npm => "JavaScript",
+            ProjectKind::Python => "Python",
+        }
+    }
+
+    /// Rules added to the prompts of the agents
+    fn rules(&self) -> &'static [&'static str] {
+        match self {
+            ProjectKind::Cargo => &[
+                "Format the code with `cargo fmt` after changing it.",
+                "Fix the warnings of `cargo clippy --all-targets`.",
+                "Run `cargo test` to verify the changes.",
+            ],
+            ProjectKind::Npm => &[
+                "Install dependencies with the package manager of the lockfile.",
+                "Run `npm test` to verify the changes.",
+            ],
+            ProjectKind::Python => &[
+                "Follow PEP 8 and add type hints to new functions.",
+                "Run `pytest` to verify the changes.",
+            ],
+        }
+    }
+
+    /// Prompt of the `/check` command, which verifies the project
+    fn check(&self) -> &'static str {
+        match self {
+            ProjectKind::Cargo => "cargo fmt, cargo clippy --all-targets and cargo test",
+            ProjectKind::Npm => "npm run lint, when the project has a lint script, and npm test",
+            ProjectKind::Python => "pytest",
+        }
+    }
+
+    /// Files that the agents have no reason to read, on top of the ones
+    /// ignored by git
+    fn ignored(&self) -> &'static [&'static str] {
+        match self {
+            ProjectKind::Cargo => &["target/", "Cargo.lock"],
+            ProjectKind::Npm => &[
+                "node_modules/",
+                "dist/",
+                "coverage/",
+                "package-lock.json",
+                "yarn.lock",
+                "pnpm-lock.yaml",
+            ],
+            ProjectKind::Python => &[
+                "__pycache__/",
+                "*.pyc",
+                ".venv/",
+                "venv/",
+                ".pytest_cache/",
+                "poetry.lock",
+            ],
+        }
+    }
+}
+
+/// Renders the starter workflow of a project of the given kinds
+pub fn workflow(kinds: &[ProjectKind]) -> String {
+    let mut yaml = String::from(
+        "# Workflow of forge for this project, merged with the default workflow.\n\
+         # See the forge.yaml configuration options in the README of forge.\n\n\
+         # Model used by all the agents, unless an agent sets its own\n\
+         # model: anthropic/claude-3.7-sonnet\n\n",
+    );
+
+    if !kinds.is_empty() {
+        let names = kinds.iter().map(|kind| kind.name()).collect::<Vec<_>>();
+        yaml.push_str(&format!(
+            "# Recommended for a {} project\ncustom_rules: |-\n",
+            names.join(" and ")
+        ));
+        for rule in kinds.iter().flat_map(|kind| kind.rules()) {
+            yaml.push_str(&format!("  - {rule}\n"));
+        }
+
+        let checks = kinds.iter().map(|kind| kind.check()).collect::<Vec<_>>();
+        yaml.push_str(&format!(
+            "\n# Commands available in the interactive session, e.g. /check\n\
+             commands:\n  \
+             - name: check\n    \
+             description: Verify the project and fix what fails\n    \
+             prompt: Run {} and fix the issues they report.\n\n",
+            checks.join(", then ")
+        ));
+    }
+
+    yaml.push_str(
+        "# Agents are merged with the default ones by id, e.g. to change the model of\n\
+         # the main agent:\n\
+         #\n\
+         # agents:\n\
+         #   - id: software-engineer\n\
+         #     model: anthropic/claude-3.5-haiku\n\
+         #\n\
+         # or to add an agent that the messages are directed to with /agent reviewer:\n\
+         #\n\
+         #   - id: reviewer\n\
+         #     description: Reviews the changes of the working tree\n\
+         #     model: anthropic/claude-3.7-sonnet\n\
+         #     system_prompt: |-\n\
+         #       You review code changes and point out bugs, without modifying files.\n\
+         #     user_prompt: |-\n\
+         #       <task>{{event.value}}</task>\n\
+         #     tools:\n\
+         #       - forge_tool_fs_read\n\
+         #       - forge_tool_fs_search\n\
+         #       - forge_tool_process_shell\n\
+         #       - forge_tool_attempt_completion\n",
+    );
+    yaml
+}
+
+/// Renders the `.forgeignore` of a project of the given kinds
+pub fn ignore_file(kinds: &[ProjectKind]) -> String {
+    let mut content = String::from(
+        "# Files hidden from the agents on top of the ones ignored by git, in the\n\
+         # .gitignore format\n",
+    );
+    for kind in kinds {
+        content.push_str(&format!("\n# {}\n", kind.name()));
+        for pattern in kind.ignored() {
+            content.push_str(&format!("{pattern}\n"));
+        }
+    }
+    content
+}
+
+/// What `forge init` did with a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scaffolded {
+    Created(PathBuf),
+    /// The file already existed and wasn't overwritten
+    Skipped(PathBuf),
+}
+
+/// Creates the starter workflow and `.forgeignore` of the project in the
+/// directory, overwriting existing ones only when forced
+pub async fn scaffold(cwd: &Path, kinds: &[ProjectKind], force: bool) -> Result<Vec<Scaffolded>> {
+    let files = [
+        (cwd.join(WORKFLOW_FILE), workflow(kinds)),
+        (cwd.join(IGNORE_FILE), ignore_file(kinds)),
+    ];
+
+    let mut scaffolded = Vec::new();
+    for (path, content) in files {
+        if !force && tokio::fs::try_exists(&path).await? {
+            scaffolded.push(Scaffolded::Skipped(path));
+            continue;
+        }
+        tokio::fs::write(&path, content)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        scaffolded.push(Scaffolded::Created(path));
+    }
+    Ok(scaffolded)
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::Workflow;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let fixture = tempfile::tempdir().unwrap();
+        std::fs::write(fixture.path().join("Cargo.toml"), "").unwrap();
+        std::fs::write(fixture.path().join("package.json"), "{}").unwrap();
+
+        let actual = ProjectKind::detect(fixture.path());
+        let expected = vec![ProjectKind::Cargo, ProjectKind::Npm];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_workflow() {
+        let actual = workflow(&[ProjectKind::Cargo]);
+        insta::assert_snapshot!(actual);
+    }
+
+    #[test]
+    fn test_workflow_is_valid() {
+        for kinds in [
+            vec![],
+            vec![ProjectKind::Cargo],
+            vec![ProjectKind::Npm, ProjectKind::Python],
+        ] {
+            let actual: Workflow = serde_yml::from_str(&workflow(&kinds)).unwrap();
+            assert_eq!(actual.commands.len(), usize::from(!kinds.is_empty()));
+        }
+    }
+
+    #[test]
+    fn test_ignore_file() {
+        let actual = ignore_file(&[ProjectKind::Python]);
+        assert!(actual.contains("\n# Python\n__pycache__/\n"));
+    }
+
+    #[tokio::test]
+    async fn test_scaffold_keeps_existing_files() {
+        let fixture = tempfile::tempdir().unwrap();
+        let workflow_path = fixture.path().join(WORKFLOW_FILE);
+        std::fs::write(&workflow_path, "model: custom").unwrap();
+
+        let actual = scaffold(fixture.path(), &[], false).await.unwrap();
+        let expected = vec![
+            Scaffolded::Skipped(workflow_path.clone()),
+            Scaffolded::Created(fixture.path().join(IGNORE_FILE)),
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(
+            std::fs::read_to_string(&workflow_path).unwrap(),
+            "model: custom"
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use forge_api::Environment;
+use forge_api::{EditMode, Environment};
 use forge_display::TitleFormat;
 use tokio::fs;
 
@@ -33,8 +33,14 @@ impl Console {
         Ok(Command::Message(content))
     }
 
-    pub async fn prompt(&self, prompt: Option<ForgePrompt>) -> anyhow::Result<Command> {
-        let mut engine = ForgeEditor::new(self.env.clone(), self.command.clone());
+    pub async fn prompt(
+        &self,
+        prompt: Option<ForgePrompt>,
+        mode: EditMode,
+        typed: String,
+    ) -> anyhow::Result<Command> {
+        let mut engine = ForgeEditor::new(self.env.clone(), self.command.clone(), mode);
+        engine.insert(typed);
         let prompt: ForgePrompt = prompt.unwrap_or_default();
 
         loop {
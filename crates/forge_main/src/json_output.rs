@@ -0,0 +1,197 @@
+use forge_api::{AgentMessage, ChatResponse, OutputStream, ToolCallId, ToolName, Usage};
+use serde::Serialize;
+use serde_json::Value;
+
+/// An event of a headless run printed with `--output json`, one JSON object
+/// per line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonEvent {
+    /// Text of the model, streamed in chunks and then sent again complete
+    Text {
+        agent: String,
+        text: String,
+        is_complete: bool,
+    },
+    /// The request to the model failed and is sent again, the text chunks of
+    /// the agent since its last complete text are to be discarded
+    Retry { agent: String, cause: String },
+    ToolCall {
+        agent: String,
+        call_id: Option<ToolCallId>,
+        name: ToolName,
+        arguments: Value,
+    },
+    /// Output of a tool while it is still executing
+    ToolOutput {
+        agent: String,
+        call_id: Option<ToolCallId>,
+        stream: OutputStream,
+        content: String,
+    },
+    ToolResult {
+        agent: String,
+        call_id: Option<ToolCallId>,
+        name: ToolName,
+        content: String,
+        is_error: bool,
+    },
+    Usage {
+        agent: String,
+        #[serde(flatten)]
+        usage: Usage,
+    },
+    /// Something the user would have been warned about in the terminal
+    Warning { agent: String, message: String },
+    /// The last event of the run
+    Result {
+        exit_code: u8,
+        /// The last complete text of the model, its answer to the task
+        text: Option<String>,
+        usage: Usage,
+        /// Cost in USD, when the provider publishes the prices of the model
+        cost: Option<f64>,
+        error: Option<String>,
+    },
+}
+
+impl JsonEvent {
+    /// Converts a message of the agents to the event printed for it, if any
+    pub fn from_message(message: &AgentMessage<ChatResponse>) -> Option<Self> {
+        let agent = message.agent.to_string();
+        let event = match &message.message {
+            ChatResponse::Text { text, is_complete, .. } => {
+                JsonEvent::Text { agent, text: text.clone(), is_complete: *is_complete }
+            }
+            ChatResponse::ToolCallStart(call) => JsonEvent::ToolCall {
+                agent,
+                call_id: call.call_id.clone(),
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+            },
+            ChatResponse::ToolCallOutput { call_id, chunk } => JsonEvent::ToolOutput {
+                agent,
+                call_id: call_id.clone(),
+                stream: chunk.stream,
+                content: chunk.content.clone(),
+            },
+            ChatResponse::ToolCallEnd(result) => JsonEvent::ToolResult {
+                agent,
+                call_id: result.call_id.clone(),
+                name: result.name.clone(),
+                content: result.content.clone(),
+                is_error: result.is_error,
+            },
+            ChatResponse::Retry { cause } => JsonEvent::Retry { agent, cause: cause.clone() },
+            ChatResponse::Usage(usage) => JsonEvent::Usage { agent, usage: usage.clone() },
+            ChatResponse::PromptInjection { source, excerpt } => JsonEvent::Warning {
+                agent,
+                message: format!("Possible prompt injection in {source}: \"{excerpt}\""),
+            },
+            ChatResponse::ToolDisabled(circuit) => JsonEvent::Warning {
+                agent,
+                message: format!(
+                    "{} failed {} times in a row, retrying in {}s",
+                    circuit.name.as_str(),
+                    circuit.failures,
+                    circuit.retry_in_secs
+                ),
+            },
+            // Nobody can answer in a headless run
+            ChatResponse::ApprovalRequest { calls, reason, .. } => JsonEvent::Warning {
+                agent,
+                message: format!(
+                    "The calls to {} were rejected, they require approval{}",
+                    calls
+                        .iter()
+                        .map(|call| call.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    reason
+                        .as_ref()
+                        .map_or(String::new(), |reason| format!(" since {reason}"))
+                ),
+            },
+            ChatResponse::EditReview { path, .. } => JsonEvent::Warning {
+                agent,
+                message: format!("The changes to {path} were rejected, edits are reviewed"),
+            },
+            ChatResponse::Artifact(_) | ChatResponse::Phase(_) => return None,
+        };
+        Some(event)
+    }
+
+    /// Prints the event on its own line
+    pub fn print(&self) -> anyhow::Result<()> {
+        println!("{}", serde_json::to_string(self)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{AgentId, ToolCallFull, ToolResult};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn message(message: ChatResponse) -> AgentMessage<ChatResponse> {
+        AgentMessage::new(AgentId::new("software-engineer"), message)
+    }
+
+    #[test]
+    fn test_tool_events() {
+        let call = ToolCallFull::new(ToolName::new("forge_tool_fs_read"))
+            .arguments(json!({"path": "README.md"}));
+        let actual = serde_json::to_value(
+            JsonEvent::from_message(&message(ChatResponse::ToolCallStart(call))).unwrap(),
+        )
+        .unwrap();
+        let expected = json!({
+            "type": "tool_call",
+            "agent": "software-engineer",
+            "call_id": null,
+            "name": "forge_tool_fs_read",
+            "arguments": {"path": "README.md"}
+        });
+        assert_eq!(actual, expected);
+
+        let result = ToolResult::new(ToolName::new("forge_tool_fs_read"))
+            .failure(anyhow::anyhow!("File not found"));
+        let actual = serde_json::to_value(
+            JsonEvent::from_message(&message(ChatResponse::ToolCallEnd(result))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(actual["type"], "tool_result");
+        assert_eq!(actual["is_error"], true);
+    }
+
+    #[test]
+    fn test_retry_event() {
+        let fixture = ChatResponse::Retry { cause: "invalid status code: 503".to_string() };
+        let actual =
+            serde_json::to_value(JsonEvent::from_message(&message(fixture)).unwrap()).unwrap();
+        let expected = json!({
+            "type": "retry",
+            "agent": "software-engineer",
+            "cause": "invalid status code: 503"
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_result_event() {
+        let event = JsonEvent::Result {
+            exit_code: 0,
+            text: Some("Done".to_string()),
+            usage: Usage { total_tokens: 10, ..Default::default() },
+            cost: None,
+            error: None,
+        };
+
+        let actual = serde_json::to_string(&event).unwrap();
+        let expected = r#"{"type":"result","exit_code":0,"text":"Done","usage":{"prompt_tokens":0,"completion_tokens":0,"total_tokens":10,"estimated_tokens":null},"cost":null,"error":null}"#;
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,15 +1,35 @@
 mod auto_update;
 mod banner;
+mod budget;
+mod cancel;
+mod ci;
+mod citation;
 mod cli;
 mod completer;
+mod conversation_html;
+mod debugger;
+mod doctor;
 mod editor;
+mod graph;
 mod info;
+mod init;
 mod input;
+mod json_output;
+mod lock;
+mod manifest;
+mod mcp;
 mod model;
+mod notify;
 mod prompt;
+mod report;
+mod sessions;
 mod state;
+mod stdin;
 mod tools_display;
+mod trust;
 mod ui;
+mod undo;
+mod watch;
 
 pub use auto_update::update_forge;
 pub use cli::Cli;
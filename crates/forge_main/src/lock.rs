@@ -0,0 +1,330 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinHandle;
+
+/// File, relative to the working directory, holding the lock of the session
+/// running in the workspace
+const LOCK_FILE: &str = ".forge/session.lock";
+
+/// Interval at which a session refreshes its lock
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A lock that hasn't been refreshed for this long was left by a session that
+/// ended abruptly
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Number of times a lock that can't be parsed is read before it's considered
+/// corrupt, since the session that holds it may be creating it right now
+const READ_ATTEMPTS: usize = 3;
+
+/// Delay between the reads of a lock that can't be parsed
+const READ_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    /// Seconds since the epoch at which the session last refreshed the lock
+    heartbeat: u64,
+}
+
+impl LockInfo {
+    fn current() -> Self {
+        Self { pid: std::process::id(), heartbeat: now() }
+    }
+
+    /// Fails unless the lock can be taken over by a new session. A stale lock
+    /// is taken over once its process is gone, as reported by `is_running`,
+    /// while `force` takes over a lock that is still held.
+    fn check(&self, now: u64, force: bool, is_running: impl FnOnce(u32) -> bool) -> Result<()> {
+        if force || self.pid == std::process::id() {
+            return Ok(());
+        }
+
+        let age = now.saturating_sub(self.heartbeat);
+        if age < STALE_AFTER.as_secs() {
+            bail!(
+                "Another forge session (PID {}) is active in this workspace. Running two sessions at once lets them overwrite each other's changes. Use --force to take over its lock anyway.",
+                self.pid
+            );
+        }
+        if is_running(self.pid) {
+            bail!(
+                "The forge session (PID {}) holding the lock of this workspace stopped refreshing it {age} seconds ago, but the process is still running. Use --force to take over its lock anyway.",
+                self.pid
+            );
+        }
+        tracing::info!(
+            pid = self.pid,
+            age,
+            "Taking over the stale lock of the workspace"
+        );
+        Ok(())
+    }
+}
+
+/// Whether a process with the PID exists. Without a way to check, the
+/// process of a stale lock is assumed to be gone.
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // 0 and negative PIDs would address process groups
+    let Some(pid) = libc::pid_t::try_from(pid).ok().filter(|pid| *pid > 0) else {
+        return false;
+    };
+    // SAFETY: signal 0 isn't delivered, it only checks whether the process
+    // exists. EPERM means that it exists but belongs to another user.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    false
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Prevents two sessions from editing the same workspace at the same time.
+/// The lock is refreshed in the background while the session runs and
+/// released when it's dropped.
+pub struct SessionLock {
+    path: PathBuf,
+    heartbeat: JoinHandle<()>,
+}
+
+impl SessionLock {
+    /// Takes the lock of the workspace, failing if another session holds it
+    /// unless `force` is set. The lock of a session that ended without
+    /// releasing it is taken over.
+    pub async fn acquire(cwd: &Path, force: bool) -> Result<Self> {
+        let path = cwd.join(LOCK_FILE);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string(&LockInfo::current())?;
+
+        // Creating the file fails if it exists, so that two sessions starting
+        // at once can't both take the lock
+        let created = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await;
+        match created {
+            Ok(mut file) => file.write_all(content.as_bytes()).await?,
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                // A lock that still can't be parsed is from a session that
+                // crashed while creating it
+                if let Some(existing) = read_existing_lock(&path).await? {
+                    existing.check(now(), force, is_running)?;
+                }
+                write_lock(&path, &LockInfo::current()).await?;
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("Failed to create {}", path.display()))
+            }
+        }
+
+        let heartbeat = tokio::spawn(Self::heartbeat(path.clone()));
+        Ok(Self { path, heartbeat })
+    }
+
+    async fn heartbeat(path: PathBuf) {
+        loop {
+            tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+
+            // Stop refreshing the lock once another session took it over
+            if read_lock(&path).await.map(|info| info.pid) != Some(std::process::id()) {
+                tracing::warn!("The lock of the session was taken over by another session");
+                return;
+            }
+            if let Err(error) = write_lock(&path, &LockInfo::current()).await {
+                tracing::warn!(error = ?error, "Failed to refresh the lock of the session");
+            }
+        }
+    }
+}
+
+async fn read_lock(path: &Path) -> Option<LockInfo> {
+    let content = tokio::fs::read_to_string(path).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Reads the lock held by another session, retrying while it can't be parsed.
+/// Returns `None` if it's corrupt or was released in the meantime.
+async fn read_existing_lock(path: &Path) -> Result<Option<LockInfo>> {
+    for attempt in 1..=READ_ATTEMPTS {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        if let Ok(info) = serde_json::from_str(&content) {
+            return Ok(Some(info));
+        }
+        if attempt < READ_ATTEMPTS {
+            tokio::time::sleep(READ_RETRY_DELAY).await;
+        }
+    }
+    Ok(None)
+}
+
+/// Writes the lock to a temporary file that is renamed into place, so that
+/// other sessions never read a lock that is partly written
+async fn write_lock(path: &Path, info: &LockInfo) -> Result<()> {
+    let tmp = path.with_extension(format!("lock.{}.tmp", info.pid));
+    tokio::fs::write(&tmp, serde_json::to_string(info)?).await?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+
+        // The lock is left alone if another session took it over
+        let is_owned = std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<LockInfo>(&content).ok())
+            .is_some_and(|info| info.pid == std::process::id());
+        if is_owned {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_check_active_lock() {
+        let fixture = LockInfo { pid: u32::MAX, heartbeat: 1000 };
+
+        let actual = fixture
+            .check(1010, false, |_| false)
+            .unwrap_err()
+            .to_string();
+        assert!(actual.starts_with("Another forge session (PID 4294967295) is active"));
+
+        assert!(fixture.check(1010, true, |_| true).is_ok());
+    }
+
+    #[test]
+    fn test_check_stale_lock() {
+        let fixture = LockInfo { pid: u32::MAX, heartbeat: 1000 };
+
+        assert!(fixture.check(1100, false, |_| false).is_ok());
+
+        let actual = fixture
+            .check(1100, false, |_| true)
+            .unwrap_err()
+            .to_string();
+        assert!(actual.contains("stopped refreshing it 100 seconds ago"));
+
+        assert!(fixture.check(1100, true, |_| true).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_running() {
+        assert!(is_running(std::process::id()));
+        assert!(!is_running(u32::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_stale_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        let crashed = LockInfo { pid: u32::MAX, heartbeat: now() - 60 };
+        tokio::fs::write(&path, serde_json::to_string(&crashed).unwrap())
+            .await
+            .unwrap();
+
+        let _lock = SessionLock::acquire(dir.path(), false).await.unwrap();
+        assert_eq!(read_lock(&path).await.unwrap().pid, std::process::id());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_corrupt_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&path, "{\"pid\": 12").await.unwrap();
+
+        let _lock = SessionLock::acquire(dir.path(), false).await.unwrap();
+        assert_eq!(read_lock(&path).await.unwrap().pid, std::process::id());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_lock_being_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&path, "").await.unwrap();
+        let other = LockInfo { pid: u32::MAX, heartbeat: now() };
+        let writer = {
+            let path = path.clone();
+            let other = other.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(READ_RETRY_DELAY / 2).await;
+                tokio::fs::write(&path, serde_json::to_string(&other).unwrap())
+                    .await
+                    .unwrap();
+            })
+        };
+
+        assert!(SessionLock::acquire(dir.path(), false).await.is_err());
+        writer.await.unwrap();
+        assert_eq!(read_lock(&path).await, Some(other));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_and_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+
+        let lock = SessionLock::acquire(dir.path(), false).await.unwrap();
+        assert_eq!(read_lock(&path).await.unwrap().pid, std::process::id());
+
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_held_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        let other = LockInfo { pid: u32::MAX, heartbeat: now() };
+        tokio::fs::write(&path, serde_json::to_string(&other).unwrap())
+            .await
+            .unwrap();
+
+        assert!(SessionLock::acquire(dir.path(), false).await.is_err());
+        assert_eq!(read_lock(&path).await, Some(other));
+
+        let _lock = SessionLock::acquire(dir.path(), true).await.unwrap();
+        assert_eq!(read_lock(&path).await.unwrap().pid, std::process::id());
+    }
+}
@@ -1,3 +1,4 @@
+use std::process::ExitCode;
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -6,13 +7,11 @@ use forge::{Cli, UI};
 use forge_api::ForgeAPI;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     // Initialize and run the UI
     let cli = Cli::parse();
 
-    let api = Arc::new(ForgeAPI::init(cli.restricted));
+    let api = Arc::new(ForgeAPI::init(cli.restricted, cli.low_bandwidth));
     let mut ui = UI::init(cli, api)?;
-    ui.run().await;
-
-    Ok(())
+    Ok(ui.run().await)
 }
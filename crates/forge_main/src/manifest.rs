@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use forge_walker::Walker;
+use serde::Serialize;
+
+use crate::info::Info;
+
+/// Files larger than this are compared by size and modification time only
+const MAX_HASH_SIZE: u64 = 10 * 1024 * 1024;
+
+/// State of a single file in the workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    hash: Option<u64>,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// A lightweight record of every file in the workspace, used to find the
+/// changes made during a session regardless of whether they were made through
+/// the fs tools or the shell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceManifest {
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Modified => write!(f, "modified"),
+            ChangeKind::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+/// A file that changed between two manifests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+impl WorkspaceManifest {
+    /// Records the files in the workspace, skipping the ones that are hidden or
+    /// ignored by git
+    pub async fn capture(cwd: PathBuf) -> Result<Self> {
+        let files = Walker::max_all().cwd(cwd.clone()).get().await?;
+        tokio::task::spawn_blocking(move || {
+            let entries = files
+                .into_iter()
+                .filter(|file| !file.is_dir())
+                .filter_map(|file| {
+                    let entry = Self::entry(&cwd.join(&file.path)).ok()?;
+                    Some((file.path, entry))
+                })
+                .collect();
+            Self { entries }
+        })
+        .await
+        .context("Failed to capture the workspace manifest")
+    }
+
+    fn entry(path: &Path) -> Result<Entry> {
+        let metadata = std::fs::metadata(path)?;
+        let size = metadata.len();
+        let hash = if size <= MAX_HASH_SIZE {
+            let mut hasher = fnv_rs::Fnv64::default();
+            hasher.write(&std::fs::read(path)?);
+            Some(hasher.finish())
+        } else {
+            None
+        };
+
+        Ok(Entry { hash, size, modified: metadata.modified().ok() })
+    }
+
+    /// Lists the files that were added, modified or removed in `current`
+    /// compared to this manifest
+    pub fn diff(&self, current: &WorkspaceManifest) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        for (path, entry) in current.entries.iter() {
+            let kind = match self.entries.get(path) {
+                None => ChangeKind::Added,
+                Some(previous) if previous.is_modified(entry) => ChangeKind::Modified,
+                Some(_) => continue,
+            };
+            changes.push(Change { path: path.clone(), kind });
+        }
+
+        for path in self.entries.keys() {
+            if !current.entries.contains_key(path) {
+                changes.push(Change { path: path.clone(), kind: ChangeKind::Removed });
+            }
+        }
+
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+}
+
+impl Entry {
+    fn is_modified(&self, other: &Entry) -> bool {
+        match (self.hash, other.hash) {
+            (Some(a), Some(b)) => a != b,
+            _ => self.size != other.size || self.modified != other.modified,
+        }
+    }
+}
+
+impl From<&[Change]> for Info {
+    fn from(changes: &[Change]) -> Self {
+        changes.iter().fold(
+            Info::new().add_title("Workspace Changes"),
+            |info, change| info.add_key_value(&change.path, change.kind),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn change(path: &str, kind: ChangeKind) -> Change {
+        Change { path: path.to_string(), kind }
+    }
+
+    #[tokio::test]
+    async fn test_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("unchanged.txt"), "same").unwrap();
+        std::fs::write(dir.path().join("modified.txt"), "before").unwrap();
+        std::fs::write(dir.path().join("removed.txt"), "gone").unwrap();
+        let fixture = WorkspaceManifest::capture(dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        std::fs::write(dir.path().join("modified.txt"), "after").unwrap();
+        std::fs::remove_file(dir.path().join("removed.txt")).unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src").join("added.rs"), "fn main() {}").unwrap();
+        let current = WorkspaceManifest::capture(dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let actual = fixture.diff(&current);
+        let expected = vec![
+            change("modified.txt", ChangeKind::Modified),
+            change("removed.txt", ChangeKind::Removed),
+            change(
+                &Path::new("src").join("added.rs").display().to_string(),
+                ChangeKind::Added,
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_diff_ignores_touched_files() {
+        let fixture = WorkspaceManifest {
+            entries: BTreeMap::from([(
+                "a.txt".to_string(),
+                Entry {
+                    hash: Some(1),
+                    size: 1,
+                    modified: Some(SystemTime::UNIX_EPOCH),
+                },
+            )]),
+        };
+        let current = WorkspaceManifest {
+            entries: BTreeMap::from([(
+                "a.txt".to_string(),
+                Entry { hash: Some(1), size: 1, modified: Some(SystemTime::now()) },
+            )]),
+        };
+
+        let actual = fixture.diff(&current);
+        assert_eq!(actual, vec![]);
+    }
+}
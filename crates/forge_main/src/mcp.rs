@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use forge_api::{
+    ToolCallContext, ToolCallFull, ToolDefinition, ToolName, ToolResult, Workflow, API,
+};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Version of the protocol used when the client doesn't request one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Tools exposed to MCP clients. The tools that only make sense inside a
+/// conversation, like attempt_completion or followup, are left out.
+const EXPOSED_TOOLS: [&str; 9] = [
+    "forge_tool_fs_read",
+    "forge_tool_fs_create",
+    "forge_tool_fs_patch",
+    "forge_tool_fs_remove",
+    "forge_tool_fs_list",
+    "forge_tool_fs_search",
+    "forge_tool_fs_info",
+    "forge_tool_fs_outline",
+    "forge_tool_process_shell",
+];
+
+/// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+/// Serves forge's tools over the Model Context Protocol, reading newline
+/// delimited JSON-RPC messages from stdin and writing the responses to stdout.
+/// The calls are executed with the policies of the workflow, e.g. the shell
+/// policy and the resource limits.
+pub struct McpServer<F> {
+    api: Arc<F>,
+    workflow: Workflow,
+    read_only: bool,
+}
+
+impl<F: API> McpServer<F> {
+    pub fn new(api: Arc<F>, workflow: Workflow, read_only: bool) -> Self {
+        Self { api, workflow, read_only }
+    }
+
+    pub async fn serve(&self) -> Result<()> {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(message).await,
+                Err(error) => Some(error_response(
+                    Value::Null,
+                    PARSE_ERROR,
+                    &format!("Invalid JSON: {error}"),
+                )),
+            };
+
+            if let Some(response) = response {
+                stdout.write_all(response.to_string().as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles a message, returning the response to send if it is a request
+    async fn handle(&self, message: Value) -> Option<Value> {
+        let method = message.get("method").and_then(Value::as_str)?;
+        // Notifications, e.g. notifications/initialized, don't get a response
+        let id = message.get("id")?.clone();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        tracing::debug!(method = method, "MCP request");
+
+        let result = match method {
+            "initialize" => initialize_result(&params),
+            "ping" => json!({}),
+            "tools/list" => tools_list_result(&self.api.tools().await),
+            "tools/call" => {
+                let Some(name) = params.get("name").and_then(Value::as_str) else {
+                    return Some(error_response(id, INVALID_PARAMS, "Missing tool name"));
+                };
+                if !EXPOSED_TOOLS.contains(&name) {
+                    return Some(error_response(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("Unknown tool: {name}"),
+                    ));
+                }
+
+                let arguments = params
+                    .get("arguments")
+                    .cloned()
+                    .unwrap_or_else(|| json!({}));
+                let call = ToolCallFull::new(ToolName::new(name)).arguments(arguments);
+                let result = self.api.call_tool(self.tool_call_context(), call).await;
+                tools_call_result(&result)
+            }
+            _ => {
+                return Some(error_response(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("Method not found: {method}"),
+                ))
+            }
+        };
+
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    fn tool_call_context(&self) -> ToolCallContext {
+        let workflow = &self.workflow;
+        ToolCallContext::default()
+            .resource_limits(workflow.resource_limits.clone().unwrap_or_default())
+            .egress(workflow.egress.clone().unwrap_or_default())
+            .shell_policy(workflow.shell_policy.clone().unwrap_or_default())
+            .approval(workflow.approval.clone().unwrap_or_default())
+            .read_only(self.read_only || workflow.read_only.unwrap_or_default())
+            .language_servers(workflow.language_servers.clone().unwrap_or_default())
+    }
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let version = params
+        .get("protocolVersion")
+        .and_then(Value::as_str)
+        .unwrap_or(PROTOCOL_VERSION);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "forge", "version": env!("CARGO_PKG_VERSION") }
+    })
+}
+
+fn tools_list_result(tools: &[ToolDefinition]) -> Value {
+    let tools = tools
+        .iter()
+        .filter(|tool| EXPOSED_TOOLS.contains(&tool.name.as_str()))
+        .map(|tool| {
+            json!({
+                "name": tool.name.as_str(),
+                "description": tool.description,
+                "inputSchema": tool.input_schema,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "tools": tools })
+}
+
+fn tools_call_result(result: &ToolResult) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": result.content.trim() }],
+        "isError": result.is_error,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_initialize_result() {
+        let actual = initialize_result(&json!({ "protocolVersion": "2025-03-26" }));
+        assert_eq!(actual["protocolVersion"], "2025-03-26");
+        assert_eq!(actual["serverInfo"]["name"], "forge");
+
+        let actual = initialize_result(&Value::Null);
+        assert_eq!(actual["protocolVersion"], PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_tools_list_result() {
+        let tool = |name: &str| ToolDefinition {
+            name: ToolName::new(name),
+            description: format!("Description of {name}"),
+            input_schema: Default::default(),
+            output_schema: None,
+            mutating: false,
+        };
+        let fixture = [
+            tool("forge_tool_fs_read"),
+            tool("forge_tool_attempt_completion"),
+            tool("forge_tool_process_shell"),
+        ];
+
+        let actual = tools_list_result(&fixture)["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "forge_tool_fs_read".to_string(),
+            "forge_tool_process_shell".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_tools_call_result() {
+        let fixture = ToolResult::new(ToolName::new("forge_tool_fs_read"))
+            .failure(anyhow::anyhow!("File not found"));
+
+        let actual = tools_call_result(&fixture);
+
+        let expected = json!({
+            "content": [{ "type": "text", "text": "ERROR:\nCaused by: File not found" }],
+            "isError": true,
+        });
+        assert_eq!(actual, expected);
+    }
+}
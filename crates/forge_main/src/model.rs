@@ -169,18 +169,39 @@ impl ForgeCommandManager {
             "/new" => Ok(Command::New),
             "/info" => Ok(Command::Info),
             "/exit" => Ok(Command::Exit),
-            "/dump" => {
-                if !parameters.is_empty() && parameters[0] == "html" {
-                    Ok(Command::Dump(Some("html".to_string())))
+            "/dump" => match parameters.first() {
+                Some(&"html") => Ok(Command::Dump(Some("html".to_string()))),
+                Some(&"md") => Ok(Command::Dump(Some("md".to_string()))),
+                _ => Ok(Command::Dump(None)),
+            },
+            "/act" => Ok(Command::Act),
+            "/plan" => {
+                if parameters.first() == Some(&"show") {
+                    Ok(Command::PlanShow)
                 } else {
-                    Ok(Command::Dump(None))
+                    Ok(Command::Plan)
                 }
             }
-            "/act" => Ok(Command::Act),
-            "/plan" => Ok(Command::Plan),
             "/help" => Ok(Command::Help),
             "/model" => Ok(Command::Model),
-            "/tools" => Ok(Command::Tools),
+            "/tools" => {
+                let tools = parameters.iter().skip(1).map(|tool| tool.to_string());
+                match parameters.first() {
+                    Some(&"enable") => Ok(Command::ToolsEnable(tools.collect())),
+                    Some(&"disable") => Ok(Command::ToolsDisable(tools.collect())),
+                    _ => Ok(Command::Tools),
+                }
+            }
+            "/changes" => Ok(Command::Changes),
+            "/undo" => Ok(Command::Undo),
+            "/sessions" => Ok(Command::Sessions),
+            "/context" => Ok(Command::Context),
+            "/mode" => Ok(Command::Mode(
+                parameters.first().map(|mode| mode.to_string()),
+            )),
+            "/agent" => Ok(Command::Agent(
+                parameters.first().map(|agent| agent.to_string()),
+            )),
             text => {
                 let parts = text.split_ascii_whitespace().collect::<Vec<&str>>();
 
@@ -238,12 +259,22 @@ pub enum Command {
     /// This can be triggered with the '/plan' command.
     #[strum(props(usage = "Enable planning mode without code changes"))]
     Plan,
+    /// Display the plan of the conversation with the progress made on each
+    /// step. This can be triggered with the '/plan show' command.
+    #[strum(props(usage = "Show the plan and the status of its steps"))]
+    PlanShow,
+    /// Switch to a mode of the workflow, chosen from a list or given by name.
+    /// This can be triggered with the '/mode' command.
+    #[strum(props(usage = "Switch to a mode defined by the workflow"))]
+    Mode(Option<String>),
     /// Switch to "help" mode.
     /// This can be triggered with the '/help' command.
     #[strum(props(usage = "Enable help mode for tool questions"))]
     Help,
-    /// Dumps the current conversation into a json file or html file
-    #[strum(props(usage = "Save conversation as JSON or HTML (use /dump html for HTML format)"))]
+    /// Dumps the current conversation into a json, html or markdown file
+    #[strum(props(
+        usage = "Save conversation as JSON, HTML or a markdown transcript (use /dump html or /dump md)"
+    ))]
     Dump(Option<String>),
     /// Switch or select the active model
     /// This can be triggered with the '/model' command.
@@ -253,6 +284,34 @@ pub enum Command {
     /// This can be triggered with the '/tools' command.
     #[strum(props(usage = "List all available tools with their descriptions and schema"))]
     Tools,
+    /// Offer tools that were disabled to the agents again. This can be
+    /// triggered with the '/tools enable' command.
+    #[strum(props(usage = "Enable tools or groups of tools, e.g. /tools enable process"))]
+    ToolsEnable(Vec<String>),
+    /// Stop offering tools to the agents for the rest of the conversation.
+    /// This can be triggered with the '/tools disable' command.
+    #[strum(props(usage = "Disable tools or groups of tools, e.g. /tools disable process"))]
+    ToolsDisable(Vec<String>),
+    /// Display the files that changed in the workspace since the session
+    /// started. This can be triggered with the '/changes' command.
+    #[strum(props(usage = "List files changed in the workspace during this session"))]
+    Changes,
+    /// Revert the changes the tools made to files during the last turn.
+    /// This can be triggered with the '/undo' command.
+    #[strum(props(usage = "Revert the file changes of the last turn"))]
+    Undo,
+    /// List the past conversations of the workspace to continue one.
+    /// This can be triggered with the '/sessions' command.
+    #[strum(props(usage = "Continue a past conversation of the workspace"))]
+    Sessions,
+    /// Display what fills the context window and what the next compaction
+    /// would summarize. This can be triggered with the '/context' command.
+    #[strum(props(usage = "Show a breakdown of the conversation context"))]
+    Context,
+    /// Direct the next messages to an agent of the workflow, chosen from a list
+    /// or given by id. This can be triggered with the '/agent' command.
+    #[strum(props(usage = "Direct the next messages to a specific agent"))]
+    Agent(Option<String>),
     /// Handles custom command defined in workflow file.
     Custom(PartialEvent),
     /// Executes a native shell command.
@@ -271,10 +330,19 @@ impl Command {
             Command::Exit => "/exit",
             Command::Act => "/act",
             Command::Plan => "/plan",
+            Command::PlanShow => "/plan show",
+            Command::Mode(_) => "/mode",
             Command::Help => "/help",
             Command::Dump(_) => "/dump",
             Command::Model => "/model",
             Command::Tools => "/tools",
+            Command::ToolsEnable(_) => "/tools enable",
+            Command::ToolsDisable(_) => "/tools disable",
+            Command::Changes => "/changes",
+            Command::Undo => "/undo",
+            Command::Sessions => "/sessions",
+            Command::Context => "/context",
+            Command::Agent(_) => "/agent",
             Command::Custom(event) => &event.name,
             Command::Shell(_) => "!shell",
         }
@@ -472,6 +540,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_tools_command() {
+        let cmd_manager = ForgeCommandManager::default();
+
+        let actual = cmd_manager
+            .parse("/tools disable process fs_remove")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Command::ToolsDisable(vec!["process".to_string(), "fs_remove".to_string()])
+        );
+
+        let actual = cmd_manager.parse("/tools enable process").unwrap();
+        assert_eq!(actual, Command::ToolsEnable(vec!["process".to_string()]));
+
+        let actual = cmd_manager.parse("/tools").unwrap();
+        assert_eq!(actual, Command::Tools);
+    }
+
+    #[test]
+    fn test_parse_agent_command() {
+        let cmd_manager = ForgeCommandManager::default();
+
+        let actual = cmd_manager.parse("/agent software-designer").unwrap();
+        assert_eq!(
+            actual,
+            Command::Agent(Some("software-designer".to_string()))
+        );
+
+        let actual = cmd_manager.parse("/agent").unwrap();
+        assert_eq!(actual, Command::Agent(None));
+    }
+
     #[test]
     fn test_shell_command_not_in_default_commands() {
         // Setup
@@ -0,0 +1,63 @@
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Rings the bell of the terminal and shows a desktop notification, so that
+/// the user notices it from another window. The bell goes to stderr, and only
+/// when it's a terminal, so that it never ends up in the output of the run.
+/// The notification is shown with `notify-send` on Linux and `osascript` on
+/// macOS, and skipped when they aren't available.
+pub fn notify(title: &str, body: &str) {
+    let mut stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        let _ = stderr.write_all(b"\x07");
+        let _ = stderr.flush();
+    }
+
+    if let Some(mut command) = desktop_command(title, body) {
+        // The notification isn't waited for, nor its failure reported
+        let _ = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+fn desktop_command(title: &str, body: &str) -> Option<Command> {
+    match std::env::consts::OS {
+        "linux" => {
+            let mut command = Command::new("notify-send");
+            command.args(["--app-name=forge", title, body]);
+            Some(command)
+        }
+        "macos" => {
+            let mut command = Command::new("osascript");
+            command.arg("-e").arg(format!(
+                "display notification {} with title {}",
+                applescript_string(body),
+                applescript_string(title)
+            ));
+            Some(command)
+        }
+        _ => None,
+    }
+}
+
+/// Quotes the text as an AppleScript string literal
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_applescript_string() {
+        let actual = applescript_string(r#"Run "cargo test" in C:\src"#);
+        let expected = r#""Run \"cargo test\" in C:\\src""#;
+        assert_eq!(actual, expected);
+    }
+}
@@ -4,17 +4,20 @@ use std::fmt::Write;
 use std::process::Command;
 
 use derive_setters::Setters;
-use forge_api::{ModelId, Usage};
+use forge_api::{Mode, ModelId, Usage};
 use forge_tracker::VERSION;
 use nu_ansi_term::{Color, Style};
-use reedline::{Prompt, PromptHistorySearchStatus};
-
-use crate::state::Mode;
+use reedline::{Prompt, PromptEditMode, PromptHistorySearchStatus, PromptViMode};
 
 // Constants
 const MULTILINE_INDICATOR: &str = "::: ";
 const RIGHT_CHEVRON: &str = "❯";
 
+/// Share of the context window above which the usage is highlighted, to warn
+/// before the window is full
+const CONTEXT_WARNING_RATIO: f64 = 0.8;
+const CONTEXT_CRITICAL_RATIO: f64 = 0.95;
+
 /// Very Specialized Prompt for the Agent Chat
 #[derive(Clone, Default, Setters)]
 #[setters(strip_option, borrow_self)]
@@ -22,6 +25,10 @@ pub struct ForgePrompt {
     pub usage: Option<Usage>,
     pub mode: Mode,
     pub model: Option<ModelId>,
+    /// Size of the context window of the model
+    pub context_length: Option<u64>,
+    /// Cost in USD of the conversation so far
+    pub cost: Option<f64>,
 }
 
 impl Prompt for ForgePrompt {
@@ -98,26 +105,53 @@ impl Prompt for ForgePrompt {
             .and_then(|u| u.estimated_tokens)
             .unwrap_or(0);
 
-        if estimated > reported {
-            write!(result, "/~{estimated}").unwrap();
+        let tokens = if estimated > reported {
+            write!(result, "/~{}", humanize_tokens(estimated)).unwrap();
+            estimated
         } else {
-            write!(result, "/{reported}").unwrap();
+            write!(result, "/{}", humanize_tokens(reported)).unwrap();
+            reported
+        };
+
+        // Append the size of the context window and how much of it is used
+        let mut color = Color::DarkGray;
+        if let Some(context_length) = self.context_length.filter(|length| *length > 0) {
+            let ratio = tokens as f64 / context_length as f64;
+            write!(
+                result,
+                " of {} ({:.0}%)",
+                humanize_tokens(context_length),
+                ratio * 100.0
+            )
+            .unwrap();
+            if ratio >= CONTEXT_CRITICAL_RATIO {
+                color = Color::Red;
+            } else if ratio >= CONTEXT_WARNING_RATIO {
+                color = Color::Yellow;
+            }
+        }
+
+        if let Some(cost) = self.cost {
+            write!(result, "/${cost:.2}").unwrap();
         }
 
         write!(result, "]").unwrap();
 
         // Apply styling once at the end
-        Cow::Owned(
-            Style::new()
-                .bold()
-                .fg(Color::DarkGray)
-                .paint(&result)
-                .to_string(),
-        )
+        Cow::Owned(Style::new().bold().fg(color).paint(&result).to_string())
     }
 
-    fn render_prompt_indicator(&self, _prompt_mode: reedline::PromptEditMode) -> Cow<str> {
-        Cow::Borrowed("")
+    fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<str> {
+        // The vim mode is shown so that keys aren't typed in the wrong one
+        match prompt_mode {
+            PromptEditMode::Vi(PromptViMode::Normal) => {
+                Cow::Owned(Style::new().fg(Color::Yellow).paint("[N] ").to_string())
+            }
+            PromptEditMode::Vi(PromptViMode::Insert) => {
+                Cow::Owned(Style::new().fg(Color::DarkGray).paint("[I] ").to_string())
+            }
+            _ => Cow::Borrowed(""),
+        }
     }
 
     fn render_prompt_multiline_indicator(&self) -> Cow<str> {
@@ -151,6 +185,19 @@ impl Prompt for ForgePrompt {
     }
 }
 
+/// Formats a number of tokens compactly, e.g. 12.3k or 1M
+fn humanize_tokens(tokens: u64) -> String {
+    let format = |value: f64, unit: &str| {
+        let value = format!("{value:.1}");
+        format!("{}{unit}", value.trim_end_matches(".0"))
+    };
+    match tokens {
+        0..1_000 => tokens.to_string(),
+        1_000..1_000_000 => format(tokens as f64 / 1_000.0, "k"),
+        _ => format(tokens as f64 / 1_000_000.0, "M"),
+    }
+}
+
 /// Gets the current git branch name if available
 fn get_git_branch() -> Option<String> {
     // First check if we're in a git repository
@@ -237,6 +284,35 @@ mod tests {
         assert!(actual.contains("0"));
     }
 
+    #[test]
+    fn test_render_prompt_right_with_context_and_cost() {
+        let usage = Usage { prompt_tokens: 170_000, ..Default::default() };
+        let mut prompt = ForgePrompt::default();
+        prompt
+            .usage(usage)
+            .context_length(200_000)
+            .cost(1.234)
+            .model(ModelId::new("anthropic/claude-3.7-sonnet"));
+
+        let actual = prompt.render_prompt_right();
+        let expected = Style::new()
+            .bold()
+            .fg(Color::Yellow)
+            .paint(format!(
+                "[{VERSION}/claude-3.7-sonnet/170k of 200k (85%)/$1.23]"
+            ))
+            .to_string();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_humanize_tokens() {
+        assert_eq!(humanize_tokens(999), "999");
+        assert_eq!(humanize_tokens(12_345), "12.3k");
+        assert_eq!(humanize_tokens(200_000), "200k");
+        assert_eq!(humanize_tokens(1_000_000), "1M");
+    }
+
     #[test]
     fn test_render_prompt_multiline_indicator() {
         let prompt = ForgePrompt::default();
@@ -245,6 +321,18 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_render_prompt_indicator() {
+        let prompt = ForgePrompt::default();
+
+        let actual = prompt.render_prompt_indicator(PromptEditMode::Vi(PromptViMode::Normal));
+        let expected = Style::new().fg(Color::Yellow).paint("[N] ").to_string();
+        assert_eq!(actual, expected);
+
+        let actual = prompt.render_prompt_indicator(PromptEditMode::Emacs);
+        assert_eq!(actual, "");
+    }
+
     #[test]
     fn test_render_prompt_history_search_indicator_passing() {
         let prompt = ForgePrompt::default();
@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use forge_api::{AgentMessage, ChatResponse, ToolCallFull, ToolResult, Usage};
+use serde::Serialize;
+
+use crate::manifest::{Change, ChangeKind};
+
+/// Directory, relative to the working directory, where reports are written
+const REPORTS_DIR: &str = ".forge/reports";
+
+/// Commands executed by the shell tool that are reported as test runs
+const TEST_COMMANDS: [&str; 10] = [
+    "cargo test",
+    "cargo nextest",
+    "npm test",
+    "yarn test",
+    "pnpm test",
+    "go test",
+    "make test",
+    "pytest",
+    "jest",
+    "vitest",
+];
+
+/// Time spent by an agent handling the run before another agent took over
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Phase {
+    pub agent: String,
+    pub duration_ms: u128,
+}
+
+/// A file that changed during the run, with the diffs produced by the tools
+/// that changed it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileReport {
+    pub path: String,
+    pub kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+/// A test command executed by the agents
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestReport {
+    pub command: String,
+    pub passed: bool,
+}
+
+/// Consolidated report of a headless workflow run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub task: String,
+    pub started_at: DateTime<Local>,
+    pub duration_ms: u128,
+    pub agents: Vec<String>,
+    pub phases: Vec<Phase>,
+    pub files: Vec<FileReport>,
+    pub tests: Vec<TestReport>,
+    /// Tokens reported by the provider across all requests
+    pub usage: Usage,
+}
+
+/// Records the messages emitted during a run to build its report
+pub struct RunRecorder {
+    task: String,
+    started_at: DateTime<Local>,
+    start: Instant,
+    phases: Vec<(String, Instant)>,
+    pending: Vec<ToolCallFull>,
+    diffs: BTreeMap<PathBuf, Vec<String>>,
+    tests: Vec<TestReport>,
+    usage: Usage,
+}
+
+impl RunRecorder {
+    pub fn new(task: impl ToString) -> Self {
+        Self {
+            task: task.to_string(),
+            started_at: Local::now(),
+            start: Instant::now(),
+            phases: Vec::new(),
+            pending: Vec::new(),
+            diffs: BTreeMap::new(),
+            tests: Vec::new(),
+            usage: Usage::default(),
+        }
+    }
+
+    pub fn record(&mut self, message: &AgentMessage<ChatResponse>) {
+        let agent = message.agent.as_str();
+        if self.phases.last().is_none_or(|(last, _)| last != agent) {
+            self.phases.push((agent.to_string(), Instant::now()));
+        }
+
+        match &message.message {
+            ChatResponse::ToolCallStart(call) => self.pending.push(call.clone()),
+            ChatResponse::ToolCallEnd(result) => {
+                let position = self
+                    .pending
+                    .iter()
+                    .position(|call| call.call_id == result.call_id && call.name == result.name);
+                if let Some(call) = position.map(|position| self.pending.remove(position)) {
+                    self.record_tool_call(&call, result);
+                }
+            }
+            ChatResponse::Usage(usage) => {
+                self.usage.prompt_tokens += usage.prompt_tokens;
+                self.usage.completion_tokens += usage.completion_tokens;
+                self.usage.total_tokens += usage.total_tokens;
+            }
+            _ => {}
+        }
+    }
+
+    fn record_tool_call(&mut self, call: &ToolCallFull, result: &ToolResult) {
+        let argument = |name: &str| call.arguments.get(name).and_then(|value| value.as_str());
+
+        match call.name.as_str() {
+            "forge_tool_fs_create" | "forge_tool_fs_patch" if !result.is_error => {
+                if let (Some(path), Some(diff)) = (argument("path"), diff(&result.content)) {
+                    self.diffs
+                        .entry(PathBuf::from(path))
+                        .or_default()
+                        .push(diff.to_string());
+                }
+            }
+            "forge_tool_process_shell" => {
+                if let Some(command) = argument("command").filter(|command| is_test(command)) {
+                    self.tests.push(TestReport {
+                        command: command.to_string(),
+                        passed: !result.is_error,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Builds the report for the files that changed in the workspace
+    pub fn finish(self, changes: &[Change], cwd: &Path) -> RunReport {
+        let end = Instant::now();
+        let phases = self
+            .phases
+            .iter()
+            .enumerate()
+            .map(|(index, (agent, start))| {
+                let until = self.phases.get(index + 1).map_or(end, |(_, next)| *next);
+                Phase {
+                    agent: agent.clone(),
+                    duration_ms: until.duration_since(*start).as_millis(),
+                }
+            })
+            .collect();
+
+        let mut agents = Vec::new();
+        for (agent, _) in &self.phases {
+            if !agents.contains(agent) {
+                agents.push(agent.clone());
+            }
+        }
+
+        let files = changes
+            .iter()
+            .map(|change| FileReport {
+                path: change.path.clone(),
+                kind: change.kind,
+                diff: self
+                    .diffs
+                    .get(&cwd.join(&change.path))
+                    .map(|diffs| diffs.join("\n")),
+            })
+            .collect();
+
+        RunReport {
+            task: self.task,
+            started_at: self.started_at,
+            duration_ms: end.duration_since(self.start).as_millis(),
+            agents,
+            phases,
+            files,
+            tests: self.tests,
+            usage: self.usage,
+        }
+    }
+}
+
+/// Extracts the diff from the output of the fs tools, which follows the
+/// metadata block
+fn diff(content: &str) -> Option<&str> {
+    let (_, rest) = content.strip_prefix("---\n")?.split_once("\n---\n")?;
+    let rest = rest.trim_end();
+    (!rest.is_empty()).then_some(rest)
+}
+
+fn is_test(command: &str) -> bool {
+    TEST_COMMANDS.iter().any(|test| command.contains(test))
+}
+
+fn format_duration(ms: u128) -> String {
+    format!("{:.1}s", Duration::from_millis(ms as u64).as_secs_f64())
+}
+
+impl RunReport {
+    pub fn to_markdown(&self) -> String {
+        let mut output = String::new();
+        let _ = self.write_markdown(&mut output);
+        output
+    }
+
+    fn write_markdown(&self, output: &mut String) -> std::fmt::Result {
+        writeln!(output, "# Run Report\n")?;
+        writeln!(output, "- **Task:** {}", self.task)?;
+        writeln!(
+            output,
+            "- **Started:** {}",
+            self.started_at.format("%Y-%m-%d %H:%M:%S")
+        )?;
+        writeln!(
+            output,
+            "- **Duration:** {}",
+            format_duration(self.duration_ms)
+        )?;
+        writeln!(output, "- **Agents:** {}", self.agents.join(", "))?;
+        writeln!(
+            output,
+            "- **Tokens:** {} prompt, {} completion, {} total",
+            self.usage.prompt_tokens, self.usage.completion_tokens, self.usage.total_tokens
+        )?;
+
+        if !self.phases.is_empty() {
+            writeln!(output, "\n## Phases\n")?;
+            writeln!(output, "| Agent | Duration |")?;
+            writeln!(output, "| --- | --- |")?;
+            for phase in &self.phases {
+                writeln!(
+                    output,
+                    "| {} | {} |",
+                    phase.agent,
+                    format_duration(phase.duration_ms)
+                )?;
+            }
+        }
+
+        writeln!(output, "\n## Files Changed\n")?;
+        if self.files.is_empty() {
+            writeln!(output, "No files changed.")?;
+        }
+        for file in &self.files {
+            writeln!(output, "- `{}` ({})", file.path, file.kind)?;
+        }
+        for file in self.files.iter().filter(|file| file.diff.is_some()) {
+            writeln!(output, "\n### `{}`\n", file.path)?;
+            writeln!(
+                output,
+                "```\n{}\n```",
+                file.diff.as_deref().unwrap_or_default()
+            )?;
+        }
+
+        writeln!(output, "\n## Tests\n")?;
+        if self.tests.is_empty() {
+            writeln!(output, "No tests were run.")?;
+        }
+        for test in &self.tests {
+            let result = if test.passed { "passed" } else { "failed" };
+            writeln!(output, "- `{}`: {result}", test.command)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the report as markdown and JSON to the reports directory and
+    /// returns the path of the markdown report
+    pub async fn save(&self, cwd: &Path) -> Result<PathBuf> {
+        let dir = cwd.join(REPORTS_DIR);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let name = self.started_at.format("%Y-%m-%d_%H-%M-%S").to_string();
+        let path = dir.join(format!("{name}.md"));
+        tokio::fs::write(
+            dir.join(format!("{name}.json")),
+            serde_json::to_string_pretty(self)?,
+        )
+        .await?;
+        tokio::fs::write(&path, self.to_markdown()).await?;
+
+        Ok(path)
+    }
+
+    /// Returns the path of the most recent markdown report
+    pub async fn last(cwd: &Path) -> Result<Option<PathBuf>> {
+        let dir = cwd.join(REPORTS_DIR);
+        if !dir.exists() {
+            return Ok(None);
+        }
+
+        let mut last = None;
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "md")
+                && last.as_ref().is_none_or(|last| path > *last)
+            {
+                last = Some(path);
+            }
+        }
+
+        Ok(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use forge_api::{AgentId, ToolCallId, ToolName};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn message(agent: &str, message: ChatResponse) -> AgentMessage<ChatResponse> {
+        AgentMessage::new(AgentId::new(agent), message)
+    }
+
+    fn tool_call(id: &str, name: &str, arguments: serde_json::Value) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new(name))
+            .call_id(ToolCallId::new(id))
+            .arguments(arguments)
+    }
+
+    fn fixture_report() -> RunReport {
+        let mut recorder = RunRecorder::new("Fix the failing test");
+        let patch = tool_call(
+            "1",
+            "forge_tool_fs_patch",
+            json!({"path": "/project/src/lib.rs"}),
+        );
+        let test = tool_call(
+            "2",
+            "forge_tool_process_shell",
+            json!({"command": "cargo test --workspace"}),
+        );
+
+        for fixture in
+            [
+                message(
+                    "software-engineer",
+                    ChatResponse::ToolCallStart(patch.clone()),
+                ),
+                message(
+                    "software-engineer",
+                    ChatResponse::ToolCallEnd(ToolResult::from(&patch).success(
+                        "---\npath: /project/src/lib.rs\n---\n1    1   |-a\n     1   |+b\n",
+                    )),
+                ),
+                message(
+                    "software-engineer",
+                    ChatResponse::ToolCallStart(test.clone()),
+                ),
+                message(
+                    "software-engineer",
+                    ChatResponse::ToolCallEnd(ToolResult::from(&test).success("ok")),
+                ),
+                message(
+                    "software-engineer",
+                    ChatResponse::Usage(Usage {
+                        prompt_tokens: 100,
+                        completion_tokens: 20,
+                        total_tokens: 120,
+                        estimated_tokens: None,
+                    }),
+                ),
+                message(
+                    "reviewer",
+                    ChatResponse::Usage(Usage {
+                        prompt_tokens: 50,
+                        completion_tokens: 10,
+                        total_tokens: 60,
+                        estimated_tokens: None,
+                    }),
+                ),
+            ]
+        {
+            recorder.record(&fixture);
+        }
+
+        let changes = vec![
+            Change { path: "notes.txt".to_string(), kind: ChangeKind::Added },
+            Change { path: "src/lib.rs".to_string(), kind: ChangeKind::Modified },
+        ];
+        let mut report = recorder.finish(&changes, Path::new("/project"));
+
+        // Timings vary between runs
+        report.started_at = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        report.duration_ms = 1500;
+        for phase in report.phases.iter_mut() {
+            phase.duration_ms = 750;
+        }
+        report
+    }
+
+    #[test]
+    fn test_run_report() {
+        let actual = fixture_report();
+
+        assert_eq!(actual.agents, vec!["software-engineer", "reviewer"]);
+        assert_eq!(
+            actual.tests,
+            vec![TestReport { command: "cargo test --workspace".to_string(), passed: true }]
+        );
+        assert_eq!(actual.usage.total_tokens, 180);
+        assert_eq!(actual.files[0].diff, None);
+        assert_eq!(
+            actual.files[1].diff.as_deref(),
+            Some("1    1   |-a\n     1   |+b")
+        );
+    }
+
+    #[test]
+    fn test_run_report_markdown() {
+        let actual = fixture_report().to_markdown();
+        insta::assert_snapshot!(actual);
+    }
+
+    #[tokio::test]
+    async fn test_last_report() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(RunReport::last(dir.path()).await.unwrap(), None);
+
+        let fixture = fixture_report();
+        let first = fixture.save(dir.path()).await.unwrap();
+        let mut fixture = fixture;
+        fixture.started_at = Local.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let second = fixture.save(dir.path()).await.unwrap();
+
+        let actual = RunReport::last(dir.path()).await.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(actual, Some(second.clone()));
+        assert!(second.with_extension("json").exists());
+    }
+}
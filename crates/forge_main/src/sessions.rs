@@ -0,0 +1,163 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use forge_api::{Conversation, ModelId};
+use serde::{Deserialize, Serialize};
+
+use crate::ui::EVENT_USER_TASK_INIT;
+
+/// Number of characters of the first task kept as the title of a session
+const TITLE_LENGTH: usize = 60;
+
+/// A conversation saved after every turn so that it can be continued in a
+/// later session with --resume or /sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// The first task of the conversation
+    pub title: String,
+    /// Workspace the conversation took place in
+    pub cwd: PathBuf,
+    pub model: Option<ModelId>,
+    pub updated_at: DateTime<Utc>,
+    /// Tokens used by the last request of the conversation
+    pub tokens: u64,
+    pub conversation: Conversation,
+}
+
+impl Session {
+    pub fn new(conversation: Conversation, cwd: PathBuf, tokens: u64) -> Self {
+        let title = conversation
+            .events
+            .iter()
+            .filter(|event| event.name.ends_with(EVENT_USER_TASK_INIT))
+            .find_map(|event| event.value.as_str())
+            .and_then(|task| task.lines().find(|line| !line.trim().is_empty()))
+            .map(|line| truncate(line.trim()))
+            .unwrap_or_else(|| "Untitled conversation".to_string());
+
+        Self {
+            title,
+            cwd,
+            model: conversation.main_model().ok(),
+            updated_at: Utc::now(),
+            tokens,
+            conversation,
+        }
+    }
+
+    fn path(dir: &Path, conversation: &Conversation) -> PathBuf {
+        dir.join(format!("{}.json", conversation.id))
+    }
+
+    pub async fn save(&self, dir: &Path) -> Result<()> {
+        tokio::fs::create_dir_all(dir).await?;
+        let path = Self::path(dir, &self.conversation);
+        tokio::fs::write(path, serde_json::to_string(self)?).await?;
+        Ok(())
+    }
+
+    /// Returns the sessions of the workspace, most recent first. Files that
+    /// can't be parsed, e.g. written by an older version, are skipped.
+    pub async fn list(dir: &Path, cwd: &Path) -> Result<Vec<Self>> {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => {
+                return Err(error).with_context(|| format!("Failed to read {}", dir.display()))
+            }
+        };
+
+        let mut sessions = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(content) = tokio::fs::read_to_string(entry.path()).await else {
+                continue;
+            };
+            if let Ok(session) = serde_json::from_str::<Self>(&content) {
+                if session.cwd == cwd {
+                    sessions.push(session);
+                }
+            }
+        }
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+        Ok(sessions)
+    }
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= TITLE_LENGTH {
+        return text.to_string();
+    }
+    let truncated = text.chars().take(TITLE_LENGTH - 3).collect::<String>();
+    format!("{}...", truncated.trim_end())
+}
+
+impl Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let date = self
+            .updated_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M");
+        write!(f, "{} · {date}", self.title)?;
+        if let Some(model) = &self.model {
+            write!(f, " · {model}")?;
+        }
+        write!(f, " · {} tokens", self.tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{ConversationId, Event, Workflow};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn conversation(task: &str) -> Conversation {
+        let mut conversation = Conversation::new(ConversationId::generate(), Workflow::new());
+        conversation.insert_event(Event::new("act/user_task_init", task));
+        conversation
+    }
+
+    #[test]
+    fn test_title() {
+        let fixture = Session::new(
+            conversation("\nFix the login form\nIt crashes"),
+            "/p".into(),
+            0,
+        );
+        assert_eq!(fixture.title, "Fix the login form");
+
+        let fixture = Session::new(conversation(&"a".repeat(100)), "/p".into(), 0);
+        assert_eq!(fixture.title, format!("{}...", "a".repeat(57)));
+
+        let fixture = Session::new(
+            Conversation::new(ConversationId::generate(), Workflow::new()),
+            "/p".into(),
+            0,
+        );
+        assert_eq!(fixture.title, "Untitled conversation");
+    }
+
+    #[tokio::test]
+    async fn test_save_and_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = Session::new(conversation("First task"), "/project".into(), 100);
+        let other = Session::new(conversation("Other project"), "/other".into(), 100);
+        let mut second = Session::new(conversation("Second task"), "/project".into(), 200);
+        second.updated_at = first.updated_at + chrono::Duration::seconds(1);
+        for session in [&first, &other, &second] {
+            session.save(dir.path()).await.unwrap();
+        }
+
+        let actual = Session::list(dir.path(), Path::new("/project"))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|session| session.title)
+            .collect::<Vec<_>>();
+        let expected = vec!["Second task".to_string(), "First task".to_string()];
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,25 +1,37 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
 use derive_setters::Setters;
-use forge_api::{ConversationId, Model, ModelId, Provider, Usage};
-use serde::Deserialize;
+use forge_api::{AgentId, ConversationId, Mode, Model, ModelId, Provider, ToolName, Usage};
 
 use crate::prompt::ForgePrompt;
 
-// TODO: convert to a new type
-#[derive(Debug, Clone, Default, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum Mode {
-    Plan,
-    #[default]
-    Act,
+/// File, relative to the working directory, where the mode used last in the
+/// project is kept
+const LAST_MODE_FILE: &str = ".forge/mode";
+
+fn last_mode_path(cwd: &Path) -> PathBuf {
+    cwd.join(LAST_MODE_FILE)
 }
 
-impl std::fmt::Display for Mode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Mode::Plan => write!(f, "PLAN"),
-            Mode::Act => write!(f, "ACT"),
-        }
+/// Reads the mode used last in the project, if any
+pub async fn load_last_mode(cwd: &Path) -> Option<Mode> {
+    tokio::fs::read_to_string(last_mode_path(cwd))
+        .await
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Remembers the mode so that the next session in the project starts in it
+pub async fn save_last_mode(cwd: &Path, mode: &Mode) -> Result<()> {
+    let path = last_mode_path(cwd);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
     }
+    tokio::fs::write(path, mode.as_str()).await?;
+    Ok(())
 }
 
 //TODO: UIState and ForgePrompt seem like the same thing and can be merged
@@ -30,10 +42,18 @@ pub struct UIState {
     pub conversation_id: Option<ConversationId>,
     pub usage: Usage,
     pub mode: Mode,
+    /// Agent chosen with `/agent` to receive the messages instead of the entry
+    /// agent of the mode
+    pub agent: Option<AgentId>,
     pub is_first: bool,
     pub model: Option<ModelId>,
     pub cached_models: Option<Vec<Model>>,
     pub provider: Option<Provider>,
+    /// Tools that the user chose to always approve in this conversation
+    pub approved_tools: HashSet<ToolName>,
+    /// Cost in USD of the requests of the conversation, when the provider
+    /// publishes the prices of the model
+    pub cost: Option<f64>,
 }
 
 impl UIState {
@@ -42,20 +62,54 @@ impl UIState {
             conversation_id: Default::default(),
             usage: Default::default(),
             mode,
+            agent: Default::default(),
             is_first: true,
             model: Default::default(),
             cached_models: Default::default(),
             provider: Default::default(),
+            approved_tools: Default::default(),
+            cost: Default::default(),
         }
     }
+
+    /// Returns the details of the model in use, once the models were loaded
+    pub fn current_model(&self) -> Option<&Model> {
+        let model = self.model.as_ref()?;
+        self.cached_models
+            .as_ref()?
+            .iter()
+            .find(|candidate| candidate.id == *model)
+    }
 }
 
 impl From<UIState> for ForgePrompt {
     fn from(state: UIState) -> Self {
+        let context_length = state.current_model().and_then(|model| model.context_length);
         ForgePrompt {
             usage: Some(state.usage),
             mode: state.mode,
             model: state.model,
+            context_length,
+            cost: state.cost,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_last_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load_last_mode(dir.path()).await, None);
+
+        save_last_mode(dir.path(), &Mode::plan()).await.unwrap();
+        assert_eq!(load_last_mode(dir.path()).await, Some(Mode::plan()));
+
+        save_last_mode(dir.path(), &Mode::act()).await.unwrap();
+        assert_eq!(load_last_mode(dir.path()).await, Some(Mode::act()));
+    }
+}
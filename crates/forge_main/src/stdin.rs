@@ -0,0 +1,184 @@
+use std::io::{IsTerminal, Read};
+
+use anyhow::{bail, Result};
+use tempfile::NamedTempFile;
+
+/// Maximum number of bytes accepted from piped stdin (1 MiB)
+pub const MAX_STDIN_BYTES: usize = 1024 * 1024;
+
+/// Content piped into forge through stdin, eg: `git diff | forge -p "review"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StdinInput {
+    content: String,
+}
+
+impl StdinInput {
+    /// Reads stdin when it is not attached to a terminal. Returns `None` when
+    /// stdin is interactive or nothing was piped.
+    pub fn read() -> Result<Option<Self>> {
+        let stdin = std::io::stdin();
+        if stdin.is_terminal() {
+            return Ok(None);
+        }
+
+        Self::read_from(stdin.lock())
+    }
+
+    /// Reads the input piped through the reader, up to the limit
+    fn read_from(reader: impl Read) -> Result<Option<Self>> {
+        let mut buffer = Vec::new();
+        reader
+            .take(MAX_STDIN_BYTES as u64 + 1)
+            .read_to_end(&mut buffer)?;
+
+        Self::parse(buffer, MAX_STDIN_BYTES)
+    }
+
+    /// Validates the raw bytes read from stdin, rejecting input that is too
+    /// large or does not look like text.
+    fn parse(buffer: Vec<u8>, limit: usize) -> Result<Option<Self>> {
+        if buffer.len() > limit {
+            bail!("Input piped through stdin exceeds the limit of {limit} bytes");
+        }
+
+        if buffer.contains(&0) {
+            bail!("Input piped through stdin appears to be binary and cannot be attached");
+        }
+
+        let content = match String::from_utf8(buffer) {
+            Ok(content) => content,
+            Err(_) => bail!("Input piped through stdin is not valid UTF-8 text"),
+        };
+
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self { content }))
+    }
+
+    /// Returns the content to use as the prompt when none is given, eg: `cat
+    /// task.md | forge`
+    pub fn into_prompt(self) -> String {
+        self.content.trim().to_string()
+    }
+
+    /// Writes the content into a temporary file so that it can be attached to
+    /// the prompt using the `@[path]` syntax. The file is removed once the
+    /// returned handle is dropped.
+    pub fn into_attachment(self) -> Result<NamedTempFile> {
+        let mut file = tempfile::Builder::new()
+            .prefix("forge-stdin-")
+            .suffix(".txt")
+            .tempfile()?;
+        std::io::Write::write_all(&mut file, self.content.as_bytes())?;
+        Ok(file)
+    }
+}
+
+/// Appends an attachment reference for the given file to the prompt
+pub fn attach(prompt: &str, file: &NamedTempFile) -> String {
+    format!("{prompt}\n\n@[{}]", file.path().display())
+}
+
+/// Returns the prompt with the piped input attached, or the input as the
+/// prompt when none is given, along with the attached file which must be kept
+/// until the prompt is processed
+pub fn with_input(
+    prompt: Option<String>,
+    input: Option<StdinInput>,
+) -> Result<(Option<String>, Option<NamedTempFile>)> {
+    Ok(match (prompt, input) {
+        (Some(prompt), Some(input)) => {
+            let file = input.into_attachment()?;
+            (Some(attach(&prompt, &file)), Some(file))
+        }
+        (prompt, input) => (prompt.or(input.map(StdinInput::into_prompt)), None),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_text() {
+        let fixture = b"diff --git a/foo b/foo\n".to_vec();
+        let actual = StdinInput::parse(fixture, 1024).unwrap();
+        let expected = Some(StdinInput { content: "diff --git a/foo b/foo\n".to_string() });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        let fixture = b"  \n".to_vec();
+        let actual = StdinInput::parse(fixture, 1024).unwrap();
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn test_parse_exceeds_limit() {
+        let fixture = vec![b'a'; 11];
+        let actual = StdinInput::parse(fixture, 10);
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let fixture = vec![b'a', 0, b'b'];
+        let actual = StdinInput::parse(fixture, 10);
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_utf8() {
+        let fixture = vec![0xff, 0xfe, b'a'];
+        let actual = StdinInput::parse(fixture, 10);
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_into_prompt() {
+        let fixture = StdinInput { content: "fix the failing test\n".to_string() };
+        let actual = fixture.into_prompt();
+        assert_eq!(actual, "fix the failing test");
+    }
+
+    #[test]
+    fn test_prompt_with_piped_input() {
+        // git diff | forge -p "review this diff"
+        let input = StdinInput::read_from(&b"diff --git a/foo b/foo\n"[..]).unwrap();
+
+        let (actual, file) = with_input(Some("review this diff".to_string()), input).unwrap();
+
+        let file = file.unwrap();
+        let expected = format!("review this diff\n\n@[{}]", file.path().display());
+        assert_eq!(actual, Some(expected));
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "diff --git a/foo b/foo\n"
+        );
+    }
+
+    #[test]
+    fn test_piped_input_as_prompt() {
+        let input = StdinInput::read_from(&b"fix the failing test\n"[..]).unwrap();
+
+        let (actual, file) = with_input(None, input).unwrap();
+
+        assert_eq!(actual, Some("fix the failing test".to_string()));
+        assert!(file.is_none());
+    }
+
+    #[test]
+    fn test_attach() {
+        let fixture = StdinInput { content: "hello".to_string() };
+        let file = fixture.into_attachment().unwrap();
+        let actual = attach("review this", &file);
+        let expected = format!("review this\n\n@[{}]", file.path().display());
+        assert_eq!(actual, expected);
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "hello");
+    }
+}
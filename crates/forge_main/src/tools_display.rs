@@ -1,18 +1,22 @@
 use colored::Colorize;
-use forge_api::ToolDefinition;
+use forge_api::{ToolDefinition, ToolName};
 use serde_json::to_string_pretty;
 
 /// Formats the list of tools for display in the shell UI, following these
 /// rules:
-/// - Name: blue bold
+/// - Name: blue bold, followed by "(disabled)" for the tools that the user
+///   disabled in the conversation
 /// - Description: default
 /// - Input json schema: dimmed, pretty-printed, multi-line
 /// - Blank line between each tool
-pub fn format_tools(tools: &[ToolDefinition]) -> String {
+pub fn format_tools(tools: &[ToolDefinition], disabled: &[ToolName]) -> String {
     let mut out = String::new();
 
     for (i, tool) in tools.iter().enumerate() {
-        let name = tool.name.as_str().blue().bold();
+        let mut name = tool.name.as_str().blue().bold().to_string();
+        if disabled.contains(&tool.name) {
+            name.push_str(&format!(" {}", "(disabled)".dimmed()));
+        }
         let description = &tool.description;
         let schema_json = to_string_pretty(&tool.input_schema).unwrap_or_else(|_| "{}".to_string());
         let schema = format!("{}", schema_json.dimmed());
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Whether the user trusts the workspaces they opened forge in. A decision
+/// made for a directory applies to its subdirectories unless they have their
+/// own.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkspaceTrust {
+    workspaces: BTreeMap<PathBuf, bool>,
+}
+
+impl WorkspaceTrust {
+    /// Reads the decisions from the file, if it exists
+    pub async fn load(path: &Path) -> Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Returns the decision for the closest directory containing the
+    /// workspace, if the user made one
+    pub fn is_trusted(&self, cwd: &Path) -> Option<bool> {
+        cwd.ancestors()
+            .find_map(|dir| self.workspaces.get(dir))
+            .copied()
+    }
+
+    pub fn set(&mut self, cwd: &Path, trusted: bool) {
+        self.workspaces.insert(cwd.to_path_buf(), trusted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_is_trusted() {
+        let mut fixture = WorkspaceTrust::default();
+        fixture.set(Path::new("/home/user/projects"), true);
+        fixture.set(Path::new("/home/user/projects/downloaded"), false);
+
+        assert_eq!(
+            fixture.is_trusted(Path::new("/home/user/projects")),
+            Some(true)
+        );
+        assert_eq!(
+            fixture.is_trusted(Path::new("/home/user/projects/forge/crates")),
+            Some(true)
+        );
+        assert_eq!(
+            fixture.is_trusted(Path::new("/home/user/projects/downloaded/repo")),
+            Some(false)
+        );
+        assert_eq!(fixture.is_trusted(Path::new("/tmp/repo")), None);
+    }
+
+    #[tokio::test]
+    async fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trusted_workspaces.json");
+        assert_eq!(
+            WorkspaceTrust::load(&path)
+                .await
+                .unwrap()
+                .is_trusted(dir.path()),
+            None
+        );
+
+        let mut fixture = WorkspaceTrust::default();
+        fixture.set(dir.path(), true);
+        fixture.save(&path).await.unwrap();
+
+        let actual = WorkspaceTrust::load(&path).await.unwrap();
+        assert_eq!(actual.is_trusted(dir.path()), Some(true));
+    }
+}
@@ -1,33 +1,69 @@
-use std::sync::Arc;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use forge_api::{
-    AgentMessage, ChatRequest, ChatResponse, Conversation, ConversationId, Event, Model, ModelId,
-    API,
+    AgentChanges, AgentId, AgentMessage, Approval, ChatRequest, ChatResponse, ContextBreakdown,
+    ContextMessage, Conversation, ConversationId, DiffConfig, DiffStyle, EditReview, EditorConfig,
+    Event, Mode, ModeConfig, Model, ModelId, NamedTool, OutputStream, Phase, SpinnerConfig,
+    TaskList, Theme, ToolCallFull, Workflow, API,
 };
-use forge_display::{MarkdownFormat, TitleFormat};
+use forge_display::{DiffFormat, MarkdownFormat, TitleFormat};
 use forge_fs::ForgeFS;
 use forge_spinner::SpinnerManager;
 use forge_tracker::ToolCallPayload;
 use inquire::error::InquireError;
 use inquire::ui::{RenderConfig, Styled};
-use inquire::Select;
+use inquire::{Confirm, Select};
 use serde::Deserialize;
 use serde_json::Value;
+use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
 
 use crate::auto_update::update_forge;
-use crate::cli::Cli;
+use crate::budget::{
+    Budget, BudgetExceeded, ToolFailures, EXIT_BUDGET_EXCEEDED, EXIT_FAILURE, EXIT_TOOL_FAILURE,
+};
+use crate::cancel::CancelKey;
+use crate::ci::{self, CiProvider, FailedRun};
+use crate::citation::Citations;
+use crate::cli::{
+    AgentsCommand, Cli, OutputFormat, ReportCommand, TopLevelCommand, WorkflowCommand,
+};
+use crate::conversation_html::{render_conversation_html, UsageSummary};
+use crate::debugger::{render_response, Recording};
+use crate::doctor::Diagnosis;
+use crate::editor::{editor_command, external_editor};
+use crate::graph::WorkflowGraph;
 use crate::info::Info;
+use crate::init::{self, ProjectKind, Scaffolded};
 use crate::input::Console;
+use crate::json_output::JsonEvent;
+use crate::lock::SessionLock;
+use crate::manifest::{Change, WorkspaceManifest};
+use crate::mcp::McpServer;
 use crate::model::{Command, ForgeCommandManager};
-use crate::state::{Mode, UIState};
+use crate::notify::notify;
+use crate::report::{RunRecorder, RunReport};
+use crate::sessions::Session;
+use crate::state::{self, UIState};
+use crate::stdin::{self, StdinInput};
+use crate::trust::WorkspaceTrust;
+use crate::undo::TurnChanges;
+use crate::watch::WorkflowWatch;
 use crate::{banner, TRACKER};
 
 // Event type constants moved to UI layer
 pub const EVENT_USER_TASK_INIT: &str = "user_task_init";
 pub const EVENT_USER_TASK_UPDATE: &str = "user_task_update";
 
+/// Number of times a change to the conversation is applied when it keeps
+/// being modified concurrently
+const MAX_CONFLICT_RETRIES: usize = 3;
+
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq, Default)]
 pub struct PartialEvent {
     pub name: String,
@@ -54,6 +90,46 @@ pub struct UI<F> {
     command: Arc<ForgeCommandManager>,
     cli: Cli,
     spinner: SpinnerManager,
+    /// Processes a prompt or an event given on the command line instead of
+    /// starting an interactive session
+    headless: bool,
+    /// Prints newline-delimited JSON events instead of the terminal UI
+    json: bool,
+    /// Last complete text of the model, reported as the answer at the end of a
+    /// run with JSON output
+    answer: Option<String>,
+    /// Limits of a headless run
+    budget: Budget,
+    /// Tool calls that failed, any of which fails a headless run
+    tool_failures: ToolFailures,
+    /// Cancels the turn of the agent when ESC is pressed
+    cancel: CancelKey,
+    /// Messages shown by the spinner for each phase of a turn
+    spinner_config: SpinnerConfig,
+    /// Key bindings of the prompt
+    editor_config: EditorConfig,
+    diff_config: DiffConfig,
+    /// Modes that the user can switch to, as defined by the workflow
+    modes: Vec<ModeConfig>,
+    /// Manifest of the workspace recorded when the session started
+    manifest: Option<WorkspaceManifest>,
+    /// Task recording the manifest in the background
+    manifest_task: Option<JoinHandle<Result<WorkspaceManifest>>>,
+    /// Records a headless run for its report
+    recorder: Option<RunRecorder>,
+    /// Sources of the answer that is being streamed
+    citations: Citations,
+    /// Files changed by the tools during the last turn
+    turn_changes: TurnChanges,
+    /// When the last turn started, to notify the user about the end of long
+    /// turns
+    turn_start: Instant,
+    /// Past conversation picked to be continued
+    resumed: Option<Conversation>,
+    /// Lock of the workspace held while the session runs
+    lock: Option<SessionLock>,
+    /// Detects the edits of the workflow file to reload it during the session
+    workflow_watch: Option<WorkflowWatch>,
     #[allow(dead_code)] // The guard is kept alive by being held in the struct
     _guard: forge_tracker::Guard,
 }
@@ -62,6 +138,11 @@ impl<F: API> UI<F> {
     /// Writes a line to the console output
     /// Takes anything that implements ToString trait
     fn writeln<T: ToString>(&mut self, content: T) -> anyhow::Result<()> {
+        // Stdout is reserved for the events when the output is JSON
+        if self.json {
+            eprintln!("{}", content.to_string());
+            return Ok(());
+        }
         self.spinner.write_ln(content)
     }
     /// Retrieve available models, using cache if present
@@ -88,18 +169,27 @@ impl<F: API> UI<F> {
 
     // Set the current mode and update conversation variable
     async fn on_mode_change(&mut self, mode: Mode) -> Result<()> {
+        // The modes are loaded along with the workflow, the conversation is only
+        // reset once the mode is known to exist
+        self.init_conversation().await?;
+        let mode = self.find_mode(&mode)?;
+
+        // The plan is carried over so that Act mode can carry it out
+        let plan = self.current_conversation().await?.and_then(|c| c.plan);
         self.on_new().await?;
         // Set the mode variable in the conversation if a conversation exists
         let conversation_id = self.init_conversation().await?;
 
         // Override the mode that was reset by the conversation
-        self.state.mode = mode.clone();
+        self.state.mode = mode.name.clone();
+        state::save_last_mode(&self.api.environment().cwd, &mode.name).await?;
 
-        // Retrieve the conversation, update it, and save it back
-        if let Some(mut conversation) = self.api.conversation(&conversation_id).await? {
-            conversation.set_variable("mode".to_string(), Value::from(mode.to_string()));
-            self.api.upsert_conversation(conversation).await?;
-        }
+        self.update_conversation(&conversation_id, |conversation| {
+            conversation.set_mode(&mode);
+            conversation.plan = plan.clone();
+            Ok(())
+        })
+        .await?;
 
         self.writeln(TitleFormat::action(format!(
             "Switched to '{}' mode (context cleared)",
@@ -108,73 +198,289 @@ impl<F: API> UI<F> {
 
         Ok(())
     }
+
+    /// Switches to the mode with the given name, or to one picked from the
+    /// modes of the workflow
+    async fn on_mode_selection(&mut self, name: Option<String>) -> Result<()> {
+        let mode = match name {
+            Some(name) => name.parse::<Mode>()?,
+            None => {
+                // The modes are loaded along with the workflow
+                self.init_conversation().await?;
+                let options = self
+                    .modes
+                    .iter()
+                    .map(|mode| match &mode.description {
+                        Some(description) => format!("{} - {description}", mode.name),
+                        None => mode.name.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                let starting_cursor = self
+                    .modes
+                    .iter()
+                    .position(|mode| mode.name == self.state.mode)
+                    .unwrap_or(0);
+
+                match Select::new("Switch to mode:", options)
+                    .with_starting_cursor(starting_cursor)
+                    .raw_prompt()
+                {
+                    Ok(option) => self.modes[option.index].name.clone(),
+                    Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+                        return Ok(())
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
+
+        self.on_mode_change(mode).await
+    }
+
+    /// Returns the definition of the mode in the workflow
+    fn find_mode(&self, mode: &Mode) -> Result<ModeConfig> {
+        self.modes
+            .iter()
+            .find(|config| config.name == *mode)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!("Mode '{}' is not defined in the workflow", mode.as_str())
+            })
+    }
+
+    /// Directs the next messages to the given agent, or to one picked from the
+    /// agents of the workflow. Picking the mode goes back to its entry agent.
+    async fn on_agent(&mut self, agent: Option<String>) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        let agents = self
+            .api
+            .conversation(&conversation_id)
+            .await?
+            .map(|conversation| conversation.agents)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|agent| !agent.disable.unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        let agent = match agent {
+            Some(id) => match agents.iter().find(|agent| agent.id.as_str() == id) {
+                Some(agent) => Some(agent.id.clone()),
+                None => {
+                    self.writeln(TitleFormat::error(format!(
+                        "Agent '{id}' is not defined in the workflow"
+                    )))?;
+                    return Ok(());
+                }
+            },
+            None => {
+                let mut options = vec![format!("{} mode (entry agent)", self.state.mode)];
+                options.extend(agents.iter().map(|agent| match &agent.description {
+                    Some(description) => format!("{} - {description}", agent.id),
+                    None => agent.id.to_string(),
+                }));
+                let starting_cursor = self
+                    .state
+                    .agent
+                    .as_ref()
+                    .and_then(|current| agents.iter().position(|agent| agent.id == *current))
+                    .map_or(0, |index| index + 1);
+
+                match Select::new("Direct the next messages to:", options)
+                    .with_starting_cursor(starting_cursor)
+                    .raw_prompt()
+                {
+                    Ok(option) => option
+                        .index
+                        .checked_sub(1)
+                        .map(|index| agents[index].id.clone()),
+                    Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+                        return Ok(())
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
+
+        match &agent {
+            Some(agent) => self.writeln(TitleFormat::action("Agent").sub_title(agent.as_str()))?,
+            None => self.writeln(TitleFormat::action(format!(
+                "Messages go to the '{}' mode",
+                self.state.mode
+            )))?,
+        }
+        self.state.agent = agent;
+
+        Ok(())
+    }
+
+    /// Prefix of the events of the user's messages: the agent chosen with
+    /// `/agent`, or else the one of the mode
+    fn event_prefix(&self) -> String {
+        match &self.state.agent {
+            Some(agent) => agent.to_string(),
+            None => match self.find_mode(&self.state.mode) {
+                Ok(mode) => mode.event_prefix().to_string(),
+                Err(_) => self.state.mode.as_str().to_string(),
+            },
+        }
+    }
+
     // Helper functions for creating events with the specific event names
     fn create_task_init_event<V: Into<Value>>(&self, content: V) -> Event {
         Event::new(
-            format!(
-                "{}/{}",
-                self.state.mode.to_string().to_lowercase(),
-                EVENT_USER_TASK_INIT
-            ),
+            format!("{}/{}", self.event_prefix(), EVENT_USER_TASK_INIT),
             content,
         )
     }
 
     fn create_task_update_event<V: Into<Value>>(&self, content: V) -> Event {
         Event::new(
-            format!(
-                "{}/{}",
-                self.state.mode.to_string().to_lowercase(),
-                EVENT_USER_TASK_UPDATE
-            ),
+            format!("{}/{}", self.event_prefix(), EVENT_USER_TASK_UPDATE),
             content,
         )
     }
 
     pub fn init(cli: Cli, api: Arc<F>) -> Result<Self> {
+        // Colors are disabled by NO_COLOR until the workflow sets the theme
+        apply_theme(Theme::default());
         // Parse CLI arguments first to get flags
         let env = api.environment();
         let command = Arc::new(ForgeCommandManager::default());
+        // A prompt or an event given on the command line runs without the
+        // interactive session
+        let headless = cli.prompt().is_some() || cli.event.is_some();
+        // The JSON output replaces the terminal UI of a headless run
+        let json = headless && cli.output == OutputFormat::Json;
+        let budget = match headless {
+            true => Budget::new(cli.max_turns, cli.timeout.map(Duration::from_secs)),
+            false => Budget::default(),
+        };
+        let mut spinner = SpinnerManager::new();
+        spinner.set_enabled(!json)?;
         Ok(Self {
             state: Default::default(),
             api,
             console: Console::new(env.clone(), command.clone()),
             cli,
             command,
-            spinner: SpinnerManager::new(),
+            spinner,
+            headless,
+            json,
+            answer: None,
+            budget,
+            tool_failures: ToolFailures::default(),
+            cancel: CancelKey::default(),
+            spinner_config: SpinnerConfig::default(),
+            editor_config: EditorConfig::default(),
+            diff_config: DiffConfig::default(),
+            modes: Vec::new(),
+            manifest: None,
+            manifest_task: None,
+            recorder: None,
+            citations: Citations::default(),
+            turn_changes: TurnChanges::default(),
+            turn_start: Instant::now(),
+            resumed: None,
+            lock: None,
+            workflow_watch: None,
             markdown: MarkdownFormat::new(),
             _guard: forge_tracker::init_tracing(env.log_path())?,
         })
     }
 
-    async fn prompt(&self) -> Result<Command> {
-        // Prompt the user for input
-        self.console.prompt(Some(self.state.clone().into())).await
+    async fn prompt(&mut self) -> Result<Command> {
+        // Prompt the user for input, starting with what they typed while the agent
+        // was working
+        let typed = self.cancel.take_typed();
+        self.console
+            .prompt(
+                Some(self.state.clone().into()),
+                self.editor_config.mode,
+                typed,
+            )
+            .await
     }
 
-    pub async fn run(&mut self) {
-        match self.run_inner().await {
-            Ok(_) => {}
-            Err(error) => {
-                self.writeln(TitleFormat::error(format!("{error:?}")))
-                    .unwrap();
+    /// Runs the session and returns the exit code of forge
+    pub async fn run(&mut self) -> ExitCode {
+        if self.api.environment().low_bandwidth {
+            TRACKER.enable_batching().await;
+        }
+        let result = self.run_inner().await;
+        if let Err(ref error) = result {
+            if let Err(error) = self.writeln(TitleFormat::error(format!("{error:?}"))) {
+                tracing::error!(error = ?error, "Failed to print the error");
+            }
+        }
+        let exit_code = match &result {
+            Err(error) if error.downcast_ref::<BudgetExceeded>().is_some() => EXIT_BUDGET_EXCEEDED,
+            Err(_) => EXIT_FAILURE,
+            Ok(_) if self.headless && self.tool_failures.any() => {
+                tracing::warn!(tools = ?self.tool_failures.tools(), "Tool calls failed");
+                EXIT_TOOL_FAILURE
+            }
+            Ok(_) => 0,
+        };
+        if self.json {
+            let event = JsonEvent::Result {
+                exit_code,
+                text: self.answer.take(),
+                usage: self.state.usage.clone(),
+                cost: self.state.cost,
+                error: result.err().map(|error| error.to_string()),
+            };
+            if let Err(error) = event.print() {
+                tracing::error!(error = ?error, "Failed to print the result");
             }
         }
+        let _ = TRACKER.flush().await;
+        ExitCode::from(exit_code)
     }
 
     async fn run_inner(&mut self) -> Result<()> {
+        match self.cli.subcommands.clone() {
+            Some(subcommand) => self.handle_subcommands(subcommand).await,
+            None => self.run_session().await,
+        }
+    }
+
+    /// Processes the prompt or the event given on the command line, or starts
+    /// an interactive session when there is none
+    async fn run_session(&mut self) -> Result<()> {
+        self.lock_workspace().await?;
+
+        if self.cli.resume {
+            self.on_sessions().await?;
+        }
+
         // Check for dispatch flag first
         if let Some(dispatch_json) = self.cli.event.clone() {
-            return self.handle_dispatch(dispatch_json).await;
+            let manifest = self.start_report(&dispatch_json).await?;
+            let result = self.handle_dispatch(dispatch_json).await;
+            self.finish_report(manifest).await?;
+            return result;
         }
 
-        // Handle direct prompt if provided
-        let prompt = self.cli.prompt.clone();
+        // Handle direct prompt if provided. Content piped through stdin is attached to
+        // it, eg: `git diff | forge -p "review"`, or is the prompt when none is given,
+        // eg: `cat task.md | forge`
+        let input = match self.cli.reads_stdin(std::io::stdin().is_terminal()) {
+            true => StdinInput::read()?,
+            false => None,
+        };
+        let (prompt, _attachment) = stdin::with_input(self.cli.prompt(), input)?;
         if let Some(prompt) = prompt {
-            self.on_message(prompt).await?;
-            return Ok(());
+            let manifest = self.start_report(&prompt).await?;
+            let result = self.on_message(prompt).await;
+            self.finish_report(manifest).await?;
+            return result;
         }
 
+        // Record the state of the workspace to report the changes made during the
+        // session
+        let cwd = self.api.environment().cwd;
+        self.manifest_task = Some(tokio::spawn(WorkspaceManifest::capture(cwd)));
+
         // Display the banner in dimmed colors since we're in interactive mode
         banner::display()?;
         self.init_conversation().await?;
@@ -186,8 +492,10 @@ impl<F: API> UI<F> {
         };
 
         loop {
+            // The workflow may have been edited while the prompt was waiting
+            self.check_workflow().await?;
             tokio::select! {
-                _ = tokio::signal::ctrl_c() => {}
+                _ = tokio::signal::ctrl_c() => self.on_cancel().await?,
                 result = self.on_command(command) => {
                     match result {
                         Ok(exit) => if exit {return Ok(())},
@@ -203,11 +511,222 @@ impl<F: API> UI<F> {
 
             self.spinner.stop(None)?;
 
-            // Centralized prompt call at the end of the loop
+            // Centralized prompt call at the end of the loop, with the commands of
+            // the workflow as it is now
+            self.check_workflow().await?;
             command = self.prompt().await?;
         }
     }
 
+    /// Prevents other sessions from editing the workspace while this one runs
+    async fn lock_workspace(&mut self) -> Result<()> {
+        let cwd = self.api.environment().cwd;
+        self.lock = Some(SessionLock::acquire(&cwd, self.cli.force).await?);
+        Ok(())
+    }
+
+    async fn handle_subcommands(&mut self, subcommand: TopLevelCommand) -> Result<()> {
+        match subcommand {
+            TopLevelCommand::Run { .. } => return self.run_session().await,
+            TopLevelCommand::Agents(AgentsCommand::List { remote }) => {
+                if remote {
+                    self.spinner.start(Some("Fetching registry"))?;
+                    let packages = self.api.remote_agents().await?;
+                    self.spinner.stop(None)?;
+                    if packages.is_empty() {
+                        self.writeln(TitleFormat::info("No agents published in the registry"))?;
+                    } else {
+                        self.writeln(Info::from(packages.as_slice()))?;
+                    }
+                } else {
+                    let workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
+                    self.writeln(Info::from(&workflow))?;
+                }
+            }
+            TopLevelCommand::Ci { run } => return self.on_ci(run).await,
+            TopLevelCommand::McpServe => {
+                let mut workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
+                if !self.is_workspace_trusted().await? {
+                    workflow.restrict_untrusted();
+                }
+                let server = McpServer::new(self.api.clone(), workflow, self.cli.read_only);
+                return server.serve().await;
+            }
+            TopLevelCommand::Init => return self.on_init().await,
+            TopLevelCommand::Doctor => {
+                self.spinner.start(Some("Checking the environment"))?;
+                let diagnosis = Diagnosis::run(self.api.as_ref()).await;
+                self.spinner.stop(None)?;
+                self.writeln(&diagnosis)?;
+                match diagnosis.errors() {
+                    0 => {}
+                    1 => bail!("1 check failed"),
+                    errors => bail!("{errors} checks failed"),
+                }
+            }
+            TopLevelCommand::Debug { dump, agent } => {
+                return self.on_debug(&dump, agent.as_deref()).await
+            }
+            TopLevelCommand::Workflow(WorkflowCommand::Graph { format }) => {
+                let workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
+                self.writeln(WorkflowGraph::new(&workflow).render(format))?;
+            }
+            TopLevelCommand::Workflow(WorkflowCommand::Schema) => {
+                self.writeln(serde_json::to_string_pretty(&Workflow::schema())?)?;
+            }
+            TopLevelCommand::Report(ReportCommand::Last) => {
+                let cwd = self.api.environment().cwd;
+                match RunReport::last(&cwd).await? {
+                    Some(path) => {
+                        let content = tokio::fs::read_to_string(&path).await?;
+                        self.writeln(self.markdown.render(&content))?;
+                    }
+                    None => self.writeln(TitleFormat::info("No run reports found"))?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scaffolds the workflow and the .forgeignore of the current project
+    async fn on_init(&mut self) -> Result<()> {
+        let cwd = self.api.environment().cwd;
+        let kinds = ProjectKind::detect(&cwd);
+        for scaffolded in init::scaffold(&cwd, &kinds, self.cli.force).await? {
+            match scaffolded {
+                Scaffolded::Created(path) => self.writeln(
+                    TitleFormat::action("Created").sub_title(path.display().to_string()),
+                )?,
+                Scaffolded::Skipped(path) => self.writeln(
+                    TitleFormat::info("Skipped, it already exists, use --force to overwrite")
+                        .sub_title(path.display().to_string()),
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Steps through the turns of a dumped conversation
+    async fn on_debug(&mut self, dump: &Path, agent: Option<&str>) -> Result<()> {
+        let content = tokio::fs::read_to_string(dump)
+            .await
+            .with_context(|| format!("Failed to read {}", dump.display()))?;
+        let recording = Recording::from_dump(&content, agent)?;
+        if recording.is_empty() {
+            bail!("The conversation has no turns to debug");
+        }
+
+        let mut index = 0;
+        let mut show_turn = true;
+        loop {
+            if show_turn {
+                self.writeln(TitleFormat::action(format!(
+                    "Turn {}/{}",
+                    index + 1,
+                    recording.len()
+                )))?;
+                self.writeln(recording.render(index))?;
+            }
+
+            let options = vec![
+                "Next",
+                "Previous",
+                "Show context",
+                "Re-run with another model",
+                "Quit",
+            ];
+            let action = match Select::new("Debugger", options).prompt() {
+                Ok(action) => action,
+                Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => "Quit",
+                Err(err) => return Err(err.into()),
+            };
+            show_turn = false;
+            match action {
+                "Next" if index + 1 < recording.len() => {
+                    index += 1;
+                    show_turn = true;
+                }
+                "Previous" if index > 0 => {
+                    index -= 1;
+                    show_turn = true;
+                }
+                "Next" | "Previous" => self.writeln(TitleFormat::info("No more turns"))?,
+                "Show context" => {
+                    self.writeln(serde_json::to_string_pretty(&recording.request(index))?)?
+                }
+                "Re-run with another model" => {
+                    let Some(model) = self.select_model().await? else {
+                        continue;
+                    };
+                    self.spinner.start(Some("Waiting for the model"))?;
+                    let response = self.api.complete(&model, recording.request(index)).await;
+                    self.spinner.stop(None)?;
+                    self.writeln(render_response(&response?, &[]))?;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Starts a headless run to fix a failing CI run
+    async fn on_ci(&mut self, run: Option<u64>) -> Result<()> {
+        let cwd = self.api.environment().cwd;
+        match CiProvider::detect(&cwd) {
+            Some(CiProvider::GitHubActions) => {}
+            None => bail!("No supported CI configuration found, expected .github/workflows"),
+        }
+        self.lock_workspace().await?;
+
+        self.spinner.start(Some("Fetching CI logs"))?;
+        let run = match run {
+            Some(id) => FailedRun::find(&cwd, id).await?,
+            None => match FailedRun::latest(&cwd).await? {
+                Some(run) => run,
+                None => {
+                    self.spinner.stop(None)?;
+                    self.writeln(TitleFormat::info("No failing CI runs found"))?;
+                    return Ok(());
+                }
+            },
+        };
+        let log = ci::into_attachment(&ci::trim_log(&run.log(&cwd).await?))?;
+        self.spinner.stop(None)?;
+        self.writeln(
+            TitleFormat::action(format!("Fixing {}", run.workflow_name))
+                .sub_title(run.url.as_str()),
+        )?;
+
+        let prompt = run.prompt(&log);
+        let manifest = self.start_report(&prompt).await?;
+        let result = self.on_message(prompt).await;
+        self.finish_report(manifest).await?;
+        result
+    }
+
+    /// Starts recording a headless run and returns the manifest of the
+    /// workspace before the run
+    async fn start_report(&mut self, task: impl ToString) -> Result<WorkspaceManifest> {
+        let manifest = WorkspaceManifest::capture(self.api.environment().cwd).await?;
+        self.recorder = Some(RunRecorder::new(task));
+        Ok(manifest)
+    }
+
+    /// Writes the report of the headless run to the reports directory
+    async fn finish_report(&mut self, manifest: WorkspaceManifest) -> Result<()> {
+        let Some(recorder) = self.recorder.take() else {
+            return Ok(());
+        };
+
+        let cwd = self.api.environment().cwd;
+        let changes = manifest.diff(&WorkspaceManifest::capture(cwd.clone()).await?);
+        let path = recorder.finish(&changes, &cwd).save(&cwd).await?;
+
+        self.writeln(
+            TitleFormat::action("Run report created").sub_title(path.display().to_string()),
+        )
+    }
+
     async fn on_command(&mut self, command: Command) -> anyhow::Result<bool> {
         match command {
             Command::Compact => {
@@ -227,10 +746,16 @@ impl<F: API> UI<F> {
                 self.on_message(content.clone()).await?;
             }
             Command::Act => {
-                self.on_mode_change(Mode::Act).await?;
+                self.on_mode_change(Mode::act()).await?;
             }
             Command::Plan => {
-                self.on_mode_change(Mode::Plan).await?;
+                self.on_mode_change(Mode::plan()).await?;
+            }
+            Command::Mode(mode) => {
+                self.on_mode_selection(mode).await?;
+            }
+            Command::PlanShow => {
+                self.on_plan_show().await?;
             }
             Command::Help => {
                 let info = Info::from(self.command.as_ref());
@@ -239,11 +764,43 @@ impl<F: API> UI<F> {
             Command::Tools => {
                 use crate::tools_display::format_tools;
                 let tools = self.api.tools().await;
-                let output = format_tools(&tools);
+                let disabled = self
+                    .current_conversation()
+                    .await?
+                    .map(|conversation| conversation.disabled_tools)
+                    .unwrap_or_default();
+                let output = format_tools(&tools, &disabled);
                 self.writeln(output)?;
             }
+            Command::ToolsEnable(tools) => {
+                self.on_tools_toggle(tools, true).await?;
+            }
+            Command::ToolsDisable(tools) => {
+                self.on_tools_toggle(tools, false).await?;
+            }
+            Command::Changes => {
+                self.on_changes().await?;
+            }
+            Command::Undo => {
+                self.on_undo().await?;
+            }
+            Command::Sessions => {
+                self.on_sessions().await?;
+            }
+            Command::Context => {
+                self.on_context().await?;
+            }
+            Command::Agent(agent) => {
+                self.on_agent(agent).await?;
+            }
             Command::Exit => {
-                update_forge().await;
+                let changes = self.workspace_changes().await?;
+                if !changes.is_empty() {
+                    self.writeln(Info::from(changes.as_slice()))?;
+                }
+                if !self.api.environment().low_bandwidth {
+                    update_forge().await;
+                }
                 return Ok(true);
             }
 
@@ -266,6 +823,201 @@ impl<F: API> UI<F> {
         Ok(false)
     }
 
+    async fn on_changes(&mut self) -> Result<()> {
+        self.spinner.start(Some("Scanning workspace"))?;
+        let changes = self.workspace_changes().await?;
+        self.spinner.stop(None)?;
+
+        if changes.is_empty() {
+            self.writeln(TitleFormat::info("No files changed in the workspace"))?;
+        } else {
+            self.writeln(Info::from(changes.as_slice()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reverts the changes the tools made to files during the last turn from
+    /// the snapshots taken before each change
+    async fn on_undo(&mut self) -> Result<()> {
+        let changes = std::mem::take(&mut self.turn_changes);
+        if changes.files().is_empty() {
+            self.writeln(TitleFormat::info("The last turn didn't change any file"))?;
+            return Ok(());
+        }
+
+        let cwd = self.api.environment().cwd;
+        let display = |path: &Path| {
+            path.strip_prefix(&cwd)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        for file in changes.files() {
+            let action = if file.created { "Remove" } else { "Restore" };
+            self.writeln(TitleFormat::info(action).sub_title(display(&file.path)))?;
+        }
+        let confirmed = match Confirm::new("Revert these changes?")
+            .with_default(true)
+            .prompt()
+        {
+            Ok(confirmed) => confirmed,
+            Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => false,
+            Err(err) => return Err(err.into()),
+        };
+        if !confirmed {
+            self.turn_changes = changes;
+            return Ok(());
+        }
+
+        for file in changes.files() {
+            let before = ForgeFS::read_utf8(&file.path).await.unwrap_or_default();
+            for _ in 0..file.snapshots {
+                self.api.undo_snapshot(&file.path).await?;
+            }
+            if file.created && file.path.exists() {
+                ForgeFS::remove_file(&file.path).await?;
+            }
+            let after = ForgeFS::read_utf8(&file.path).await.unwrap_or_default();
+
+            self.writeln(TitleFormat::action("Reverted").sub_title(display(&file.path)))?;
+            self.writeln(self.format_diff(&before, &after))?;
+        }
+
+        // Let the agent know that its changes are gone
+        if let Some(conversation_id) = self.state.conversation_id.clone() {
+            let paths = changes
+                .files()
+                .iter()
+                .map(|file| file.path.display().to_string())
+                .collect::<Vec<_>>();
+            let message = ContextMessage::user(format!(
+                "I reverted the changes you made to these files during the last turn: {}",
+                paths.join(", ")
+            ));
+            self.api.append_message(&conversation_id, message).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the conversation once it has started
+    async fn current_conversation(&self) -> Result<Option<Conversation>> {
+        match self.state.conversation_id.as_ref() {
+            Some(conversation_id) => self.api.conversation(conversation_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Enables or disables the tools named by the patterns for the rest of the
+    /// conversation. A pattern is the name of a tool, its name without the
+    /// `forge_tool_` prefix or a group of tools, e.g. `process` for the shell.
+    async fn on_tools_toggle(&mut self, patterns: Vec<String>, enable: bool) -> Result<()> {
+        let action = if enable { "enable" } else { "disable" };
+        if patterns.is_empty() {
+            self.writeln(TitleFormat::error(format!(
+                "Name the tools to {action}, e.g. /tools {action} process"
+            )))?;
+            return Ok(());
+        }
+
+        let conversation_id = self.init_conversation().await?;
+        let names = self
+            .api
+            .conversation(&conversation_id)
+            .await?
+            .map(|conversation| conversation.tool_names())
+            .unwrap_or_default();
+        let mut tools = Vec::new();
+        for pattern in patterns.iter() {
+            let matched = names.iter().filter(|name| name.matches(pattern));
+            let count = tools.len();
+            tools.extend(matched.cloned());
+            if tools.len() == count {
+                self.writeln(TitleFormat::error(format!("No tool matches '{pattern}'")))?;
+                return Ok(());
+            }
+        }
+
+        self.update_conversation(&conversation_id, |conversation| {
+            if enable {
+                conversation.enable_tools(&tools);
+            } else {
+                conversation.disable_tools(&tools);
+            }
+            Ok(())
+        })
+        .await?;
+
+        let names = tools
+            .iter()
+            .map(|tool| tool.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let title = if enable { "Enabled" } else { "Disabled" };
+        self.writeln(TitleFormat::action(title).sub_title(names))?;
+
+        Ok(())
+    }
+
+    async fn on_plan_show(&mut self) -> Result<()> {
+        let plan = self.current_conversation().await?.and_then(|c| c.plan);
+        let Some(plan) = plan else {
+            self.writeln(TitleFormat::info(
+                "There is no plan yet, switch to Plan mode with /plan to create one",
+            ))?;
+            return Ok(());
+        };
+
+        let mut title = TitleFormat::info(format!(
+            "Plan ({}/{} steps completed)",
+            plan.completed(),
+            plan.steps.len()
+        ));
+        if let Some(path) = plan.path.as_ref() {
+            title = title.sub_title(path.display().to_string());
+        }
+        self.writeln(title)?;
+        self.writeln(plan.to_string())?;
+        Ok(())
+    }
+
+    async fn on_context(&mut self) -> Result<()> {
+        let conversation = self.current_conversation().await?;
+        let Some(conversation) = conversation else {
+            self.writeln(TitleFormat::info("The conversation hasn't started yet"))?;
+            return Ok(());
+        };
+
+        let main_agent_id = AgentId::new(Conversation::MAIN_AGENT_NAME);
+        let context = conversation
+            .context(&main_agent_id)
+            .cloned()
+            .unwrap_or_default();
+        let compact = conversation
+            .get_agent(&main_agent_id)
+            .ok()
+            .and_then(|agent| agent.compact.as_ref());
+
+        self.writeln(Info::from(&ContextBreakdown::new(&context, compact)))?;
+        Ok(())
+    }
+
+    /// Compares the workspace against the manifest recorded when the session
+    /// started
+    async fn workspace_changes(&mut self) -> Result<Vec<Change>> {
+        if let Some(task) = self.manifest_task.take() {
+            self.manifest = Some(task.await??);
+        }
+
+        let Some(manifest) = self.manifest.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        let current = WorkspaceManifest::capture(self.api.environment().cwd).await?;
+        Ok(manifest.diff(&current))
+    }
+
     async fn on_compaction(&mut self) -> Result<(), anyhow::Error> {
         self.spinner.start(Some("Compacting"))?;
         let conversation_id = self.init_conversation().await?;
@@ -335,26 +1087,56 @@ impl<F: API> UI<F> {
                 workflow.model = Some(model.clone());
             })
             .await?;
+        if let Some(watch) = &mut self.workflow_watch {
+            watch.sync();
+        }
 
         // Get the conversation to update
         let conversation_id = self.init_conversation().await?;
 
-        if let Some(mut conversation) = self.api.conversation(&conversation_id).await? {
-            // Update the model in the conversation
-            conversation.set_main_model(model.clone())?;
-
-            // Upsert the updated conversation
-            self.api.upsert_conversation(conversation).await?;
+        self.update_conversation(&conversation_id, |conversation| {
+            Ok(conversation.set_main_model(model.clone())?)
+        })
+        .await?;
 
-            // Update the UI state with the new model
-            self.state.model = Some(model.clone());
+        // Update the UI state with the new model
+        self.state.model = Some(model.clone());
 
-            self.writeln(TitleFormat::action(format!("Switched to model: {model}")))?;
-        }
+        self.writeln(TitleFormat::action(format!("Switched to model: {model}")))?;
 
         Ok(())
     }
 
+    /// Applies the change to the stored conversation. If the conversation is
+    /// modified concurrently, the change is applied again to the latest
+    /// version.
+    async fn update_conversation(
+        &self,
+        conversation_id: &ConversationId,
+        f: impl Fn(&mut Conversation) -> Result<()>,
+    ) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            let mut conversation = self
+                .api
+                .conversation(conversation_id)
+                .await?
+                .with_context(|| format!("Conversation {conversation_id} was not found"))?;
+            f(&mut conversation)?;
+
+            match self.api.upsert_conversation(conversation).await {
+                Err(error) if is_conflict(&error) && attempt < MAX_CONFLICT_RETRIES => {
+                    tracing::debug!(
+                        attempt = attempt,
+                        "Conversation modified concurrently, retrying"
+                    );
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     // Handle dispatching events from the CLI
     async fn handle_dispatch(&mut self, json: String) -> Result<()> {
         // Initialize the conversation
@@ -389,23 +1171,79 @@ impl<F: API> UI<F> {
                     .write_workflow(self.cli.workflow.as_deref(), &workflow)
                     .await?;
 
-                // Get the mode from the config
-                let mode = workflow
+                let path = self.api.workflow_path(self.cli.workflow.as_deref()).await;
+                self.workflow_watch = Some(WorkflowWatch::new(path));
+
+                let trusted = self.is_workspace_trusted().await?;
+                self.override_workflow(&mut workflow, trusted);
+                if !trusted {
+                    // Headless runs can't ask, so say how to trust the workspace
+                    let hint = if self.headless {
+                        ", pass --trust to enable them"
+                    } else {
+                        ""
+                    };
+                    self.writeln(
+                        TitleFormat::info("Workspace not trusted").sub_title(format!(
+                            "shell, network and the commands of forge.yaml are disabled{hint}"
+                        )),
+                    )?;
+                }
+
+                self.apply_workflow_settings(&workflow)?;
+
+                // The mode passed on the command line takes precedence over the one
+                // configured for the project, then globally, then the one used last.
+                // Workflows written before `default_mode` set the `mode` variable.
+                let env = self.api.environment();
+                let legacy_mode = workflow
                     .variables
                     .get("mode")
-                    .cloned()
-                    .and_then(|value| serde_json::from_value(value).ok())
-                    .unwrap_or(Mode::Act);
+                    .and_then(|value| value.as_str())
+                    .and_then(|name| name.parse::<Mode>().ok());
+                let mode = self
+                    .cli
+                    .mode
+                    .clone()
+                    .or(workflow.default_mode.clone())
+                    .or(legacy_mode)
+                    .or(env.default_mode);
+                let mode = match mode {
+                    Some(mode) => mode,
+                    // The mode used last may have been removed from the workflow since
+                    None => state::load_last_mode(&env.cwd)
+                        .await
+                        .filter(|mode| self.find_mode(mode).is_ok())
+                        .unwrap_or_default(),
+                };
+                let mode_config = self.find_mode(&mode)?;
 
                 self.state = UIState::new(mode).provider(self.api.environment().provider);
-                self.command.register_all(&workflow);
 
                 // We need to try and get the conversation ID first before fetching the model
-                if let Some(ref path) = self.cli.conversation {
-                    let conversation: Conversation = serde_json::from_str(
-                        ForgeFS::read_to_string(path.as_os_str()).await?.as_str(),
-                    )
-                    .context("Failed to parse Conversation")?;
+                let conversation = match (self.resumed.take(), self.cli.conversation.as_ref()) {
+                    (Some(conversation), _) => Some(conversation),
+                    (None, Some(path)) => Some(
+                        serde_json::from_str::<Conversation>(
+                            ForgeFS::read_to_string(path.as_os_str()).await?.as_str(),
+                        )
+                        .context("Failed to parse Conversation")?,
+                    ),
+                    (None, None) => None,
+                };
+                if let Some(mut conversation) = conversation {
+                    if self.cli.read_only {
+                        conversation
+                            .agents
+                            .iter_mut()
+                            .for_each(|agent| agent.read_only = Some(true));
+                    }
+                    if self.cli.review_edits {
+                        conversation
+                            .agents
+                            .iter_mut()
+                            .for_each(|agent| agent.review_edits = Some(true));
+                    }
 
                     let conversation_id = conversation.id.clone();
                     self.state.model = Some(conversation.main_model()?);
@@ -416,12 +1254,132 @@ impl<F: API> UI<F> {
                     let conversation = self.api.init_conversation(workflow.clone()).await?;
                     self.state.model = Some(conversation.main_model()?);
                     self.state.conversation_id = Some(conversation.id.clone());
+                    self.update_conversation(&conversation.id, |conversation| {
+                        conversation.set_mode(&mode_config);
+                        Ok(())
+                    })
+                    .await?;
                     Ok(conversation.id)
                 }
             }
         }
     }
 
+    /// Adjusts the workflow to the session: the options given on the command
+    /// line and the trust of the workspace only apply to it, so they aren't
+    /// written to the workflow
+    fn override_workflow(&self, workflow: &mut Workflow, trusted: bool) {
+        if self.cli.read_only {
+            workflow.read_only = Some(true);
+        }
+        if self.cli.review_edits {
+            workflow.review_edits = Some(true);
+        }
+        if !trusted {
+            workflow.restrict_untrusted();
+        }
+    }
+
+    /// Applies the settings of the workflow to the terminal UI
+    fn apply_workflow_settings(&mut self, workflow: &Workflow) -> Result<()> {
+        self.modes = workflow.available_modes();
+        self.command.register_all(workflow);
+        self.spinner_config = workflow.spinner.clone().unwrap_or_default();
+        self.spinner
+            .set_enabled(self.spinner_config.is_enabled() && !self.json)?;
+        self.editor_config = workflow.editor.clone().unwrap_or_default();
+        self.diff_config = workflow.diff.clone().unwrap_or_default();
+        apply_theme(workflow.theme.unwrap_or_default());
+        self.markdown = MarkdownFormat::new();
+        Ok(())
+    }
+
+    /// Reloads the workflow if its file was edited since it was read. A
+    /// workflow that fails to load is reported and the previous one is kept.
+    async fn check_workflow(&mut self) -> Result<()> {
+        if !self
+            .workflow_watch
+            .as_mut()
+            .is_some_and(|watch| watch.changed())
+        {
+            return Ok(());
+        }
+        if let Err(error) = self.reload_workflow().await {
+            self.writeln(TitleFormat::error(format!(
+                "Failed to reload the workflow, keeping the previous one: {error:?}"
+            )))?;
+        }
+        Ok(())
+    }
+
+    /// Applies the workflow file to the running session: its agents, templates
+    /// and commands replace the ones read when the conversation started, while
+    /// the messages of the conversation are kept
+    async fn reload_workflow(&mut self) -> Result<()> {
+        let Some(conversation_id) = self.state.conversation_id.clone() else {
+            return Ok(());
+        };
+        let mut workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
+        // The model selected for the conversation is kept if the workflow no
+        // longer sets one
+        if workflow.model.is_none() {
+            workflow.model = self.state.model.clone();
+        }
+        let trusted = self.is_workspace_trusted().await?;
+        self.override_workflow(&mut workflow, trusted);
+
+        let commands_before = self.command.list();
+        self.apply_workflow_settings(&workflow)?;
+        let commands_after = self.command.list();
+
+        // The current mode may have been removed from the workflow
+        let mode = self
+            .find_mode(&self.state.mode)
+            .or_else(|_| self.find_mode(&Mode::default()))?;
+        self.state.mode = mode.name.clone();
+
+        let changes = Mutex::new(AgentChanges::default());
+        self.update_conversation(&conversation_id, |conversation| {
+            *changes.lock().unwrap() = conversation.reload(workflow.clone());
+            conversation.set_mode(&mode);
+            Ok(())
+        })
+        .await?;
+        let changes = changes.into_inner().unwrap();
+
+        if let Some(conversation) = self.api.conversation(&conversation_id).await? {
+            self.state.model = Some(conversation.main_model()?);
+            if let Some(agent) = &self.state.agent {
+                if !conversation.agents.iter().any(|a| &a.id == agent) {
+                    self.state.agent = None;
+                }
+            }
+        }
+
+        let mut summary = Vec::new();
+        summary.extend(changes.added.iter().map(|id| format!("+{id}")));
+        summary.extend(changes.modified.iter().map(|id| format!("~{id}")));
+        summary.extend(changes.removed.iter().map(|id| format!("-{id}")));
+        summary.extend(
+            commands_after
+                .iter()
+                .filter(|command| !commands_before.iter().any(|c| c.name == command.name))
+                .map(|command| format!("+{}", command.name)),
+        );
+        summary.extend(
+            commands_before
+                .iter()
+                .filter(|command| !commands_after.iter().any(|c| c.name == command.name))
+                .map(|command| format!("-{}", command.name)),
+        );
+        let summary = match summary.is_empty() {
+            true => "no changes to the agents or commands".to_string(),
+            false => summary.join(" "),
+        };
+        self.writeln(TitleFormat::action("Workflow reloaded").sub_title(summary))?;
+        Ok(())
+    }
+
     async fn on_message(&mut self, content: String) -> Result<()> {
         self.spinner.start(None)?;
         let conversation_id = self.init_conversation().await?;
@@ -447,22 +1405,124 @@ impl<F: API> UI<F> {
         &mut self,
         stream: &mut (impl StreamExt<Item = Result<AgentMessage<ChatResponse>>> + Unpin),
     ) -> Result<()> {
-        while let Some(message) = stream.next().await {
-            match message {
-                Ok(message) => self.handle_chat_response(message)?,
-                Err(err) => {
-                    self.spinner.stop(None)?;
-                    return Err(err);
+        self.citations = Citations::default();
+        self.turn_changes = TurnChanges::default();
+        self.turn_start = Instant::now();
+        // The prices and the context window shown in the prompt come with the list of
+        // models
+        if self.state.cached_models.is_none() && !self.api.environment().low_bandwidth {
+            self.state.cached_models = self.api.models().await.ok();
+        }
+
+        // Dropping the stream aborts the request to the provider and the running
+        // tools
+        self.cancel = CancelKey::listen();
+        loop {
+            let pressed = self.cancel.pressed();
+            let expired = self.budget.expired();
+            let message = tokio::select! {
+                message = stream.next() => message,
+                _ = pressed => return self.on_cancel().await,
+                exceeded = expired => Some(Err(exceeded.into())),
+            };
+            let result = match message {
+                Some(Ok(message)) => self.handle_chat_response(message),
+                Some(Err(err)) => Err(err),
+                None => break,
+            };
+            if let Err(err) = result {
+                self.cancel.pause();
+                self.spinner.stop(None)?;
+                self.notify_if_slow("The task failed");
+                // The completed steps are kept when the budget stops the run
+                if err.downcast_ref::<BudgetExceeded>().is_some() {
+                    self.save_session().await?;
                 }
+                return Err(err);
             }
         }
 
+        self.cancel.pause();
         self.spinner.stop(None)?;
+        self.notify_if_slow("The task is complete");
+
+        if !self.citations.is_empty() {
+            let footnotes = self.citations.footnotes(&self.api.environment().cwd);
+            self.writeln(footnotes)?;
+        }
+
+        self.save_session().await
+    }
+
+    /// Notifies the user once the turn has run for longer than
+    /// `FORGE_NOTIFY_AFTER` seconds, so that a long turn can be left running in
+    /// the background. Scripts reading `--output json` aren't notified.
+    fn notify_if_slow(&self, message: &str) {
+        if self.json {
+            return;
+        }
+        let Some(after) = self.api.environment().notify_after else {
+            return;
+        };
+        if self.turn_start.elapsed() >= Duration::from_secs(after) {
+            notify("Forge", message);
+        }
+    }
 
+    /// Returns to the prompt after the turn was cancelled. The orchestrator
+    /// stores the conversation after each completed step of the turn, so the
+    /// next message continues from the last one without the partial response
+    /// or the interrupted tool calls.
+    async fn on_cancel(&mut self) -> Result<()> {
+        self.cancel.pause();
+        self.spinner.stop(None)?;
+        self.writeln(
+            TitleFormat::info("Cancelled")
+                .sub_title("the conversation continues from the last completed step"),
+        )?;
+        self.save_session().await
+    }
+
+    /// Saves the conversation so that it can be continued in a later session
+    async fn save_session(&self) -> Result<()> {
+        let Some(conversation) = self.current_conversation().await? else {
+            return Ok(());
+        };
+        let env = self.api.environment();
+        Session::new(conversation, env.cwd.clone(), self.state.usage.total_tokens)
+            .save(&env.sessions_path())
+            .await
+    }
+
+    /// Lets the user pick a past conversation of the workspace and continues
+    /// it
+    async fn on_sessions(&mut self) -> Result<()> {
+        let env = self.api.environment();
+        let sessions = Session::list(&env.sessions_path(), &env.cwd).await?;
+        if sessions.is_empty() {
+            self.writeln(TitleFormat::info("No past conversations in this workspace"))?;
+            return Ok(());
+        }
+
+        let session = match Select::new("Continue a conversation", sessions).prompt() {
+            Ok(session) => session,
+            Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+                return Ok(())
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        self.state = UIState::default();
+        self.resumed = Some(session.conversation);
+        self.init_conversation().await?;
+        // The task was given already, the next messages update it
+        self.state.is_first = false;
+        self.state.usage.total_tokens = session.tokens;
+        self.writeln(TitleFormat::action("Resumed").sub_title(session.title))?;
         Ok(())
     }
 
-    /// Modified version of handle_dump that supports HTML format
+    /// Modified version of handle_dump that supports HTML and markdown formats
     async fn on_dump(&mut self, format: Option<String>) -> Result<()> {
         if let Some(conversation_id) = self.state.conversation_id.clone() {
             let conversation = self.api.conversation(&conversation_id).await?;
@@ -472,7 +1532,9 @@ impl<F: API> UI<F> {
                 if let Some(format) = format {
                     if format == "html" {
                         // Export as HTML
-                        let html_content = conversation.to_html();
+                        let usage =
+                            UsageSummary { usage: self.state.usage.clone(), cost: self.state.cost };
+                        let html_content = render_conversation_html(&conversation, Some(&usage));
                         let path = format!("{timestamp}-dump.html");
                         tokio::fs::write(path.as_str(), html_content).await?;
 
@@ -482,6 +1544,17 @@ impl<F: API> UI<F> {
                         )?;
                         return Ok(());
                     }
+                    if format == "md" {
+                        // Export as a markdown transcript
+                        let path = format!("{timestamp}-dump.md");
+                        tokio::fs::write(path.as_str(), conversation.to_markdown()).await?;
+
+                        self.writeln(
+                            TitleFormat::action("Conversation markdown dump created".to_string())
+                                .sub_title(path.to_string()),
+                        )?;
+                        return Ok(());
+                    }
                 } else {
                     // Default: Export as JSON
                     let path = format!("{timestamp}-dump.json");
@@ -501,7 +1574,26 @@ impl<F: API> UI<F> {
         Ok(())
     }
 
+    /// Tells the user which step of the turn forge is performing
+    fn on_phase(&mut self, phase: Phase) -> Result<()> {
+        let message = self.spinner_config.message(&phase);
+        self.spinner.start(Some(&message))
+    }
+
     fn handle_chat_response(&mut self, message: AgentMessage<ChatResponse>) -> Result<()> {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(&message);
+        }
+        self.turn_changes.record(&message.message);
+        match &message.message {
+            ChatResponse::Phase(Phase::WaitingForModel(_)) => self.budget.record_turn()?,
+            ChatResponse::ToolCallEnd(result) => self.tool_failures.record(result),
+            _ => {}
+        }
+        if self.json {
+            return self.on_json_message(message);
+        }
+
         match message.message {
             ChatResponse::Text { mut text, is_complete, is_md, is_summary } => {
                 if is_complete && !text.trim().is_empty() {
@@ -512,14 +1604,42 @@ impl<F: API> UI<F> {
                     self.writeln(text)?;
                 }
             }
-            ChatResponse::ToolCallStart(_) => {
+            ChatResponse::ToolCallStart(call) => {
+                self.on_phase(Phase::ExecutingTool(call.name))?;
+            }
+            ChatResponse::ToolCallOutput { chunk, .. } => {
                 self.spinner.stop(None)?;
+                match chunk.stream {
+                    OutputStream::Stdout => {
+                        print!("{}", chunk.content);
+                        std::io::stdout().flush()?;
+                    }
+                    OutputStream::Stderr => {
+                        eprint!("{}", chunk.content);
+                        std::io::stderr().flush()?;
+                    }
+                }
             }
             ChatResponse::ToolCallEnd(toolcall_result) => {
+                if let Some(source) = toolcall_result.source.clone() {
+                    self.citations.add(source);
+                }
+
+                // Keep the user informed of the progress on the task list
+                if toolcall_result.name == TaskList::tool_name() && !toolcall_result.is_error {
+                    self.spinner.stop(None)?;
+                    self.writeln(TitleFormat::action("Tasks"))?;
+                    self.writeln(toolcall_result.content.trim())?;
+                }
+
                 // Only track toolcall name in case of success else track the error.
                 let payload = if toolcall_result.is_error {
-                    ToolCallPayload::new(toolcall_result.name.into_string())
-                        .with_cause(toolcall_result.content)
+                    let payload = ToolCallPayload::new(toolcall_result.name.into_string())
+                        .with_cause(toolcall_result.content);
+                    match toolcall_result.error_code {
+                        Some(code) => payload.with_code(code.to_string()),
+                        None => payload,
+                    }
                 } else {
                     ToolCallPayload::new(toolcall_result.name.into_string())
                 };
@@ -531,12 +1651,220 @@ impl<F: API> UI<F> {
                 }
             }
             ChatResponse::Usage(usage) => {
+                if let Some(pricing) = self.state.current_model().and_then(|model| model.pricing) {
+                    *self.state.cost.get_or_insert_default() += pricing.cost(&usage);
+                }
                 self.state.usage = usage;
             }
+            ChatResponse::PromptInjection { source, excerpt } => {
+                self.spinner.stop(None)?;
+                self.writeln(
+                    TitleFormat::warning("Possible prompt injection")
+                        .sub_title(format!("{source}: \"{excerpt}\"")),
+                )?;
+            }
+            ChatResponse::ApprovalRequest { calls, reason, reply } => {
+                self.spinner.stop(None)?;
+                self.cancel.pause();
+                let names = calls
+                    .iter()
+                    .map(|call| call.name.as_str())
+                    .collect::<Vec<_>>();
+                self.notify_if_slow(&format!("{} requires approval", names.join(", ")));
+                reply.send(self.approve(&calls, reason.as_deref())?);
+                self.cancel.resume();
+            }
+            ChatResponse::EditReview { path, old_content, new_content, reply } => {
+                self.spinner.stop(None)?;
+                self.cancel.pause();
+                self.notify_if_slow(&format!("Review the changes to {path}"));
+                reply.send(self.review_edit(&path, &old_content, new_content)?);
+                self.cancel.resume();
+            }
+            // Diffs are already shown inline, other clients can link to the file
+            ChatResponse::Artifact(artifact) => {
+                if self.cli.verbose {
+                    self.writeln(
+                        TitleFormat::debug("Artifact")
+                            .sub_title(artifact.path.display().to_string()),
+                    )?;
+                }
+            }
+            ChatResponse::Phase(phase) => self.on_phase(phase)?,
+            // Only complete texts are shown, so there is nothing to discard
+            ChatResponse::Retry { cause } => {
+                self.spinner.stop(None)?;
+                self.writeln(TitleFormat::warning("Retrying").sub_title(cause))?;
+            }
+            ChatResponse::ToolDisabled(circuit) => {
+                self.spinner.stop(None)?;
+                self.writeln(TitleFormat::warning("Tool disabled").sub_title(format!(
+                    "{} failed {} times in a row, retrying in {}s",
+                    circuit.name.as_str(),
+                    circuit.failures,
+                    circuit.retry_in_secs
+                )))?;
+            }
         }
         Ok(())
     }
 
+    /// Prints the message as a JSON event. Calls that need the approval of the
+    /// user and edits that are reviewed are rejected, since nobody can answer.
+    fn on_json_message(&mut self, message: AgentMessage<ChatResponse>) -> Result<()> {
+        if let Some(event) = JsonEvent::from_message(&message) {
+            event.print()?;
+        }
+        match message.message {
+            ChatResponse::Text { text, is_complete: true, .. } if !text.trim().is_empty() => {
+                self.answer = Some(text);
+            }
+            ChatResponse::Usage(usage) => {
+                if let Some(pricing) = self.state.current_model().and_then(|model| model.pricing) {
+                    *self.state.cost.get_or_insert_default() += pricing.cost(&usage);
+                }
+                self.state.usage = usage;
+            }
+            ChatResponse::ApprovalRequest { reply, .. } => reply.send(Approval::Reject),
+            ChatResponse::EditReview { reply, .. } => reply.send(EditReview::Reject),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Decides whether the workspace is trusted, asking the user the first
+    /// time forge is used in it. The workspace isn't trusted when there is
+    /// nobody to ask.
+    async fn is_workspace_trusted(&mut self) -> Result<bool> {
+        if self.cli.trust {
+            return Ok(true);
+        }
+
+        let env = self.api.environment();
+        let path = env.trust_path();
+        let mut trust = WorkspaceTrust::load(&path).await?;
+        if let Some(trusted) = trust.is_trusted(&env.cwd) {
+            return Ok(trusted);
+        }
+        if !std::io::stdin().is_terminal() {
+            return Ok(false);
+        }
+
+        self.writeln(
+            TitleFormat::action("New workspace").sub_title(env.cwd.display().to_string()),
+        )?;
+        let trusted = match Confirm::new("Do you trust this workspace?")
+            .with_default(false)
+            .with_help_message(
+                "Untrusted workspaces can't run shell commands, access the network or use the commands of their forge.yaml",
+            )
+            .prompt()
+        {
+            Ok(trusted) => trusted,
+            Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => false,
+            Err(err) => return Err(err.into()),
+        };
+
+        trust.set(&env.cwd, trusted);
+        trust.save(&path).await?;
+        Ok(trusted)
+    }
+
+    /// Asks the user to approve the calls. Calls that a policy denies, for the
+    /// given reason, can't be always approved since the answer only applies
+    /// to them.
+    fn approve(&mut self, calls: &[ToolCallFull], reason: Option<&str>) -> Result<Approval> {
+        if reason.is_none()
+            && calls
+                .iter()
+                .all(|call| self.state.approved_tools.contains(&call.name))
+        {
+            return Ok(Approval::Approve);
+        }
+
+        for call in calls {
+            self.writeln(TitleFormat::action("Approval required").sub_title(format!(
+                "{} {}",
+                call.name.as_str(),
+                call.arguments
+            )))?;
+        }
+        if let Some(reason) = reason {
+            self.writeln(TitleFormat::warning(format!("Denied because {reason}")))?;
+        }
+
+        let (message, help) = match calls {
+            [_] => (
+                "Allow this tool call?",
+                "Always approves every call to this tool in the conversation",
+            ),
+            _ => (
+                "Allow these tool calls?",
+                "Always approves every call to these tools in the conversation",
+            ),
+        };
+        let (options, help) = match reason {
+            Some(_) => (vec!["Yes", "No"], "Only approves this call"),
+            None => (vec!["Yes", "No", "Always"], help),
+        };
+        let approval = match Select::new(message, options)
+            .with_help_message(help)
+            .prompt()
+        {
+            Ok("Yes") => Approval::Approve,
+            Ok("Always") => {
+                self.state
+                    .approved_tools
+                    .extend(calls.iter().map(|call| call.name.clone()));
+                Approval::Always
+            }
+            Ok(_) | Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+                Approval::Reject
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(approval)
+    }
+
+    /// Formats the changes to a file in the style of the workflow
+    fn format_diff(&self, old: &str, new: &str) -> String {
+        let context = self.diff_config.context();
+        match self.diff_config.style {
+            DiffStyle::Unified => DiffFormat::format_with_context(old, new, context),
+            DiffStyle::SideBySide => {
+                DiffFormat::side_by_side(old, new, self.diff_config.width, context)
+            }
+        }
+    }
+
+    /// Shows the diff of a file that is about to be written and asks the user
+    /// whether to write it
+    fn review_edit(
+        &mut self,
+        path: &str,
+        old_content: &str,
+        new_content: String,
+    ) -> Result<EditReview> {
+        self.writeln(TitleFormat::action("Review").sub_title(path))?;
+        self.writeln(self.format_diff(old_content, &new_content))?;
+
+        let options = vec!["Accept", "Reject", "Edit"];
+        let review = match Select::new("Write these changes?", options)
+            .with_help_message("Edit opens the new content in $VISUAL or $EDITOR")
+            .prompt()
+        {
+            Ok("Accept") => EditReview::Accept,
+            Ok("Edit") => EditReview::Edit(edit_in_editor(path, new_content)?),
+            Ok(_) | Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+                EditReview::Reject
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(review)
+    }
+
     async fn on_custom_event(&mut self, event: Event) -> Result<()> {
         let conversation_id = self.init_conversation().await?;
         let chat = ChatRequest::new(event, conversation_id);
@@ -546,3 +1874,48 @@ impl<F: API> UI<F> {
         }
     }
 }
+
+/// Opens the content in the editor of the user and returns it once the editor
+/// exits. The temporary file keeps the extension of the path so that the
+/// editor highlights the syntax.
+fn edit_in_editor(path: &str, content: String) -> Result<String> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("forge-edit-");
+    let suffix = Path::new(path)
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()));
+    if let Some(suffix) = &suffix {
+        builder.suffix(suffix);
+    }
+    let mut file = builder.tempfile()?;
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+
+    let editor = external_editor();
+    let status = editor_command(&editor)
+        .arg(file.path())
+        .status()
+        .with_context(|| format!("Failed to start the editor '{editor}'"))?;
+    if !status.success() {
+        bail!("The editor '{editor}' exited with {status}");
+    }
+
+    Ok(std::fs::read_to_string(file.path())?)
+}
+
+fn is_conflict(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<forge_api::Error>(),
+        Some(forge_api::Error::ConversationConflict(..))
+    )
+}
+
+/// Uses the theme of the workflow for all the output
+fn apply_theme(theme: Theme) {
+    let theme = match theme {
+        Theme::Dark => forge_display::Theme::Dark,
+        Theme::Light => forge_display::Theme::Light,
+        Theme::Plain => forge_display::Theme::Plain,
+    };
+    theme.apply();
+}
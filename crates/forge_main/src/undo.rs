@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use forge_api::{ChatResponse, FileChange, FileChangeKind};
+
+/// How to revert the changes a turn made to a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileUndo {
+    pub path: PathBuf,
+    /// Number of snapshots taken by the tools during the turn, one before
+    /// every change to the file while it existed
+    pub snapshots: usize,
+    /// Whether the file didn't exist before the turn
+    pub created: bool,
+}
+
+/// Records the files changed by the tools during the last turn, as reported
+/// with their results, so that the changes can be reverted from the snapshot
+/// store
+#[derive(Default)]
+pub struct TurnChanges {
+    files: Vec<FileUndo>,
+}
+
+impl TurnChanges {
+    pub fn record(&mut self, message: &ChatResponse) {
+        // Failed calls may have changed files before failing
+        if let ChatResponse::ToolCallEnd(result) = message {
+            for change in &result.changes {
+                self.add(change);
+            }
+        }
+    }
+
+    /// Records a change to the file, counting the snapshots it added to the
+    /// store
+    fn add(&mut self, change: &FileChange) {
+        let position = self.files.iter().position(|file| file.path == change.path);
+        let file = match position {
+            Some(position) => &mut self.files[position],
+            None => {
+                self.files.push(FileUndo {
+                    path: change.path.clone(),
+                    snapshots: 0,
+                    created: change.kind == FileChangeKind::Created,
+                });
+                self.files.last_mut().expect("a file was just added")
+            }
+        };
+        match change.kind {
+            FileChangeKind::Created => {}
+            FileChangeKind::Modified => file.snapshots += 1,
+            FileChangeKind::Restored => file.snapshots = file.snapshots.saturating_sub(1),
+        }
+        // A file restored to its content before the turn has nothing to revert
+        self.files.retain(|file| file.created || file.snapshots > 0);
+    }
+
+    /// Returns the files changed during the turn, in the order they were first
+    /// changed
+    pub fn files(&self) -> &[FileUndo] {
+        &self.files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{ToolName, ToolResult};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn result(changes: &mut TurnChanges, name: &str, files: &[(&str, FileChangeKind)]) {
+        let result = ToolResult::new(ToolName::new(name)).success("").changes(
+            files
+                .iter()
+                .map(|(path, kind)| FileChange::new(*path, *kind))
+                .collect::<Vec<_>>(),
+        );
+        changes.record(&ChatResponse::ToolCallEnd(result));
+    }
+
+    #[test]
+    fn test_turn_changes() {
+        let mut fixture = TurnChanges::default();
+        result(
+            &mut fixture,
+            "forge_tool_fs_patch",
+            &[("/project/a.rs", FileChangeKind::Modified)],
+        );
+        result(
+            &mut fixture,
+            "forge_tool_fs_create",
+            &[("/project/b.rs", FileChangeKind::Created)],
+        );
+        result(
+            &mut fixture,
+            "forge_tool_fs_patch",
+            &[("/project/b.rs", FileChangeKind::Modified)],
+        );
+        result(
+            &mut fixture,
+            "forge_tool_fs_patch",
+            &[("/project/a.rs", FileChangeKind::Modified)],
+        );
+        result(&mut fixture, "forge_tool_fs_read", &[]);
+
+        let actual = fixture.files().to_vec();
+        let expected = vec![
+            FileUndo {
+                path: PathBuf::from("/project/a.rs"),
+                snapshots: 2,
+                created: false,
+            },
+            FileUndo {
+                path: PathBuf::from("/project/b.rs"),
+                snapshots: 1,
+                created: true,
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_rename_turn() {
+        let mut fixture = TurnChanges::default();
+        result(
+            &mut fixture,
+            "forge_tool_fs_patch",
+            &[("/project/a.rs", FileChangeKind::Modified)],
+        );
+        result(
+            &mut fixture,
+            "forge_tool_code_rename",
+            &[
+                ("/project/a.rs", FileChangeKind::Modified),
+                ("/project/b.rs", FileChangeKind::Modified),
+                ("/project/c.rs", FileChangeKind::Modified),
+            ],
+        );
+        result(
+            &mut fixture,
+            "forge_tool_fs_undo",
+            &[("/project/c.rs", FileChangeKind::Restored)],
+        );
+
+        let actual = fixture.files().to_vec();
+        let expected = vec![
+            FileUndo {
+                path: PathBuf::from("/project/a.rs"),
+                snapshots: 2,
+                created: false,
+            },
+            FileUndo {
+                path: PathBuf::from("/project/b.rs"),
+                snapshots: 1,
+                created: false,
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_failed_calls_are_recorded() {
+        let mut fixture = TurnChanges::default();
+        let result = ToolResult::new(ToolName::new("forge_tool_code_rename"))
+            .failure(anyhow::anyhow!("Permission denied"))
+            .changes(vec![FileChange::new(
+                "/project/a.rs",
+                FileChangeKind::Modified,
+            )]);
+        fixture.record(&ChatResponse::ToolCallEnd(result));
+
+        let expected = vec![FileUndo {
+            path: PathBuf::from("/project/a.rs"),
+            snapshots: 1,
+            created: false,
+        }];
+        assert_eq!(fixture.files(), expected.as_slice());
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Detects the changes made to the workflow file while the session runs, so
+/// that they can be applied before the next message. The modification time
+/// of the file is compared, which is cheap enough to be checked before every
+/// prompt.
+#[derive(Debug)]
+pub struct WorkflowWatch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WorkflowWatch {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    /// Whether the file was modified, created or removed since the last check
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+
+    /// Accepts the current content of the file, e.g. after forge wrote it
+    /// itself
+    pub fn sync(&mut self) {
+        self.modified = modified(&self.path);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn touch(path: &Path, time: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("forge.yaml");
+        std::fs::write(&path, "model: a").unwrap();
+        let mut watch = WorkflowWatch::new(path.clone());
+        assert!(!watch.changed());
+
+        touch(&path, SystemTime::now() + Duration::from_secs(5));
+        assert!(watch.changed());
+        assert!(!watch.changed());
+
+        touch(&path, SystemTime::now() + Duration::from_secs(10));
+        watch.sync();
+        assert!(!watch.changed());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watch.changed());
+    }
+}
@@ -12,8 +12,8 @@ use tokio_stream::StreamExt;
 use tracing::{debug, error};
 
 use super::request::Request;
-use super::response::{EventData, ListModelResponse};
-use crate::retry::StatusCodeRetryPolicy;
+use super::response::{EventData, ListModelResponse, MessageStart};
+use crate::retry::{retry_after, send_with_retry, StatusCodeRetryPolicy};
 use crate::utils::format_http_context;
 
 #[derive(Clone, Builder)]
@@ -24,6 +24,9 @@ pub struct Anthropic {
     anthropic_version: String,
     #[builder(default = "RetryConfig::default()")]
     retry_config: RetryConfig,
+    /// Requests the responses in one piece instead of streaming them
+    #[builder(default)]
+    low_bandwidth: bool,
 }
 
 impl Anthropic {
@@ -63,6 +66,31 @@ impl Anthropic {
     }
 }
 
+impl Anthropic {
+    /// Requests the whole response at once, which takes less bandwidth than
+    /// streaming it in events
+    async fn complete(
+        &self,
+        request: Request,
+    ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+        let url = self.url("/messages")?;
+        debug!(url = %url, "Requesting the complete response");
+
+        let request = self
+            .client
+            .post(url.clone())
+            .headers(self.headers())
+            .json(&request);
+        let body = send_with_retry(request, &self.retry_config, &url).await?;
+        let messages = serde_json::from_str::<MessageStart>(&body)
+            .with_context(|| format!("Failed to parse Anthropic response: {body}"))?
+            .into_messages()
+            .with_context(|| format!("Failed to create completion message: {body}"))?;
+
+        Ok(Box::pin(tokio_stream::iter(messages.into_iter().map(Ok))))
+    }
+}
+
 #[async_trait::async_trait]
 impl ProviderService for Anthropic {
     async fn chat(
@@ -73,8 +101,11 @@ impl ProviderService for Anthropic {
         let max_tokens = context.max_tokens.unwrap_or(4000);
         let request = Request::try_from(context)?
             .model(model.as_str().to_string())
-            .stream(true)
+            .stream(!self.low_bandwidth)
             .max_tokens(max_tokens as u64);
+        if self.low_bandwidth {
+            return self.complete(request).await;
+        }
 
         let url = self.url("/messages")?;
         debug!(url = %url, model = %model, "Connecting Upstream");
@@ -94,55 +125,64 @@ impl ProviderService for Anthropic {
             Some(self.retry_config.max_retry_attempts),
             status_codes.clone(),
         )));
+        let mut opened = false;
         let stream = es
             .take_while(|message| !matches!(message, Err(reqwest_eventsource::Error::StreamEnded)))
-            .then(|event| async {
-                match event {
-                    Ok(event) => match event {
-                        Event::Open => None,
-                        Event::Message(event) if ["[DONE]", ""].contains(&event.data.as_str()) => {
-                            debug!("Received completion from Upstream");
-                            None
-                        }
-                        Event::Message(message) => Some(
-                            serde_json::from_str::<EventData>(&message.data)
-                                .with_context(|| "Failed to parse Anthropic event")
-                                .and_then(|event| {
-                                    ChatCompletionMessage::try_from(event).with_context(|| {
-                                        format!(
-                                            "Failed to create completion message: {}",
-                                            message.data
-                                        )
-                                    })
-                                }),
-                        ),
-                    },
-                    Err(error) => match error {
-                        reqwest_eventsource::Error::StreamEnded => None,
-                        reqwest_eventsource::Error::InvalidStatusCode(_, response) => {
-                            let headers = response.headers().clone();
-                            let status = response.status();
-                             match response.text().await {
-                                Ok(ref body) => {
-                                    debug!(status = ?status, headers = ?headers, body = body, "Invalid status code");
-                                    Some(Err(anyhow::anyhow!("Invalid status code: {}, reason: {}", status, body)))
-                                }
-                                Err(error) => {
-                                    error!(status = ?status, headers = ?headers, body = ?error, "Invalid status code (body not available)");
-                                    Some(Err(anyhow::anyhow!("Invalid status code: {}", status)))
+            .then(move |event| {
+                // Every connection but the first is a reconnect after the
+                // stream dropped
+                let reconnected =
+                    matches!(event, Ok(Event::Open)) && std::mem::replace(&mut opened, true);
+                async move {
+                    match event {
+                        Ok(event) => match event {
+                            Event::Open => reconnected
+                                .then(|| Ok(ChatCompletionMessage::default().reconnected(true))),
+                            Event::Message(event) if ["[DONE]", ""].contains(&event.data.as_str()) => {
+                                debug!("Received completion from Upstream");
+                                None
+                            }
+                            Event::Message(message) => Some(
+                                serde_json::from_str::<EventData>(&message.data)
+                                    .with_context(|| "Failed to parse Anthropic event")
+                                    .and_then(|event| {
+                                        ChatCompletionMessage::try_from(event).with_context(|| {
+                                            format!(
+                                                "Failed to create completion message: {}",
+                                                message.data
+                                            )
+                                        })
+                                    }),
+                            ),
+                        },
+                        Err(error) => match error {
+                            reqwest_eventsource::Error::StreamEnded => None,
+                            reqwest_eventsource::Error::InvalidStatusCode(_, response) => {
+                                let headers = response.headers().clone();
+                                let status = response.status();
+                                let retry_after = retry_after(&headers);
+                                 match response.text().await {
+                                    Ok(ref body) => {
+                                        debug!(status = ?status, headers = ?headers, body = body, "Invalid status code");
+                                        Some(Err(anyhow::anyhow!("Invalid status code: {}{}, reason: {}", status, retry_after, body)))
+                                    }
+                                    Err(error) => {
+                                        error!(status = ?status, headers = ?headers, body = ?error, "Invalid status code (body not available)");
+                                        Some(Err(anyhow::anyhow!("Invalid status code: {}{}", status, retry_after)))
+                                    }
                                 }
                             }
-                        }
-                        reqwest_eventsource::Error::InvalidContentType(_, ref response) => {
-                            let status_code = response.status();
-                            debug!(response = ?response, "Invalid content type");
-                            Some(Err(anyhow::anyhow!(error).context(format!("Http Status: {status_code}" ))))
-                        }
-                        error => {
-                            debug!(error = %error, "Failed to receive chat completion event");
-                            Some(Err(error.into()))
-                        }
-                    },
+                            reqwest_eventsource::Error::InvalidContentType(_, ref response) => {
+                                let status_code = response.status();
+                                debug!(response = ?response, "Invalid content type");
+                                Some(Err(anyhow::anyhow!(error).context(format!("Http Status: {status_code}" ))))
+                            }
+                            error => {
+                                debug!(error = %error, "Failed to receive chat completion event");
+                                Some(Err(error.into()))
+                            }
+                        },
+                    }
                 }
             }).map(move |response| {
                 match response {
@@ -242,6 +282,8 @@ mod tests {
                 call_id: Some(ToolCallId::new("math-1")),
                 content: serde_json::json!({"result": 4}).to_string(),
                 is_error: false,
+                error_code: None,
+                source: None,
             }])
             .tool_choice(ToolChoice::Call(ToolName::new("math")));
         let request = Request::try_from(context)
@@ -68,6 +68,7 @@ impl TryFrom<forge_domain::Context> for Request {
                 .collect::<std::result::Result<Vec<_>, _>>()?,
             system,
             temperature: request.temperature.map(|t| t.value()),
+            top_p: request.top_p.map(|t| t.value()),
             tool_choice: request.tool_choice.map(ToolChoice::from),
             ..Default::default()
         })
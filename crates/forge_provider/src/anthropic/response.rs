@@ -23,6 +23,7 @@ impl From<Model> for forge_domain::Model {
             name: Some(value.display_name),
             description: None,
             context_length: None,
+            pricing: None,
         }
     }
 }
@@ -39,6 +40,24 @@ pub struct MessageStart {
     pub usage: Usage,
 }
 
+impl MessageStart {
+    /// Converts a response that wasn't streamed to the messages its events
+    /// would have produced
+    pub fn into_messages(self) -> anyhow::Result<Vec<ChatCompletionMessage>> {
+        let mut messages =
+            vec![ChatCompletionMessage::assistant(Content::part("")).model(self.model)];
+        for block in self.content {
+            messages.push(ChatCompletionMessage::try_from(block)?);
+        }
+        let mut last = ChatCompletionMessage::assistant(Content::part("")).usage(self.usage);
+        if let Some(stop_reason) = self.stop_reason {
+            last = last.finish_reason(stop_reason);
+        }
+        messages.push(last);
+        Ok(messages)
+    }
+}
+
 #[derive(Deserialize, PartialEq, Clone, Debug)]
 pub struct Usage {
     pub input_tokens: Option<u64>,
@@ -174,6 +193,9 @@ impl TryFrom<Event> for ChatCompletionMessage {
             | Event::ContentBlockDelta { delta: content_block, .. } => {
                 ChatCompletionMessage::try_from(content_block)?
             }
+            Event::MessageStart { message } => {
+                ChatCompletionMessage::assistant(Content::part("")).model(message.model)
+            }
             Event::MessageDelta { delta, .. } => {
                 ChatCompletionMessage::assistant(Content::part("")).finish_reason(delta.stop_reason)
             }
@@ -233,6 +255,34 @@ mod tests {
         assert!(matches!(event_data, EventData::Unknown(_)));
     }
 
+    #[test]
+    fn test_message_into_messages() {
+        let fixture = r#"{"id":"msg_01","type":"message","role":"assistant","content":[{"type":"text","text":"Reading the file"},{"type":"tool_use","id":"toolu_01","name":"forge_tool_fs_read","input":{"path":"/project/lib.rs"}}],"model":"claude-3-7-sonnet-20250219","stop_reason":"tool_use","stop_sequence":null,"usage":{"input_tokens":100,"output_tokens":20}}"#;
+        let actual = serde_json::from_str::<MessageStart>(fixture)
+            .unwrap()
+            .into_messages()
+            .unwrap();
+
+        let expected = vec![
+            ChatCompletionMessage::assistant(Content::part("")).model("claude-3-7-sonnet-20250219"),
+            ChatCompletionMessage::assistant(Content::part("Reading the file")),
+            ChatCompletionMessage::assistant(Content::part("")).add_tool_call(ToolCallPart {
+                call_id: Some(ToolCallId::new("toolu_01")),
+                name: Some(ToolName::new("forge_tool_fs_read")),
+                arguments_part: r#"{"path":"/project/lib.rs"}"#.to_string(),
+            }),
+            ChatCompletionMessage::assistant(Content::part(""))
+                .usage(forge_domain::Usage {
+                    prompt_tokens: 100,
+                    completion_tokens: 20,
+                    total_tokens: 120,
+                    estimated_tokens: None,
+                })
+                .finish_reason(StopReason::ToolUse),
+        ];
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_event_deser() {
         let tests = vec![
@@ -2,48 +2,65 @@
 
 use anyhow::{Context as _, Result};
 use forge_domain::{
-    ChatCompletionMessage, Context, Model, ModelId, Provider, ProviderService, ResultStream,
-    RetryConfig,
+    ChatCompletionMessage, Context, Error, Model, ModelId, Provider, ProviderService, ResultStream,
+    RetryConfig, TimeoutConfig,
 };
 
 use crate::anthropic::Anthropic;
 use crate::open_router::OpenRouter;
+use crate::timeout::with_timeouts;
 
-pub enum Client {
+pub struct Client {
+    inner: InnerClient,
+    timeout_config: TimeoutConfig,
+}
+
+enum InnerClient {
     OpenAICompat(OpenRouter),
     Anthropic(Anthropic),
 }
 
 impl Client {
-    pub fn new(provider: Provider, retry_config: RetryConfig) -> Result<Self> {
+    pub fn new(
+        provider: Provider,
+        retry_config: RetryConfig,
+        timeout_config: TimeoutConfig,
+        low_bandwidth: bool,
+    ) -> Result<Self> {
         let client = reqwest::Client::builder()
+            .gzip(low_bandwidth)
+            .connect_timeout(timeout_config.connect_timeout())
             .pool_idle_timeout(std::time::Duration::from_secs(90))
             .pool_max_idle_per_host(5)
             .build()?;
 
-        match &provider {
-            Provider::OpenAI { url, .. } => Ok(Client::OpenAICompat(
+        let inner = match &provider {
+            Provider::OpenAI { url, .. } => InnerClient::OpenAICompat(
                 OpenRouter::builder()
                     .client(client)
                     .provider(provider.clone())
                     .retry_config(retry_config.clone())
+                    .low_bandwidth(low_bandwidth)
                     .build()
                     .with_context(|| format!("Failed to initialize: {url}"))?,
-            )),
+            ),
 
-            Provider::Anthropic { url, key } => Ok(Client::Anthropic(
+            Provider::Anthropic { url, key } => InnerClient::Anthropic(
                 Anthropic::builder()
                     .client(client)
                     .api_key(key.to_string())
                     .base_url(url.clone())
                     .anthropic_version("2023-06-01".to_string())
                     .retry_config(retry_config.clone())
+                    .low_bandwidth(low_bandwidth)
                     .build()
                     .with_context(|| {
                         format!("Failed to initialize Anthropic client with URL: {url}")
                     })?,
-            )),
-        }
+            ),
+        };
+
+        Ok(Self { inner, timeout_config })
     }
 }
 
@@ -54,16 +71,24 @@ impl ProviderService for Client {
         model: &ModelId,
         context: Context,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
-        match self {
-            Client::OpenAICompat(provider) => provider.chat(model, context).await,
-            Client::Anthropic(provider) => provider.chat(model, context).await,
-        }
+        let request = async {
+            match &self.inner {
+                InnerClient::OpenAICompat(provider) => provider.chat(model, context).await,
+                InnerClient::Anthropic(provider) => provider.chat(model, context).await,
+            }
+        };
+        // In low bandwidth mode the whole response is received before the stream
+        // is returned, so the request itself must complete in time
+        let stream = tokio::time::timeout(self.timeout_config.stream_timeout(), request)
+            .await
+            .map_err(|_| Error::ProviderStreamTimeout(self.timeout_config.stream_timeout_secs))??;
+        Ok(with_timeouts(stream, &self.timeout_config))
     }
 
     async fn models(&self) -> anyhow::Result<Vec<Model>> {
-        match self {
-            Client::OpenAICompat(provider) => provider.models().await,
-            Client::Anthropic(provider) => provider.models().await,
+        match &self.inner {
+            InnerClient::OpenAICompat(provider) => provider.models().await,
+            InnerClient::Anthropic(provider) => provider.models().await,
         }
     }
 }
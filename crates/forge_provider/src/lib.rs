@@ -2,6 +2,7 @@ mod anthropic;
 mod builder;
 mod open_router;
 mod retry;
+mod timeout;
 mod utils;
 
 // Re-export from builder.rs
@@ -1,4 +1,4 @@
-use forge_domain::ModelId;
+use forge_domain::{ModelId, ModelPricing};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -29,6 +29,17 @@ pub struct Pricing {
     pub request: Option<String>,
 }
 
+impl Pricing {
+    /// Parses the prices, which OpenRouter sends as strings in USD per token
+    pub fn per_token(&self) -> Option<ModelPricing> {
+        let parse = |price: &Option<String>| price.as_deref()?.parse::<f64>().ok();
+        Some(ModelPricing {
+            prompt: parse(&self.prompt)?,
+            completion: parse(&self.completion)?,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TopProvider {
     pub context_length: Option<u64>,
@@ -40,3 +51,26 @@ pub struct TopProvider {
 pub struct ListModelResponse {
     pub data: Vec<OpenRouterModel>,
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_pricing_per_token() {
+        let fixture = Pricing {
+            prompt: Some("0.000003".to_string()),
+            completion: Some("0.000015".to_string()),
+            image: None,
+            request: Some("0".to_string()),
+        };
+        let actual = fixture.per_token();
+        let expected = Some(ModelPricing { prompt: 0.000003, completion: 0.000015 });
+        assert_eq!(actual, expected);
+
+        let fixture = Pricing { completion: None, ..fixture };
+        assert_eq!(fixture.per_token(), None);
+    }
+}
@@ -16,7 +16,7 @@ use super::model::{ListModelResponse, OpenRouterModel};
 use super::request::OpenRouterRequest;
 use super::response::OpenRouterResponse;
 use crate::open_router::transformers::{ProviderPipeline, Transformer};
-use crate::retry::StatusCodeRetryPolicy;
+use crate::retry::{retry_after, send_with_retry, StatusCodeRetryPolicy};
 use crate::utils::format_http_context;
 
 #[derive(Clone, Builder)]
@@ -25,6 +25,9 @@ pub struct OpenRouter {
     provider: Provider,
     #[builder(default = "RetryConfig::default()")]
     retry_config: RetryConfig,
+    /// Requests the responses in one piece instead of streaming them
+    #[builder(default)]
+    low_bandwidth: bool,
 }
 
 impl OpenRouter {
@@ -107,52 +110,61 @@ impl OpenRouter {
             status_codes.clone(),
         )));
 
+        let mut opened = false;
         let stream = es
             .take_while(|message| !matches!(message, Err(reqwest_eventsource::Error::StreamEnded)))
-            .then(|event| async {
-                match event {
-                    Ok(event) => match event {
-                        Event::Open => None,
-                        Event::Message(event) if ["[DONE]", ""].contains(&event.data.as_str()) => {
-                            debug!("Received completion from Upstream");
-                            None
-                        }
-                        Event::Message(message) => Some(
-                            serde_json::from_str::<OpenRouterResponse>(&message.data)
-                                .with_context(|| format!("Failed to parse OpenRouter response: {}", message.data))
-                                .and_then(|event| {
-                                    ChatCompletionMessage::try_from(event.clone())
-                                        .with_context(|| format!("Failed to create completion message: {}", message.data))
-                                }),
-                        ),
-                    },
-                    Err(error) => match error {
-                        reqwest_eventsource::Error::StreamEnded => None,
-                        reqwest_eventsource::Error::InvalidStatusCode(_, response) => {
-                            let headers = response.headers().clone();
-                            let status = response.status();
-                            match response.text().await {
-                                Ok(ref body) => {
-                                    debug!(status = ?status, headers = ?headers, body = body, "Invalid status code");
-                                    Some(Err(anyhow::anyhow!("Invalid status code: {} Reason: {}", status, body)))
-                                }
-                                Err(error) => {
-                                    debug!(status = ?status, headers = ?headers, body = ?error, "Invalid status code (body not available)");
-                                    Some(Err(anyhow::anyhow!("Invalid status code: {}", status)))
+            .then(move |event| {
+                // Every connection but the first is a reconnect after the
+                // stream dropped
+                let reconnected =
+                    matches!(event, Ok(Event::Open)) && std::mem::replace(&mut opened, true);
+                async move {
+                    match event {
+                        Ok(event) => match event {
+                            Event::Open => reconnected
+                                .then(|| Ok(ChatCompletionMessage::default().reconnected(true))),
+                            Event::Message(event) if ["[DONE]", ""].contains(&event.data.as_str()) => {
+                                debug!("Received completion from Upstream");
+                                None
+                            }
+                            Event::Message(message) => Some(
+                                serde_json::from_str::<OpenRouterResponse>(&message.data)
+                                    .with_context(|| format!("Failed to parse OpenRouter response: {}", message.data))
+                                    .and_then(|event| {
+                                        ChatCompletionMessage::try_from(event.clone())
+                                            .with_context(|| format!("Failed to create completion message: {}", message.data))
+                                    }),
+                            ),
+                        },
+                        Err(error) => match error {
+                            reqwest_eventsource::Error::StreamEnded => None,
+                            reqwest_eventsource::Error::InvalidStatusCode(_, response) => {
+                                let headers = response.headers().clone();
+                                let status = response.status();
+                                let retry_after = retry_after(&headers);
+                                match response.text().await {
+                                    Ok(ref body) => {
+                                        debug!(status = ?status, headers = ?headers, body = body, "Invalid status code");
+                                        Some(Err(anyhow::anyhow!("Invalid status code: {}{} Reason: {}", status, retry_after, body)))
+                                    }
+                                    Err(error) => {
+                                        debug!(status = ?status, headers = ?headers, body = ?error, "Invalid status code (body not available)");
+                                        Some(Err(anyhow::anyhow!("Invalid status code: {}{}", status, retry_after)))
+                                    }
                                 }
                             }
-                        }
-                        reqwest_eventsource::Error::InvalidContentType(_, ref response) => {
-                            let status_code = response.status();
-                            debug!(response = ?response, "Invalid content type");
-                            Some(Err(anyhow::anyhow!(error).context(format!("Http Status: {status_code}" ))))
+                            reqwest_eventsource::Error::InvalidContentType(_, ref response) => {
+                                let status_code = response.status();
+                                debug!(response = ?response, "Invalid content type");
+                                Some(Err(anyhow::anyhow!(error).context(format!("Http Status: {status_code}" ))))
 
-                        }
-                        error => {
-                            debug!(error = %error, "Failed to receive chat completion event");
-                            Some(Err(error.into()))
-                        }
-                    },
+                            }
+                            error => {
+                                debug!(error = %error, "Failed to receive chat completion event");
+                                Some(Err(error.into()))
+                            }
+                        },
+                    }
                 }
             }).map(move |response| {
                 match response {
@@ -164,6 +176,37 @@ impl OpenRouter {
         Ok(Box::pin(stream.filter_map(|x| x)))
     }
 
+    /// Requests the whole response at once, which takes less bandwidth than
+    /// streaming it in events
+    async fn inner_complete(
+        &self,
+        model: &ModelId,
+        context: ChatContext,
+    ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+        let mut request = OpenRouterRequest::from(context)
+            .model(model.clone())
+            .stream(false);
+        request = ProviderPipeline::new(&self.provider).transform(request);
+
+        let url = self.url("chat/completions")?;
+        debug!(url = %url, model = %model, "Requesting the complete response");
+
+        let request = self
+            .client
+            .post(url.clone())
+            .headers(self.headers())
+            .json(&request);
+        let body = send_with_retry(request, &self.retry_config, &url).await?;
+        let message = serde_json::from_str::<OpenRouterResponse>(&body)
+            .with_context(|| format!("Failed to parse OpenRouter response: {body}"))
+            .and_then(|response| {
+                ChatCompletionMessage::try_from(response)
+                    .with_context(|| format!("Failed to create completion message: {body}"))
+            })?;
+
+        Ok(Box::pin(tokio_stream::once(Ok(message))))
+    }
+
     async fn inner_models(&self) -> Result<Vec<Model>> {
         let url = self.url("models")?;
         debug!(url = %url, "Fetching models");
@@ -219,7 +262,11 @@ impl ProviderService for OpenRouter {
         model: &ModelId,
         context: ChatContext,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
-        self.inner_chat(model, context).await
+        if self.low_bandwidth {
+            self.inner_complete(model, context).await
+        } else {
+            self.inner_chat(model, context).await
+        }
     }
 
     async fn models(&self) -> Result<Vec<Model>> {
@@ -234,6 +281,7 @@ impl From<OpenRouterModel> for Model {
             name: value.name,
             description: value.description,
             context_length: value.context_length,
+            pricing: value.pricing.and_then(|pricing| pricing.per_token()),
         }
     }
 }
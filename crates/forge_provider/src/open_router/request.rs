@@ -260,7 +260,7 @@ impl From<Context> for OpenRouterRequest {
             temperature: request.temperature.map(|t| t.value()),
             tool_choice: request.tool_choice.map(|tc| tc.into()),
             seed: Default::default(),
-            top_p: Default::default(),
+            top_p: request.top_p.map(|t| t.value()),
             top_k: Default::default(),
             frequency_penalty: Default::default(),
             presence_penalty: Default::default(),
@@ -364,6 +364,7 @@ mod tests {
             role: Role::User,
             content: "Hello".to_string(),
             tool_calls: None,
+            metadata: None,
         });
         let router_message = OpenRouterMessage::from(user_message);
         assert_json_snapshot!(router_message);
@@ -385,6 +386,7 @@ mod tests {
             role: Role::User,
             content: xml_content.to_string(),
             tool_calls: None,
+            metadata: None,
         });
         let router_message = OpenRouterMessage::from(message);
         assert_json_snapshot!(router_message);
@@ -402,6 +404,7 @@ mod tests {
             role: Role::Assistant,
             content: "Using tool".to_string(),
             tool_calls: Some(vec![tool_call]),
+            metadata: None,
         });
         let router_message = OpenRouterMessage::from(assistant_message);
         assert_json_snapshot!(router_message);
@@ -116,7 +116,7 @@ impl TryFrom<OpenRouterResponse> for ModelResponse {
 
     fn try_from(res: OpenRouterResponse) -> Result<Self, Self::Error> {
         match res {
-            OpenRouterResponse::Success { choices, usage, .. } => {
+            OpenRouterResponse::Success { choices, usage, model, provider, .. } => {
                 if let Some(choice) = choices.first() {
                     let mut response = match choice {
                         Choice::NonChat { text, finish_reason, .. } => {
@@ -177,6 +177,8 @@ impl TryFrom<OpenRouterResponse> for ModelResponse {
                     if let Some(usage) = usage {
                         response.usage = Some(usage.into());
                     }
+                    response.model = Some(model);
+                    response.provider = provider;
                     Ok(response)
                 } else {
                     let default_response = ModelResponse::assistant(Content::full(""));
@@ -221,4 +223,16 @@ mod tests {
         let event = "{\"id\":\"gen-1739949430-JZMcABaj4fg8oFDtRNDZ\",\"provider\":\"OpenAI\",\"model\":\"openai/gpt-4o-mini\",\"object\":\"chat.completion.chunk\",\"created\":1739949430,\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":null,\"tool_calls\":[{\"index\":0,\"id\":\"call_bhjvz9w48ov4DSRhM15qLMmh\",\"type\":\"function\",\"function\":{\"name\":\"forge_tool_process_shell\",\"arguments\":\"\"}}],\"refusal\":null},\"logprobs\":null,\"finish_reason\":null,\"native_finish_reason\":null}],\"system_fingerprint\":\"fp_00428b782a\"}";
         assert!(Fixture::test_response_compatibility(event));
     }
+
+    #[test]
+    fn test_open_router_response_metadata() {
+        let event = "{\"id\":\"gen-1739949430-JZMcABaj4fg8oFDtRNDZ\",\"provider\":\"OpenAI\",\"model\":\"openai/gpt-4o-mini\",\"object\":\"chat.completion.chunk\",\"created\":1739949430,\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"finish_reason\":\"stop\"}]}";
+        let response = serde_json::from_str::<OpenRouterResponse>(event).unwrap();
+
+        let actual = ChatCompletionMessage::try_from(response).unwrap();
+
+        assert_eq!(actual.model.as_deref(), Some("openai/gpt-4o-mini"));
+        assert_eq!(actual.provider.as_deref(), Some("OpenAI"));
+        assert_eq!(actual.finish_reason, Some(FinishReason::Stop));
+    }
 }
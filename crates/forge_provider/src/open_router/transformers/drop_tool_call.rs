@@ -54,6 +54,7 @@ mod tests {
                     role: Role::Assistant,
                     content: "Using tool".to_string(),
                     tool_calls: Some(vec![tool_call]),
+                    metadata: None,
                 }),
                 ContextMessage::ToolMessage(tool_result),
             ],
@@ -61,6 +62,7 @@ mod tests {
             tool_choice: None,
             max_tokens: None,
             temperature: None,
+            top_p: None,
         };
 
         let request = OpenRouterRequest::from(context);
@@ -55,16 +55,19 @@ mod tests {
                         role: Role::System,
                         content: c.to_string(),
                         tool_calls: None,
+                        metadata: None,
                     }),
                     'u' => ContextMessage::ContentMessage(ContentMessage {
                         role: Role::User,
                         content: c.to_string(),
                         tool_calls: None,
+                        metadata: None,
                     }),
                     'a' => ContextMessage::ContentMessage(ContentMessage {
                         role: Role::Assistant,
                         content: c.to_string(),
                         tool_calls: None,
+                        metadata: None,
                     }),
                     _ => {
                         panic!("Invalid character in test message");
@@ -75,6 +78,7 @@ mod tests {
             tool_choice: None,
             max_tokens: None,
             temperature: None,
+            top_p: None,
         };
 
         let request = OpenRouterRequest::from(context);
@@ -1,7 +1,14 @@
 use std::time::Duration;
 
+use anyhow::Context as _;
+use forge_domain::RetryConfig;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Url};
 use reqwest_eventsource::retry::RetryPolicy;
 use reqwest_eventsource::Error;
+use tracing::debug;
+
+use crate::utils::format_http_context;
 
 /// A RetryPolicy that only retries on specific status codes
 pub struct StatusCodeRetryPolicy {
@@ -57,3 +64,65 @@ impl RetryPolicy for StatusCodeRetryPolicy {
         self.inner.set_reconnection_time(duration)
     }
 }
+
+/// The `Retry-After` header of a response in seconds, formatted to be
+/// appended to the error of the request, so that the orchestrator waits as
+/// long as the provider asks before retrying the turn. Dates aren't supported
+/// since providers send seconds.
+pub(crate) fn retry_after(headers: &HeaderMap) -> String {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|seconds| format!(" Retry-After: {seconds}"))
+        .unwrap_or_default()
+}
+
+/// Sends a request whose response isn't streamed and returns its body,
+/// retrying on the same status codes and transport errors as the streamed
+/// requests
+pub(crate) async fn send_with_retry(
+    request: RequestBuilder,
+    retry_config: &RetryConfig,
+    url: &Url,
+) -> anyhow::Result<String> {
+    let mut backoff = Duration::from_millis(retry_config.initial_backoff_ms);
+    let mut attempt = 0;
+    loop {
+        let response = request
+            .try_clone()
+            .context("The request can't be retried")?
+            .send()
+            .await;
+        let can_retry = attempt < retry_config.max_retry_attempts;
+        match response {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() {
+                    return response
+                        .text()
+                        .await
+                        .with_context(|| format_http_context(Some(status), "POST", url));
+                }
+                if !(can_retry && retry_config.retry_status_codes.contains(&status.as_u16())) {
+                    let retry_after = retry_after(response.headers());
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(anyhow::anyhow!(
+                        "Invalid status code: {status}{retry_after}, reason: {body}"
+                    ))
+                    .context(format_http_context(Some(status), "POST", url));
+                }
+                debug!(status = ?status, attempt, "Retrying the request");
+            }
+            Err(error) if can_retry => {
+                debug!(error = %error, attempt, "Retrying the request");
+            }
+            Err(error) => {
+                return Err(error).context(format_http_context(None, "POST", url));
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = backoff.mul_f64(retry_config.backoff_factor as f64);
+        attempt += 1;
+    }
+}
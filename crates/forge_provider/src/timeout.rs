@@ -0,0 +1,120 @@
+use forge_domain::{BoxStream, ChatCompletionMessage, Error, TimeoutConfig};
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+
+/// State of a response stream wrapped with timeouts
+struct TimedStream {
+    stream: BoxStream<ChatCompletionMessage, anyhow::Error>,
+    received: bool,
+    timed_out: bool,
+}
+
+/// Fails the response stream when the first event doesn't arrive within the
+/// first token timeout or when the whole response takes longer than the stream
+/// timeout. The stream ends after the timeout error.
+pub fn with_timeouts(
+    stream: BoxStream<ChatCompletionMessage, anyhow::Error>,
+    config: &TimeoutConfig,
+) -> BoxStream<ChatCompletionMessage, anyhow::Error> {
+    let start = Instant::now();
+    let first_token_deadline =
+        (start + config.first_token_timeout()).min(start + config.stream_timeout());
+    let stream_deadline = start + config.stream_timeout();
+    let config = config.clone();
+
+    let state = TimedStream { stream, received: false, timed_out: false };
+    Box::pin(futures::stream::unfold(state, move |mut state| {
+        let config = config.clone();
+        async move {
+            if state.timed_out {
+                return None;
+            }
+
+            let deadline = if state.received {
+                stream_deadline
+            } else {
+                first_token_deadline
+            };
+            match tokio::time::timeout_at(deadline, state.stream.next()).await {
+                Ok(Some(message)) => {
+                    state.received = true;
+                    Some((message, state))
+                }
+                Ok(None) => None,
+                Err(_) => {
+                    let error = if deadline == stream_deadline {
+                        Error::ProviderStreamTimeout(config.stream_timeout_secs)
+                    } else {
+                        Error::ProviderFirstTokenTimeout(config.first_token_timeout_secs)
+                    };
+                    state.timed_out = true;
+                    Some((Err(error.into()), state))
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use forge_domain::Content;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn config() -> TimeoutConfig {
+        TimeoutConfig::default()
+            .first_token_timeout_secs(10u64)
+            .stream_timeout_secs(60u64)
+    }
+
+    /// A stream that sends a message after each of the delays
+    fn delayed(delays: Vec<u64>) -> BoxStream<ChatCompletionMessage, anyhow::Error> {
+        Box::pin(futures::stream::iter(delays).then(|delay| async move {
+            tokio::time::sleep(Duration::from_secs(delay)).await;
+            Ok(ChatCompletionMessage::assistant(Content::part(format!(
+                "after {delay}"
+            ))))
+        }))
+    }
+
+    async fn collect(stream: BoxStream<ChatCompletionMessage, anyhow::Error>) -> Vec<String> {
+        stream
+            .map(|message| match message {
+                Ok(_) => "message".to_string(),
+                Err(error) => error.to_string(),
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_within_timeouts() {
+        let actual = collect(with_timeouts(delayed(vec![5, 20, 20]), &config())).await;
+        assert_eq!(actual, vec!["message", "message", "message"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_first_token_timeout() {
+        let actual = collect(with_timeouts(delayed(vec![15, 1]), &config())).await;
+        assert_eq!(
+            actual,
+            vec!["The provider didn't respond within 10 seconds"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_timeout() {
+        let actual = collect(with_timeouts(delayed(vec![5, 30, 30]), &config())).await;
+        assert_eq!(
+            actual,
+            vec![
+                "message",
+                "message",
+                "The response of the provider didn't complete within 60 seconds"
+            ]
+        );
+    }
+}
@@ -4,7 +4,9 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use base64::Engine;
-use forge_domain::{Attachment, AttachmentService, ContentType, EnvironmentService};
+use forge_domain::{
+    wrap_untrusted, Attachment, AttachmentService, ContentType, EnvironmentService,
+};
 
 use crate::{FsReadService, Infrastructure};
 
@@ -41,7 +43,11 @@ impl<F: Infrastructure> ForgeChatRequest<F> {
 
         writeln!(response, "---")?;
 
-        writeln!(response, "{}", &content)?;
+        writeln!(
+            response,
+            "{}",
+            wrap_untrusted(&path.display().to_string(), &content)
+        )?;
 
         Ok(response)
     }
@@ -122,8 +128,10 @@ pub mod tests {
     use bytes::Bytes;
     use forge_domain::{
         AttachmentService, CommandOutput, ContentType, Environment, EnvironmentService, Provider,
+        DEFAULT_REGISTRY_URL,
     };
     use forge_snaps::Snapshot;
+    use url::Url;
 
     use crate::attachment::ForgeChatRequest;
     use crate::{
@@ -146,6 +154,15 @@ pub mod tests {
                 base_path: PathBuf::from("/base"),
                 provider: Provider::open_router("test-key"),
                 retry_config: Default::default(),
+                timeout_config: Default::default(),
+                registry_url: Url::parse(DEFAULT_REGISTRY_URL).unwrap(),
+                shell_timeout: 240,
+                tool_timeout: 300,
+                max_tool_result_chars: 50_000,
+                default_mode: None,
+                low_bandwidth: false,
+                notify_after: None,
+                template_env: vec![],
             }
         }
     }
@@ -303,6 +320,10 @@ pub mod tests {
         async fn undo_snapshot(&self, _: &Path) -> anyhow::Result<()> {
             unimplemented!()
         }
+
+        async fn read_snapshot(&self, _: &Path, _: usize) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
@@ -339,6 +360,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    timed_out: false,
+                    violation: None,
                 });
             } else if command.contains("echo") {
                 if command.contains(">") && command.contains(">&2") {
@@ -358,6 +381,8 @@ pub mod tests {
                         stderr: stderr.to_string(),
                         command,
                         exit_code: Some(0),
+                        timed_out: false,
+                        violation: None,
                     });
                 } else if command.contains(">&2") {
                     // Command with only stderr
@@ -368,6 +393,8 @@ pub mod tests {
                         stderr: format!("{content}\n"),
                         command,
                         exit_code: Some(0),
+                        timed_out: false,
+                        violation: None,
                     });
                 } else {
                     // Standard echo command
@@ -396,6 +423,8 @@ pub mod tests {
                         stderr: "".to_string(),
                         command,
                         exit_code: Some(0),
+                        timed_out: false,
+                        violation: None,
                     });
                 }
             } else if command == "pwd" || command == "cd" {
@@ -405,6 +434,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    timed_out: false,
+                    violation: None,
                 });
             } else if command == "true" {
                 // true command returns success with no output
@@ -413,6 +444,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    timed_out: false,
+                    violation: None,
                 });
             } else if command.starts_with("/bin/ls") || command.contains("whoami") {
                 // Full path commands
@@ -421,6 +454,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    timed_out: false,
+                    violation: None,
                 });
             } else if command == "non_existent_command" {
                 // Command not found
@@ -429,6 +464,8 @@ pub mod tests {
                     stderr: "command not found: non_existent_command\n".to_string(),
                     command,
                     exit_code: Some(-1),
+                    timed_out: false,
+                    violation: None,
                 });
             }
 
@@ -438,6 +475,8 @@ pub mod tests {
                 stderr: "".to_string(),
                 command,
                 exit_code: Some(0),
+                timed_out: false,
+                violation: None,
             })
         }
     }
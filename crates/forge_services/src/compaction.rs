@@ -3,7 +3,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use forge_domain::{
     extract_tag_content, Agent, ChatCompletionMessage, Compact, CompactionService, Context,
-    ContextMessage, ProviderService, Role, TemplateService,
+    ContextMessage, ProviderService, TemplateService,
 };
 use futures::StreamExt;
 use tracing::{debug, info};
@@ -167,65 +167,10 @@ impl<T: TemplateService, P: ProviderService> ForgeCompactionService<T, P> {
     }
 }
 
-/// Finds a sequence in the context for compaction, starting from the first
-/// assistant message and including all messages up to the last possible message
-/// (respecting preservation window)
+/// Finds a sequence in the context for compaction, see
+/// [`Context::compaction_sequence`]
 fn find_sequence(context: &Context, preserve_last_n: usize) -> Option<(usize, usize)> {
-    let messages = &context.messages;
-    if messages.is_empty() {
-        return None;
-    }
-
-    // len will be always > 0
-    let length = messages.len();
-
-    // Find the first assistant message index
-    let start = messages
-        .iter()
-        .enumerate()
-        .find(|(_, message)| message.has_role(Role::Assistant))
-        .map(|(index, _)| index)?;
-
-    // Don't compact if there's no assistant message
-    if start >= length {
-        return None;
-    }
-
-    // Calculate the end index based on preservation window
-    // If we need to preserve all or more messages than we have, there's nothing to
-    // compact
-    if preserve_last_n >= length {
-        return None;
-    }
-
-    // Use saturating subtraction to prevent potential overflow
-    let end = length.saturating_sub(preserve_last_n).saturating_sub(1);
-
-    // Ensure we have at least two messages to create a meaningful summary
-    // If start > end or end is invalid, don't compact
-    if start > end || end >= length || end.saturating_sub(start) < 1 {
-        return None;
-    }
-
-    // Don't break between a tool call and its result
-    if messages.get(end).is_some_and(|msg| msg.has_tool_call()) {
-        // If the last message has a tool call, adjust end to include the tool result
-        // This means either not compacting at all, or reducing the end by 1
-        if end == start {
-            // If start == end and it has a tool call, don't compact
-            return None;
-        } else {
-            // Otherwise reduce end by 1
-            return Some((start, end.saturating_sub(1)));
-        }
-    }
-
-    // Return the sequence only if it has at least one message
-    if end >= start {
-        Some((start, end))
-    } else {
-        None
-    }
+    context.compaction_sequence(preserve_last_n)
 }
 
 #[async_trait::async_trait]
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use anyhow::{Context as AnyhowContext, Result};
 use forge_domain::{
     AgentId, CompactionResult, CompactionService, Conversation, ConversationId,
-    ConversationService, Workflow,
+    ConversationService, Error, Workflow,
 };
 use tokio::sync::Mutex;
 
@@ -27,6 +27,21 @@ impl<C: CompactionService> ForgeConversationService<C> {
     }
 }
 
+impl<C> Drop for ForgeConversationService<C> {
+    /// Removes the temporary directories of the conversations once the last
+    /// handle to the service is dropped, at the end of the session
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.workflows) > 1 {
+            return;
+        }
+        if let Ok(workflows) = self.workflows.try_lock() {
+            for conversation in workflows.values() {
+                let _ = std::fs::remove_dir_all(conversation.tmp_dir());
+            }
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl<C: CompactionService> ConversationService for ForgeConversationService<C> {
     async fn update<F, T>(&self, id: &ConversationId, f: F) -> Result<T>
@@ -35,19 +50,32 @@ impl<C: CompactionService> ConversationService for ForgeConversationService<C> {
     {
         let mut workflows = self.workflows.lock().await;
         let conversation = workflows.get_mut(id).context("Conversation not found")?;
-        Ok(f(conversation))
+        let output = f(conversation);
+        conversation.version += 1;
+        Ok(output)
     }
 
     async fn find(&self, id: &ConversationId) -> Result<Option<Conversation>> {
         Ok(self.workflows.lock().await.get(id).cloned())
     }
 
-    async fn upsert(&self, conversation: Conversation) -> Result<()> {
-        self.workflows
-            .lock()
-            .await
-            .insert(conversation.id.clone(), conversation);
-        Ok(())
+    async fn upsert(&self, mut conversation: Conversation) -> Result<u64> {
+        let mut workflows = self.workflows.lock().await;
+        if let Some(stored) = workflows.get(&conversation.id) {
+            if stored.version != conversation.version {
+                return Err(Error::ConversationConflict(
+                    conversation.id,
+                    conversation.version,
+                    stored.version,
+                )
+                .into());
+            }
+        }
+
+        conversation.version += 1;
+        let version = conversation.version;
+        workflows.insert(conversation.id.clone(), conversation);
+        Ok(version)
     }
 
     async fn create(&self, workflow: Workflow) -> Result<Conversation> {
@@ -107,3 +135,44 @@ impl<C: CompactionService> ConversationService for ForgeConversationService<C> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::{Agent, Context};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    struct NoCompaction;
+
+    #[async_trait::async_trait]
+    impl CompactionService for NoCompaction {
+        async fn compact_context(&self, _agent: &Agent, context: Context) -> Result<Context> {
+            Ok(context)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upsert_conflict() {
+        let service = ForgeConversationService::new(Arc::new(NoCompaction));
+        let conversation = service.create(Workflow::new()).await.unwrap();
+
+        // Two writers read the same version
+        let first = service.find(&conversation.id).await.unwrap().unwrap();
+        let second = first.clone();
+
+        let actual = service.upsert(first).await.unwrap();
+        assert_eq!(actual, 1);
+
+        let error = service.upsert(second).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::ConversationConflict(_, 0, 1))
+        ));
+
+        // Writing the latest version succeeds
+        let latest = service.find(&conversation.id).await.unwrap().unwrap();
+        let actual = service.upsert(latest).await.unwrap();
+        assert_eq!(actual, 2);
+    }
+}
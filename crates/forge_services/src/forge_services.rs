@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
-use forge_domain::Services;
+use forge_domain::{EnvironmentService, Services};
 
 use crate::attachment::ForgeChatRequest;
 use crate::compaction::ForgeCompactionService;
 use crate::conversation::ForgeConversationService;
 use crate::provider::ForgeProviderService;
+use crate::registry::ForgeRegistryService;
 use crate::suggestion::ForgeSuggestionService;
 use crate::template::ForgeTemplateService;
 use crate::tool_service::ForgeToolService;
@@ -33,12 +34,14 @@ pub struct ForgeServices<F> {
     compaction_service: Arc<ForgeCompactionService<ForgeTemplateService, ForgeProviderService>>,
     workflow_service: Arc<ForgeWorkflowService<F>>,
     suggestion_service: Arc<ForgeSuggestionService<F>>,
+    registry_service: Arc<ForgeRegistryService<F>>,
 }
 
 impl<F: Infrastructure> ForgeServices<F> {
     pub fn new(infra: Arc<F>) -> Self {
         let tool_service = Arc::new(ForgeToolService::new(infra.clone()));
-        let template_service = Arc::new(ForgeTemplateService::new());
+        let env = infra.environment_service().get_environment();
+        let template_service = Arc::new(ForgeTemplateService::from_env(&env));
         let provider_service = Arc::new(ForgeProviderService::new(infra.clone()));
         let attachment_service = Arc::new(ForgeChatRequest::new(infra.clone()));
         let compaction_service = Arc::new(ForgeCompactionService::new(
@@ -51,6 +54,7 @@ impl<F: Infrastructure> ForgeServices<F> {
 
         let workflow_service = Arc::new(ForgeWorkflowService::new(infra.clone()));
         let suggestion_service = Arc::new(ForgeSuggestionService::new(infra.clone()));
+        let registry_service = Arc::new(ForgeRegistryService::new(infra.clone()));
         Self {
             infra,
             conversation_service,
@@ -61,6 +65,7 @@ impl<F: Infrastructure> ForgeServices<F> {
             template_service,
             workflow_service,
             suggestion_service,
+            registry_service,
         }
     }
 }
@@ -75,6 +80,7 @@ impl<F: Infrastructure> Services for ForgeServices<F> {
     type CompactionService = ForgeCompactionService<Self::TemplateService, Self::ProviderService>;
     type WorkflowService = ForgeWorkflowService<F>;
     type SuggestionService = ForgeSuggestionService<F>;
+    type RegistryService = ForgeRegistryService<F>;
 
     fn tool_service(&self) -> &Self::ToolService {
         &self.tool_service
@@ -111,6 +117,10 @@ impl<F: Infrastructure> Services for ForgeServices<F> {
     fn suggestion_service(&self) -> &Self::SuggestionService {
         self.suggestion_service.as_ref()
     }
+
+    fn registry_service(&self) -> &Self::RegistryService {
+        self.registry_service.as_ref()
+    }
 }
 
 impl<F: Infrastructure> Infrastructure for ForgeServices<F> {
@@ -1,9 +1,11 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::Result;
 use bytes::Bytes;
-use forge_domain::{CommandOutput, EnvironmentService};
+use forge_domain::{CommandOutput, CommandOutputChunk, EnvironmentService, ResourceLimits};
 use forge_snaps::Snapshot;
+use tokio::sync::mpsc::Sender;
 
 /// Repository for accessing system environment information
 /// This uses the EnvironmentService trait from forge_domain
@@ -85,6 +87,10 @@ pub trait FsSnapshotService: Send + Sync {
 
     /// Restores the most recent snapshot for the given file path
     async fn undo_snapshot(&self, file_path: &Path) -> Result<()>;
+
+    /// Returns the content of a snapshot of the file, 0 being the most recent
+    /// one
+    async fn read_snapshot(&self, file_path: &Path, index: usize) -> Result<Vec<u8>>;
 }
 
 /// Service for executing shell commands
@@ -96,6 +102,49 @@ pub trait CommandExecutorService: Send + Sync {
         command: String,
         working_dir: PathBuf,
     ) -> anyhow::Result<CommandOutput>;
+
+    /// Executes a shell command, forwarding its output through the sender as
+    /// it is produced, and returns the complete output once it finishes. If
+    /// the command doesn't finish within the timeout it is terminated and the
+    /// returned output is marked as timed out. The variables are added to the
+    /// environment of the command. The default implementation doesn't enforce
+    /// the resource limits or set the variables.
+    async fn execute_command_streamed(
+        &self,
+        command: String,
+        working_dir: PathBuf,
+        sender: Sender<CommandOutputChunk>,
+        timeout: Option<Duration>,
+        _limits: ResourceLimits,
+        _env: Vec<(String, String)>,
+    ) -> anyhow::Result<CommandOutput> {
+        let execution = self.execute_command(command.clone(), working_dir);
+        let output = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, execution).await {
+                Ok(output) => output?,
+                Err(_) => CommandOutput {
+                    command,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    exit_code: None,
+                    timed_out: true,
+                    violation: None,
+                },
+            },
+            None => execution.await?,
+        };
+        if !output.stdout.is_empty() {
+            sender
+                .send(CommandOutputChunk::stdout(&output.stdout))
+                .await?;
+        }
+        if !output.stderr.is_empty() {
+            sender
+                .send(CommandOutputChunk::stderr(&output.stderr))
+                .await?;
+        }
+        Ok(output)
+    }
 }
 
 #[async_trait::async_trait]
@@ -6,10 +6,12 @@ mod forge_services;
 mod infra;
 mod metadata;
 mod provider;
+mod registry;
 mod suggestion;
 mod template;
 mod tool_service;
 mod tools;
+mod truncation;
 mod workflow;
 
 pub use clipper::*;
@@ -21,8 +21,11 @@ impl ForgeProviderService {
         let env = infra.environment_service().get_environment();
         let provider = env.provider.clone();
         let retry_config = env.retry_config;
+        let timeout_config = env.timeout_config;
         Self {
-            client: Arc::new(Client::new(provider, retry_config).unwrap()),
+            client: Arc::new(
+                Client::new(provider, retry_config, timeout_config, env.low_bandwidth).unwrap(),
+            ),
         }
     }
 }
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_domain::{AgentPackage, EnvironmentService, RegistryIndex, RegistryService};
+use reqwest::Client;
+
+use crate::Infrastructure;
+
+/// Fetches the community registry index of agent and workflow packages
+pub struct ForgeRegistryService<F> {
+    infra: Arc<F>,
+    client: Client,
+}
+
+impl<F> ForgeRegistryService<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self { infra, client: Client::new() }
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> RegistryService for ForgeRegistryService<F> {
+    async fn packages(&self) -> anyhow::Result<Vec<AgentPackage>> {
+        let url = self
+            .infra
+            .environment_service()
+            .get_environment()
+            .registry_url;
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch registry index from {url}"))?
+            .error_for_status()
+            .with_context(|| format!("Registry index at {url} returned an error"))?;
+
+        let index: RegistryIndex = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse registry index from {url}"))?;
+
+        Ok(index.packages)
+    }
+}
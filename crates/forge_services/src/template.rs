@@ -1,8 +1,15 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use forge_domain::TemplateService;
-use handlebars::Handlebars;
+use chrono::Local;
+use forge_domain::{Environment, TemplateService};
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+    RenderError, RenderErrorReason,
+};
 use rust_embed::Embed;
+use tracing::{debug, warn};
 
 #[derive(Embed)]
 #[folder = "../../templates/"]
@@ -11,6 +18,9 @@ struct Templates;
 #[derive(Clone)]
 pub struct ForgeTemplateService {
     hb: Arc<Handlebars<'static>>,
+    /// Renders the templates of untrusted workspaces, without the helpers that
+    /// read the environment and the files of the user
+    untrusted: Arc<Handlebars<'static>>,
 }
 
 impl Default for ForgeTemplateService {
@@ -21,14 +31,190 @@ impl Default for ForgeTemplateService {
 
 impl ForgeTemplateService {
     pub fn new() -> Self {
+        Self::build(
+            None,
+            Vec::new(),
+            std::env::current_dir().unwrap_or_default(),
+        )
+    }
+
+    /// Uses the templates of the user's template directory in place of the
+    /// built-in ones with the same name, so that users can customize the
+    /// partials without rebuilding forge, and lets the templates read the
+    /// environment variables the user allowed and the files of the working
+    /// directory
+    pub fn from_env(env: &Environment) -> Self {
+        Self::build(
+            env.templates_path().as_deref(),
+            env.template_env.clone(),
+            env.cwd.clone(),
+        )
+    }
+
+    fn build(overrides: Option<&Path>, env_allowlist: Vec<String>, cwd: PathBuf) -> Self {
+        let mut hb = Self::handlebars(overrides);
+        hb.register_helper(
+            "file",
+            Box::new(
+                move |h: &Helper,
+                      _: &Handlebars,
+                      _: &Context,
+                      _: &mut RenderContext,
+                      out: &mut dyn Output|
+                      -> HelperResult {
+                    let path = h
+                        .param(0)
+                        .and_then(|param| param.value().as_str())
+                        .ok_or(RenderErrorReason::ParamNotFoundForIndex("file", 0))?;
+                    out.write(&read_project_file(&cwd, Path::new(path))?)?;
+                    Ok(())
+                },
+            ),
+        );
+        hb.register_helper(
+            "env",
+            Box::new(
+                move |h: &Helper,
+                      _: &Handlebars,
+                      _: &Context,
+                      _: &mut RenderContext,
+                      out: &mut dyn Output|
+                      -> HelperResult {
+                    let name = h
+                        .param(0)
+                        .and_then(|param| param.value().as_str())
+                        .ok_or(RenderErrorReason::ParamNotFoundForIndex("env", 0))?;
+                    out.write(&read_env(name, &env_allowlist)?)?;
+                    Ok(())
+                },
+            ),
+        );
+
+        Self {
+            hb: Arc::new(hb),
+            untrusted: Arc::new(Self::handlebars(overrides)),
+        }
+    }
+
+    /// Handlebars with the templates and the helpers that are safe for any
+    /// workspace
+    fn handlebars(overrides: Option<&Path>) -> Handlebars<'static> {
         let mut hb = Handlebars::new();
         hb.set_strict_mode(true);
         hb.register_escape_fn(|str| str.to_string());
 
         // Register all partial templates
         hb.register_embed_templates::<Templates>().unwrap();
+        if let Some(dir) = overrides {
+            register_overrides(&mut hb, dir);
+        }
+
+        hb.register_helper("date", Box::new(date_helper));
+        hb.register_helper("truncate", Box::new(truncate_helper));
+        hb.register_helper("json", Box::new(json_helper));
+        hb
+    }
+}
+
+/// Registers the `.hbs` files of the directory by their file name, replacing
+/// the built-in templates. A template that fails to parse is skipped, leaving
+/// the built-in one in place.
+fn register_overrides(hb: &mut Handlebars, dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let paths = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "hbs"));
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let result = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(hb.register_template_string(name, content)?));
+        match result {
+            Ok(()) => debug!(template = name, "Using the user's template"),
+            Err(error) => warn!(path = %path.display(), error = %error, "Skipping the template"),
+        }
+    }
+}
+
+handlebars_helper!(json_helper: |value: Json| serde_json::to_string_pretty(value).unwrap_or_default());
+handlebars_helper!(truncate_helper: |text: str, length: u64| truncate(text, length as usize));
+
+/// `{{date}}` is today's date, `{{date format="%A %H:%M"}}` formats the
+/// current time with the specifiers of strftime
+fn date_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let format = h
+        .hash_get("format")
+        .and_then(|format| format.value().as_str())
+        .unwrap_or("%Y-%m-%d");
+    let mut date = String::new();
+    write!(date, "{}", Local::now().format(format))
+        .map_err(|_| RenderErrorReason::Other(format!("Invalid date format '{format}'")))?;
+    out.write(&date)?;
+    Ok(())
+}
+
+/// Whether the variable holds the credentials of a provider, which templates
+/// can't read even when the user allowed them
+fn is_secret(name: &str) -> bool {
+    name == "FORGE_KEY" || name.ends_with("_API_KEY") || name.ends_with("_TOKEN")
+}
+
+/// Reads an environment variable, which must be allowed by the user and must
+/// not be a secret so that the templates can't send API keys to the model
+fn read_env(name: &str, allowlist: &[String]) -> Result<String, RenderError> {
+    if is_secret(name) {
+        return Err(RenderErrorReason::Other(format!(
+            "{name} holds a secret and can't be read by templates"
+        ))
+        .into());
+    }
+    if !allowlist.iter().any(|allowed| allowed == name) {
+        return Err(RenderErrorReason::Other(format!(
+            "{name} can't be read by templates, add it to FORGE_TEMPLATE_ENV to allow it"
+        ))
+        .into());
+    }
+    Ok(std::env::var(name).unwrap_or_default())
+}
+
+/// `{{file "path"}}` inlines a file, which must be within the working
+/// directory so that the templates can't send other files of the user to the
+/// model
+fn read_project_file(cwd: &Path, path: &Path) -> Result<String, RenderError> {
+    let error = |reason: String| RenderErrorReason::Other(reason);
+    let cwd = cwd.canonicalize().map_err(RenderErrorReason::from)?;
+    let resolved = cwd
+        .join(path)
+        .canonicalize()
+        .map_err(|e| error(format!("Failed to read {}: {e}", path.display())))?;
+    if !resolved.starts_with(&cwd) {
+        return Err(error(format!(
+            "{} is outside of the working directory",
+            path.display()
+        ))
+        .into());
+    }
+    std::fs::read_to_string(&resolved)
+        .map_err(|e| error(format!("Failed to read {}: {e}", path.display())).into())
+}
 
-        Self { hb: Arc::new(hb) }
+/// Keeps the first `length` characters of the text, marking the cut with an
+/// ellipsis
+fn truncate(text: &str, length: usize) -> String {
+    match text.char_indices().nth(length) {
+        Some((index, _)) => format!("{}...", &text[..index]),
+        None => text.to_string(),
     }
 }
 
@@ -43,6 +229,16 @@ impl TemplateService for ForgeTemplateService {
         let rendered = self.hb.render_template(&template, object)?;
         Ok(rendered)
     }
+
+    fn render_untrusted(
+        &self,
+        template: impl ToString,
+        object: &impl serde::Serialize,
+    ) -> anyhow::Result<String> {
+        let template = template.to_string();
+        let rendered = self.untrusted.render_template(&template, object)?;
+        Ok(rendered)
+    }
 }
 
 #[cfg(test)]
@@ -97,4 +293,118 @@ mod tests {
         // values
         assert!(actual.contains("<operating_system>test-os</operating_system>"));
     }
+
+    #[test]
+    fn test_helpers() {
+        let service = ForgeTemplateService::new();
+        let data = json!({"notes": "a long note", "config": {"retries": 2}});
+
+        let actual = service
+            .render("{{truncate notes 6}}|{{json config}}", &data)
+            .unwrap();
+        assert_eq!(actual, "a long...|{\n  \"retries\": 2\n}");
+
+        let actual = service.render("{{date}}", &data).unwrap();
+        assert_eq!(actual, Local::now().format("%Y-%m-%d").to_string());
+    }
+
+    #[test]
+    fn test_env_helper_allowlist() {
+        let allowlist = ["PATH", "FORGE_UNDEFINED_VARIABLE"];
+        let service = ForgeTemplateService::build(
+            None,
+            allowlist.map(String::from).to_vec(),
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+        );
+
+        let actual = service.render("{{env \"PATH\"}}", &json!({})).unwrap();
+        assert_eq!(actual, std::env::var("PATH").unwrap());
+
+        let actual = service.render("{{env \"FORGE_UNDEFINED_VARIABLE\"}}", &json!({}));
+        assert_eq!(actual.unwrap(), "");
+
+        let actual = service.render("{{env \"FORGE_HOME\"}}", &json!({}));
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .contains("FORGE_HOME can't be read by templates"));
+    }
+
+    #[test]
+    fn test_env_helper_refuses_secrets() {
+        let allowlist = ["FORGE_KEY", "GITHUB_TOKEN", "ANTHROPIC_API_KEY"];
+        let service = ForgeTemplateService::build(
+            None,
+            allowlist.map(String::from).to_vec(),
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+        );
+
+        for name in allowlist {
+            let actual = service.render(format!("{{{{env \"{name}\"}}}}"), &json!({}));
+            assert!(actual
+                .unwrap_err()
+                .to_string()
+                .contains(&format!("{name} holds a secret")));
+        }
+    }
+
+    #[test]
+    fn test_render_untrusted() {
+        let service = ForgeTemplateService::new();
+
+        let actual = service.render_untrusted("{{truncate \"abc\" 1}}", &json!({}));
+        assert_eq!(actual.unwrap(), "a...");
+
+        for template in ["{{env \"FORGE_HOME\"}}", "{{file \"Cargo.toml\"}}"] {
+            assert!(service.render_untrusted(template, &json!({})).is_err());
+        }
+    }
+
+    #[test]
+    fn test_file_helper() {
+        let service =
+            ForgeTemplateService::build(None, vec![], PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+
+        let actual = service
+            .render("{{file \"Cargo.toml\"}}", &json!({}))
+            .unwrap();
+        assert!(actual.contains("name = \"forge_services\""));
+
+        let actual = service.render("{{file \"../../Cargo.toml\"}}", &json!({}));
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .contains("../../Cargo.toml is outside of the working directory"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("partial-tool-required.hbs"),
+            "Call a tool, {{name}}.",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("partial-broken.hbs"), "{{#if}}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let service = ForgeTemplateService::build(Some(dir.path()), vec![], dir.path().into());
+
+        let actual = service
+            .render("{{> partial-tool-required.hbs}}", &json!({"name": "Forge"}))
+            .unwrap();
+        assert_eq!(actual, "Call a tool, Forge.");
+
+        // The other built-in templates are kept
+        let data = json!({
+            "env": {"os": "test-os", "cwd": "/test", "shell": "/bin/sh", "home": "/home/test"},
+            "files": []
+        });
+        let actual = service
+            .render("{{> partial-system-info.hbs}}", &data)
+            .unwrap();
+        assert!(actual.contains("<operating_system>test-os</operating_system>"));
+        assert!(service
+            .render("{{> partial-broken.hbs}}", &json!({}))
+            .is_err());
+    }
 }
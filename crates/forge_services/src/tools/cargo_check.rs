@@ -0,0 +1,353 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::bail;
+use forge_display::TitleFormat;
+use forge_domain::{
+    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolErrorCode,
+    ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::utils::{assert_absolute_path, format_display_path};
+use crate::{Clipper, CommandExecutorService, Infrastructure};
+
+/// Number of diagnostics returned when no limit is given
+const DEFAULT_LIMIT: usize = 20;
+
+/// Number of characters of cargo's stderr kept when the build fails without
+/// compiler diagnostics
+const MAX_STDERR_CHARS: usize = 5_000;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CargoCheckInput {
+    /// The absolute path of the directory of the crate or workspace to check.
+    pub path: String,
+
+    /// Name of the package to check. If not provided, the default members of
+    /// the workspace are checked.
+    pub package: Option<String>,
+
+    /// Whether to check tests, examples and benches as well as the library
+    /// and binaries. Defaults to false.
+    #[serde(default)]
+    pub all_targets: bool,
+
+    /// Maximum number of diagnostics to return, errors first. Defaults to 20.
+    pub limit: Option<usize>,
+}
+
+/// A diagnostic emitted by rustc, as reported by `cargo check
+/// --message-format=json`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagnostic {
+    level: String,
+    code: Option<String>,
+    message: String,
+    file: String,
+    line: u64,
+    column: u64,
+    rendered: String,
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    level: String,
+    code: Option<CompilerCode>,
+    message: String,
+    #[serde(default)]
+    spans: Vec<CompilerSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CompilerCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: String,
+    line_start: u64,
+    column_start: u64,
+    is_primary: bool,
+}
+
+/// Runs `cargo check` on a Rust crate or workspace and returns the compiler
+/// errors and warnings as a compact list, errors first, each with its file,
+/// line, column, error code and the message rendered by rustc. Duplicates
+/// reported for several targets and follow-up errors at the same location are
+/// removed. Prefer this over running cargo through the shell tool to check
+/// whether Rust code compiles, since it uses far fewer tokens. Requires
+/// absolute paths.
+#[derive(ToolDescription)]
+pub struct CargoCheck<F>(Arc<F>);
+
+impl<F: Infrastructure> CargoCheck<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+
+    async fn call(
+        &self,
+        context: ToolCallContext,
+        input: CargoCheckInput,
+    ) -> anyhow::Result<String> {
+        let path = PathBuf::from(&input.path);
+        assert_absolute_path(&path)?;
+
+        let mut command = "cargo check --message-format=json --quiet".to_string();
+        if let Some(package) = &input.package {
+            let valid = package
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if package.is_empty() || !valid {
+                bail!("Invalid package name: {package}");
+            }
+            command.push_str(&format!(" --package {package}"));
+        }
+        if input.all_targets {
+            command.push_str(" --all-targets");
+        }
+
+        let env = self.0.environment_service().get_environment();
+        context
+            .send_text(
+                TitleFormat::debug("Cargo check")
+                    .sub_title(format_display_path(&path, env.cwd.as_path())?),
+            )
+            .await?;
+
+        // The JSON messages aren't meant for the user, so the streamed output
+        // is discarded
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(64);
+        let execute = self.0.command_executor_service().execute_command_streamed(
+            command,
+            path,
+            sender,
+            Some(Duration::from_secs(env.shell_timeout)),
+            context.resource_limits.clone(),
+            Vec::new(),
+        );
+        let drain = async { while receiver.recv().await.is_some() {} };
+        let (output, _) = tokio::join!(execute, drain);
+        let output = output?;
+
+        if output.timed_out {
+            return Err(ToolErrorCode::Timeout.error(format!(
+                "cargo check did not finish within {} seconds",
+                env.shell_timeout
+            )));
+        }
+
+        let diagnostics = parse(&output.stdout);
+        if diagnostics.is_empty() {
+            if output.success() {
+                return Ok("cargo check finished without errors or warnings.".to_string());
+            }
+            let stderr = output.stderr.trim();
+            let clipped = Clipper::from_start(MAX_STDERR_CHARS).clip(stderr);
+            return Err(ToolErrorCode::CommandFailed.error(format!(
+                "cargo check failed:\n{}",
+                clipped.prefix_content().unwrap_or(stderr)
+            )));
+        }
+
+        Ok(format_diagnostics(
+            &diagnostics,
+            input.limit.unwrap_or(DEFAULT_LIMIT),
+        ))
+    }
+}
+
+/// Extracts the diagnostics from cargo's JSON messages, removing duplicates
+/// and cascading errors that share the location of an earlier error
+fn parse(stdout: &str) -> Vec<Diagnostic> {
+    let mut seen = HashSet::new();
+    let mut diagnostics = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|message| message.reason == "compiler-message")
+        .filter_map(|message| message.message)
+        .filter_map(|message| {
+            // Summaries like "aborting due to 2 previous errors" have no spans
+            let span = message.spans.iter().find(|span| span.is_primary)?;
+            Some(Diagnostic {
+                level: message.level,
+                code: message.code.map(|code| code.code),
+                message: message.message,
+                file: span.file_name.clone(),
+                line: span.line_start,
+                column: span.column_start,
+                rendered: message.rendered.unwrap_or_default(),
+            })
+        })
+        .filter(|diagnostic| {
+            let location = (
+                diagnostic.level.clone(),
+                diagnostic.file.clone(),
+                diagnostic.line,
+            );
+            seen.insert(location)
+        })
+        .collect::<Vec<_>>();
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.level != "error");
+    diagnostics
+}
+
+fn format_diagnostics(diagnostics: &[Diagnostic], limit: usize) -> String {
+    let count = |level: &str| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == level)
+            .count()
+    };
+    let mut result = format!("{} errors, {} warnings", count("error"), count("warning"));
+    if diagnostics.len() > limit {
+        result.push_str(&format!(" (showing the first {limit})"));
+    }
+    result.push('\n');
+
+    for diagnostic in diagnostics.iter().take(limit) {
+        let code = diagnostic
+            .code
+            .as_ref()
+            .map(|code| format!("[{code}]"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            result,
+            "\n{}:{}:{}: {}{code}: {}",
+            diagnostic.file,
+            diagnostic.line,
+            diagnostic.column,
+            diagnostic.level,
+            diagnostic.message
+        );
+        // The first line of the rendered message repeats the level and message
+        for line in diagnostic.rendered.trim_end().lines().skip(1) {
+            let _ = writeln!(result, "{line}");
+        }
+    }
+
+    result.trim_end().to_string()
+}
+
+impl<F> NamedTool for CargoCheck<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_cargo_check")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for CargoCheck<F> {
+    type Input = CargoCheckInput;
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+        self.call(context, input).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn message(level: &str, code: Option<&str>, message: &str, line: u64) -> String {
+        serde_json::json!({
+            "reason": "compiler-message",
+            "package_id": "app 0.1.0",
+            "message": {
+                "level": level,
+                "code": code.map(|code| serde_json::json!({ "code": code })),
+                "message": message,
+                "spans": [
+                    { "file_name": "src/main.rs", "line_start": line, "column_start": 5, "is_primary": true }
+                ],
+                "rendered": format!("{level}: {message}\n --> src/main.rs:{line}:5\n  |\n")
+            }
+        })
+        .to_string()
+    }
+
+    fn fixture() -> String {
+        [
+            serde_json::json!({ "reason": "compiler-artifact", "package_id": "dep 1.0.0" })
+                .to_string(),
+            message(
+                "warning",
+                Some("unused_variables"),
+                "unused variable: `x`",
+                2,
+            ),
+            message("error", Some("E0308"), "mismatched types", 4),
+            // Reported again when checking the test target
+            message("error", Some("E0308"), "mismatched types", 4),
+            // Follow-up error at the same location
+            message("error", Some("E0282"), "type annotations needed", 4),
+            message("error", Some("E0425"), "cannot find value `y`", 7),
+            serde_json::json!({
+                "reason": "compiler-message",
+                "message": {
+                    "level": "error",
+                    "code": null,
+                    "message": "aborting due to 2 previous errors",
+                    "spans": [],
+                    "rendered": "error: aborting due to 2 previous errors\n"
+                }
+            })
+            .to_string(),
+            serde_json::json!({ "reason": "build-finished", "success": false }).to_string(),
+        ]
+        .join("\n")
+    }
+
+    #[test]
+    fn test_parse() {
+        let actual = parse(&fixture())
+            .into_iter()
+            .map(|diagnostic| (diagnostic.level, diagnostic.code, diagnostic.line))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            ("error".to_string(), Some("E0308".to_string()), 4),
+            ("error".to_string(), Some("E0425".to_string()), 7),
+            (
+                "warning".to_string(),
+                Some("unused_variables".to_string()),
+                2,
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format() {
+        let actual = format_diagnostics(&parse(&fixture()), 2);
+        let expected = r#"2 errors, 1 warnings (showing the first 2)
+
+src/main.rs:4:5: error[E0308]: mismatched types
+ --> src/main.rs:4:5
+  |
+
+src/main.rs:7:5: error[E0425]: cannot find value `y`
+ --> src/main.rs:7:5
+  |"#;
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_display::TitleFormat;
+use forge_domain::{
+    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::syn;
+use crate::tools::utils::{assert_absolute_path, format_display_path, list_files};
+use crate::Infrastructure;
+
+/// Maximum number of matches returned by a single search
+const MAX_MATCHES: usize = 200;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CodeSearchInput {
+    /// The absolute path of the file or directory to search in. Directories
+    /// are searched recursively.
+    pub path: String,
+
+    /// The code pattern to search for, written in the language of the
+    /// searched files. Use `$NAME` to match a single expression, type or
+    /// identifier and `$$$` (or a named `$$$ARGS`) to match any sequence of
+    /// code, e.g. `fn $NAME(&self) -> Result<$T>` or `console.log($$$)`.
+    pub pattern: String,
+
+    /// Glob pattern to filter files (e.g., '*.rs' for Rust files). If not
+    /// provided, all supported files are searched. Since patterns are
+    /// language specific, set this to the language of the pattern.
+    pub file_pattern: Option<String>,
+}
+
+/// Searches code by syntax rather than text. The pattern is compared token by
+/// token against the code, so differences in formatting, line breaks and
+/// comments don't matter, and strings or comments that merely contain the
+/// pattern aren't matched. Metavariables capture parts of the code: `$NAME`
+/// captures a single expression, type or identifier and `$$$` any sequence of
+/// code; a metavariable used twice must capture the same code. Returns each
+/// match with its line number and captures. Supports Rust, Python, JavaScript,
+/// TypeScript, Go, Java, C++, Ruby and Scala. Prefer this over regex search
+/// when looking for code shapes like function signatures or call sites.
+/// Requires absolute paths.
+#[derive(ToolDescription)]
+pub struct CodeSearch<F>(Arc<F>);
+
+impl<F: Infrastructure> CodeSearch<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+
+    fn format_display_path(&self, path: &Path) -> anyhow::Result<String> {
+        let env = self.0.environment_service().get_environment();
+        format_display_path(path, env.cwd.as_path())
+    }
+
+    async fn call(
+        &self,
+        context: ToolCallContext,
+        input: CodeSearchInput,
+    ) -> anyhow::Result<String> {
+        let path = Path::new(&input.path);
+        assert_absolute_path(path)?;
+
+        let pattern = input
+            .file_pattern
+            .as_ref()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .with_context(|| format!("Invalid glob pattern: {pattern}"))
+            })
+            .transpose()?;
+
+        context
+            .send_text(TitleFormat::debug("Code search").sub_title(format!(
+                "'{}' at {}",
+                input.pattern,
+                self.format_display_path(path)?
+            )))
+            .await?;
+
+        let mut result = String::new();
+        let mut count = 0;
+        'files: for path in list_files(path).await? {
+            let matches_pattern = pattern.as_ref().is_none_or(|pattern| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| pattern.matches(name))
+            });
+            if !matches_pattern {
+                continue;
+            }
+
+            // Skip binary or unreadable files silently
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let Some(matches) = syn::search(&path, &content, &input.pattern) else {
+                continue;
+            };
+
+            let display_path = self.format_display_path(&path)?;
+            for found in matches {
+                if count == MAX_MATCHES {
+                    writeln!(
+                        result,
+                        "... results truncated to {MAX_MATCHES} matches, use a narrower path or file_pattern"
+                    )?;
+                    break 'files;
+                }
+
+                writeln!(result, "{display_path}:{}:{}", found.line, found.text)?;
+                for (name, text) in found.captures {
+                    writeln!(result, "  ${name} = {text}")?;
+                }
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Ok("No matches found.".to_string());
+        }
+
+        Ok(result.trim_end().to_string())
+    }
+}
+
+impl<F> NamedTool for CodeSearch<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_code_search")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for CodeSearch<F> {
+    type Input = CodeSearchInput;
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+        self.call(context, input).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tokio::fs;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::tools::utils::TempDir;
+
+    #[tokio::test]
+    async fn test_code_search() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "fn load(&self) -> Result<String> {\n    todo!()\n}\n\nfn size(&self) -> usize {\n    0\n}\n",
+        )
+        .await
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("main.py"),
+            "def load(self):\n    pass\n",
+        )
+        .await
+        .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let search = CodeSearch::new(infra);
+        let actual = search
+            .call(
+                ToolCallContext::default(),
+                CodeSearchInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    pattern: "fn $NAME(&self) -> Result<$T>".to_string(),
+                    file_pattern: Some("*.rs".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+
+        let expected = format!(
+            "{}:1:fn load(&self) -> Result<String>\n  $NAME = load\n  $T = String",
+            temp_dir.path().join("lib.rs").display()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_code_search_no_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("main.py"),
+            "def load(self):\n    pass\n",
+        )
+        .await
+        .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let search = CodeSearch::new(infra);
+        let actual = search
+            .call(
+                ToolCallContext::default(),
+                CodeSearchInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    pattern: "save($$$)".to_string(),
+                    file_pattern: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(actual, "No matches found.");
+    }
+}
@@ -0,0 +1,328 @@
+use std::path::{Component, Path};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::Bytes;
+use forge_display::TitleFormat;
+use forge_domain::{
+    EnvironmentService, ExecutableTool, FileChange, FileChangeKind, NamedTool, ToolCallContext,
+    ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::fetch::{check_egress, client};
+use crate::tools::utils::{assert_absolute_path, format_display_path};
+use crate::{FsCreateDirsService, FsMetaService, FsWriteService, Infrastructure};
+
+/// Largest file that can be downloaded, in bytes
+const MAX_DOWNLOAD_SIZE: u64 = 50 * 1024 * 1024;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DownloadInput {
+    /// URL of the file to download
+    pub url: String,
+    /// Absolute path inside the project where the file is saved, missing
+    /// directories are created
+    pub path: String,
+    /// Expected media type of the file, e.g. application/json or image/*. The
+    /// download fails if the server responds with another type. HTML pages are
+    /// rejected unless requested, since they usually are error or login pages.
+    pub content_type: Option<String>,
+    /// Replace the file if it already exists (default: false)
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Downloads the file at a URL to a path inside the project, e.g. test
+/// fixtures, schemas or release binaries. Use this instead of running curl or
+/// wget in the shell. Files larger than 50 MB are rejected, and so are HTML
+/// pages unless content_type is text/html. Hosts that are not permitted by the
+/// egress policy can't be reached. Use the fetch tool instead to read the
+/// content of a page.
+#[derive(Debug, ToolDescription)]
+pub struct Download<F> {
+    infra: Arc<F>,
+    max_size: u64,
+}
+
+impl<F: Infrastructure> Download<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self { infra, max_size: MAX_DOWNLOAD_SIZE }
+    }
+}
+
+impl<F> NamedTool for Download<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_net_download")
+    }
+}
+
+/// Ensures that the path points inside the project
+fn assert_inside(path: &Path, cwd: &Path) -> Result<()> {
+    assert_absolute_path(path)?;
+    let escapes = path
+        .components()
+        .any(|component| component == Component::ParentDir);
+    if escapes || !path.starts_with(cwd) {
+        bail!(
+            "Files can only be downloaded inside the project directory {}",
+            cwd.display()
+        );
+    }
+    Ok(())
+}
+
+/// Fails unless the media type of the response is the expected one. Without
+/// an expectation anything but HTML is accepted.
+fn check_content_type(actual: &str, expected: Option<&str>) -> Result<()> {
+    let media_type = actual.split(';').next().unwrap_or_default().trim();
+    let matches = match expected {
+        Some(expected) => match expected.strip_suffix("/*") {
+            Some(kind) => media_type.split('/').next() == Some(kind),
+            None => media_type.eq_ignore_ascii_case(expected),
+        },
+        None => !media_type.eq_ignore_ascii_case("text/html"),
+    };
+    if !matches {
+        match expected {
+            Some(expected) => bail!("Expected content of type {expected} but received {actual}"),
+            None => bail!(
+                "The URL returned an HTML page, which usually is an error or login page. Pass content_type text/html to download it anyway."
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for Download<F> {
+    type Input = DownloadInput;
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> Result<String> {
+        let url = Url::parse(&input.url)
+            .with_context(|| format!("Failed to parse URL: {}", input.url))?;
+        let path = Path::new(&input.path);
+        let cwd = self.infra.environment_service().get_environment().cwd;
+        assert_inside(path, &cwd)?;
+
+        if !input.overwrite && self.infra.file_meta_service().exists(path).await? {
+            bail!(
+                "File already exists at {}. If you need to replace it, set overwrite to true.",
+                input.path
+            );
+        }
+
+        check_egress(&context.egress, &url).await?;
+        let mut response = client(&context.egress)?
+            .get(url.as_str())
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to download {url}: {e}"))?;
+        if !response.status().is_success() {
+            bail!(
+                "Failed to download {url} - status code {}",
+                response.status()
+            );
+        }
+
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        check_content_type(&content_type, input.content_type.as_deref())?;
+
+        let too_large = || {
+            anyhow!(
+                "The file at {url} is larger than the limit of {} bytes",
+                self.max_size
+            )
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_size)
+        {
+            return Err(too_large());
+        }
+
+        // The declared length can be missing or wrong, so the limit is also
+        // checked while reading the body
+        let mut content = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| anyhow!("Failed to read the response from {url}: {e}"))?
+        {
+            content.extend_from_slice(&chunk);
+            if content.len() as u64 > self.max_size {
+                return Err(too_large());
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            self.infra.create_dirs_service().create_dirs(parent).await?;
+        }
+        let size = content.len();
+        let kind = if self.infra.file_meta_service().exists(path).await? {
+            FileChangeKind::Modified
+        } else {
+            FileChangeKind::Created
+        };
+        self.infra
+            .file_write_service()
+            .write(path, Bytes::from(content))
+            .await?;
+        context.record_change(FileChange::new(path, kind));
+
+        let display_path = format_display_path(path, &cwd)?;
+        context
+            .send_text(TitleFormat::debug("Download").sub_title(format!("{url} → {display_path}")))
+            .await?;
+
+        Ok(format!(
+            "Downloaded {size} bytes of {content_type} to {}",
+            input.path
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::FsReadService;
+
+    fn fixture(url: String, path: &str) -> DownloadInput {
+        DownloadInput {
+            url,
+            path: path.to_string(),
+            content_type: None,
+            overwrite: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/schema.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type": "object"}"#)
+            .create();
+        let infra = Arc::new(MockInfrastructure::new());
+        let download = Download::new(infra.clone());
+
+        let input = DownloadInput {
+            content_type: Some("application/json".to_string()),
+            ..fixture(
+                format!("{}/schema.json", server.url()),
+                "/test/fixtures/schema.json",
+            )
+        };
+        let actual = download
+            .call(ToolCallContext::default(), input)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            "Downloaded 18 bytes of application/json to /test/fixtures/schema.json"
+        );
+        let content = infra
+            .file_read_service()
+            .read_utf8(Path::new("/test/fixtures/schema.json"))
+            .await
+            .unwrap();
+        assert_eq!(content, r#"{"type": "object"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_download_outside_project() {
+        let download = Download::new(Arc::new(MockInfrastructure::new()));
+
+        for path in ["/etc/passwd", "/test/../etc/passwd"] {
+            let actual = download
+                .call(
+                    ToolCallContext::default(),
+                    fixture("https://example.com/file".to_string(), path),
+                )
+                .await
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                actual,
+                "Files can only be downloaded inside the project directory /test"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_html_rejected() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/release.tar.gz")
+            .with_status(200)
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body("<html><body>Sign in</body></html>")
+            .create();
+        let download = Download::new(Arc::new(MockInfrastructure::new()));
+
+        let actual = download
+            .call(
+                ToolCallContext::default(),
+                fixture(
+                    format!("{}/release.tar.gz", server.url()),
+                    "/test/release.tar.gz",
+                ),
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(actual.starts_with("The URL returned an HTML page"));
+    }
+
+    #[tokio::test]
+    async fn test_download_too_large() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/large.bin")
+            .with_status(200)
+            .with_body("0123456789")
+            .create();
+        let download = Download { infra: Arc::new(MockInfrastructure::new()), max_size: 5 };
+
+        let actual = download
+            .call(
+                ToolCallContext::default(),
+                fixture(format!("{}/large.bin", server.url()), "/test/large.bin"),
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(actual.ends_with("is larger than the limit of 5 bytes"));
+    }
+
+    #[test]
+    fn test_check_content_type() {
+        assert!(check_content_type("image/png", Some("image/*")).is_ok());
+        assert!(
+            check_content_type("application/json; charset=utf-8", Some("application/json")).is_ok()
+        );
+        assert!(check_content_type("text/plain", Some("application/json")).is_err());
+        assert!(check_content_type("text/html", Some("text/html")).is_ok());
+        assert!(check_content_type("application/octet-stream", None).is_ok());
+    }
+}
@@ -2,9 +2,12 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Result};
 use forge_display::TitleFormat;
-use forge_domain::{ExecutableTool, NamedTool, ToolCallContext, ToolDescription};
+use forge_domain::{
+    detect_injection, wrap_untrusted, EgressPolicy, ExecutableTool, NamedTool, ToolCallContext,
+    ToolDescription,
+};
 use forge_tool_macros::ToolDescription;
-use reqwest::{Client, Url};
+use reqwest::{redirect, Client, Url};
 use schemars::JsonSchema;
 use serde::Deserialize;
 
@@ -15,6 +18,9 @@ use crate::{FsWriteService, Infrastructure};
 /// Fetch tool returns the content of MAX_LENGTH.
 const MAX_LENGTH: usize = 40_000;
 
+/// Maximum number of redirects followed for a single request
+const MAX_REDIRECTS: usize = 10;
+
 /// Retrieves content from URLs as markdown or raw text. Enables access to
 /// current online information including websites, APIs and documentation. Use
 /// for obtaining up-to-date information beyond training data, verifying facts,
@@ -23,9 +29,9 @@ const MAX_LENGTH: usize = 40_000;
 /// requiring authentication. Respects robots.txt and may be blocked by
 /// anti-scraping measures. For large pages, returns the first 40,000 characters
 /// and stores the complete content in a temporary file for subsequent access.
+/// Hosts that are not permitted by the egress policy can't be fetched.
 #[derive(Debug, ToolDescription)]
 pub struct Fetch<F> {
-    client: Client,
     infra: Arc<F>,
 }
 
@@ -37,10 +43,43 @@ impl<F: Infrastructure> NamedTool for Fetch<F> {
 
 impl<F: Infrastructure> Fetch<F> {
     pub fn new(infra: Arc<F>) -> Self {
-        Self { client: Client::new(), infra }
+        Self { infra }
     }
 }
 
+/// Creates a client that only follows redirects to hosts permitted by the
+/// egress policy
+pub(super) fn client(policy: &EgressPolicy) -> Result<Client> {
+    let policy = policy.clone();
+    let redirect = redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        match policy.check_url(attempt.url()) {
+            Ok(()) => attempt.follow(),
+            Err(error) => attempt.error(error),
+        }
+    });
+    Ok(Client::builder().redirect(redirect).build()?)
+}
+
+/// Checks the url against the egress policy, including the addresses its host
+/// resolves to
+pub(super) async fn check_egress(policy: &EgressPolicy, url: &Url) -> Result<()> {
+    policy.check_url(url)?;
+
+    if let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) {
+        // Resolution failures are reported by the request itself
+        if let Ok(addrs) = tokio::net::lookup_host((host, port)).await {
+            for addr in addrs {
+                policy.check_ip(host, addr.ip())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn default_raw() -> Option<bool> {
     Some(false)
 }
@@ -55,9 +94,9 @@ pub struct FetchInput {
 }
 
 impl<F: Infrastructure> Fetch<F> {
-    async fn check_robots_txt(&self, url: &Url) -> Result<()> {
+    async fn check_robots_txt(&self, client: &Client, url: &Url) -> Result<()> {
         let robots_url = format!("{}://{}/robots.txt", url.scheme(), url.authority());
-        let robots_response = self.client.get(&robots_url).send().await;
+        let robots_response = client.get(&robots_url).send().await;
 
         if let Ok(robots) = robots_response {
             if robots.status().is_success() {
@@ -95,10 +134,12 @@ impl<F: Infrastructure> Fetch<F> {
         context: &ToolCallContext,
         force_raw: bool,
     ) -> Result<(String, String)> {
-        self.check_robots_txt(url).await?;
+        check_egress(&context.egress, url).await?;
+
+        let client = client(&context.egress)?;
+        self.check_robots_txt(&client, url).await?;
 
-        let response = self
-            .client
+        let response = client
             .get(url.as_str())
             .send()
             .await
@@ -134,19 +175,31 @@ impl<F: Infrastructure> Fetch<F> {
             || content_type.contains("text/html")
             || content_type.is_empty();
 
-        if is_page_html && !force_raw {
-            let content = html2md::parse_html(&page_raw);
-            Ok((content, String::new()))
-        } else {
-            Ok((
+        match (is_page_html && !force_raw)
+            .then(|| to_markdown(&page_raw))
+            .flatten()
+        {
+            Some(content) => Ok((content, String::new())),
+            None => Ok((
                 page_raw,
                 format!(
                     "Content type {content_type} cannot be simplified to markdown; Raw content provided instead"),
-            ))
+            )),
         }
     }
 }
 
+#[cfg(feature = "browser")]
+fn to_markdown(html: &str) -> Option<String> {
+    Some(html2md::parse_html(html))
+}
+
+/// Pages are returned as they are when the converter isn't built in
+#[cfg(not(feature = "browser"))]
+fn to_markdown(_html: &str) -> Option<String> {
+    None
+}
+
 #[async_trait::async_trait]
 impl<F: Infrastructure> ExecutableTool for Fetch<F> {
     type Input = FetchInput;
@@ -179,7 +232,7 @@ impl<F: Infrastructure> ExecutableTool for Fetch<F> {
 
         // Build metadata with all required fields in a single fluent chain
         let metadata = Metadata::default()
-            .add("URL", url)
+            .add("URL", url.as_str())
             .add("total_chars", original_length)
             .add("start_char", "0")
             .add("end_char", end.to_string())
@@ -193,6 +246,9 @@ impl<F: Infrastructure> ExecutableTool for Fetch<F> {
 
         // Determine output. If truncated then use truncated content else the actual.
         let output = truncated.prefix_content().unwrap_or(content.as_str());
+        if let Some(excerpt) = detect_injection(output) {
+            context.send_prompt_injection(url.as_str(), excerpt).await?;
+        }
 
         // Create truncation tag only if content was actually truncated and stored in a
         // temp file
@@ -204,12 +260,14 @@ impl<F: Infrastructure> ExecutableTool for Fetch<F> {
             _ => String::new(),
         };
 
+        let output = wrap_untrusted(url.as_str(), output);
         Ok(format!("{metadata}{output}{truncation_tag}",))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use pretty_assertions::assert_eq;
     use regex::Regex;
     use tokio::runtime::Runtime;
 
@@ -219,7 +277,7 @@ mod tests {
     async fn setup() -> (Fetch<MockInfrastructure>, mockito::ServerGuard) {
         let server = mockito::Server::new_async().await;
         let infra = Arc::new(MockInfrastructure::new());
-        let fetch = Fetch { client: Client::new(), infra };
+        let fetch = Fetch { infra };
         (fetch, server)
     }
 
@@ -243,6 +301,7 @@ mod tests {
             .to_string()
     }
 
+    #[cfg(feature = "browser")]
     #[tokio::test]
     async fn test_fetch_html_content() {
         let (fetch, mut server) = setup().await;
@@ -422,12 +481,41 @@ mod tests {
         insta::assert_snapshot!("fetch_large_content_minimal", normalized_result);
     }
 
+    #[tokio::test]
+    async fn test_fetch_metadata_endpoint_blocked() {
+        let fetch = Fetch { infra: Arc::new(MockInfrastructure::new()) };
+        let input = FetchInput {
+            url: "http://169.254.169.254/latest/meta-data".to_string(),
+            raw: None,
+        };
+
+        let actual = fetch
+            .call(ToolCallContext::default(), input)
+            .await
+            .unwrap_err()
+            .to_string();
+
+        let expected =
+            "Access to '169.254.169.254' is blocked because it serves cloud instance metadata";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_host_not_allowed() {
+        let (fetch, server) = setup().await;
+        let context = ToolCallContext::default()
+            .egress(EgressPolicy::default().allow(vec!["example.com".to_string()]));
+        let input = FetchInput { url: format!("{}/test.html", server.url()), raw: None };
+
+        let actual = fetch.call(context, input).await.unwrap_err().to_string();
+
+        let expected = "Access to '127.0.0.1' is not permitted by the egress policy";
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_fetch_invalid_url() {
-        let fetch = Fetch {
-            client: Client::new(),
-            infra: Arc::new(MockInfrastructure::new()),
-        };
+        let fetch = Fetch { infra: Arc::new(MockInfrastructure::new()) };
         let rt = Runtime::new().unwrap();
 
         let input = FetchInput { url: "not a valid url".to_string(), raw: None };
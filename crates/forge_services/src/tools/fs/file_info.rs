@@ -1,14 +1,19 @@
+use std::fs::Metadata;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use forge_display::TitleFormat;
 use forge_domain::{
     EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
 };
 use forge_tool_macros::ToolDescription;
+use schemars::schema::RootSchema;
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
 
 use crate::tools::utils::{assert_absolute_path, format_display_path};
 use crate::Infrastructure;
@@ -19,11 +24,150 @@ pub struct FSFileInfoInput {
     pub path: String,
 }
 
+/// The metadata of a file or directory
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FileInfo {
+    /// Either file, directory or symlink
+    pub kind: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Whether the file can't be written to
+    pub readonly: bool,
+    /// Unix permissions in octal notation, e.g. 644
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+    /// Time of the last modification in RFC 3339 format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /// Time of creation in RFC 3339 format, if the platform records it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Provenance of the file, if it's inside a git repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+}
+
+/// The state of a file in its git repository
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GitInfo {
+    /// Whether the file is tracked by the repository
+    pub tracked: bool,
+    /// Whether the file has changes that aren't committed
+    pub dirty: bool,
+    /// The last commit that changed the file, if it's tracked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_commit: Option<LastCommit>,
+}
+
+/// Summary of the last commit that changed a file
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LastCommit {
+    /// Abbreviated hash of the commit
+    pub hash: String,
+    pub author: String,
+    /// Time of the commit in RFC 3339 format
+    pub date: String,
+    /// How long ago the commit was made, e.g. "3 days ago"
+    pub age: String,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+impl From<&Metadata> for FileInfo {
+    fn from(meta: &Metadata) -> Self {
+        let kind = if meta.is_dir() {
+            "directory"
+        } else if meta.is_symlink() {
+            "symlink"
+        } else {
+            "file"
+        };
+        let timestamp = |time: std::io::Result<SystemTime>| {
+            time.ok()
+                .map(|time| DateTime::<Utc>::from(time).to_rfc3339())
+        };
+
+        Self {
+            kind: kind.to_string(),
+            size: meta.len(),
+            readonly: meta.permissions().readonly(),
+            permissions: permissions(meta),
+            modified: timestamp(meta.modified()),
+            created: timestamp(meta.created()),
+            git: None,
+        }
+    }
+}
+
+/// Runs git in the directory, returning its output if it succeeds
+async fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the git state of the file, or nothing if it isn't inside a
+/// repository
+async fn git_info(path: &Path) -> Option<GitInfo> {
+    let dir = path.parent()?;
+    let file = path.file_name()?.to_str()?;
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).await?;
+
+    let tracked = git(dir, &["ls-files", "--error-unmatch", "--", file])
+        .await
+        .is_some();
+    let status = git(dir, &["status", "--porcelain", "--", file]).await?;
+    let last_commit = git(
+        dir,
+        &[
+            "log",
+            "-1",
+            "--format=%h%x00%an%x00%aI%x00%ar%x00%s",
+            "--",
+            file,
+        ],
+    )
+    .await
+    .and_then(|output| {
+        let mut fields = output.trim_end().split('\0').map(str::to_string);
+        Some(LastCommit {
+            hash: fields.next().filter(|hash| !hash.is_empty())?,
+            author: fields.next()?,
+            date: fields.next()?,
+            age: fields.next()?,
+            subject: fields.next()?,
+        })
+    });
+
+    Some(GitInfo { tracked, dirty: !status.trim().is_empty(), last_commit })
+}
+
+#[cfg(unix)]
+fn permissions(meta: &Metadata) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(format!("{:o}", meta.permissions().mode() & 0o777))
+}
+
+#[cfg(not(unix))]
+fn permissions(_meta: &Metadata) -> Option<String> {
+    None
+}
+
 /// Request to retrieve detailed metadata about a file or directory at the
-/// specified path. Returns comprehensive information including size, creation
-/// time, last modified time, permissions, and type. Path must be absolute. Use
-/// this when you need to understand file characteristics without reading the
-/// actual content.
+/// specified path. Returns a JSON object with the type, size in bytes,
+/// permissions, and the last modified and creation times. For files in a git
+/// repository it also tells whether the file is tracked and has uncommitted
+/// changes, and the hash, author, age and subject of the last commit that
+/// changed it. Path must be absolute. Use this when you need to understand
+/// file characteristics without reading the actual content, e.g. to learn the
+/// provenance of a file before editing it.
 #[derive(ToolDescription)]
 pub struct FSFileInfo<F> {
     infra: Arc<F>,
@@ -72,12 +216,21 @@ impl<F: Infrastructure> ExecutableTool for FSFileInfo<F> {
         context
             .send_text(TitleFormat::debug("Info").title(self.format_display_path(path)?))
             .await?;
-        Ok(format!("{meta:?}"))
+        let mut info = FileInfo::from(&meta);
+        if meta.is_file() {
+            info.git = git_info(path).await;
+        }
+        Ok(serde_json::to_string_pretty(&info)?)
+    }
+
+    fn output_schema(&self) -> RootSchema {
+        schemars::schema_for!(FileInfo)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
     use tokio::fs;
 
     use super::*;
@@ -100,9 +253,11 @@ mod test {
             .await
             .unwrap();
 
-        assert!(result.contains("FileType"));
-        assert!(result.contains("permissions"));
-        assert!(result.contains("modified"));
+        let actual: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(actual["kind"], "file");
+        assert_eq!(actual["size"], 12);
+        assert_eq!(actual["readonly"], false);
+        assert!(actual["modified"].is_string());
     }
 
     #[tokio::test]
@@ -122,9 +277,60 @@ mod test {
             .await
             .unwrap();
 
-        assert!(result.contains("FileType"));
-        assert!(result.contains("permissions"));
-        assert!(result.contains("modified"));
+        let actual: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(actual["kind"], "directory");
+        assert!(actual["modified"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_fs_file_info_git() {
+        let temp_dir = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=Jane", "-c", "user.email=jane@example.com"])
+                .args(args)
+                .current_dir(temp_dir.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        fs::write(temp_dir.path().join("tracked.txt"), "v1")
+            .await
+            .unwrap();
+        git(&["add", "tracked.txt"]);
+        git(&["commit", "-q", "-m", "Add tracked file"]);
+        fs::write(temp_dir.path().join("tracked.txt"), "v2")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("untracked.txt"), "new")
+            .await
+            .unwrap();
+
+        let stub = Arc::new(crate::tools::registry::tests::Stub::default());
+        let fs_info = FSFileInfo::new(stub);
+        let info = |name: &str| {
+            let path = temp_dir.path().join(name).to_string_lossy().to_string();
+            let fs_info = &fs_info;
+            async move {
+                let result = fs_info
+                    .call(ToolCallContext::default(), FSFileInfoInput { path })
+                    .await
+                    .unwrap();
+                serde_json::from_str::<serde_json::Value>(&result).unwrap()
+            }
+        };
+
+        let actual = info("tracked.txt").await;
+        assert_eq!(actual["git"]["tracked"], true);
+        assert_eq!(actual["git"]["dirty"], true);
+        assert_eq!(actual["git"]["last_commit"]["author"], "Jane");
+        assert_eq!(actual["git"]["last_commit"]["subject"], "Add tracked file");
+        assert!(actual["git"]["last_commit"]["age"].is_string());
+
+        let actual = info("untracked.txt").await;
+        assert_eq!(actual["git"]["tracked"], false);
+        assert_eq!(actual["git"]["dirty"], true);
+        assert!(actual["git"].get("last_commit").is_none());
     }
 
     #[tokio::test]
@@ -1,5 +1,5 @@
-use std::collections::HashSet;
-use std::path::Path;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Context;
@@ -8,14 +8,27 @@ use forge_domain::{
     EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
 };
 use forge_tool_macros::ToolDescription;
-use forge_walker::Walker;
+use ignore::WalkBuilder;
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::Deserialize;
+use tracing::warn;
 
 use crate::tools::utils::{assert_absolute_path, format_display_path};
 use crate::Infrastructure;
 
+/// Maximum number of matches reported for a single file
+const MAX_MATCHES_PER_FILE: usize = 50;
+
+/// Maximum number of matches reported for a search
+const MAX_MATCHES: usize = 500;
+
+/// Matching lines longer than this are clipped around the match
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// Number of bytes at the beginning of a file checked for NUL bytes
+const BINARY_DETECTION_BYTES: usize = 8192;
+
 #[derive(Deserialize, JsonSchema)]
 pub struct FSFindInput {
     /// The absolute path of the directory or file to search in. If it's a
@@ -28,6 +41,11 @@ pub struct FSFindInput {
     /// performed.
     pub regex: Option<String>,
 
+    /// Set to true to search for the regex as plain text, so that characters
+    /// like '(' or '.' don't need to be escaped.
+    #[serde(default)]
+    pub literal: bool,
+
     /// Glob pattern to filter files (e.g., '*.ts' for TypeScript files). If not
     /// provided, it will search all files (*).
     pub file_pattern: Option<String>,
@@ -65,10 +83,11 @@ impl FSFindInput {
 }
 
 /// Recursively searches directories for files by content (regex) and/or name
-/// (glob pattern). Provides context-rich results with line numbers for content
-/// matches. Two modes: content search (when regex provided) or file finder
-/// (when regex omitted). Uses case-insensitive Rust regex syntax. Requires
-/// absolute paths. Avoids binary files and excluded directories. Best for code
+/// (glob pattern). Content matches are returned as `path:line:column:line
+/// content`, at most 50 per file and 500 in total. Two modes: content search
+/// (when regex provided) or file finder (when regex omitted). Uses
+/// case-insensitive Rust regex syntax. Requires absolute paths. Respects
+/// .gitignore files and skips binary files and hidden files. Best for code
 /// exploration, API usage discovery, configuration settings, or finding
 /// patterns across projects.
 #[derive(ToolDescription)]
@@ -120,7 +139,11 @@ impl<F: Infrastructure> FSFind<F> {
         // Create content regex pattern if provided
         let regex = match &input.regex {
             Some(regex) => {
-                let pattern = format!("(?i){regex}"); // Case-insensitive by default
+                let pattern = match input.literal {
+                    true => regex::escape(regex),
+                    false => regex.clone(),
+                };
+                let pattern = format!("(?i){pattern}"); // Case-insensitive by default
                 Some(
                     Regex::new(&pattern)
                         .with_context(|| format!("Invalid regex pattern: {regex}"))?,
@@ -131,7 +154,9 @@ impl<F: Infrastructure> FSFind<F> {
 
         let paths = retrieve_file_paths(path).await?;
 
+        let mut files = Vec::new();
         let mut matches = Vec::new();
+        let mut clipped_files = 0;
 
         for path in paths {
             if !input.match_file_path(path.as_path())? {
@@ -139,82 +164,183 @@ impl<F: Infrastructure> FSFind<F> {
             }
 
             // File name only search mode
-            if regex.is_none() {
-                matches.push((self.format_display_path(&path)?).to_string());
+            let Some(regex) = &regex else {
+                files.push(self.format_display_path(&path)?);
                 continue;
+            };
+
+            if matches.len() >= MAX_MATCHES {
+                break;
             }
 
-            // Content matching mode - read and search file contents
-            let content = match tokio::fs::read_to_string(&path).await {
-                Ok(content) => content,
-                Err(e) => {
-                    // Skip binary or unreadable files silently
-                    if e.kind() != std::io::ErrorKind::InvalidData {
-                        matches.push(format!(
-                            "Error reading {}: {}",
-                            self.format_display_path(&path)?,
-                            e
-                        ));
-                    }
-                    continue;
-                }
+            let Some(content) = read_text(&path).await else {
+                continue;
             };
 
-            // Process the file line by line to find content matches
-            if let Some(regex) = &regex {
-                let mut found_match = false;
-
-                for (line_num, line) in content.lines().enumerate() {
-                    if regex.is_match(line) {
-                        found_match = true;
-                        // Format match in ripgrep style: filepath:line_num:content
-                        matches.push(format!(
-                            "{}:{}:{}",
-                            self.format_display_path(&path)?,
-                            line_num + 1,
-                            line
-                        ));
-                    }
-                }
-
-                // If no matches found in content but we're looking for content,
-                // don't add this file to matches
-                if !found_match && input.regex.is_some() {
-                    continue;
-                }
+            let display_path = self.format_display_path(&path)?;
+            let file_matches = search_content(&display_path, &content, regex);
+            if file_matches.len() > MAX_MATCHES_PER_FILE {
+                clipped_files += 1;
             }
+            let remaining = MAX_MATCHES - matches.len();
+            matches.extend(
+                file_matches
+                    .into_iter()
+                    .take(MAX_MATCHES_PER_FILE.min(remaining)),
+            );
         }
 
-        // Format and return results
+        // File name only search mode
+        let Some(regex) = regex else {
+            if files.is_empty() {
+                return Ok("No matches found.".to_string());
+            }
+            context
+                .send_text(GrepFormat::new(files.clone()).format())
+                .await?;
+            return Ok(files.join("\n"));
+        };
+
         if matches.is_empty() {
             return Ok("No matches found.".to_string());
         }
 
-        let mut formatted_output = GrepFormat::new(matches.clone());
-
-        // Use GrepFormat for content search, simple list for filename search
-        if let Some(regex) = regex {
-            formatted_output = formatted_output.regex(regex);
+        let lines = matches
+            .iter()
+            .map(|found| format!("{}:{}:{}", found.path, found.line, found.snippet))
+            .collect::<Vec<_>>();
+        context
+            .send_text(GrepFormat::new(lines).regex(regex).format())
+            .await?;
+
+        let mut output = matches
+            .iter()
+            .map(SearchMatch::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if clipped_files > 0 {
+            output.push_str(&format!(
+                "\n[Only the first {MAX_MATCHES_PER_FILE} matches of {clipped_files} file(s) are shown]"
+            ));
+        }
+        if matches.len() >= MAX_MATCHES {
+            output.push_str(&format!(
+                "\n[The search stopped after {MAX_MATCHES} matches, use a more specific regex or file_pattern to see the rest]"
+            ));
         }
+        Ok(output)
+    }
+}
+
+/// A line matching the regex of a search
+#[derive(Debug, PartialEq)]
+struct SearchMatch {
+    path: String,
+    /// Line number, starting at 1
+    line: usize,
+    /// Column of the start of the match in characters, starting at 1
+    column: usize,
+    /// The matching line, clipped around the match when it's too long
+    snippet: String,
+}
 
-        context.send_text(formatted_output.format()).await?;
-        Ok(matches.join("\n"))
+impl Display for SearchMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.path, self.line, self.column, self.snippet
+        )
     }
 }
 
-async fn retrieve_file_paths(dir: &Path) -> anyhow::Result<HashSet<std::path::PathBuf>> {
-    if dir.is_dir() {
-        Ok(Walker::max_all()
-            .cwd(dir.to_path_buf())
-            .get()
-            .await
-            .with_context(|| format!("Failed to walk directory '{}'", dir.display()))?
-            .into_iter()
-            .map(|file| dir.join(file.path))
-            .collect::<HashSet<_>>())
+/// Returns the lines of the content matching the regex
+fn search_content(path: &str, content: &str, regex: &Regex) -> Vec<SearchMatch> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let found = regex.find(line)?;
+            Some(SearchMatch {
+                path: path.to_string(),
+                line: index + 1,
+                column: line[..found.start()].chars().count() + 1,
+                snippet: snippet(line, found.start()),
+            })
+        })
+        .collect()
+}
+
+/// Clips long lines, e.g. of minified files, keeping the text around the match
+fn snippet(line: &str, match_start: usize) -> String {
+    let line = line.trim_end();
+    if line.chars().count() <= MAX_SNIPPET_CHARS {
+        return line.to_string();
+    }
+
+    let before = line[..match_start.min(line.len())].chars().count();
+    let skip = before.saturating_sub(MAX_SNIPPET_CHARS / 4);
+    let clipped = line
+        .chars()
+        .skip(skip)
+        .take(MAX_SNIPPET_CHARS)
+        .collect::<String>();
+    let prefix = if skip > 0 { "..." } else { "" };
+    let suffix = if skip + MAX_SNIPPET_CHARS < line.chars().count() {
+        "..."
     } else {
-        Ok(HashSet::from_iter([dir.to_path_buf()]))
+        ""
+    };
+    format!("{prefix}{clipped}{suffix}")
+}
+
+/// Reads the file as text, returning None for binary and unreadable files.
+/// Like ripgrep, a file is considered binary when its beginning contains a NUL
+/// byte.
+async fn read_text(path: &Path) -> Option<String> {
+    let bytes = match tokio::fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!(path = %path.display(), error = %error, "Skipping unreadable file");
+            return None;
+        }
+    };
+    if bytes
+        .iter()
+        .take(BINARY_DETECTION_BYTES)
+        .any(|byte| *byte == 0)
+    {
+        return None;
     }
+    String::from_utf8(bytes).ok()
+}
+
+/// Lists the files to search, respecting the .gitignore, .ignore and
+/// .forgeignore files
+async fn retrieve_file_paths(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![dir.to_path_buf()]);
+    }
+
+    let root = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        WalkBuilder::new(&root)
+            .hidden(true)
+            .git_global(true)
+            .git_ignore(true)
+            .ignore(true)
+            .add_custom_ignore_filename(forge_walker::IGNORE_FILE)
+            // Respect the .gitignore files even outside of git repositories
+            .require_git(false)
+            .sort_by_file_path(|a, b| a.cmp(b))
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+            .map(|entry| entry.into_path())
+            .collect::<Vec<_>>()
+    })
+    .await
+    .with_context(|| format!("Failed to walk directory '{}'", dir.display()))
 }
 
 impl<F> NamedTool for FSFind<F> {
@@ -264,6 +390,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("test".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -295,6 +422,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("test".to_string()),
                     file_pattern: Some("*.rs".to_string()),
+                    literal: false,
                 },
             )
             .await
@@ -328,6 +456,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: None,
                     file_pattern: Some("test*.txt".to_string()),
+                    literal: false,
                 },
             )
             .await
@@ -358,6 +487,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("test".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -394,6 +524,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("test".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -426,6 +557,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("test".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -454,6 +586,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("nonexistent".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -482,6 +615,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: None,
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -506,6 +640,7 @@ mod test {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     regex: Some("[invalid".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await;
@@ -528,6 +663,7 @@ mod test {
                     path: "relative/path".to_string(),
                     regex: Some("test".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await;
@@ -584,6 +720,7 @@ mod test {
                     path: temp_dir.path().join("best.txt").display().to_string(),
                     regex: Some("nice".to_string()),
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -592,7 +729,7 @@ mod test {
         let lines: Vec<_> = result.lines().collect();
         assert_eq!(lines.len(), 1);
         assert!(lines[0].eq(&format!(
-            "{}:1:nice code.",
+            "{}:1:1:nice code.",
             temp_dir.path().join("best.txt").display()
         )));
 
@@ -604,6 +741,7 @@ mod test {
                     path: temp_dir.path().join("best.txt").display().to_string(),
                     regex: None,
                     file_pattern: None,
+                    literal: false,
                 },
             )
             .await
@@ -612,4 +750,134 @@ mod test {
         assert_eq!(lines.len(), 1);
         assert!(lines[0].eq(&format!("{}", temp_dir.path().join("best.txt").display())));
     }
+
+    #[tokio::test]
+    async fn test_fs_search_skips_ignored_and_binary_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(".gitignore"), "target/\n")
+            .await
+            .unwrap();
+        fs::create_dir(temp_dir.path().join("target"))
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("target/build.log"), "needle")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("data.bin"), b"needle\0\x01")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "let needle = 1;")
+            .await
+            .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let actual = FSFind::new(infra)
+            .call(
+                ToolCallContext::default(),
+                FSFindInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    regex: Some("needle".to_string()),
+                    file_pattern: None,
+                    literal: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        let expected = format!(
+            "{}:1:5:let needle = 1;",
+            temp_dir.path().join("main.rs").display()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_caps_matches_per_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = "match\n".repeat(MAX_MATCHES_PER_FILE + 10);
+        fs::write(temp_dir.path().join("many.txt"), content)
+            .await
+            .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let actual = FSFind::new(infra)
+            .call(
+                ToolCallContext::default(),
+                FSFindInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    regex: Some("match".to_string()),
+                    file_pattern: None,
+                    literal: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        let lines = actual.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), MAX_MATCHES_PER_FILE + 1);
+        assert_eq!(
+            lines.last().unwrap(),
+            &"[Only the first 50 matches of 1 file(s) are shown]"
+        );
+    }
+
+    #[test]
+    fn test_snippet() {
+        let line = format!("{}needle{}", "a".repeat(300), "b".repeat(300));
+        let actual = snippet(&line, 300);
+        let expected = format!("...{}needle{}...", "a".repeat(50), "b".repeat(144));
+        assert_eq!(actual, expected);
+
+        assert_eq!(snippet("short line  ", 0), "short line");
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_regex_and_literal() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "fn parse(input: &str) {}\nfn parse_all() {}\nlet total = a.b;",
+        )
+        .await
+        .unwrap();
+        let search = |regex: &str, literal: bool| FSFindInput {
+            path: temp_dir.path().to_string_lossy().to_string(),
+            regex: Some(regex.to_string()),
+            file_pattern: None,
+            literal,
+        };
+        let infra = Arc::new(MockInfrastructure::new());
+        let fs_search = FSFind::new(infra);
+
+        // Regex syntax is interpreted unless the search is literal
+        let actual = fs_search
+            .call(ToolCallContext::default(), search(r"fn \w+\(", false))
+            .await
+            .unwrap();
+        assert_eq!(actual.lines().count(), 2);
+
+        let actual = fs_search
+            .call(ToolCallContext::default(), search("parse(", true))
+            .await
+            .unwrap();
+        assert_eq!(
+            actual,
+            format!(
+                "{}:1:4:fn parse(input: &str) {{}}",
+                temp_dir.path().join("lib.rs").display()
+            )
+        );
+
+        let actual = fs_search
+            .call(ToolCallContext::default(), search("a.b", true))
+            .await
+            .unwrap();
+        assert_eq!(actual.lines().count(), 1);
+        assert!(actual.ends_with("let total = a.b;"));
+
+        let actual = fs_search
+            .call(ToolCallContext::default(), search("parse(", false))
+            .await;
+        assert!(actual.is_err());
+    }
 }
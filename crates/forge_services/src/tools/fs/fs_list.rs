@@ -1,33 +1,50 @@
-use std::path::Path;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
-use forge_domain::{ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName};
+use chrono::{DateTime, Utc};
+use forge_domain::{
+    ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolErrorCode, ToolName,
+};
 use forge_tool_macros::ToolDescription;
-use forge_walker::Walker;
+use forge_walker::{File, Walker};
 use schemars::JsonSchema;
 use serde::Deserialize;
+use tokio::process::Command;
 
 use crate::tools::utils::assert_absolute_path;
 
-#[derive(Deserialize, JsonSchema)]
+#[derive(Default, Deserialize, JsonSchema)]
 pub struct FSListInput {
     /// The path of the directory to list contents for (absolute path required)
     pub path: String,
     /// Whether to list files recursively. Use true for recursive listing, false
     /// or omit for top-level only.
     pub recursive: Option<bool>,
+    /// Maximum depth of the listing, 1 lists the top-level contents only.
+    /// Takes precedence over recursive.
+    pub max_depth: Option<usize>,
+    /// Glob pattern the paths of the files must match, relative to the
+    /// directory, e.g. '*.rs' or 'src/**/*.ts'. Directories are left out when
+    /// it's set.
+    pub pattern: Option<String>,
+    /// Whether to include the size in bytes, the time of the last modification
+    /// and the git status of each file.
+    pub metadata: Option<bool>,
 }
 
 /// Request to list files and directories within the specified directory. If
 /// recursive is true, it will list all files and directories recursively. If
 /// recursive is false or not provided, it will only list the top-level
-/// contents. The path must be absolute. Do not use this tool to confirm the
-/// existence of files you may have created, as the user will let you know if
-/// the files were created successfully or not.
+/// contents. Use max_depth to limit a recursive listing, pattern to only list
+/// the files matching a glob, and metadata to get the size, modification time
+/// and git status of the files. Directories are listed before files. The path
+/// must be absolute. Do not use this tool to confirm the existence of files you
+/// may have created, as the user will let you know if the files were created
+/// successfully or not.
 #[derive(Default, ToolDescription)]
-pub struct FSList {
-    sorted: bool,
-}
+pub struct FSList;
 
 impl NamedTool for FSList {
     fn tool_name() -> ToolName {
@@ -44,12 +61,28 @@ impl ExecutableTool for FSList {
         assert_absolute_path(dir)?;
 
         if !dir.exists() {
-            return Err(anyhow::anyhow!("Directory '{}' does not exist", input.path));
+            return Err(
+                ToolErrorCode::NotFound.error(format!("Directory '{}' does not exist", input.path))
+            );
         }
 
-        let mut paths = Vec::new();
         let recursive = input.recursive.unwrap_or(false);
-        let max_depth = if recursive { usize::MAX } else { 1 };
+        let max_depth = match input.max_depth {
+            Some(depth) => depth.max(1),
+            None if recursive => usize::MAX,
+            None => 1,
+        };
+        let pattern = input
+            .pattern
+            .as_deref()
+            .map(glob::Pattern::new)
+            .transpose()
+            .with_context(|| {
+                format!(
+                    "Invalid glob pattern: {}",
+                    input.pattern.as_deref().unwrap_or_default()
+                )
+            })?;
 
         let walker = Walker::max_all()
             .cwd(dir.to_path_buf())
@@ -58,26 +91,42 @@ impl ExecutableTool for FSList {
         let mut files = walker
             .get()
             .await
-            .with_context(|| format!("Failed to read directory contents from '{}'", input.path))?;
-
-        // Sort the files for consistent snapshots
-        if self.sorted {
-            files.sort_by(|a, b| a.path.cmp(&b.path));
-        }
-
-        for entry in files {
+            .with_context(|| format!("Failed to read directory contents from '{}'", input.path))?
+            .into_iter()
             // Skip the root directory itself
-            if entry.path == dir.to_string_lossy() {
+            .filter(|entry| !entry.path.is_empty() && entry.path != "/")
+            .filter(|entry| match &pattern {
+                Some(pattern) => !entry.is_dir() && pattern.matches(&entry.path),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+        files.sort_by(compare_entries);
+
+        let statuses = match input.metadata.unwrap_or(false) {
+            true => Some(git_statuses(dir).await),
+            false => None,
+        };
+
+        let mut paths = Vec::with_capacity(files.len());
+        for entry in files {
+            if entry.is_dir() {
+                paths.push(format!(r#"<dir path="{}">"#, entry.path));
                 continue;
             }
 
-            if !entry.path.is_empty() {
-                if entry.is_dir() {
-                    paths.push(format!(r#"<dir path="{}">"#, entry.path));
-                } else {
-                    paths.push(format!(r#"<file path="{}">"#, entry.path));
-                };
+            let mut attributes = format!(r#"path="{}""#, entry.path);
+            if let Some(statuses) = &statuses {
+                let path = dir.join(&entry.path);
+                attributes.push_str(&format!(r#" size="{}""#, entry.size));
+                if let Some(modified) = modified(&path).await {
+                    attributes.push_str(&format!(r#" modified="{modified}""#));
+                }
+                let canonical = path.canonicalize().unwrap_or(path);
+                if let Some(status) = statuses.get(&canonical) {
+                    attributes.push_str(&format!(r#" git="{status}""#));
+                }
             }
+            paths.push(format!("<file {attributes}>"));
         }
 
         Ok(format!(
@@ -88,6 +137,75 @@ impl ExecutableTool for FSList {
     }
 }
 
+/// Orders the entries as a tree, listing the directories before the files at
+/// each level
+fn compare_entries(a: &File, b: &File) -> Ordering {
+    let a_parts = a.path.trim_end_matches('/').split('/').collect::<Vec<_>>();
+    let b_parts = b.path.trim_end_matches('/').split('/').collect::<Vec<_>>();
+
+    for (index, (a_part, b_part)) in a_parts.iter().zip(&b_parts).enumerate() {
+        if a_part != b_part {
+            let a_is_file = index == a_parts.len() - 1 && !a.is_dir();
+            let b_is_file = index == b_parts.len() - 1 && !b.is_dir();
+            return (a_is_file, a_part).cmp(&(b_is_file, b_part));
+        }
+    }
+    a_parts.len().cmp(&b_parts.len())
+}
+
+/// Returns the time of the last modification of the file in RFC 3339 format
+async fn modified(path: &Path) -> Option<String> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    Some(DateTime::<Utc>::from(modified).to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+/// Returns the git status of the changed files of the repository containing
+/// the directory, by their canonical path. Returns nothing outside of a
+/// repository.
+async fn git_statuses(dir: &Path) -> HashMap<PathBuf, &'static str> {
+    let git = |args: &'static [&'static str]| {
+        let dir = dir.to_path_buf();
+        async move {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .await
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+    };
+
+    let Some(root) = git(&["rev-parse", "--show-toplevel"]).await else {
+        return HashMap::new();
+    };
+    let root = PathBuf::from(root.trim());
+    let Some(output) = git(&["status", "--porcelain", "--untracked-files=all"]).await else {
+        return HashMap::new();
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let (code, path) = line.split_at_checked(3)?;
+            // Renames are reported as "old -> new"
+            let path = path.rsplit(" -> ").next()?.trim_matches('"');
+            let status = match code.trim() {
+                "??" => "untracked",
+                code if code.contains('A') => "added",
+                code if code.contains('D') => "deleted",
+                code if code.contains('R') => "renamed",
+                code if code.contains('U') => "conflicted",
+                _ => "modified",
+            };
+            Some((root.join(path), status))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use insta::assert_snapshot;
@@ -96,23 +214,17 @@ mod test {
     use super::*;
     use crate::tools::utils::TempDir;
 
-    impl FSList {
-        fn new(sorted: bool) -> Self {
-            Self { sorted }
-        }
-    }
-
     #[tokio::test]
     async fn test_fs_list_empty_directory() {
         let temp_dir = TempDir::new().unwrap();
 
-        let fs_list = FSList::new(true);
+        let fs_list = FSList;
         let result = fs_list
             .call(
                 ToolCallContext::default(),
                 FSListInput {
                     path: temp_dir.path().to_string_lossy().to_string(),
-                    recursive: None,
+                    ..Default::default()
                 },
             )
             .await
@@ -134,13 +246,13 @@ mod test {
         fs::create_dir(temp_dir.path().join("dir1")).await.unwrap();
         fs::create_dir(temp_dir.path().join("dir2")).await.unwrap();
 
-        let fs_list = FSList::new(true);
+        let fs_list = FSList;
         let result = fs_list
             .call(
                 ToolCallContext::default(),
                 FSListInput {
                     path: temp_dir.path().to_string_lossy().to_string(),
-                    recursive: None,
+                    ..Default::default()
                 },
             )
             .await
@@ -154,13 +266,13 @@ mod test {
         let temp_dir = TempDir::new().unwrap();
         let nonexistent_dir = temp_dir.path().join("nonexistent");
 
-        let fs_list = FSList::new(true);
+        let fs_list = FSList;
         let result = fs_list
             .call(
                 ToolCallContext::default(),
                 FSListInput {
                     path: nonexistent_dir.to_string_lossy().to_string(),
-                    recursive: None,
+                    ..Default::default()
                 },
             )
             .await;
@@ -182,13 +294,13 @@ mod test {
             .await
             .unwrap();
 
-        let fs_list = FSList::new(true);
+        let fs_list = FSList;
         let result = fs_list
             .call(
                 ToolCallContext::default(),
                 FSListInput {
                     path: temp_dir.path().to_string_lossy().to_string(),
-                    recursive: None,
+                    ..Default::default()
                 },
             )
             .await
@@ -218,7 +330,7 @@ mod test {
             .await
             .unwrap();
 
-        let fs_list = FSList::new(true);
+        let fs_list = FSList;
 
         // Test recursive listing
         let result = fs_list
@@ -227,6 +339,7 @@ mod test {
                 FSListInput {
                     path: temp_dir.path().to_string_lossy().to_string(),
                     recursive: Some(true),
+                    ..Default::default()
                 },
             )
             .await
@@ -237,11 +350,11 @@ mod test {
 
     #[tokio::test]
     async fn test_fs_list_relative_path() {
-        let fs_list = FSList::new(true);
+        let fs_list = FSList;
         let result = fs_list
             .call(
                 ToolCallContext::default(),
-                FSListInput { path: "relative/path".to_string(), recursive: None },
+                FSListInput { path: "relative/path".to_string(), ..Default::default() },
             )
             .await;
 
@@ -251,4 +364,96 @@ mod test {
             .to_string()
             .contains("Path must be absolute"));
     }
+
+    async fn create_tree(temp_dir: &TempDir) {
+        fs::create_dir_all(temp_dir.path().join("src/tools"))
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("src/tools/shell.rs"), "shell")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("src/main.rs"), "main")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("README.md"), "readme")
+            .await
+            .unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_max_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        create_tree(&temp_dir).await;
+
+        let result = FSList
+            .call(
+                ToolCallContext::default(),
+                FSListInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    recursive: Some(true),
+                    max_depth: Some(2),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_snapshot!(TempDir::normalize(&result));
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        create_tree(&temp_dir).await;
+
+        let result = FSList
+            .call(
+                ToolCallContext::default(),
+                FSListInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    recursive: Some(true),
+                    pattern: Some("*.rs".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_snapshot!(TempDir::normalize(&result));
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        create_tree(&temp_dir).await;
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(temp_dir.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        git(&["add", "README.md"]);
+
+        let result = FSList
+            .call(
+                ToolCallContext::default(),
+                FSListInput {
+                    path: temp_dir.path().to_string_lossy().to_string(),
+                    metadata: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let readme = result
+            .lines()
+            .find(|line| line.contains("README.md"))
+            .unwrap();
+        assert!(readme.starts_with(r#"<file path="README.md" size="6" modified=""#));
+        assert!(readme.ends_with(r#" git="added">"#));
+    }
 }
@@ -0,0 +1,241 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_display::TitleFormat;
+use forge_domain::{
+    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::syn;
+use crate::tools::utils::{assert_absolute_path, format_display_path, list_files};
+use crate::Infrastructure;
+
+/// Maximum number of files included in a single outline
+const MAX_FILES: usize = 100;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FSOutlineInput {
+    /// The absolute path of the file or directory to outline. Directories are
+    /// outlined recursively.
+    pub path: String,
+
+    /// Glob pattern to filter files (e.g., '*.rs' for Rust files). If not
+    /// provided, all supported files are outlined.
+    pub file_pattern: Option<String>,
+}
+
+/// Lists the functions, types, traits and impls defined in source files along
+/// with their line numbers, without reading the whole files. Accepts a file or
+/// a directory (searched recursively) and an optional glob pattern to filter
+/// files. Supports Rust, Python, JavaScript, TypeScript, Go, Java, C++, Ruby
+/// and Scala. Use this to understand the structure of code before reading it,
+/// then read only the relevant line ranges. Requires absolute paths.
+#[derive(ToolDescription)]
+pub struct FSOutline<F>(Arc<F>);
+
+impl<F: Infrastructure> FSOutline<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+
+    fn format_display_path(&self, path: &Path) -> anyhow::Result<String> {
+        let env = self.0.environment_service().get_environment();
+        format_display_path(path, env.cwd.as_path())
+    }
+
+    async fn call(
+        &self,
+        context: ToolCallContext,
+        input: FSOutlineInput,
+    ) -> anyhow::Result<String> {
+        let path = Path::new(&input.path);
+        assert_absolute_path(path)?;
+
+        let pattern = input
+            .file_pattern
+            .as_ref()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .with_context(|| format!("Invalid glob pattern: {pattern}"))
+            })
+            .transpose()?;
+
+        let display_path = self.format_display_path(path)?;
+        let title = match &input.file_pattern {
+            Some(pattern) => format!("Outline '{pattern}' files at {display_path}"),
+            None => format!("Outline {display_path}"),
+        };
+        context.send_text(TitleFormat::debug(title)).await?;
+
+        let mut outlines = Vec::new();
+        let mut truncated = false;
+        for path in list_files(path).await? {
+            let matches_pattern = pattern.as_ref().is_none_or(|pattern| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| pattern.matches(name))
+            });
+            if !matches_pattern {
+                continue;
+            }
+
+            // Skip binary or unreadable files silently
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let Some(symbols) = syn::outline(&path, &content) else {
+                continue;
+            };
+
+            if outlines.len() == MAX_FILES {
+                truncated = true;
+                break;
+            }
+
+            let mut outline = self.format_display_path(&path)?;
+            for symbol in symbols {
+                outline.push('\n');
+                outline.push_str(&format!("  {symbol}"));
+            }
+            outlines.push(outline);
+        }
+
+        if outlines.is_empty() {
+            return Ok("No supported source files found.".to_string());
+        }
+
+        let mut output = outlines.join("\n\n");
+        if truncated {
+            output.push_str(&format!(
+                "\n\n... outline truncated to {MAX_FILES} files, use a narrower path or file_pattern"
+            ));
+        }
+
+        Ok(output)
+    }
+}
+
+impl<F> NamedTool for FSOutline<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_fs_outline")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for FSOutline<F> {
+    type Input = FSOutlineInput;
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+        self.call(context, input).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tokio::fs;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::tools::utils::TempDir;
+
+    fn input(path: impl AsRef<Path>, file_pattern: Option<&str>) -> FSOutlineInput {
+        FSOutlineInput {
+            path: path.as_ref().to_string_lossy().to_string(),
+            file_pattern: file_pattern.map(ToString::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_outline_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir(&src).await.unwrap();
+        fs::write(
+            src.join("lib.rs"),
+            "struct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn new() -> Self {\n        todo!()\n    }\n}\n",
+        )
+        .await
+        .unwrap();
+        fs::write(src.join("main.py"), "def main():\n    pass\n")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "fn not_code() {}")
+            .await
+            .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let outline = FSOutline::new(infra);
+        let actual = outline
+            .call(ToolCallContext::default(), input(temp_dir.path(), None))
+            .await
+            .unwrap();
+
+        let expected = format!(
+            "{}\n  1: struct Point\n  5: impl Point\n    6: fn new() -> Self\n\n{}\n  1: def main():",
+            src.join("lib.rs").display(),
+            src.join("main.py").display()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_outline_with_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "fn run() {}")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("main.py"), "def main():\n    pass\n")
+            .await
+            .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let outline = FSOutline::new(infra);
+        let actual = outline
+            .call(
+                ToolCallContext::default(),
+                input(temp_dir.path(), Some("*.py")),
+            )
+            .await
+            .unwrap();
+
+        assert!(actual.contains("main.py"));
+        assert!(!actual.contains("lib.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_outline_unsupported() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "Hello world")
+            .await
+            .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let outline = FSOutline::new(infra);
+        let actual = outline
+            .call(ToolCallContext::default(), input(temp_dir.path(), None))
+            .await
+            .unwrap();
+
+        assert_eq!(actual, "No supported source files found.");
+    }
+
+    #[tokio::test]
+    async fn test_fs_outline_relative_path() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let outline = FSOutline::new(infra);
+        let result = outline
+            .call(ToolCallContext::default(), input(Path::new("src"), None))
+            .await;
+
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Path must be absolute"));
+    }
+}
@@ -3,10 +3,11 @@ use std::fmt::Write;
 use std::path::Path;
 use std::sync::Arc;
 
-use anyhow::{bail, Context};
+use anyhow::Context;
 use forge_display::TitleFormat;
 use forge_domain::{
-    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolErrorCode,
+    ToolName,
 };
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
@@ -16,7 +17,7 @@ use crate::tools::utils::{assert_absolute_path, format_display_path};
 use crate::{FsReadService, Infrastructure};
 
 // Define maximum character limits
-const MAX_RANGE_SIZE: u64 = 40_000;
+pub(super) const MAX_RANGE_SIZE: u64 = 40_000;
 
 /// Ensures that the given character range is valid and doesn't exceed the
 /// maximum size
@@ -32,12 +33,12 @@ const MAX_RANGE_SIZE: u64 = 40_000;
 pub fn assert_valid_range(start_char: u64, end_char: u64) -> anyhow::Result<()> {
     // Check that end_char is not less than start_char
     if end_char < start_char {
-        bail!("Invalid range: end character ({end_char}) must not be less than start character ({start_char})")
+        return Err(ToolErrorCode::InvalidArgs.error(format!("Invalid range: end character ({end_char}) must not be less than start character ({start_char})")));
     }
 
     // Check that the range size doesn't exceed the maximum
     if end_char.saturating_sub(start_char) > MAX_RANGE_SIZE {
-        bail!("The requested range exceeds the maximum size of {MAX_RANGE_SIZE} characters. Please specify a smaller range.")
+        return Err(ToolErrorCode::TooLarge.error(format!("The requested range exceeds the maximum size of {MAX_RANGE_SIZE} characters. Please specify a smaller range.")));
     }
 
     Ok(())
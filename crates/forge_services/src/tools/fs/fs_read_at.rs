@@ -0,0 +1,231 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use forge_display::TitleFormat;
+use forge_domain::{
+    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::fs_read::MAX_RANGE_SIZE;
+use crate::infra::FsSnapshotService;
+use crate::tools::utils::{assert_absolute_path, format_display_path};
+use crate::Infrastructure;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FSReadAtInput {
+    /// The absolute path of the file to read. A file that was removed since
+    /// can be read as well.
+    pub path: String,
+
+    /// A git revision to read the file as it was committed in, e.g. `HEAD`,
+    /// `HEAD~3`, a branch name or a commit hash.
+    pub revision: Option<String>,
+
+    /// The checkpoint to read the file at. The file tools take a checkpoint
+    /// before every change they make: 0 is the content before the most
+    /// recent change, 1 the content before the change preceding it, and so
+    /// on.
+    pub checkpoint: Option<usize>,
+}
+
+/// Reads the content of a file at a past point in time: at a git revision, or
+/// at a checkpoint taken by the file tools before they changed it. Use it to
+/// compare a file before and after your own changes or to consult an earlier
+/// implementation, instead of undoing changes or asking the user. Provide
+/// exactly one of revision or checkpoint. Only the first 40,000 characters are
+/// returned.
+#[derive(ToolDescription)]
+pub struct FSReadAt<F>(Arc<F>);
+
+impl<F: Infrastructure> FSReadAt<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self(infra)
+    }
+
+    /// Reads the file as committed in the git revision
+    async fn read_revision(&self, path: &Path, revision: &str) -> anyhow::Result<Vec<u8>> {
+        // A revision starting with a dash would be parsed as an option
+        if revision.is_empty() || revision.starts_with('-') {
+            bail!("Invalid git revision: {revision:?}")
+        }
+        let dir = path.parent().context("The path has no parent directory")?;
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("The path has no file name")?;
+
+        let output = Command::new("git")
+            .args(["show", &format!("{revision}:./{name}")])
+            .current_dir(dir)
+            .output()
+            .await
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "Failed to read {} at {revision}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl<F> NamedTool for FSReadAt<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_fs_read_at")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for FSReadAt<F> {
+    type Input = FSReadAtInput;
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+        let path = Path::new(&input.path);
+        assert_absolute_path(path)?;
+
+        let (content, key, value) = match (input.revision.as_deref(), input.checkpoint) {
+            (Some(revision), None) => (
+                self.read_revision(path, revision).await?,
+                "revision",
+                revision.to_string(),
+            ),
+            (None, Some(checkpoint)) => (
+                self.0
+                    .file_snapshot_service()
+                    .read_snapshot(path, checkpoint)
+                    .await?,
+                "checkpoint",
+                checkpoint.to_string(),
+            ),
+            _ => bail!("Provide exactly one of revision or checkpoint"),
+        };
+        let content = String::from_utf8(content)
+            .map_err(|_| anyhow::anyhow!("{} is a binary file", path.display()))?;
+
+        let env = self.0.environment_service().get_environment();
+        let display_path = format_display_path(path, env.cwd.as_path())?;
+        let message =
+            TitleFormat::debug("Read").sub_title(format!("{display_path} ({key} {value})"));
+        context.send_text(message).await?;
+
+        let total_chars = content.chars().count();
+        let mut response = String::new();
+        writeln!(response, "---")?;
+        writeln!(response, "path: {}", path.display())?;
+        writeln!(response, "{key}: {value}")?;
+        if total_chars as u64 > MAX_RANGE_SIZE {
+            writeln!(response, "total_chars: {total_chars}")?;
+            writeln!(response, "truncated: true")?;
+        }
+        writeln!(response, "---")?;
+        let content = content
+            .chars()
+            .take(MAX_RANGE_SIZE as usize)
+            .collect::<String>();
+        writeln!(response, "{content}")?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::fs;
+
+    use super::*;
+    use crate::tools::registry::tests::Stub;
+    use crate::tools::utils::TempDir;
+
+    fn input(path: &Path) -> FSReadAtInput {
+        FSReadAtInput {
+            path: path.to_string_lossy().to_string(),
+            revision: None,
+            checkpoint: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_at_revision() {
+        let temp_dir = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=Jane", "-c", "user.email=jane@example.com"])
+                .args(args)
+                .current_dir(temp_dir.path())
+                .output()
+                .unwrap()
+        };
+        let path = temp_dir.path().join("lib.rs");
+        git(&["init", "-q"]);
+        fs::write(&path, "fn v1() {}").await.unwrap();
+        git(&["add", "lib.rs"]);
+        git(&["commit", "-q", "-m", "First version"]);
+        fs::write(&path, "fn v2() {}").await.unwrap();
+        git(&["commit", "-q", "-am", "Second version"]);
+
+        let fs_read_at = FSReadAt::new(Arc::new(Stub::default()));
+        let actual = fs_read_at
+            .call(
+                ToolCallContext::default(),
+                FSReadAtInput { revision: Some("HEAD~1".to_string()), ..input(&path) },
+            )
+            .await
+            .unwrap();
+        let expected = format!(
+            "---\npath: {}\nrevision: HEAD~1\n---\nfn v1() {{}}\n",
+            path.display()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_at_invalid_revision() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("lib.rs");
+        let fs_read_at = FSReadAt::new(Arc::new(Stub::default()));
+
+        let actual = fs_read_at
+            .call(
+                ToolCallContext::default(),
+                FSReadAtInput {
+                    revision: Some("--output=/tmp/x".to_string()),
+                    ..input(&path)
+                },
+            )
+            .await;
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid git revision"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_at_requires_one_point() {
+        let fs_read_at = FSReadAt::new(Arc::new(Stub::default()));
+        let path = Path::new("/project/lib.rs");
+
+        let neither = fs_read_at
+            .call(ToolCallContext::default(), input(path))
+            .await;
+        let both = fs_read_at
+            .call(
+                ToolCallContext::default(),
+                FSReadAtInput {
+                    revision: Some("HEAD".to_string()),
+                    checkpoint: Some(0),
+                    ..input(path)
+                },
+            )
+            .await;
+        assert!(neither.is_err());
+        assert!(both.is_err());
+    }
+}
@@ -1,7 +1,10 @@
 use std::path::Path;
 use std::sync::Arc;
 
-use forge_domain::{ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName};
+use forge_domain::{
+    ExecutableTool, FileChange, FileChangeKind, NamedTool, ToolCallContext, ToolDescription,
+    ToolErrorCode, ToolName,
+};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -37,22 +40,29 @@ impl<T> NamedTool for FSRemove<T> {
 impl<T: Infrastructure> ExecutableTool for FSRemove<T> {
     type Input = FSRemoveInput;
 
-    async fn call(&self, _context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
         let path = Path::new(&input.path);
         assert_absolute_path(path)?;
 
         // Check if the file exists
         if !self.0.file_meta_service().exists(path).await? {
-            return Err(anyhow::anyhow!("File not found: {}", input.path));
+            return Err(ToolErrorCode::NotFound.error(format!("File not found: {}", input.path)));
         }
 
         // Check if it's a file
         if !self.0.file_meta_service().is_file(path).await? {
-            return Err(anyhow::anyhow!("Path is not a file: {}", input.path));
+            return Err(
+                ToolErrorCode::InvalidArgs.error(format!("Path is not a file: {}", input.path))
+            );
         }
 
         // Remove the file
         self.0.file_remove_service().remove(path).await?;
+        context.record_change(FileChange::new(path, FileChangeKind::Modified));
 
         Ok(format!("Successfully removed file: {}", input.path))
     }
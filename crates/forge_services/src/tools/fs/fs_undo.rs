@@ -3,7 +3,8 @@ use std::sync::Arc;
 
 use forge_display::TitleFormat;
 use forge_domain::{
-    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+    EnvironmentService, ExecutableTool, FileChange, FileChangeKind, NamedTool, ToolCallContext,
+    ToolDescription, ToolName,
 };
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
@@ -60,11 +61,16 @@ pub struct UndoInput {
 #[async_trait::async_trait]
 impl<F: Infrastructure> ExecutableTool for FsUndo<F> {
     type Input = UndoInput;
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
     async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
         let path = Path::new(&input.path);
         assert_absolute_path(path)?;
 
         self.0.file_snapshot_service().undo_snapshot(path).await?;
+        context.record_change(FileChange::new(path, FileChangeKind::Restored));
 
         // Format the path for display
         let display_path = self.format_display_path(path)?;
@@ -7,14 +7,15 @@ use bytes::Bytes;
 use console::strip_ansi_codes;
 use forge_display::{DiffFormat, TitleFormat};
 use forge_domain::{
-    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+    EnvironmentService, ExecutableTool, FileChange, FileChangeKind, NamedTool, ToolCallContext,
+    ToolDescription, ToolName,
 };
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::tools::syn;
-use crate::tools::utils::{assert_absolute_path, format_display_path};
+use crate::tools::utils::{assert_absolute_path, format_display_path, send_diff_artifact};
 use crate::{FsMetaService, FsReadService, FsWriteService, Infrastructure};
 
 #[derive(Deserialize, JsonSchema)]
@@ -71,21 +72,15 @@ impl<F> NamedTool for FSWrite<F> {
 impl<F: Infrastructure> ExecutableTool for FSWrite<F> {
     type Input = FSWriteInput;
 
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
     async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
         // Validate absolute path requirement
         let path = Path::new(&input.path);
         assert_absolute_path(path)?;
 
-        // Validate file content if it's a supported language file
-        let syntax_warning = syn::validate(&input.path, &input.content);
-
-        // Create parent directories if they don't exist
-        if let Some(parent) = Path::new(&input.path).parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .with_context(|| format!("Failed to create directories: {}", input.path))?;
-        }
-
         // Check if the file exists
         let file_exists = self.0.file_meta_service().is_file(path).await?;
 
@@ -109,11 +104,33 @@ impl<F: Infrastructure> ExecutableTool for FSWrite<F> {
             "".to_string()
         };
 
+        // The user may reject or modify the content when edits are reviewed
+        let formatted_path = self.format_display_path(path)?;
+        let content = context
+            .review_edit(&formatted_path, &old_content, input.content.clone())
+            .await?;
+
+        // Validate file content if it's a supported language file
+        let syntax_warning = syn::validate(&input.path, &content);
+
+        // Create parent directories if they don't exist
+        if let Some(parent) = Path::new(&input.path).parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directories: {}", input.path))?;
+        }
+
         // Write file only after validation passes and directories are created
         self.0
             .file_write_service()
-            .write(Path::new(&input.path), Bytes::from(input.content.clone()))
+            .write(Path::new(&input.path), Bytes::from(content.clone()))
             .await?;
+        let kind = if file_exists {
+            FileChangeKind::Modified
+        } else {
+            FileChangeKind::Created
+        };
+        context.record_change(FileChange::new(&input.path, kind));
 
         let mut result = String::new();
 
@@ -124,7 +141,10 @@ impl<F: Infrastructure> ExecutableTool for FSWrite<F> {
         } else {
             writeln!(result, "operation: CREATE")?;
         }
-        writeln!(result, "total_chars: {}", input.content.len())?;
+        writeln!(result, "total_chars: {}", content.len())?;
+        if content != input.content {
+            writeln!(result, "edited_by_user: true")?;
+        }
         if let Some(warning) = syntax_warning {
             writeln!(result, "Warning: {}", &warning.to_string())?;
         }
@@ -140,8 +160,7 @@ impl<F: Infrastructure> ExecutableTool for FSWrite<F> {
             "Create"
         };
 
-        // Use the formatted path for display
-        let formatted_path = self.format_display_path(path)?;
+        send_diff_artifact(&context, &formatted_path, &old_content, &new_content).await?;
 
         context
             .send_text(format!(
@@ -214,6 +233,7 @@ mod test {
         assert_eq!(content, "Hello, World!")
     }
 
+    #[cfg(feature = "repomap")]
     #[tokio::test]
     async fn test_fs_write_invalid_rust() {
         let temp_dir = TempDir::new().unwrap();
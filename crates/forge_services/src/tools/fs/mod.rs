@@ -1,7 +1,10 @@
 mod file_info;
 mod fs_find;
 mod fs_list;
+#[cfg(feature = "repomap")]
+mod fs_outline;
 mod fs_read;
+mod fs_read_at;
 mod fs_remove;
 mod fs_undo;
 mod fs_write;
@@ -9,7 +12,10 @@ mod fs_write;
 pub use file_info::*;
 pub use fs_find::*;
 pub use fs_list::*;
+#[cfg(feature = "repomap")]
+pub use fs_outline::*;
 pub use fs_read::*;
+pub use fs_read_at::*;
 pub use fs_remove::*;
 pub use fs_undo::*;
 pub use fs_write::*;
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use forge_domain::LanguageServer;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, Notify};
+use url::Url;
+
+/// Maximum time to wait for the response to a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum time to wait for the server to publish the diagnostics of a file
+const DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Response = Result<Value, String>;
+
+/// State shared between the client and the task reading the messages sent by
+/// the server
+struct Shared {
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    pending: Mutex<HashMap<i64, oneshot::Sender<Response>>>,
+    diagnostics: Mutex<HashMap<PathBuf, Vec<Value>>>,
+    published: Notify,
+    running: AtomicBool,
+}
+
+impl Shared {
+    async fn send(&self, message: &Value) -> anyhow::Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(&encode(message)).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn handle(&self, message: Value) {
+        let method = message.get("method").and_then(Value::as_str);
+        match (message.get("id"), method) {
+            // Requests sent by the server, e.g. for configuration or progress
+            // tokens, are acknowledged without a meaningful result
+            (Some(id), Some(method)) => {
+                let result = match method {
+                    "workspace/configuration" => {
+                        let items = message["params"]["items"]
+                            .as_array()
+                            .map_or(0, |items| items.len());
+                        Value::Array(vec![Value::Null; items])
+                    }
+                    _ => Value::Null,
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                if let Err(error) = self.send(&response).await {
+                    tracing::warn!(error = ?error, "Failed to respond to the language server");
+                }
+            }
+            (None, Some("textDocument/publishDiagnostics")) => {
+                let params = &message["params"];
+                let Some(path) = params["uri"]
+                    .as_str()
+                    .and_then(|uri| Url::parse(uri).ok())
+                    .and_then(|uri| uri.to_file_path().ok())
+                else {
+                    return;
+                };
+                let diagnostics = params["diagnostics"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                self.diagnostics.lock().unwrap().insert(path, diagnostics);
+                self.published.notify_waiters();
+            }
+            (Some(id), None) => {
+                let Some(sender) = id
+                    .as_i64()
+                    .and_then(|id| self.pending.lock().unwrap().remove(&id))
+                else {
+                    return;
+                };
+                let response = match message.get("error") {
+                    Some(error) => Err(error["message"]
+                        .as_str()
+                        .unwrap_or("Unknown error")
+                        .to_string()),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(response);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A language server process that is spoken to with JSON-RPC over stdio
+pub struct LspClient {
+    shared: Arc<Shared>,
+    next_id: AtomicI64,
+    /// Versions of the documents opened in the server
+    documents: tokio::sync::Mutex<HashMap<PathBuf, i64>>,
+    _process: Child,
+}
+
+impl LspClient {
+    /// Starts the server and initializes it with the root of the workspace
+    pub async fn start(server: &LanguageServer, root: &Path) -> anyhow::Result<Self> {
+        let mut process = Command::new(&server.command)
+            .args(&server.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to start the language server `{}`, make sure it is installed",
+                    server.command
+                )
+            })?;
+
+        let stdin = process.stdin.take().context("Missing stdin")?;
+        let stdout = process.stdout.take().context("Missing stdout")?;
+        let shared = Arc::new(Shared {
+            stdin: tokio::sync::Mutex::new(stdin),
+            pending: Mutex::new(HashMap::new()),
+            diagnostics: Mutex::new(HashMap::new()),
+            published: Notify::new(),
+            running: AtomicBool::new(true),
+        });
+        tokio::spawn(listen(stdout, shared.clone()));
+
+        let client = Self {
+            shared,
+            next_id: AtomicI64::new(1),
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+            _process: process,
+        };
+
+        let root_uri = Url::from_directory_path(root)
+            .map_err(|_| anyhow!("Invalid workspace root: {}", root.display()))?;
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        client
+            .request(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "rootUri": root_uri,
+                    "workspaceFolders": [{ "uri": root_uri, "name": name }],
+                    "capabilities": {
+                        "textDocument": {
+                            "synchronization": { "didSave": false },
+                            "publishDiagnostics": { "relatedInformation": false },
+                            "hover": { "contentFormat": ["markdown", "plaintext"] },
+                            "definition": { "linkSupport": true },
+                            "references": {}
+                        },
+                        "workspace": { "configuration": true, "workspaceFolders": true }
+                    }
+                }),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to initialize the language server `{}`",
+                    server.command
+                )
+            })?;
+        client.notify("initialized", json!({})).await?;
+
+        Ok(client)
+    }
+
+    /// Returns false once the server has exited
+    pub fn is_running(&self) -> bool {
+        self.shared.running.load(Ordering::SeqCst)
+    }
+
+    /// Sends a request and waits for its result
+    pub async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        self.shared.pending.lock().unwrap().insert(id, sender);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(error) = self.shared.send(&message).await {
+            self.shared.pending.lock().unwrap().remove(&id);
+            return Err(error.context("The language server is not running"));
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(error))) => Err(anyhow!("{method} failed: {error}")),
+            Ok(Err(_)) => Err(anyhow!("The language server exited before responding")),
+            Err(_) => {
+                self.shared.pending.lock().unwrap().remove(&id);
+                Err(anyhow!(
+                    "The language server didn't respond to {method} within {} seconds",
+                    REQUEST_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> anyhow::Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.shared.send(&message).await
+    }
+
+    /// Sends the current content of the file to the server, opening the
+    /// document on first use. Previously published diagnostics of the file
+    /// are discarded since they may be outdated.
+    pub async fn sync(&self, path: &Path, content: &str) -> anyhow::Result<()> {
+        let uri = file_uri(path)?;
+        self.shared.diagnostics.lock().unwrap().remove(path);
+
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(path) {
+            Some(version) => {
+                *version += 1;
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": *version },
+                        "contentChanges": [{ "text": content }]
+                    }),
+                )
+                .await
+            }
+            None => {
+                documents.insert(path.to_path_buf(), 1);
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": LanguageServer::language_id(path),
+                            "version": 1,
+                            "text": content
+                        }
+                    }),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Waits for the server to publish the diagnostics of the file, returning
+    /// `None` if they aren't published in time
+    pub async fn diagnostics(&self, path: &Path) -> Option<Vec<Value>> {
+        let deadline = tokio::time::Instant::now() + DIAGNOSTICS_TIMEOUT;
+        loop {
+            let published = self.shared.published.notified();
+            if let Some(diagnostics) = self.shared.diagnostics.lock().unwrap().get(path) {
+                return Some(diagnostics.clone());
+            }
+            if !self.is_running() {
+                return None;
+            }
+            tokio::time::timeout_at(deadline, published).await.ok()?;
+        }
+    }
+}
+
+/// Reads the messages sent by the server until it exits
+async fn listen(stdout: ChildStdout, shared: Arc<Shared>) {
+    let mut reader = BufReader::new(stdout);
+    loop {
+        match read_message(&mut reader).await {
+            Ok(Some(message)) => shared.handle(message).await,
+            Ok(None) => break,
+            Err(error) => {
+                tracing::warn!(error = ?error, "Failed to read a message from the language server");
+                break;
+            }
+        }
+    }
+
+    shared.running.store(false, Ordering::SeqCst);
+    // Dropping the senders fails the requests waiting for a response
+    shared.pending.lock().unwrap().clear();
+    shared.published.notify_waiters();
+}
+
+pub fn file_uri(path: &Path) -> anyhow::Result<Url> {
+    Url::from_file_path(path).map_err(|_| anyhow!("Invalid file path: {}", path.display()))
+}
+
+/// Frames the message with the `Content-Length` header of the base protocol
+fn encode(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len()).into_bytes()
+}
+
+/// Reads the next framed message, returning `None` at the end of the stream
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+
+    let length = length.context("Missing Content-Length header")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_message() {
+        let first = json!({ "jsonrpc": "2.0", "id": 1, "result": { "name": "café" } });
+        let second = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+        let mut fixture = encode(&first);
+        fixture.extend(
+            format!(
+                "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length: {}\r\n\r\n{second}",
+                second.to_string().len()
+            )
+            .into_bytes(),
+        );
+
+        let mut reader = fixture.as_slice();
+        let mut actual = Vec::new();
+        while let Some(message) = read_message(&mut reader).await.unwrap() {
+            actual.push(message);
+        }
+        assert_eq!(actual, vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn test_read_message_without_length() {
+        let mut reader = "Content-Type: text/plain\r\n\r\n{}".as_bytes();
+        let actual = read_message(&mut reader).await;
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,448 @@
+mod client;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use client::{file_uri, LspClient};
+use derive_more::Display;
+use forge_display::TitleFormat;
+use forge_domain::{
+    EnvironmentService, ExecutableTool, LanguageServer, NamedTool, ToolCallContext,
+    ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::tools::utils::{assert_absolute_path, format_display_path};
+use crate::Infrastructure;
+
+/// Maximum number of locations returned for definitions and references
+const MAX_LOCATIONS: usize = 100;
+
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[serde(rename_all = "snake_case")]
+pub enum LspAction {
+    /// Errors and warnings reported for the file
+    #[display("diagnostics")]
+    Diagnostics,
+    /// Where the symbol at the position is defined
+    #[display("definition")]
+    Definition,
+    /// Every usage of the symbol at the position
+    #[display("references")]
+    References,
+    /// Type information and documentation of the symbol at the position
+    #[display("hover")]
+    Hover,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LspInput {
+    /// The query to run: 'diagnostics', 'definition', 'references' or
+    /// 'hover'.
+    pub action: LspAction,
+
+    /// The absolute path of the file to query.
+    pub path: String,
+
+    /// The line of the symbol (1-based). Required for every action except
+    /// 'diagnostics'.
+    pub line: Option<usize>,
+
+    /// The column of the symbol within the line (1-based). Required for every
+    /// action except 'diagnostics'.
+    pub character: Option<usize>,
+}
+
+/// Queries the language server of a file for compiler-grade information
+/// without running a build. 'diagnostics' returns the errors and warnings of
+/// the file, 'definition' finds where the symbol at a position is defined,
+/// 'references' lists every usage of it and 'hover' returns its type and
+/// documentation. Positions are 1-based line and column numbers, as shown by
+/// the read tool. Use diagnostics after editing a file to check it compiles,
+/// and definition or references instead of text search when navigating code.
+/// The first query of a language may take a while as the server indexes the
+/// project. Requires absolute paths.
+#[derive(ToolDescription)]
+pub struct Lsp<F> {
+    infra: Arc<F>,
+    /// Running servers by their command line
+    clients: Mutex<HashMap<String, Arc<LspClient>>>,
+}
+
+impl<F: Infrastructure> Lsp<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self { infra, clients: Mutex::new(HashMap::new()) }
+    }
+
+    fn format_display_path(&self, path: &Path) -> anyhow::Result<String> {
+        let env = self.infra.environment_service().get_environment();
+        format_display_path(path, env.cwd.as_path())
+    }
+
+    /// Returns the running client of the server, starting it if needed
+    async fn client(&self, server: &LanguageServer) -> anyhow::Result<Arc<LspClient>> {
+        let key = std::iter::once(&server.command)
+            .chain(&server.args)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&key).filter(|client| client.is_running()) {
+            return Ok(client.clone());
+        }
+
+        let root = self.infra.environment_service().get_environment().cwd;
+        let client = Arc::new(LspClient::start(server, &root).await?);
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    async fn call(&self, context: ToolCallContext, input: LspInput) -> anyhow::Result<String> {
+        let path = PathBuf::from(&input.path);
+        assert_absolute_path(&path)?;
+
+        let defaults = LanguageServer::defaults();
+        let servers = if context.language_servers.is_empty() {
+            &defaults
+        } else {
+            &context.language_servers
+        };
+        let Some(server) = LanguageServer::find(servers, &path) else {
+            bail!(
+                "No language server is configured for {}. Configure one with `language_servers` in forge.yaml",
+                input.path
+            );
+        };
+
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", input.path))?;
+
+        context
+            .send_text(
+                TitleFormat::debug(format!("LSP {}", input.action))
+                    .sub_title(self.format_display_path(&path)?),
+            )
+            .await?;
+
+        let client = self.client(server).await?;
+        client.sync(&path, &content).await?;
+
+        if input.action == LspAction::Diagnostics {
+            let Some(diagnostics) = client.diagnostics(&path).await else {
+                bail!(
+                    "The language server didn't report diagnostics for {}",
+                    input.path
+                );
+            };
+            if diagnostics.is_empty() {
+                return Ok("No diagnostics found.".to_string());
+            }
+            let display_path = self.format_display_path(&path)?;
+            return Ok(diagnostics
+                .iter()
+                .map(|diagnostic| {
+                    format!("{display_path}:{}", format_diagnostic(diagnostic, &content))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+
+        let (Some(line), Some(character)) = (input.line, input.character) else {
+            bail!(
+                "line and character are required for the {} action",
+                input.action
+            );
+        };
+        let text = content
+            .lines()
+            .nth(line.saturating_sub(1))
+            .with_context(|| format!("Line {line} is beyond the end of {}", input.path))?;
+        let params = json!({
+            "textDocument": { "uri": file_uri(&path)? },
+            "position": {
+                "line": line.saturating_sub(1),
+                "character": to_utf16(text, character.saturating_sub(1))
+            }
+        });
+
+        match input.action {
+            LspAction::Hover => {
+                let result = client.request("textDocument/hover", params).await?;
+                Ok(hover_text(&result["contents"])
+                    .filter(|text| !text.is_empty())
+                    .unwrap_or_else(|| "No hover information found.".to_string()))
+            }
+            LspAction::Definition | LspAction::References => {
+                let result = if input.action == LspAction::Definition {
+                    client.request("textDocument/definition", params).await?
+                } else {
+                    let mut params = params;
+                    params["context"] = json!({ "includeDeclaration": true });
+                    client.request("textDocument/references", params).await?
+                };
+                self.format_locations(locations(&result)).await
+            }
+            LspAction::Diagnostics => unreachable!(),
+        }
+    }
+
+    /// Formats each location as `path:line:column: code`
+    async fn format_locations(
+        &self,
+        locations: Vec<(Url, usize, usize)>,
+    ) -> anyhow::Result<String> {
+        if locations.is_empty() {
+            return Ok("No locations found.".to_string());
+        }
+
+        let mut files = HashMap::new();
+        let mut result = String::new();
+        for (uri, line, character) in locations.iter().take(MAX_LOCATIONS) {
+            let Ok(path) = uri.to_file_path() else {
+                writeln!(result, "{uri}:{}:{}", line + 1, character + 1)?;
+                continue;
+            };
+            if !files.contains_key(&path) {
+                let content = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+                files.insert(path.clone(), content);
+            }
+            let text = files[&path].lines().nth(*line).unwrap_or_default();
+            writeln!(
+                result,
+                "{}:{}:{}: {}",
+                self.format_display_path(&path)?,
+                line + 1,
+                from_utf16(text, *character) + 1,
+                text.trim()
+            )?;
+        }
+        if locations.len() > MAX_LOCATIONS {
+            writeln!(
+                result,
+                "... {} more locations not shown",
+                locations.len() - MAX_LOCATIONS
+            )?;
+        }
+
+        Ok(result.trim_end().to_string())
+    }
+}
+
+/// Extracts the uri and zero-based start position of the locations in a
+/// definition or references result, which is either a location, a list of
+/// locations or a list of location links
+fn locations(result: &Value) -> Vec<(Url, usize, usize)> {
+    let items = match result {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        Value::Null => Vec::new(),
+        item => vec![item],
+    };
+
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let (uri, range) = match item.get("targetUri") {
+                Some(uri) => (uri, &item["targetSelectionRange"]),
+                None => (&item["uri"], &item["range"]),
+            };
+            let uri = Url::parse(uri.as_str()?).ok()?;
+            let line = range["start"]["line"].as_u64()? as usize;
+            let character = range["start"]["character"].as_u64()? as usize;
+            Some((uri, line, character))
+        })
+        .collect()
+}
+
+/// Extracts the text of hover contents, which are either markup content, a
+/// marked string or a list of marked strings
+fn hover_text(contents: &Value) -> Option<String> {
+    match contents {
+        Value::String(text) => Some(text.trim().to_string()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(hover_text)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        ),
+        Value::Object(object) => {
+            let value = object.get("value")?.as_str()?.trim();
+            match object.get("language").and_then(Value::as_str) {
+                Some(language) => Some(format!("```{language}\n{value}\n```")),
+                None => Some(value.to_string()),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Formats a diagnostic as `line:column: severity[code]: message (source)`
+fn format_diagnostic(diagnostic: &Value, content: &str) -> String {
+    let start = &diagnostic["range"]["start"];
+    let line = start["line"].as_u64().unwrap_or_default() as usize;
+    let character = start["character"].as_u64().unwrap_or_default() as usize;
+    let text = content.lines().nth(line).unwrap_or_default();
+
+    let severity = match diagnostic["severity"].as_u64() {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "info",
+        Some(4) => "hint",
+        _ => "error",
+    };
+    let code = match &diagnostic["code"] {
+        Value::String(code) => format!("[{code}]"),
+        Value::Number(code) => format!("[{code}]"),
+        _ => String::new(),
+    };
+    let message = diagnostic["message"].as_str().unwrap_or_default();
+    let source = diagnostic["source"]
+        .as_str()
+        .map(|source| format!(" ({source})"))
+        .unwrap_or_default();
+
+    format!(
+        "{}:{}: {severity}{code}: {message}{source}",
+        line + 1,
+        from_utf16(text, character) + 1
+    )
+}
+
+/// Converts a column in characters to the UTF-16 code units used by LSP
+fn to_utf16(line: &str, column: usize) -> usize {
+    line.chars().take(column).map(char::len_utf16).sum()
+}
+
+/// Converts a column in UTF-16 code units to characters
+fn from_utf16(line: &str, units: usize) -> usize {
+    let mut total = 0;
+    line.chars()
+        .take_while(|c| {
+            total += c.len_utf16();
+            total <= units
+        })
+        .count()
+}
+
+impl<F> NamedTool for Lsp<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_lsp")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for Lsp<F> {
+    type Input = LspInput;
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+        self.call(context, input).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+
+    #[test]
+    fn test_locations() {
+        let fixture = json!([
+            {
+                "uri": "file:///project/src/lib.rs",
+                "range": { "start": { "line": 4, "character": 7 }, "end": { "line": 4, "character": 12 } }
+            },
+            {
+                "targetUri": "file:///project/src/main.rs",
+                "targetRange": { "start": { "line": 0, "character": 0 }, "end": { "line": 9, "character": 1 } },
+                "targetSelectionRange": { "start": { "line": 2, "character": 3 }, "end": { "line": 2, "character": 7 } }
+            }
+        ]);
+
+        let actual = locations(&fixture)
+            .into_iter()
+            .map(|(uri, line, character)| (uri.to_string(), line, character))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            ("file:///project/src/lib.rs".to_string(), 4, 7),
+            ("file:///project/src/main.rs".to_string(), 2, 3),
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(locations(&Value::Null), vec![]);
+    }
+
+    #[test]
+    fn test_hover_text() {
+        let markup = json!({ "kind": "markdown", "value": "```rust\nfn load()\n```" });
+        assert_eq!(
+            hover_text(&markup),
+            Some("```rust\nfn load()\n```".to_string())
+        );
+
+        let marked = json!([{ "language": "python", "value": "def load()" }, "Loads the file"]);
+        assert_eq!(
+            hover_text(&marked),
+            Some("```python\ndef load()\n```\n\nLoads the file".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_diagnostic() {
+        let fixture = json!({
+            "range": { "start": { "line": 1, "character": 10 }, "end": { "line": 1, "character": 12 } },
+            "severity": 1,
+            "code": "E0308",
+            "source": "rustc",
+            "message": "mismatched types"
+        });
+        let content = "fn main() {\n    let 🦀: u8 = \"\";\n}\n";
+
+        let actual = format_diagnostic(&fixture, content);
+        let expected = "2:10: error[E0308]: mismatched types (rustc)";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_utf16() {
+        let fixture = "a🦀b";
+        assert_eq!(to_utf16(fixture, 2), 3);
+        assert_eq!(from_utf16(fixture, 3), 2);
+    }
+
+    #[tokio::test]
+    async fn test_lsp_unsupported_file() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let lsp = Lsp::new(infra);
+        let actual = lsp
+            .call(
+                ToolCallContext::default(),
+                LspInput {
+                    action: LspAction::Diagnostics,
+                    path: "/project/README.md".to_string(),
+                    line: None,
+                    character: None,
+                },
+            )
+            .await;
+
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .contains("No language server is configured"));
+    }
+}
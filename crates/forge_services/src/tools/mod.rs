@@ -1,9 +1,16 @@
+mod cargo_check;
+#[cfg(feature = "repomap")]
+mod code_search;
 mod completion;
+mod download;
 mod fetch;
 mod followup;
 mod fs;
+mod lsp;
 mod patch;
 mod registry;
+#[cfg(feature = "repomap")]
+mod rename;
 mod shell;
 mod syn;
 mod utils;
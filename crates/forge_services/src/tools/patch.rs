@@ -5,7 +5,8 @@ use std::sync::Arc;
 use bytes::Bytes;
 use forge_display::{DiffFormat, TitleFormat};
 use forge_domain::{
-    EnvironmentService, ExecutableTool, NamedTool, ToolCallContext, ToolDescription, ToolName,
+    EnvironmentService, ExecutableTool, FileChange, FileChangeKind, NamedTool, ToolCallContext,
+    ToolDescription, ToolErrorCode, ToolName,
 };
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
@@ -16,7 +17,7 @@ use tokio::fs;
 
 // No longer using dissimilar for fuzzy matching
 use crate::tools::syn;
-use crate::tools::utils::{assert_absolute_path, format_display_path};
+use crate::tools::utils::{assert_absolute_path, format_display_path, send_diff_artifact};
 use crate::{FsWriteService, Infrastructure};
 
 // Removed fuzzy matching threshold as we only use exact matching now
@@ -243,6 +244,10 @@ impl<F: Infrastructure> ApplyPatchJson<F> {
 impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
     type Input = Input;
 
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
     async fn call(&self, context: ToolCallContext, patch: Self::Input) -> anyhow::Result<String> {
         let path = Path::new(&patch.path);
         assert_absolute_path(path)?;
@@ -261,11 +266,19 @@ impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
             &patch.search,
             &patch.operation,
             &patch.content,
-        )?;
+        )
+        // The search text is missing when the file changed since it was read
+        .map_err(|error| ToolErrorCode::Conflict.error(error.to_string()))?;
 
         // Format the display path for output
         let display_path = self.format_display_path(path)?;
 
+        // The user may reject or modify the content when edits are reviewed
+        let proposed_content = current_content.clone();
+        let current_content = context
+            .review_edit(&display_path, &old_content, current_content)
+            .await?;
+
         // Generate diff between old and new content
         let diff = DiffFormat::format(&old_content, &current_content);
 
@@ -274,12 +287,16 @@ impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
             .file_write_service()
             .write(path, Bytes::from(current_content.clone()))
             .await?;
+        context.record_change(FileChange::new(path, FileChangeKind::Modified));
 
         let mut result = String::new();
 
         writeln!(result, "---")?;
         writeln!(result, "path: {}", path.display())?;
         writeln!(result, "total_chars: {}", current_content.len())?;
+        if current_content != proposed_content {
+            writeln!(result, "edited_by_user: true")?;
+        }
 
         // Check for syntax errors
         if let Some(warning) = syn::validate(path, &current_content).map(|e| e.to_string()) {
@@ -290,6 +307,8 @@ impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
 
         writeln!(result, "{}", console::strip_ansi_codes(&diff).as_ref())?;
 
+        send_diff_artifact(&context, &display_path, &old_content, &current_content).await?;
+
         context
             .send_text(format!(
                 "{}",
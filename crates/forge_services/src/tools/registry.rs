@@ -2,10 +2,17 @@ use std::sync::Arc;
 
 use forge_domain::Tool;
 
+use super::cargo_check::CargoCheck;
+#[cfg(feature = "repomap")]
+use super::code_search::CodeSearch;
 use super::completion::Completion;
+use super::download::Download;
 use super::fetch::Fetch;
 use super::fs::*;
+use super::lsp::Lsp;
 use super::patch::*;
+#[cfg(feature = "repomap")]
+use super::rename::CodeRename;
 use super::shell::Shell;
 use crate::tools::followup::Followup;
 use crate::Infrastructure;
@@ -21,21 +28,42 @@ impl<F: Infrastructure> ToolRegistry<F> {
 
     /// Returns all available tools configured with the given infrastructure
     pub fn tools(&self) -> Vec<Tool> {
-        vec![
+        let mut tools = vec![
             FSRead::new(self.infra.clone()).into(),
+            FSReadAt::new(self.infra.clone()).into(),
             FSWrite::new(self.infra.clone()).into(),
             FSRemove::new(self.infra.clone()).into(),
-            FSList::default().into(),
+            FSList.into(),
             FSFind::new(self.infra.clone()).into(),
+            Lsp::new(self.infra.clone()).into(),
             FSFileInfo::new(self.infra.clone()).into(),
             FsUndo::new(self.infra.clone()).into(),
             ApplyPatchJson::new(self.infra.clone()).into(),
             Shell::new(self.infra.clone()).into(),
+            CargoCheck::new(self.infra.clone()).into(),
             Completion.into(),
             Followup::new(self.infra.clone()).into(),
             Fetch::new(self.infra.clone()).into(),
+            Download::new(self.infra.clone()).into(),
+        ];
+        tools.extend(self.syntax_tools());
+        tools
+    }
+
+    /// The tools that parse the code, which need the grammars of the languages
+    #[cfg(feature = "repomap")]
+    fn syntax_tools(&self) -> Vec<Tool> {
+        vec![
+            FSOutline::new(self.infra.clone()).into(),
+            CodeSearch::new(self.infra.clone()).into(),
+            CodeRename::new(self.infra.clone()).into(),
         ]
     }
+
+    #[cfg(not(feature = "repomap"))]
+    fn syntax_tools(&self) -> Vec<Tool> {
+        Vec::new()
+    }
 }
 
 #[cfg(test)]
@@ -43,8 +71,11 @@ pub mod tests {
     use std::path::{Path, PathBuf};
 
     use bytes::Bytes;
-    use forge_domain::{CommandOutput, Environment, EnvironmentService, Provider};
+    use forge_domain::{
+        CommandOutput, Environment, EnvironmentService, Provider, DEFAULT_REGISTRY_URL,
+    };
     use forge_snaps::Snapshot;
+    use url::Url;
 
     use super::*;
     use crate::{
@@ -68,6 +99,15 @@ pub mod tests {
                 pid: std::process::id(),
                 provider: Provider::anthropic("test-key"),
                 retry_config: Default::default(),
+                timeout_config: Default::default(),
+                registry_url: Url::parse(DEFAULT_REGISTRY_URL).unwrap(),
+                shell_timeout: 240,
+                tool_timeout: 300,
+                max_tool_result_chars: 50_000,
+                default_mode: None,
+                low_bandwidth: false,
+                notify_after: None,
+                template_env: vec![],
             },
         }
     }
@@ -130,6 +170,10 @@ pub mod tests {
         async fn undo_snapshot(&self, _: &Path) -> anyhow::Result<()> {
             Ok(())
         }
+
+        async fn read_snapshot(&self, _: &Path, _: usize) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
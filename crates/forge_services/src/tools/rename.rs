@@ -0,0 +1,296 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use console::strip_ansi_codes;
+use forge_display::{DiffFormat, TitleFormat};
+use forge_domain::{
+    EnvironmentService, ExecutableTool, FileChange, FileChangeKind, NamedTool, ToolCallContext,
+    ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::syn;
+use crate::tools::utils::{assert_absolute_path, format_display_path, list_files};
+use crate::{FsWriteService, Infrastructure};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CodeRenameInput {
+    /// The absolute path of the file or directory in which the symbol is
+    /// renamed. Directories are searched recursively.
+    pub path: String,
+
+    /// The current name of the symbol
+    pub old_name: String,
+
+    /// The new name of the symbol
+    pub new_name: String,
+
+    /// Glob pattern to filter files (e.g., '*.rs' for Rust files). If not
+    /// provided, all supported files are searched.
+    pub file_pattern: Option<String>,
+
+    /// If set to true, the diff of the rename is returned without modifying
+    /// any file.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Renames a symbol (function, type, variable, field, module...) across a file
+/// or a whole directory in a single step. Matching is syntax-aware: only
+/// identifiers exactly equal to old_name are renamed, while strings, comments
+/// and longer identifiers that contain old_name are left untouched. Supports
+/// Rust, Python, JavaScript, TypeScript, Go, Java, C++, Ruby and Scala. Prefer
+/// this over a series of patches when renaming. Set dry_run to preview the
+/// diff first. Requires absolute paths.
+#[derive(ToolDescription)]
+pub struct CodeRename<F>(Arc<F>);
+
+impl<F: Infrastructure> CodeRename<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+
+    fn format_display_path(&self, path: &Path) -> anyhow::Result<String> {
+        let env = self.0.environment_service().get_environment();
+        format_display_path(path, env.cwd.as_path())
+    }
+
+    async fn call(
+        &self,
+        context: ToolCallContext,
+        input: CodeRenameInput,
+    ) -> anyhow::Result<String> {
+        let path = Path::new(&input.path);
+        assert_absolute_path(path)?;
+
+        for name in [&input.old_name, &input.new_name] {
+            if !syn::is_identifier(name) {
+                anyhow::bail!("'{name}' is not a valid identifier");
+            }
+        }
+
+        let pattern = input
+            .file_pattern
+            .as_ref()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .with_context(|| format!("Invalid glob pattern: {pattern}"))
+            })
+            .transpose()?;
+
+        let title = if input.dry_run {
+            "Rename (dry run)"
+        } else {
+            "Rename"
+        };
+        context
+            .send_text(TitleFormat::debug(title).sub_title(format!(
+                "{} → {} at {}",
+                input.old_name,
+                input.new_name,
+                self.format_display_path(path)?
+            )))
+            .await?;
+
+        let mut files = Vec::new();
+        let mut diffs = String::new();
+        let mut total = 0;
+        for path in list_files(path).await? {
+            let matches_pattern = pattern.as_ref().is_none_or(|pattern| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| pattern.matches(name))
+            });
+            if !matches_pattern {
+                continue;
+            }
+
+            // Skip binary or unreadable files silently
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let Some((renamed, count)) =
+                syn::rename(&path, &content, &input.old_name, &input.new_name)
+            else {
+                continue;
+            };
+            if count == 0 {
+                continue;
+            }
+
+            if !input.dry_run {
+                self.0
+                    .file_write_service()
+                    .write(&path, Bytes::from(renamed.clone()))
+                    .await?;
+                context.record_change(FileChange::new(&path, FileChangeKind::Modified));
+            }
+
+            let display_path = self.format_display_path(&path)?;
+            let diff = DiffFormat::format(&content, &renamed);
+            context.send_text(&diff).await?;
+            writeln!(diffs, "{display_path}\n{}", strip_ansi_codes(&diff))?;
+            files.push(format!("{display_path}: {count}"));
+            total += count;
+        }
+
+        if files.is_empty() {
+            return Ok(format!("No occurrences of '{}' found.", input.old_name));
+        }
+
+        let mut result = String::new();
+        writeln!(result, "---")?;
+        writeln!(
+            result,
+            "{} '{}' to '{}': {total} occurrences in {} files",
+            if input.dry_run {
+                "Would rename"
+            } else {
+                "Renamed"
+            },
+            input.old_name,
+            input.new_name,
+            files.len()
+        )?;
+        for file in &files {
+            writeln!(result, "{file}")?;
+        }
+        writeln!(result, "---")?;
+        write!(result, "{diffs}")?;
+
+        Ok(result)
+    }
+}
+
+impl<F> NamedTool for CodeRename<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("forge_tool_code_rename")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for CodeRename<F> {
+    type Input = CodeRenameInput;
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
+        self.call(context, input).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tokio::fs;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::tools::utils::TempDir;
+    use crate::FsReadService;
+
+    fn input(path: impl AsRef<Path>, dry_run: bool) -> CodeRenameInput {
+        CodeRenameInput {
+            path: path.as_ref().to_string_lossy().to_string(),
+            old_name: "parse".to_string(),
+            new_name: "tokenize".to_string(),
+            file_pattern: None,
+            dry_run,
+        }
+    }
+
+    async fn setup() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "pub fn parse() {}\n\n// Calls parse\nfn run() {\n    parse();\n    parse_all();\n}\n",
+        )
+        .await
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("main.py"),
+            "from lib import parse\n\nparse(\"parse\")\n",
+        )
+        .await
+        .unwrap();
+        temp_dir
+    }
+
+    #[tokio::test]
+    async fn test_code_rename() {
+        let temp_dir = setup().await;
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let rename = CodeRename::new(infra.clone());
+        let result = rename
+            .call(ToolCallContext::default(), input(temp_dir.path(), false))
+            .await
+            .unwrap();
+
+        assert!(result.contains("Renamed 'parse' to 'tokenize': 4 occurrences in 2 files"));
+
+        let actual = infra
+            .file_read_service()
+            .read_utf8(&temp_dir.path().join("lib.rs"))
+            .await
+            .unwrap();
+        let expected = "pub fn tokenize() {}\n\n// Calls parse\nfn run() {\n    tokenize();\n    parse_all();\n}\n";
+        assert_eq!(actual, expected);
+
+        let actual = infra
+            .file_read_service()
+            .read_utf8(&temp_dir.path().join("main.py"))
+            .await
+            .unwrap();
+        let expected = "from lib import tokenize\n\ntokenize(\"parse\")\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_code_rename_dry_run() {
+        let temp_dir = setup().await;
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let rename = CodeRename::new(infra.clone());
+        let result = rename
+            .call(ToolCallContext::default(), input(temp_dir.path(), true))
+            .await
+            .unwrap();
+
+        assert!(result.contains("Would rename 'parse' to 'tokenize': 4 occurrences in 2 files"));
+        assert!(result.contains("tokenize();"));
+
+        let actual = infra
+            .file_read_service()
+            .read_utf8(&temp_dir.path().join("lib.rs"))
+            .await;
+        assert!(actual.is_err());
+        let actual = fs::read_to_string(temp_dir.path().join("lib.rs"))
+            .await
+            .unwrap();
+        assert!(actual.starts_with("pub fn parse() {}"));
+    }
+
+    #[tokio::test]
+    async fn test_code_rename_invalid_identifier() {
+        let temp_dir = setup().await;
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let rename = CodeRename::new(infra);
+        let mut fixture = input(temp_dir.path(), false);
+        fixture.new_name = "to-kenize".to_string();
+        let result = rename.call(ToolCallContext::default(), fixture).await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "'to-kenize' is not a valid identifier"
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::bail;
 use forge_display::TitleFormat;
 use forge_domain::{
-    CommandOutput, Environment, EnvironmentService, ExecutableTool, NamedTool, ToolCallContext,
-    ToolDescription, ToolName,
+    CommandOutput, Environment, EnvironmentService, ExecutableTool, NamedTool, PolicyAction,
+    ToolCallContext, ToolCallFull, ToolDescription, ToolErrorCode, ToolName,
 };
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
@@ -32,6 +33,10 @@ pub struct ShellInput {
     /// If false (default), ANSI escape codes will be stripped from the output.
     #[serde(default)]
     pub keep_ansi: bool,
+    /// Maximum number of seconds the command may run before it is terminated.
+    /// Defaults to the configured shell timeout. On timeout the output
+    /// captured so far is returned.
+    pub timeout: Option<u64>,
 }
 
 // Strips out the ansi codes from content.
@@ -39,18 +44,29 @@ fn strip_ansi(content: String) -> String {
     String::from_utf8_lossy(&strip(content.as_bytes())).into_owned()
 }
 
-/// Formats command output by wrapping non-empty stdout/stderr in XML tags.
-/// stderr is commonly used for warnings and progress info, so success is
-/// determined by exit status, not stderr presence. Returns Ok(output) on
-/// success or Err(output) on failure, with a status message if both streams are
-/// empty.
+/// Result of running a command through the shell tool
+struct ShellOutput {
+    output: CommandOutput,
+    /// Directory the command was executed in
+    cwd: PathBuf,
+    /// Time taken by the command to complete or be terminated
+    duration: Duration,
+}
+
+/// Formats command output as a metadata header carrying the command, cwd,
+/// exit code and duration, followed by the non-empty stdout/stderr wrapped in
+/// XML tags. Each stream is truncated independently. stderr is commonly used
+/// for warnings and progress info, so success is determined by exit status,
+/// not stderr presence. Returns Ok(output) on success or Err(output) on
+/// failure, with a status message if both streams are empty.
 async fn format_output<F: Infrastructure>(
     infra: &Arc<F>,
-    mut output: CommandOutput,
+    shell_output: ShellOutput,
     keep_ansi: bool,
     prefix_chars: usize,
     suffix_chars: usize,
 ) -> anyhow::Result<String> {
+    let ShellOutput { mut output, cwd, duration } = shell_output;
     let mut formatted_output = String::new();
 
     if !keep_ansi {
@@ -61,7 +77,9 @@ async fn format_output<F: Infrastructure>(
     // Create metadata
     let mut metadata = Metadata::default()
         .add("command", &output.command)
-        .add_optional("exit_code", output.exit_code);
+        .add("cwd", cwd.display())
+        .add_optional("exit_code", output.exit_code)
+        .add("duration_ms", duration.as_millis());
 
     let mut is_truncated = false;
 
@@ -113,6 +131,28 @@ async fn format_output<F: Infrastructure>(
         ));
     }
 
+    // Report the resource limit that the command violated
+    if let Some(violation) = output.violation {
+        metadata = metadata.add("resource_limit_exceeded", violation);
+        if !formatted_output.is_empty() {
+            formatted_output.push('\n');
+        }
+        formatted_output.push_str(&format!(
+            "<resource_limit>The command was terminated: {violation}, the output above may be incomplete</resource_limit>"
+        ));
+    }
+
+    // Mark the output as partial if the command was terminated
+    if output.timed_out {
+        metadata = metadata.add("timed_out", "true");
+        if !formatted_output.is_empty() {
+            formatted_output.push('\n');
+        }
+        formatted_output.push_str(
+            "<timeout>TIMEOUT: the command did not finish in time and was terminated, the output above is incomplete</timeout>",
+        );
+    }
+
     // Handle empty outputs
     let result = if formatted_output.is_empty() {
         if output.success() {
@@ -124,10 +164,14 @@ async fn format_output<F: Infrastructure>(
         formatted_output
     };
 
+    // A command that fails isn't a failure of the tool, unlike one that
+    // doesn't finish in time
     if output.success() {
         Ok(format!("{metadata}{result}"))
+    } else if output.timed_out {
+        Err(ToolErrorCode::Timeout.error(format!("{metadata}{result}")))
     } else {
-        bail!(format!("{metadata}{result}"))
+        Err(ToolErrorCode::CommandFailed.error(format!("{metadata}{result}")))
     }
 }
 
@@ -163,8 +207,13 @@ fn tag_output(result: ClipperResult, tag: &str, content: &str) -> String {
 /// directory changes. Use for file system interaction, running utilities,
 /// installing packages, or executing build commands. For operations requiring
 /// unrestricted access, advise users to run forge CLI with '-u' flag. Returns
-/// complete output including stdout, stderr, and exit code for diagnostic
-/// purposes.
+/// a header with the command, cwd, exit code and duration followed by stdout
+/// and stderr as separate sections. A non-zero exit code is reported as a
+/// failure. Intermediate files that shouldn't end up in the workspace can be
+/// kept in the temporary directory of the conversation, available as
+/// `$FORGE_TMP`. Commands get no input from the terminal, so interactive
+/// programs must be run with their non-interactive options, e.g.
+/// `git commit -m`.
 #[derive(ToolDescription)]
 pub struct Shell<I> {
     env: Environment,
@@ -177,6 +226,37 @@ impl<I: Infrastructure> Shell<I> {
         let env = infra.environment_service().get_environment();
         Self { env, infra }
     }
+
+    /// Checks the command against the shell policy of the agent, asking the
+    /// user for approval like for the other tools when the policy requires it
+    async fn check_policy(
+        &self,
+        context: &ToolCallContext,
+        input: &ShellInput,
+    ) -> anyhow::Result<()> {
+        let policy = &context.shell_policy;
+        let Some(reason) = policy.denial(&input.command)? else {
+            return Ok(());
+        };
+
+        match policy.on_deny {
+            PolicyAction::Block => Err(ToolErrorCode::PermissionDenied.error(format!(
+                "Command blocked by the shell policy because {reason}"
+            ))),
+            PolicyAction::Ask => {
+                let mut call =
+                    ToolCallFull::new(Self::tool_name()).arguments(serde_json::to_value(input)?);
+                call.call_id = context.call_id.clone();
+                let denial = format!("the shell policy denies it because {reason}");
+                if !context.approve_denied(&call, &denial).await? {
+                    return Err(ToolErrorCode::PermissionDenied.error(format!(
+                        "Command rejected by the user, the shell policy denies it because {reason}"
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
 impl<I> NamedTool for Shell<I> {
@@ -189,21 +269,48 @@ impl<I> NamedTool for Shell<I> {
 impl<I: Infrastructure> ExecutableTool for Shell<I> {
     type Input = ShellInput;
 
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
     async fn call(&self, context: ToolCallContext, input: Self::Input) -> anyhow::Result<String> {
         // Validate empty command
         if input.command.trim().is_empty() {
             bail!("Command string is empty or contains only whitespace".to_string());
         }
+        self.check_policy(&context, &input).await?;
         let title_format = TitleFormat::debug(format!("Execute [{}]", self.env.shell.as_str()))
             .sub_title(&input.command);
 
         context.send_text(title_format).await?;
 
-        let output = self
+        // Forward the output to the UI as it is produced
+        let timeout = Duration::from_secs(input.timeout.unwrap_or(self.env.shell_timeout));
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(64);
+        let started = Instant::now();
+        let execute = self
             .infra
             .command_executor_service()
-            .execute_command(input.command, input.cwd)
-            .await?;
+            .execute_command_streamed(
+                input.command,
+                input.cwd.clone(),
+                sender,
+                Some(timeout),
+                context.resource_limits.clone(),
+                context
+                    .tmp_dir
+                    .iter()
+                    .map(|dir| ("FORGE_TMP".to_string(), dir.display().to_string()))
+                    .collect(),
+            );
+        let forward = async {
+            while let Some(chunk) = receiver.recv().await {
+                context.send_output(chunk).await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let (output, _) = tokio::try_join!(execute, forward)?;
+        let output = ShellOutput { output, cwd: input.cwd, duration: started.elapsed() };
 
         format_output(
             &self.infra,
@@ -228,8 +335,10 @@ mod tests {
             stderr: "".to_string(),
             command: "echo".into(),
             exit_code: Some(0),
+            timed_out: false,
+            violation: None,
         };
-        let small_result = format_output(&infra, small_output, false, 5, 5)
+        let small_result = format_output(&infra, fixture_output(small_output), false, 5, 5)
             .await
             .unwrap();
         insta::assert_snapshot!(
@@ -243,8 +352,10 @@ mod tests {
             stderr: "".to_string(),
             command: "echo".into(),
             exit_code: Some(0),
+            timed_out: false,
+            violation: None,
         };
-        let large_result = format_output(&infra, large_output, false, 100, 100)
+        let large_result = format_output(&infra, fixture_output(large_output), false, 100, 100)
             .await
             .unwrap();
         insta::assert_snapshot!(
@@ -255,12 +366,31 @@ mod tests {
     use std::env;
     use std::sync::Arc;
 
+    use forge_domain::{AgentId, Approval, ChatResponse, ResourceViolation, ShellPolicy};
     use pretty_assertions::assert_eq;
 
     use super::*;
     use crate::attachment::tests::MockInfrastructure;
     use crate::tools::utils::TempDir;
 
+    /// Wraps the command output with a fixed cwd and duration
+    fn fixture_output(output: CommandOutput) -> ShellOutput {
+        ShellOutput {
+            output,
+            cwd: PathBuf::from("/test"),
+            duration: Duration::from_millis(42),
+        }
+    }
+
+    /// Replaces the cwd and duration that vary between runs
+    fn normalize(result: &str) -> String {
+        let cwd = env::current_dir().unwrap().display().to_string();
+        let duration = regex::Regex::new(r"duration_ms: \d+").unwrap();
+        duration
+            .replace_all(&result.replace(&cwd, "[CWD]"), "duration_ms: [DURATION]")
+            .into_owned()
+    }
+
     /// Platform-specific error message patterns for command not found errors
     #[cfg(target_os = "windows")]
     const COMMAND_NOT_FOUND_PATTERNS: [&str; 2] = [
@@ -286,6 +416,7 @@ mod tests {
                     command: "echo 'Hello, World!'".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
@@ -309,11 +440,12 @@ mod tests {
                     },
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
             .unwrap();
-        insta::assert_snapshot!(result);
+        insta::assert_snapshot!(normalize(&result));
     }
 
     #[tokio::test]
@@ -327,11 +459,12 @@ mod tests {
                     command: "echo 'to stdout' && echo 'to stderr' >&2".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
             .unwrap();
-        insta::assert_snapshot!(result);
+        insta::assert_snapshot!(normalize(&result));
     }
 
     #[tokio::test]
@@ -351,13 +484,14 @@ mod tests {
                     },
                     cwd: temp_dir.clone(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
             .unwrap();
         insta::assert_snapshot!(
             "format_output_working_directory",
-            TempDir::normalize(&result)
+            normalize(&TempDir::normalize(&result))
         );
     }
 
@@ -371,12 +505,17 @@ mod tests {
                     command: "non_existent_command".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await;
 
         assert!(result.is_err());
         let err = result.unwrap_err();
+        assert_eq!(
+            ToolErrorCode::from_error(&err),
+            Some(ToolErrorCode::CommandFailed)
+        );
 
         // Check if any of the platform-specific patterns match
         let matches_pattern = COMMAND_NOT_FOUND_PATTERNS
@@ -400,6 +539,7 @@ mod tests {
                     command: "".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await;
@@ -410,6 +550,93 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_shell_policy_blocked() {
+        let shell = Shell::new(Arc::new(MockInfrastructure::new()));
+        let context = ToolCallContext::default()
+            .shell_policy(ShellPolicy::default().deny(vec![r"rm\s+-rf".to_string()]));
+        let result = shell
+            .call(
+                context,
+                ShellInput {
+                    command: "rm -rf /".to_string(),
+                    cwd: env::current_dir().unwrap(),
+                    keep_ansi: true,
+                    timeout: None,
+                },
+            )
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            r"Command blocked by the shell policy because it matches the deny pattern `rm\s+-rf`"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shell_policy_approved() {
+        let shell = Shell::new(Arc::new(MockInfrastructure::new()));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .shell_policy(
+                ShellPolicy::default()
+                    .allow(vec!["^ls".to_string()])
+                    .on_deny(PolicyAction::Ask),
+            );
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                if let ChatResponse::ApprovalRequest { reason, reply, .. } =
+                    message.unwrap().message
+                {
+                    assert!(reason.is_some_and(|reason| reason.contains("shell policy")));
+                    reply.send(Approval::Approve);
+                }
+            }
+        });
+        let result = shell
+            .call(
+                context,
+                ShellInput {
+                    command: "echo 'Hello, World!'".to_string(),
+                    cwd: env::current_dir().unwrap(),
+                    keep_ansi: true,
+                    timeout: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert!(result.contains("Mock command executed successfully"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_policy_ask_without_user() {
+        let shell = Shell::new(Arc::new(MockInfrastructure::new()));
+        let context = ToolCallContext::default().shell_policy(
+            ShellPolicy::default()
+                .allow(vec!["^ls".to_string()])
+                .on_deny(PolicyAction::Ask),
+        );
+        let result = shell
+            .call(
+                context,
+                ShellInput {
+                    command: "echo 'Hello, World!'".to_string(),
+                    cwd: env::current_dir().unwrap(),
+                    keep_ansi: true,
+                    timeout: None,
+                },
+            )
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Command rejected by the user"));
+    }
+
     #[tokio::test]
     async fn test_description() {
         assert!(
@@ -433,17 +660,18 @@ mod tests {
                     } else {
                         "pwd".to_string()
                     },
-                    cwd: current_dir.clone(),
+                    cwd: current_dir,
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
             .unwrap();
 
         assert_eq!(
-            result,
+            normalize(&result),
             format!(
-                "{}<stdout>\n{}\n\n</stdout>",
+                "{}<stdout>\n[CWD]\n\n</stdout>",
                 Metadata::default()
                     .add(
                         "command",
@@ -453,9 +681,9 @@ mod tests {
                             "pwd"
                         }
                     )
+                    .add("cwd", "[CWD]")
                     .add("exit_code", 0)
-                    .to_string(),
-                current_dir.display()
+                    .add("duration_ms", "[DURATION]")
             )
         );
     }
@@ -470,11 +698,12 @@ mod tests {
                     command: "echo 'first' && echo 'second'".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
             .unwrap();
-        insta::assert_snapshot!(result);
+        insta::assert_snapshot!(normalize(&result));
     }
 
     #[tokio::test]
@@ -487,6 +716,7 @@ mod tests {
                     command: "true".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
@@ -506,6 +736,7 @@ mod tests {
                     command: "echo ''".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
@@ -525,6 +756,7 @@ mod tests {
                     command: "echo $PATH".to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await
@@ -551,6 +783,7 @@ mod tests {
                     command: cmd.to_string(),
                     cwd: env::current_dir().unwrap(),
                     keep_ansi: true,
+                    timeout: None,
                 },
             )
             .await;
@@ -572,10 +805,18 @@ mod tests {
             stderr: "\x1b[31mWarning\x1b[0m".to_string(),
             command: "ls -la".into(),
             exit_code: Some(0),
+            timed_out: false,
+            violation: None,
         };
-        let preserved = format_output(&infra, ansi_output, true, PREFIX_CHARS, SUFFIX_CHARS)
-            .await
-            .unwrap();
+        let preserved = format_output(
+            &infra,
+            fixture_output(ansi_output),
+            true,
+            PREFIX_CHARS,
+            SUFFIX_CHARS,
+        )
+        .await
+        .unwrap();
         insta::assert_snapshot!("format_output_ansi_preserved", preserved);
 
         // Test with keep_ansi = false (should strip ANSI codes)
@@ -584,13 +825,93 @@ mod tests {
             stderr: "\x1b[31mWarning\x1b[0m".to_string(),
             command: "ls -la".into(),
             exit_code: Some(0),
+            timed_out: false,
+            violation: None,
         };
-        let stripped = format_output(&infra, ansi_output, false, PREFIX_CHARS, SUFFIX_CHARS)
-            .await
-            .unwrap();
+        let stripped = format_output(
+            &infra,
+            fixture_output(ansi_output),
+            false,
+            PREFIX_CHARS,
+            SUFFIX_CHARS,
+        )
+        .await
+        .unwrap();
         insta::assert_snapshot!("format_output_ansi_stripped", stripped);
     }
 
+    #[tokio::test]
+    async fn test_format_output_non_zero_exit_code() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let fixture = CommandOutput {
+            stdout: "".to_string(),
+            stderr: "error: could not compile".to_string(),
+            command: "cargo build".into(),
+            exit_code: Some(101),
+            timed_out: false,
+            violation: None,
+        };
+        let actual = format_output(
+            &infra,
+            fixture_output(fixture),
+            false,
+            PREFIX_CHARS,
+            SUFFIX_CHARS,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        insta::assert_snapshot!(actual);
+    }
+
+    #[tokio::test]
+    async fn test_format_output_timed_out() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let fixture = CommandOutput {
+            stdout: "compiling...".to_string(),
+            stderr: "".to_string(),
+            command: "cargo build".into(),
+            exit_code: None,
+            timed_out: true,
+            violation: None,
+        };
+        let actual = format_output(
+            &infra,
+            fixture_output(fixture),
+            false,
+            PREFIX_CHARS,
+            SUFFIX_CHARS,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        insta::assert_snapshot!(actual);
+    }
+
+    #[tokio::test]
+    async fn test_format_output_resource_violation() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let fixture = CommandOutput {
+            stdout: "yyyy".to_string(),
+            stderr: "".to_string(),
+            command: "yes".into(),
+            exit_code: None,
+            timed_out: false,
+            violation: Some(ResourceViolation::OutputSize),
+        };
+        let actual = format_output(
+            &infra,
+            fixture_output(fixture),
+            false,
+            PREFIX_CHARS,
+            SUFFIX_CHARS,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        insta::assert_snapshot!(actual);
+    }
+
     #[tokio::test]
     async fn test_format_output_with_large_command_output() {
         let infra = Arc::new(MockInfrastructure::new());
@@ -609,11 +930,19 @@ mod tests {
             stderr: test_string,
             command: "ls -la".into(),
             exit_code: Some(0),
+            timed_out: false,
+            violation: None,
         };
 
-        let preserved = format_output(&infra, ansi_output, false, TINY_PREFIX, TINY_SUFFIX)
-            .await
-            .unwrap();
+        let preserved = format_output(
+            &infra,
+            fixture_output(ansi_output),
+            false,
+            TINY_PREFIX,
+            TINY_SUFFIX,
+        )
+        .await
+        .unwrap();
         // Use a specific name for the snapshot instead of auto-generated name
         insta::assert_snapshot!(
             "format_output_large_command",
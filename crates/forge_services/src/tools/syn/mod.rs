@@ -1,3 +1,15 @@
+#[cfg(feature = "repomap")]
+mod outline;
+#[cfg(feature = "repomap")]
+mod rename;
+#[cfg(feature = "repomap")]
+mod search;
 mod validate;
 
+#[cfg(feature = "repomap")]
+pub use outline::outline;
+#[cfg(feature = "repomap")]
+pub use rename::{is_identifier, rename};
+#[cfg(feature = "repomap")]
+pub use search::search;
 pub use validate::validate;
@@ -0,0 +1,208 @@
+use std::fmt;
+use std::path::Path;
+
+use tree_sitter::{Node, Parser};
+
+use super::validate::extension;
+
+/// Longest signature included in an outline, longer ones are truncated
+const MAX_SIGNATURE_LENGTH: usize = 120;
+
+/// A definition in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Line of the definition (1-based)
+    pub line: usize,
+    /// Number of definitions that enclose this one
+    pub depth: usize,
+    /// First line of the definition
+    pub signature: String,
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}: {}",
+            "  ".repeat(self.depth),
+            self.line,
+            self.signature
+        )
+    }
+}
+
+/// Node kinds of the definitions that are included in the outline of each
+/// language
+fn definitions(ext: &str) -> &'static [&'static str] {
+    match ext.to_lowercase().as_str() {
+        "rs" => &[
+            "function_item",
+            "function_signature_item",
+            "struct_item",
+            "enum_item",
+            "union_item",
+            "trait_item",
+            "impl_item",
+            "mod_item",
+            "type_item",
+            "macro_definition",
+        ],
+        "py" => &["function_definition", "class_definition"],
+        "ts" | "js" | "tsx" => &[
+            "function_declaration",
+            "generator_function_declaration",
+            "class_declaration",
+            "abstract_class_declaration",
+            "method_definition",
+            "interface_declaration",
+            "type_alias_declaration",
+            "enum_declaration",
+        ],
+        "go" => &[
+            "function_declaration",
+            "method_declaration",
+            "type_declaration",
+        ],
+        "java" => &[
+            "class_declaration",
+            "interface_declaration",
+            "enum_declaration",
+            "record_declaration",
+            "method_declaration",
+            "constructor_declaration",
+        ],
+        "cpp" | "cc" | "cxx" | "c++" => &[
+            "function_definition",
+            "class_specifier",
+            "struct_specifier",
+            "enum_specifier",
+            "namespace_definition",
+        ],
+        "rb" => &["class", "module", "method", "singleton_method"],
+        "scala" => &[
+            "class_definition",
+            "object_definition",
+            "trait_definition",
+            "function_definition",
+        ],
+        _ => &[],
+    }
+}
+
+/// Lists the functions, types and impls defined in the file. Returns `None`
+/// when the language of the file isn't supported.
+pub fn outline(path: impl AsRef<Path>, content: &str) -> Option<Vec<Symbol>> {
+    let ext = path.as_ref().extension()?.to_str()?;
+    let kinds = definitions(ext);
+    if kinds.is_empty() {
+        return None;
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&extension(ext)?).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let mut symbols = Vec::new();
+    collect(tree.root_node(), content, kinds, 0, &mut symbols);
+    Some(symbols)
+}
+
+fn collect(node: Node, content: &str, kinds: &[&str], depth: usize, symbols: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if kinds.contains(&child.kind()) {
+            symbols.push(Symbol {
+                line: child.start_position().row + 1,
+                depth,
+                signature: signature(&content[child.byte_range()]),
+            });
+            collect(child, content, kinds, depth + 1, symbols);
+        } else {
+            collect(child, content, kinds, depth, symbols);
+        }
+    }
+}
+
+/// Reduces a definition to its first line without the opening brace
+fn signature(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    let line = line.strip_suffix('{').unwrap_or(line).trim_end();
+    if line.chars().count() > MAX_SIGNATURE_LENGTH {
+        let truncated = line.chars().take(MAX_SIGNATURE_LENGTH).collect::<String>();
+        format!("{truncated}...")
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const RUST: &str = r#"
+/// A parser
+pub struct Parser {
+    input: String,
+}
+
+impl Parser {
+    pub fn new(input: impl ToString) -> Self {
+        Self { input: input.to_string() }
+    }
+
+    fn parse(&self) -> Result<Ast, Error> {
+        todo!()
+    }
+}
+
+pub trait Visit {
+    fn visit(&self);
+}
+
+mod tests {
+    fn test_parse() {}
+}
+"#;
+
+    fn render(symbols: Vec<Symbol>) -> String {
+        symbols
+            .iter()
+            .map(|symbol| symbol.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_outline_rust() {
+        let actual = render(outline("lib.rs", RUST).unwrap());
+        assert_snapshot!(actual);
+    }
+
+    #[test]
+    fn test_outline_python() {
+        let actual = render(outline("test.py", include_str!("lang/python/valid.py")).unwrap());
+        assert_snapshot!(actual);
+    }
+
+    #[test]
+    fn test_outline_javascript() {
+        let actual = render(outline("test.js", include_str!("lang/javascript/valid.js")).unwrap());
+        assert_snapshot!(actual);
+    }
+
+    #[test]
+    fn test_outline_unsupported() {
+        assert_eq!(outline("notes.txt", "hello"), None);
+        assert_eq!(outline("Makefile", "all:"), None);
+    }
+
+    #[test]
+    fn test_signature_truncated() {
+        let fixture = format!("fn {}() {{", "a".repeat(200));
+        let actual = signature(&fixture);
+        assert_eq!(actual.chars().count(), MAX_SIGNATURE_LENGTH + 3);
+    }
+}
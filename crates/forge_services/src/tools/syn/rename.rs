@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use tree_sitter::{Node, Parser};
+
+use super::validate::extension;
+
+/// Returns true when the name can be used as an identifier in the supported
+/// languages
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+/// Renames every identifier matching `from` to `to` in the file, leaving
+/// strings, comments and longer identifiers containing `from` untouched.
+/// Returns the new content with the number of renamed occurrences, or `None`
+/// when the language of the file isn't supported.
+pub fn rename(
+    path: impl AsRef<Path>,
+    content: &str,
+    from: &str,
+    to: &str,
+) -> Option<(String, usize)> {
+    let ext = path.as_ref().extension()?.to_str()?;
+    let mut parser = Parser::new();
+    parser.set_language(&extension(ext)?).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let mut ranges = Vec::new();
+    collect(tree.root_node(), content, from, &mut ranges);
+
+    let mut renamed = content.to_string();
+    for range in ranges.iter().rev() {
+        renamed.replace_range(range.clone(), to);
+    }
+
+    Some((renamed, ranges.len()))
+}
+
+fn collect(node: Node, content: &str, name: &str, ranges: &mut Vec<std::ops::Range<usize>>) {
+    if node.child_count() == 0 {
+        if is_identifier_kind(node.kind()) && &content[node.byte_range()] == name {
+            ranges.push(node.byte_range());
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect(child, content, name, ranges);
+    }
+}
+
+/// Node kinds of identifiers, e.g. `identifier`, `type_identifier`,
+/// `field_identifier` or Ruby's `constant`
+fn is_identifier_kind(kind: &str) -> bool {
+    kind.ends_with("identifier") || kind == "constant"
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rename_rust() {
+        let fixture = r#"
+struct Parser;
+
+impl Parser {
+    // Creates a Parser
+    fn new() -> Parser {
+        let parser_name = "Parser";
+        Parser
+    }
+}
+"#;
+
+        let actual = rename("lib.rs", fixture, "Parser", "Lexer").unwrap();
+        let expected = (
+            r#"
+struct Lexer;
+
+impl Lexer {
+    // Creates a Parser
+    fn new() -> Lexer {
+        let parser_name = "Parser";
+        Lexer
+    }
+}
+"#
+            .to_string(),
+            4,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_rename_python() {
+        let fixture = "def load(path):\n    return load_all(path)\n\nload('a')\n";
+
+        let actual = rename("main.py", fixture, "load", "read").unwrap();
+        let expected = (
+            "def read(path):\n    return load_all(path)\n\nread('a')\n".to_string(),
+            2,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_rename_unsupported() {
+        let actual = rename("notes.txt", "load", "load", "read");
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn test_is_identifier() {
+        assert!(is_identifier("snake_case"));
+        assert!(is_identifier("_Private1"));
+        assert!(!is_identifier("1st"));
+        assert!(!is_identifier("foo-bar"));
+        assert!(!is_identifier(""));
+    }
+}
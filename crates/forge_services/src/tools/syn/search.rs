@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use tree_sitter::{Node, Parser};
+
+use super::validate::extension;
+
+/// Longest sequence of tokens a metavariable can capture
+const MAX_CAPTURE_TOKENS: usize = 256;
+
+/// Longest match text included in a result, longer ones are truncated
+const MAX_TEXT_LENGTH: usize = 160;
+
+lazy_static! {
+    static ref METAVARIABLE: Regex =
+        Regex::new(r"\$\$\$([A-Z_][A-Z0-9_]*)?|\$([A-Z_][A-Z0-9_]*)").unwrap();
+}
+
+/// Code that matches a structural pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Line where the match starts (1-based)
+    pub line: usize,
+    /// The matched code with whitespace collapsed
+    pub text: String,
+    /// Code captured by each named metavariable, in the order they were bound
+    pub captures: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Element {
+    /// A token that must appear as is
+    Token(String),
+    /// `$NAME`, a single expression, type or identifier
+    Single(String),
+    /// `$$$` or `$$$NAME`, any number of tokens
+    Multiple(Option<String>),
+}
+
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+    line: usize,
+    /// Change of the nesting depth caused by the token, for brackets and the
+    /// angle brackets of generics
+    nesting: isize,
+}
+
+/// Searches the file for code matching the pattern. Patterns are written in
+/// the language of the file and are compared token by token, so formatting
+/// and comments don't matter. `$NAME` matches a single expression, type or
+/// identifier, and `$$$` (optionally named, e.g. `$$$ARGS`) matches any
+/// sequence of tokens. A metavariable used more than once must match the same
+/// code every time. Returns `None` when the language of the file isn't
+/// supported.
+pub fn search(path: impl AsRef<Path>, content: &str, pattern: &str) -> Option<Vec<Match>> {
+    let ext = path.as_ref().extension()?.to_str()?;
+    let mut parser = Parser::new();
+    parser.set_language(&extension(ext)?).ok()?;
+
+    // Metavariables aren't valid syntax, so they are replaced by placeholder
+    // identifiers before the pattern is tokenized
+    let mut placeholders = HashMap::new();
+    let pattern = METAVARIABLE.replace_all(pattern, |captures: &regex::Captures| {
+        let placeholder = format!("forge_metavariable_{}", placeholders.len());
+        let element = match (captures.get(1), captures.get(2)) {
+            (_, Some(name)) => Element::Single(name.as_str().to_string()),
+            (name, None) => Element::Multiple(name.map(|name| name.as_str().to_string())),
+        };
+        placeholders.insert(placeholder.clone(), element);
+        placeholder
+    });
+    let elements = tokenize(&mut parser, &pattern)?
+        .into_iter()
+        .map(|token| {
+            placeholders
+                .get(token.text)
+                .cloned()
+                .unwrap_or_else(|| Element::Token(token.text.to_string()))
+        })
+        .collect::<Vec<_>>();
+    if elements.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let tokens = tokenize(&mut parser, content)?;
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < tokens.len() {
+        let mut captures = Vec::new();
+        match match_at(&elements, &tokens, start, &mut captures) {
+            Some(end) if end > start => {
+                let text = &content[tokens[start].start..tokens[end - 1].end];
+                matches.push(Match {
+                    line: tokens[start].line,
+                    text: truncate(&collapse(text)),
+                    captures,
+                });
+                start = end;
+            }
+            _ => start += 1,
+        }
+    }
+
+    Some(matches)
+}
+
+/// Splits the content into the leaves of its syntax tree, skipping comments
+/// and trailing commas
+fn tokenize<'a>(parser: &mut Parser, content: &'a str) -> Option<Vec<Token<'a>>> {
+    let tree = parser.parse(content, None)?;
+    let mut tokens = Vec::new();
+    collect(tree.root_node(), content, &mut tokens);
+
+    let closing = |token: Option<&Token>| token.is_some_and(|token| token.nesting < 0);
+    let trailing = (0..tokens.len())
+        .filter(|&index| tokens[index].text == "," && closing(tokens.get(index + 1)))
+        .collect::<Vec<_>>();
+    for index in trailing.into_iter().rev() {
+        tokens.remove(index);
+    }
+
+    Some(tokens)
+}
+
+fn collect<'a>(node: Node, content: &'a str, tokens: &mut Vec<Token<'a>>) {
+    if node.kind().contains("comment") || node.is_missing() {
+        return;
+    }
+
+    if node.child_count() == 0 {
+        let range = node.byte_range();
+        if !range.is_empty() {
+            let text = &content[range.clone()];
+            let generic = node.parent().is_some_and(|parent| {
+                parent.kind().contains("_arguments") || parent.kind().contains("_parameters")
+            });
+            let nesting = match text {
+                "(" | "[" | "{" => 1,
+                ")" | "]" | "}" => -1,
+                "<" if generic => 1,
+                ">" if generic => -1,
+                _ => 0,
+            };
+            tokens.push(Token {
+                text,
+                start: range.start,
+                end: range.end,
+                line: node.start_position().row + 1,
+                nesting,
+            });
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect(child, content, tokens);
+    }
+}
+
+/// Matches the pattern against the tokens starting at `index` and returns the
+/// index of the first token after the match
+fn match_at(
+    elements: &[Element],
+    tokens: &[Token],
+    index: usize,
+    captures: &mut Vec<(String, String)>,
+) -> Option<usize> {
+    let Some((element, rest)) = elements.split_first() else {
+        return Some(index);
+    };
+
+    match element {
+        Element::Token(text) => {
+            let token = tokens.get(index)?;
+            if token.text == text {
+                match_at(rest, tokens, index + 1, captures)
+            } else {
+                None
+            }
+        }
+        Element::Single(name) => capture(Some(name), true, rest, tokens, index, captures),
+        Element::Multiple(name) => capture(name.as_ref(), false, rest, tokens, index, captures),
+    }
+}
+
+/// Tries to capture increasingly long balanced sequences of tokens until the
+/// rest of the pattern matches
+fn capture(
+    name: Option<&String>,
+    single: bool,
+    rest: &[Element],
+    tokens: &[Token],
+    index: usize,
+    captures: &mut Vec<(String, String)>,
+) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut end = index;
+    let limit = tokens.len().min(index + MAX_CAPTURE_TOKENS);
+
+    loop {
+        // Single metavariables capture at least one token
+        if depth == 0 && (!single || end > index) {
+            let text = tokens[index..end]
+                .iter()
+                .map(|token| token.text)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let bound = name.and_then(|name| {
+                captures
+                    .iter()
+                    .find(|(bound, _)| bound == name)
+                    .map(|(_, text)| text.clone())
+            });
+
+            match (name, bound) {
+                (_, Some(bound)) if bound != text => {}
+                (Some(name), None) => {
+                    captures.push((name.clone(), text));
+                    if let Some(end) = match_at(rest, tokens, end, captures) {
+                        return Some(end);
+                    }
+                    captures.pop();
+                }
+                _ => {
+                    if let Some(end) = match_at(rest, tokens, end, captures) {
+                        return Some(end);
+                    }
+                }
+            }
+        }
+
+        if end == limit {
+            return None;
+        }
+
+        let token = &tokens[end];
+        if single && depth == 0 && matches!(token.text, "," | ";") {
+            return None;
+        }
+        depth = depth.checked_add_signed(token.nesting)?;
+        end += 1;
+    }
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() > MAX_TEXT_LENGTH {
+        let truncated = text.chars().take(MAX_TEXT_LENGTH).collect::<String>();
+        format!("{truncated}...")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const RUST: &str = r#"
+impl Store {
+    fn load(&self) -> Result<Vec<u8>> {
+        todo!()
+    }
+
+    fn save(
+        &self,
+    ) -> Result<()>
+    {
+        todo!()
+    }
+
+    fn size(&self) -> usize {
+        0
+    }
+}
+"#;
+
+    #[test]
+    fn test_search_rust() {
+        let actual = search("lib.rs", RUST, "fn $NAME(&self) -> Result<$T>").unwrap();
+        let expected = vec![
+            Match {
+                line: 3,
+                text: "fn load(&self) -> Result<Vec<u8>>".to_string(),
+                captures: vec![
+                    ("NAME".to_string(), "load".to_string()),
+                    ("T".to_string(), "Vec < u8 >".to_string()),
+                ],
+            },
+            Match {
+                line: 7,
+                text: "fn save( &self, ) -> Result<()>".to_string(),
+                captures: vec![
+                    ("NAME".to_string(), "save".to_string()),
+                    ("T".to_string(), "( )".to_string()),
+                ],
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_search_multiple() {
+        let fixture = "foo(1, 2);\nfoo();\nbar(3);\n";
+
+        let actual = search("main.js", fixture, "foo($$$ARGS)")
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.line, found.captures))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (1, vec![("ARGS".to_string(), "1 , 2".to_string())]),
+            (2, vec![("ARGS".to_string(), "".to_string())]),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_search_single_is_one_argument() {
+        let fixture = "foo(1, 2)\nfoo(x)\n";
+
+        let actual = search("main.py", fixture, "foo($A)")
+            .unwrap()
+            .into_iter()
+            .map(|found| found.line)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![2]);
+    }
+
+    #[test]
+    fn test_search_repeated_metavariable() {
+        let fixture = "a == a\na == b\n";
+
+        let actual = search("main.py", fixture, "$X == $X")
+            .unwrap()
+            .into_iter()
+            .map(|found| found.text)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec!["a == a".to_string()]);
+    }
+
+    #[test]
+    fn test_search_ignores_strings_and_comments() {
+        let fixture = "// foo(1)\nlet s = \"foo(1)\";\nfoo(1);\n";
+
+        let actual = search("lib.rs", fixture, "foo(1)")
+            .unwrap()
+            .into_iter()
+            .map(|found| found.line)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![3]);
+    }
+
+    #[test]
+    fn test_search_unsupported() {
+        assert_eq!(search("notes.txt", "foo", "foo"), None);
+    }
+}
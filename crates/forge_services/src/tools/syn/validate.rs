@@ -38,6 +38,7 @@ pub enum Error {
 /// * Rust (.rs)
 /// * JavaScript/TypeScript (.js, .jsx, .ts, .tsx)
 /// * Python (.py)
+#[cfg(feature = "repomap")]
 pub fn extension(ext: &str) -> Option<Language> {
     match ext.to_lowercase().as_str() {
         "rs" => Some(tree_sitter_rust::LANGUAGE.into()),
@@ -54,6 +55,12 @@ pub fn extension(ext: &str) -> Option<Language> {
     }
 }
 
+/// No language is supported when the grammars aren't built in
+#[cfg(not(feature = "repomap"))]
+pub fn extension(_ext: &str) -> Option<Language> {
+    None
+}
+
 /// Validates source code content using Tree-sitter parsers.
 ///
 /// This function attempts to parse the provided content using a Tree-sitter
@@ -106,7 +113,7 @@ pub fn validate(path: impl AsRef<Path>, content: &str) -> Option<Error> {
     })
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "repomap"))]
 mod tests {
     use std::path::PathBuf;
 
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use forge_display::DiffFormat;
+use forge_domain::{Artifact, ArtifactKind, ToolCallContext};
+
+/// Publishes the diff of a change to a file as an artifact, so that clients
+/// can link to it. The diff is written to the temporary directory of the
+/// conversation, nothing is published without one or when nothing changed.
+pub async fn send_diff_artifact(
+    context: &ToolCallContext,
+    display_path: &str,
+    old: &str,
+    new: &str,
+) -> anyhow::Result<()> {
+    let Some(tmp_dir) = &context.tmp_dir else {
+        return Ok(());
+    };
+    if old == new {
+        return Ok(());
+    }
+
+    let dir = tmp_dir.join("artifacts");
+    tokio::fs::create_dir_all(&dir).await?;
+    let mut artifact = Artifact::new(ArtifactKind::Diff, Path::new(""), display_path);
+    artifact.path = dir.join(format!("{}.diff", artifact.id));
+    tokio::fs::write(&artifact.path, DiffFormat::unified(display_path, old, new)).await?;
+
+    context.send_artifact(artifact).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use forge_domain::{AgentId, ChatResponse};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_diff_artifact() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .tmp_dir(dir.path().to_path_buf());
+
+        send_diff_artifact(&context, "src/lib.rs", "a\n", "b\n")
+            .await
+            .unwrap();
+
+        let ChatResponse::Artifact(artifact) = rx.recv().await.unwrap().unwrap().message else {
+            panic!("Expected an artifact");
+        };
+        assert_eq!(artifact.kind, ArtifactKind::Diff);
+        assert_eq!(artifact.title, "src/lib.rs");
+        let actual = tokio::fs::read_to_string(&artifact.path).await.unwrap();
+        assert_eq!(
+            actual,
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_diff_artifact_without_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let context = ToolCallContext::default()
+            .agent_id(AgentId::new("test"))
+            .sender(Some(Arc::new(tx)))
+            .tmp_dir(dir.path().to_path_buf());
+
+        send_diff_artifact(&context, "src/lib.rs", "a\n", "a\n")
+            .await
+            .unwrap();
+
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -1,7 +1,9 @@
+mod artifact;
 mod path;
 #[cfg(test)]
 mod temp_dir;
 
+pub use artifact::*;
 pub use path::*;
 #[cfg(test)]
 pub use temp_dir::*;
@@ -1,6 +1,12 @@
 use std::path::Path;
+#[cfg(feature = "repomap")]
+use std::path::PathBuf;
 
-use anyhow::bail;
+#[cfg(feature = "repomap")]
+use anyhow::Context;
+use forge_domain::ToolErrorCode;
+#[cfg(feature = "repomap")]
+use forge_walker::Walker;
 
 /// Ensures that the given path is absolute
 ///
@@ -12,7 +18,7 @@ use anyhow::bail;
 /// * `Err(String)` with an error message if the path is relative
 pub fn assert_absolute_path(path: &Path) -> anyhow::Result<()> {
     if !path.is_absolute() {
-        bail!("Path must be absolute. Please provide an absolute path starting with '/' (Unix) or 'C:\\' (Windows)".to_string())
+        Err(ToolErrorCode::InvalidArgs.error("Path must be absolute. Please provide an absolute path starting with '/' (Unix) or 'C:\\' (Windows)"))
     } else {
         Ok(())
     }
@@ -48,6 +54,28 @@ pub fn format_display_path(path: &Path, cwd: &Path) -> anyhow::Result<String> {
     }
 }
 
+/// Returns the files under the path sorted by path, or the path itself when
+/// it's a file
+#[cfg(feature = "repomap")]
+pub async fn list_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = Walker::max_all()
+        .cwd(path.to_path_buf())
+        .get()
+        .await
+        .with_context(|| format!("Failed to walk directory '{}'", path.display()))?
+        .into_iter()
+        .filter(|file| !file.is_dir())
+        .map(|file| path.join(file.path))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
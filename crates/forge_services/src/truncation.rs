@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use forge_domain::{ToolDefinition, ToolName};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::Clipper;
+
+/// Number of truncated outputs kept for the read more tool, older ones are
+/// discarded
+const MAX_STORED_OUTPUTS: usize = 32;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ReadMoreInput {
+    /// The token given at the end of the truncated output.
+    pub token: String,
+}
+
+/// Truncates tool results that exceed the maximum size and keeps the rest of
+/// their content, so that it can be read in chunks with the read more tool.
+/// Prevents a single call, e.g. a broad search, from filling the context.
+pub struct TruncatedOutputs {
+    max_chars: usize,
+    outputs: Mutex<BTreeMap<u64, String>>,
+    next_id: AtomicU64,
+}
+
+impl TruncatedOutputs {
+    pub const TOOL_NAME: &str = "tool_forge_read_more";
+
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            outputs: Default::default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn tool_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: ToolName::new(Self::TOOL_NAME),
+            description: "Reads the next part of a tool result that was truncated because it was too large. Pass the token given at the end of the truncated result. Prefer narrowing down the original call, e.g. a more specific search, over reading every part.".to_string(),
+            input_schema: schemars::schema_for!(ReadMoreInput),
+            output_schema: None,
+            mutating: false,
+        }
+    }
+
+    /// Returns the content unchanged if it fits, otherwise its first part
+    /// followed by the token to read the rest
+    pub fn truncate(&self, content: String) -> String {
+        let clipped = Clipper::from_start(self.max_chars).clip(&content);
+        let Some(prefix) = clipped.prefix_content() else {
+            return content;
+        };
+
+        let rest = content[prefix.len()..].to_string();
+        let remaining = rest.chars().count();
+        let token = self.store(rest);
+        format!(
+            "{prefix}\n<truncation>The result is truncated, {remaining} more characters remain. Call {} with the token \"{token}\" to read the next part.</truncation>",
+            Self::TOOL_NAME
+        )
+    }
+
+    /// Returns the next part of the truncated output identified by the token
+    pub fn read_more(&self, token: &str) -> anyhow::Result<String> {
+        let id = token
+            .strip_prefix("output-")
+            .and_then(|id| id.parse::<u64>().ok())
+            .with_context(|| format!("Invalid token: {token}"))?;
+        let content = self
+            .outputs
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .with_context(|| format!("The output of token {token} is no longer available"))?;
+        Ok(self.truncate(content))
+    }
+
+    fn store(&self, content: String) -> String {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut outputs = self.outputs.lock().unwrap();
+        outputs.insert(id, content);
+        while outputs.len() > MAX_STORED_OUTPUTS {
+            outputs.pop_first();
+        }
+        format!("output-{id}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn token(output: &str) -> String {
+        output
+            .split('"')
+            .nth(1)
+            .expect("The output should contain a token")
+            .to_string()
+    }
+
+    #[test]
+    fn test_small_output() {
+        let fixture = TruncatedOutputs::new(10);
+        let actual = fixture.truncate("short".to_string());
+        assert_eq!(actual, "short");
+    }
+
+    #[test]
+    fn test_read_more() {
+        let fixture = TruncatedOutputs::new(10);
+
+        let first = fixture.truncate("0123456789abcdefghijklmnopqrstuvwxyz".to_string());
+        assert_eq!(
+            first,
+            "0123456789\n<truncation>The result is truncated, 26 more characters remain. Call tool_forge_read_more with the token \"output-1\" to read the next part.</truncation>"
+        );
+
+        let second = fixture.read_more(&token(&first)).unwrap();
+        assert!(second.starts_with("abcdefghij\n<truncation>"));
+        assert!(second.contains("16 more characters remain"));
+
+        let third = fixture.read_more(&token(&second)).unwrap();
+        let last = fixture.read_more(&token(&third)).unwrap();
+        assert_eq!(last, "uvwxyz");
+
+        // Each part can only be read once
+        assert!(fixture.read_more(&token(&first)).is_err());
+    }
+
+    #[test]
+    fn test_old_outputs_are_discarded() {
+        let fixture = TruncatedOutputs::new(1);
+        let tokens = (0..=MAX_STORED_OUTPUTS)
+            .map(|_| token(&fixture.truncate("ab".to_string())))
+            .collect::<Vec<_>>();
+
+        assert!(fixture.read_more(&tokens[0]).is_err());
+        assert_eq!(fixture.read_more(&tokens[MAX_STORED_OUTPUTS]).unwrap(), "b");
+    }
+}
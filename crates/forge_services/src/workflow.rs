@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use forge_domain::{Workflow, WorkflowService};
 
 use crate::{FsReadService, FsWriteService, Infrastructure};
@@ -61,7 +61,11 @@ impl<F: Infrastructure> ForgeWorkflowService<F> {
     pub async fn read(&self, path: &Path) -> anyhow::Result<Workflow> {
         // First, try to find the config file in parent directories if needed
         let path = &self.resolve_path(Some(path.into())).await;
+        let workflow = self.read_file(path).await?;
+        Ok(workflow.path(path.canonicalize().unwrap_or_else(|_| path.clone())))
+    }
 
+    async fn read_file(&self, path: &Path) -> anyhow::Result<Workflow> {
         if !path.exists() {
             let workflow = Workflow::new();
             self.infra
@@ -72,6 +76,17 @@ impl<F: Infrastructure> ForgeWorkflowService<F> {
             Ok(workflow)
         } else {
             let content = self.infra.file_read_service().read_utf8(path).await?;
+            let value: serde_json::Value = serde_yml::from_str(&content)
+                .with_context(|| format!("Failed to parse workflow from {}", path.display()))?;
+            let violations = Workflow::validate(&value);
+            if !violations.is_empty() {
+                let violations = violations
+                    .iter()
+                    .map(|violation| format!("  - {violation}"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                bail!("Invalid workflow {}:\n{violations}", path.display());
+            }
             let workflow: Workflow = serde_yml::from_str(&content)
                 .with_context(|| format!("Failed to parse workflow from {}", path.display()))?;
             Ok(workflow)
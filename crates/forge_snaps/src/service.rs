@@ -56,6 +56,32 @@ impl SnapshotService {
         Ok(latest_path)
     }
 
+    /// Returns the content of a snapshot of the file, 0 being the most recent
+    /// one
+    pub async fn read_snapshot(&self, path: PathBuf, index: usize) -> Result<Vec<u8>> {
+        let snapshot = Snapshot::create(path.clone()).await?;
+        let snapshot_dir = self.snapshots_directory.join(snapshot.path_hash());
+        if !ForgeFS::exists(&snapshot_dir) {
+            return Err(anyhow::anyhow!("No snapshots found for {:?}", path));
+        }
+
+        let mut snapshots = Vec::new();
+        let mut dir = ForgeFS::read_dir(&snapshot_dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            if entry.file_name().to_string_lossy().ends_with(".snap") {
+                snapshots.push(entry.path());
+            }
+        }
+        // The file names start with the time of the snapshot
+        snapshots.sort_by(|a, b| b.cmp(a));
+
+        let count = snapshots.len();
+        let snapshot_path = snapshots.get(index).with_context(|| {
+            format!("Snapshot {index} not found for {path:?}, there are {count} snapshots")
+        })?;
+        ForgeFS::read(snapshot_path).await
+    }
+
     pub async fn undo_snapshot(&self, path: PathBuf) -> Result<()> {
         let snapshot = Snapshot::create(path.clone()).await?;
 
@@ -166,6 +192,47 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_read_snapshot() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+        ctx.write_content("First content").await?;
+        ctx.create_snapshot().await?;
+        ctx.write_content("Second content").await?;
+        ctx.create_snapshot().await?;
+        ctx.write_content("Final content").await?;
+
+        // Act
+        let latest = ctx.service.read_snapshot(ctx.test_file.clone(), 0).await?;
+        let oldest = ctx.service.read_snapshot(ctx.test_file.clone(), 1).await?;
+        let missing = ctx.service.read_snapshot(ctx.test_file.clone(), 2).await;
+
+        // Assert
+        assert_eq!(String::from_utf8(latest)?, "Second content");
+        assert_eq!(String::from_utf8(oldest)?, "First content");
+        assert!(missing.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_snapshot_of_removed_file() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+        let initial_content = "Initial content";
+
+        // Act
+        ctx.write_content(initial_content).await?;
+        ctx.create_snapshot().await?;
+        ForgeFS::remove_file(&ctx.test_file).await?;
+        ctx.undo_snapshot().await?;
+
+        // Assert
+        assert_eq!(ctx.read_content().await?, initial_content);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_undo_snapshot_no_snapshots() -> Result<()> {
         // Arrange
@@ -64,7 +64,15 @@ pub struct Snapshot {
 
 impl Snapshot {
     pub async fn create(path: PathBuf) -> anyhow::Result<Self> {
-        let path = path.canonicalize()?;
+        // A removed file is identified through its directory so that it can be
+        // restored from its snapshots
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(err) => match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) if !path.exists() => parent.canonicalize()?.join(name),
+                _ => return Err(err.into()),
+            },
+        };
         let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?;
 
         Ok(Self {
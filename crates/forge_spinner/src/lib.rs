@@ -13,6 +13,7 @@ pub struct SpinnerManager {
     start_time: Option<Instant>,
     message: Option<String>,
     tracker: Option<JoinHandle<()>>,
+    disabled: bool,
 }
 
 impl SpinnerManager {
@@ -20,9 +21,21 @@ impl SpinnerManager {
         Self::default()
     }
 
+    /// Shows or hides the spinner, a disabled spinner is never started
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.disabled = !enabled;
+        if self.disabled {
+            self.stop(None)?;
+        }
+        Ok(())
+    }
+
     /// Start the spinner with a message
     pub fn start(&mut self, message: Option<&str>) -> Result<()> {
         self.stop(None)?;
+        if self.disabled {
+            return Ok(());
+        }
 
         let words = [
             "Thinking",
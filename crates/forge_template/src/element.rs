@@ -63,6 +63,7 @@ impl Element {
 
         result.push_str(&format!("<{}", self.name));
         for (key, value) in &self.attr {
+            let value = html_escape::encode_double_quoted_attribute(value);
             result.push_str(&format!(" {key}=\"{value}\""));
         }
         result.push('>');
@@ -183,6 +184,14 @@ mod test {
         assert_eq!(actual, expected);
     }
     #[test]
+    fn test_element_with_attribute_escape() {
+        let html = Element::new("pre").attr("data-language", "\"><script>alert(1)</script>");
+        let actual = html.render();
+        let expected =
+            "<pre data-language=\"&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;\"></pre>";
+        assert_eq!(actual, expected);
+    }
+    #[test]
     fn test_element_with_css_style_classes() {
         let html = Element::new("div.foo.bar");
         let actual = html.render();
@@ -1,5 +1,6 @@
 use crate::Event;
 
+#[cfg(feature = "telemetry")]
 pub mod posthog;
 
 ///
@@ -7,4 +8,13 @@ pub mod posthog;
 #[async_trait::async_trait]
 pub trait Collect: Send + Sync {
     async fn collect(&self, event: Event) -> super::Result<()>;
+
+    /// Sends several events at once, one by one unless the collector supports
+    /// batches
+    async fn collect_batch(&self, events: Vec<Event>) -> super::Result<()> {
+        for event in events {
+            self.collect(event).await?;
+        }
+        Ok(())
+    }
 }
@@ -33,6 +33,7 @@ impl Payload {
         let mut properties = HashMap::new();
         let distinct_id = input.client_id.to_string();
         let event = input.event_name.to_string();
+        let timestamp = input.timestamp.naive_utc();
 
         if let Ok(Value::Object(map)) = serde_json::to_value(input) {
             for (key, value) in map {
@@ -45,25 +46,27 @@ impl Payload {
             event,
             distinct_id,
             properties,
-            timestamp: Some(chrono::Utc::now().naive_utc()),
+            timestamp: Some(timestamp),
         }
     }
 }
 
+#[derive(Debug, Serialize)]
+struct BatchPayload {
+    api_key: String,
+    batch: Vec<Payload>,
+}
+
 impl Tracker {
-    fn create_request(&self, event: Event) -> Result<reqwest::Request> {
-        let url = reqwest::Url::parse("https://us.i.posthog.com/capture/")?;
+    fn create_request(&self, path: &str, body: String) -> Result<reqwest::Request> {
+        let url = reqwest::Url::parse("https://us.i.posthog.com/")?.join(path)?;
         let mut request = reqwest::Request::new(reqwest::Method::POST, url);
         request.headers_mut().insert(
             HeaderName::from_static("content-type"),
             HeaderValue::from_static("application/json"),
         );
 
-        let event = Payload::new(self.api_secret.to_string(), event);
-
-        let _ = request
-            .body_mut()
-            .insert(reqwest::Body::from(serde_json::to_string(&event)?));
+        let _ = request.body_mut().insert(reqwest::Body::from(body));
 
         Ok(request)
     }
@@ -73,7 +76,23 @@ impl Tracker {
 impl Collect for Tracker {
     // TODO: move http request to a dispatch
     async fn collect(&self, event: Event) -> Result<()> {
-        let request = self.create_request(event)?;
+        let event = Payload::new(self.api_secret.to_string(), event);
+        let request = self.create_request("capture/", serde_json::to_string(&event)?)?;
+        let client = reqwest::Client::new();
+        client.execute(request).await?;
+
+        Ok(())
+    }
+
+    async fn collect_batch(&self, events: Vec<Event>) -> Result<()> {
+        let batch = BatchPayload {
+            api_key: self.api_secret.to_string(),
+            batch: events
+                .into_iter()
+                .map(|event| Payload::new(self.api_secret.to_string(), event))
+                .collect(),
+        };
+        let request = self.create_request("batch/", serde_json::to_string(&batch)?)?;
         let client = reqwest::Client::new();
         client.execute(request).await?;
 
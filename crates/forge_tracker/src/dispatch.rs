@@ -10,9 +10,12 @@ use tokio::time::Duration;
 
 use super::Result;
 use crate::can_track::can_track;
-use crate::collect::{posthog, Collect};
-use crate::{Event, EventKind};
+#[cfg(feature = "telemetry")]
+use crate::collect::posthog;
+use crate::collect::Collect;
+use crate::{Event, EventKind, Sanitizer};
 
+#[cfg(feature = "telemetry")]
 const POSTHOG_API_SECRET: &str = match option_env!("POSTHOG_API_SECRET") {
     Some(val) => val,
     None => "dev",
@@ -27,23 +30,29 @@ const PARAPHRASE: &str = "forge_key";
 
 const DEFAULT_CLIENT_ID: &str = "<anonymous>";
 
+/// Number of events sent at once when the events are batched
+const BATCH_SIZE: usize = 20;
+
 pub struct Tracker {
     collectors: Vec<Box<dyn Collect>>,
     can_track: bool,
+    sanitizer: Sanitizer,
     start_time: DateTime<Utc>,
     email: Mutex<Option<Vec<String>>>,
+    /// Events waiting to be sent, when the events are batched
+    batch: Mutex<Option<Vec<Event>>>,
 }
 
 impl Default for Tracker {
     fn default() -> Self {
-        let posthog_tracker = Box::new(posthog::Tracker::new(POSTHOG_API_SECRET));
         let start_time = Utc::now();
-        let can_track = can_track();
         Self {
-            collectors: vec![posthog_tracker],
-            can_track,
+            collectors: collectors(),
+            can_track: cfg!(feature = "telemetry") && can_track(),
+            sanitizer: Sanitizer::from_env(),
             start_time,
             email: Mutex::new(None),
+            batch: Mutex::new(None),
         }
     }
 }
@@ -61,6 +70,8 @@ impl Tracker {
 
     pub async fn dispatch(&'static self, event_kind: EventKind) -> Result<()> {
         if self.can_track {
+            let event_kind = self.sanitizer.event_kind(event_kind);
+
             // Create a new event
             let event = Event {
                 event_name: event_kind.name(),
@@ -76,7 +87,21 @@ impl Tracker {
                 user: user(),
                 version: version(),
                 email: self.email().await.clone(),
+                timestamp: Utc::now(),
             };
+            let event = self.sanitizer.event(event);
+
+            let mut batch = self.batch.lock().await;
+            if let Some(events) = batch.as_mut() {
+                events.push(event);
+                if events.len() < BATCH_SIZE {
+                    return Ok(());
+                }
+                let events = std::mem::take(events);
+                drop(batch);
+                return self.send(events).await;
+            }
+            drop(batch);
 
             // Dispatch the event to all collectors
             for collector in &self.collectors {
@@ -87,6 +112,34 @@ impl Tracker {
         Ok(())
     }
 
+    /// Sends the events in batches instead of one request per event, to save
+    /// bandwidth. The events still waiting are sent by [Tracker::flush].
+    pub async fn enable_batching(&self) {
+        self.batch.lock().await.get_or_insert_with(Vec::new);
+    }
+
+    /// Sends the events waiting in the batch
+    pub async fn flush(&self) -> Result<()> {
+        let events = self
+            .batch
+            .lock()
+            .await
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default();
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.send(events).await
+    }
+
+    async fn send(&self, events: Vec<Event>) -> Result<()> {
+        for collector in &self.collectors {
+            collector.collect_batch(events.clone()).await?;
+        }
+        Ok(())
+    }
+
     async fn email(&'static self) -> Vec<String> {
         let mut guard = self.email.lock().await;
         if guard.is_none() {
@@ -96,6 +149,17 @@ impl Tracker {
     }
 }
 
+/// The collectors the events are sent to
+#[cfg(feature = "telemetry")]
+fn collectors() -> Vec<Box<dyn Collect>> {
+    vec![Box::new(posthog::Tracker::new(POSTHOG_API_SECRET))]
+}
+
+#[cfg(not(feature = "telemetry"))]
+fn collectors() -> Vec<Box<dyn Collect>> {
+    Vec::new()
+}
+
 // Get the email address
 async fn email() -> HashSet<String> {
     fn parse(output: Output) -> Option<String> {
@@ -211,10 +275,53 @@ fn parse_email(text: String) -> Vec<String> {
 #[cfg(test)]
 mod tests {
 
+    use std::sync::{Arc, Mutex as StdMutex};
+
     use lazy_static::lazy_static;
+    use pretty_assertions::assert_eq;
 
     use super::*;
 
+    /// Records the number of events of each request
+    #[derive(Clone, Default)]
+    struct Requests(Arc<StdMutex<Vec<usize>>>);
+
+    #[async_trait::async_trait]
+    impl Collect for Requests {
+        async fn collect(&self, _event: Event) -> Result<()> {
+            self.0.lock().unwrap().push(1);
+            Ok(())
+        }
+
+        async fn collect_batch(&self, events: Vec<Event>) -> Result<()> {
+            self.0.lock().unwrap().push(events.len());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batching() {
+        let requests = Requests::default();
+        let tracker: &'static Tracker = Box::leak(Box::new(Tracker {
+            collectors: vec![Box::new(requests.clone())],
+            can_track: true,
+            sanitizer: Sanitizer::new(false),
+            start_time: Utc::now(),
+            email: Mutex::new(Some(Vec::new())),
+            batch: Mutex::new(None),
+        }));
+        tracker.enable_batching().await;
+
+        for _ in 0..BATCH_SIZE + 3 {
+            tracker.dispatch(EventKind::Ping).await.unwrap();
+        }
+        assert_eq!(*requests.0.lock().unwrap(), vec![BATCH_SIZE]);
+
+        tracker.flush().await.unwrap();
+        tracker.flush().await.unwrap();
+        assert_eq!(*requests.0.lock().unwrap(), vec![BATCH_SIZE, 3]);
+    }
+
     lazy_static! {
         static ref TRACKER: Tracker = Tracker::default();
     }
@@ -15,6 +15,7 @@ pub enum Error {
     #[debug("Url Parser Error: {}", _0)]
     UrlParser(url::ParseError),
 
+    #[cfg(feature = "telemetry")]
     #[debug("PostHog Error: {}", _0)]
     PostHog(posthog_rs::Error),
 
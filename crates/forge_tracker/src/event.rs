@@ -19,6 +19,10 @@ pub struct Event {
     pub args: Vec<String>,
     pub version: String,
     pub email: Vec<String>,
+    /// When the event happened, which differs from when it's sent when the
+    /// events are batched
+    #[serde(skip)]
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -44,20 +48,28 @@ impl From<Name> for String {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ToolCallPayload {
-    tool_name: String,
+    pub(crate) tool_name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    cause: Option<String>,
+    pub(crate) cause: Option<String>,
+    /// The class of the failure, kept when the cause is redacted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) code: Option<String>,
 }
 
 impl ToolCallPayload {
     pub fn new(tool_name: String) -> Self {
-        Self { tool_name, cause: None }
+        Self { tool_name, cause: None, code: None }
     }
 
     pub fn with_cause(mut self, cause: String) -> Self {
         self.cause = Some(cause);
         self
     }
+
+    pub fn with_code(mut self, code: String) -> Self {
+        self.code = Some(code);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -4,8 +4,10 @@ mod dispatch;
 mod error;
 mod event;
 mod log;
+mod sanitize;
 pub use can_track::VERSION;
 pub use dispatch::Tracker;
 use error::Result;
 pub use event::{Event, EventKind, ToolCallPayload};
 pub use log::{init_tracing, Guard};
+pub use sanitize::Sanitizer;
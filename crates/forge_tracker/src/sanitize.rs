@@ -0,0 +1,153 @@
+use std::env;
+use std::hash::Hasher;
+
+use crate::{Event, EventKind, ToolCallPayload};
+
+/// Sends the events unredacted when set to true
+const VERBOSE_ENV_VAR_NAME: &str = "FORGE_TRACKER_VERBOSE";
+
+/// Removes what could reveal the projects of the user from the events before
+/// they are sent: paths are replaced by a hash, which still tells events of
+/// the same project apart, and the bodies of prompts and error messages are
+/// replaced by a hash of their content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sanitizer {
+    verbose: bool,
+}
+
+impl Sanitizer {
+    pub fn new(verbose: bool) -> Self {
+        Self { verbose }
+    }
+
+    /// Redacts the events unless FORGE_TRACKER_VERBOSE is set to true
+    pub fn from_env() -> Self {
+        let verbose = env::var(VERBOSE_ENV_VAR_NAME)
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or_default();
+        Self::new(verbose)
+    }
+
+    pub fn event_kind(&self, event_kind: EventKind) -> EventKind {
+        if self.verbose {
+            return event_kind;
+        }
+        match event_kind {
+            EventKind::Prompt(content) => EventKind::Prompt(redact(&content)),
+            EventKind::Error(content) => EventKind::Error(redact(&content)),
+            EventKind::ToolCall(payload) => EventKind::ToolCall(ToolCallPayload {
+                cause: payload.cause.as_deref().map(redact),
+                ..payload
+            }),
+            event_kind => event_kind,
+        }
+    }
+
+    pub fn event(&self, event: Event) -> Event {
+        if self.verbose {
+            return event;
+        }
+        Event {
+            path: event.path.as_deref().map(redact),
+            cwd: event.cwd.as_deref().map(redact),
+            // Flags are kept, their values and the prompt may contain paths or names
+            args: event
+                .args
+                .iter()
+                .map(|arg| match arg.split_once('=') {
+                    Some((flag, value)) if flag.starts_with('-') => {
+                        format!("{flag}={}", redact(value))
+                    }
+                    _ if arg.starts_with('-') => arg.clone(),
+                    _ => redact(arg),
+                })
+                .collect(),
+            ..event
+        }
+    }
+}
+
+/// Replaces the text by a hash of it, so that identical values can still be
+/// grouped
+fn redact(text: &str) -> String {
+    let mut hasher = fnv_rs::Fnv64::default();
+    hasher.write(text.as_bytes());
+    format!("redacted:{:x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn event() -> Event {
+        Event {
+            event_name: "tool_call".to_string().into(),
+            event_value: String::new(),
+            start_time: Utc::now(),
+            cores: 8,
+            client_id: "client".to_string(),
+            os_name: "Linux".to_string(),
+            up_time: 0,
+            path: Some("/home/jane/.local/bin/forge".to_string()),
+            cwd: Some("/home/jane/acme-secret-project".to_string()),
+            user: "jane".to_string(),
+            args: vec![
+                "-p".to_string(),
+                "fix acme billing".to_string(),
+                "--workflow=/home/jane/acme.yaml".to_string(),
+                "--verbose".to_string(),
+            ],
+            version: "0.1.0".to_string(),
+            email: Vec::new(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_redacts_event() {
+        let actual = Sanitizer::new(false).event(event());
+
+        assert_eq!(actual.cwd, Some(redact("/home/jane/acme-secret-project")));
+        assert_eq!(actual.path, Some(redact("/home/jane/.local/bin/forge")));
+        assert_eq!(
+            actual.args,
+            vec![
+                "-p".to_string(),
+                redact("fix acme billing"),
+                format!("--workflow={}", redact("/home/jane/acme.yaml")),
+                "--verbose".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redacts_tool_call_cause() {
+        let fixture = EventKind::ToolCall(
+            ToolCallPayload::new("forge_tool_fs_read".to_string())
+                .with_cause("File not found: /home/jane/acme/billing.rs".to_string())
+                .with_code("not_found".to_string()),
+        );
+
+        let actual = Sanitizer::new(false).event_kind(fixture).value();
+        let expected = format!(
+            r#"{{"tool_name":"forge_tool_fs_read","cause":"{}","code":"not_found"}}"#,
+            redact("File not found: /home/jane/acme/billing.rs")
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_verbose_keeps_event() {
+        let actual = Sanitizer::new(true).event(event());
+        assert_eq!(actual.cwd, event().cwd);
+        assert_eq!(actual.args, event().args);
+
+        let actual = Sanitizer::new(true)
+            .event_kind(EventKind::Error("Failed to read /home/jane".to_string()))
+            .value();
+        assert_eq!(actual, "Failed to read /home/jane");
+    }
+}
@@ -1,3 +1,3 @@
 mod walker;
 
-pub use walker::{File, Walker};
+pub use walker::{File, Walker, IGNORE_FILE};
@@ -43,6 +43,10 @@ pub struct Walker {
     skip_binary: bool,
 }
 
+/// Name of the files listing, in the gitignore format, the files hidden from
+/// the agents on top of the ones ignored by git
+pub const IGNORE_FILE: &str = ".forgeignore";
+
 const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024; // 1MB
 const DEFAULT_MAX_FILES: usize = 100;
 const DEFAULT_MAX_TOTAL_SIZE: u64 = 10 * 1024 * 1024; // 10MB
@@ -118,6 +122,7 @@ impl Walker {
             .git_global(true) // Use global gitignore
             .git_ignore(true) // Use local .gitignore
             .ignore(true) // Use .ignore files
+            .add_custom_ignore_filename(IGNORE_FILE)
             .max_depth(Some(self.max_depth))
             // TODO: use build_parallel() for better performance
             .build();
@@ -383,4 +388,26 @@ mod tests {
         assert!(dir.is_dir());
         assert!(dir.path.ends_with('/'));
     }
+
+    #[tokio::test]
+    async fn test_walker_respects_forgeignore() {
+        let fixture =
+            fixtures::create_sized_files(&[("main.rs".into(), 10), ("generated.rs".into(), 10)])
+                .unwrap();
+        fs::write(fixture.path().join(IGNORE_FILE), "generated.rs\n").unwrap();
+
+        let actual = Walker::min_all()
+            .cwd(fixture.path().to_path_buf())
+            .get()
+            .await
+            .unwrap();
+
+        let actual = actual
+            .iter()
+            .filter_map(|file| file.file_name.as_deref())
+            .filter(|name| name.ends_with(".rs"))
+            .collect::<Vec<_>>();
+        let expected = vec!["main.rs"];
+        assert_eq!(actual, expected);
+    }
 }